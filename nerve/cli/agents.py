@@ -11,6 +11,7 @@ from nerve.defaults import (
     DEFAULT_AGENTS_LOAD_PATH,
 )
 from nerve.models import Configuration, Workflow
+from nerve.runtime import network
 
 cli = typer.Typer(
     no_args_is_help=True,
@@ -45,7 +46,7 @@ def agents(
 
 def _fetch_awesome_agents() -> list[dict[str, t.Any]] | None:
     try:
-        response = requests.get("https://api.awesomeagents.ai/index.json")
+        response = requests.get("https://api.awesomeagents.ai/index.json", **network.get_request_options())
         response.raise_for_status()
         agents = response.json()
         return [agent for agent in agents if "nerve" in agent.get("stack", [])]
@@ -153,7 +153,7 @@ def _show_results(eval: Evaluation) -> None:
 
     for _case_name, case_runs in eval.runs.items():
         for run in case_runs:
-            total_cost += run.usage.get("cost", 0.0)
+            total_cost += run.usage.get("cost") or 0.0
             # total_tokens += run.usage.get("total_tokens", 0)
             total_steps += run.steps
             total_time += run.time
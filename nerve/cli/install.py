@@ -13,6 +13,7 @@ from nerve.defaults import (
     DEFAULT_AGENTS_LOAD_PATH,
 )
 from nerve.models import Configuration, Workflow
+from nerve.runtime import network
 
 cli = typer.Typer(
     no_args_is_help=True,
@@ -28,7 +29,7 @@ def _download_and_unzip(url: str) -> pathlib.Path:
     print(f"🌐 downloading {url} ...")
 
     # download to temporary file
-    with requests.get(url, stream=True, verify=True, allow_redirects=True) as response:
+    with requests.get(url, stream=True, allow_redirects=True, **network.get_request_options()) as response:
         response.raise_for_status()
         with open(local_zip_path, "wb") as zip_file:
             for chunk in response.iter_content(chunk_size=8192):
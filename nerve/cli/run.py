@@ -6,11 +6,15 @@ from typer_di import Depends, TyperDI
 
 import nerve
 from nerve.cli.utils import _get_run_args
+from nerve.generation.errors import GeneratorError
 from nerve.models import Configuration, Mode, Workflow
 from nerve.runtime import logging, state
 from nerve.runtime.agent import Agent
+from nerve.runtime.audit import AuditTrail
 from nerve.runtime.flow import Flow
+from nerve.runtime.replay import Replay
 from nerve.runtime.runner import Arguments
+from nerve.server.events import EventStream, serve_events
 
 cli = TyperDI(
     no_args_is_help=True,
@@ -57,9 +61,21 @@ async def _run(extra_args: list[str], args: Arguments) -> None:
     if args.trace:
         state.set_trace_file(args.trace)
 
+    if args.audit:
+        state.set_audit_trail(AuditTrail(args.audit))
+
+    if args.replay:
+        logger.info(f"▶️  replaying {args.replay} ...")
+        state.set_replay(Replay.from_path(args.replay))
+
     if args.interactive:
         state.set_mode(Mode.INTERACTIVE)
 
+    if args.events:
+        stream = EventStream()
+        stream.attach()
+        events_server = asyncio.create_task(serve_events(stream, args.events, args.events_token))
+
     # make variables available to the runtime
     start_state = args.start_state
     start_state.update(_get_start_state_from_args(extra_args))
@@ -91,6 +107,21 @@ async def _run(extra_args: list[str], args: Arguments) -> None:
         logger.error(f"path '{args.input_path}' is not a valid workflow or agent configuration")
         raise typer.Abort()
 
+    if args.health_check:
+        for actor in flow.actors:
+            try:
+                await actor.health_check()
+            except GeneratorError as e:
+                logger.error(f"generator {actor.generation_engine.generator_id} is not available: {e}")
+                raise typer.Abort() from e
+            logger.info(f"💚 generator {actor.generation_engine.generator_id} is available")
+
+    if args.checkpoint:
+        flow.enable_checkpoints(args.checkpoint)
+
     await flow.run(args.task)
 
+    if args.events:
+        events_server.cancel()
+
     logger.debug("exiting")
@@ -8,6 +8,7 @@ from loguru import logger
 from nerve.defaults import (
     DEFAULT_AGENTS_LOAD_PATH,
     DEFAULT_CONVERSATION_STRATEGY,
+    DEFAULT_EVENTS_TOKEN,
     DEFAULT_GENERATOR,
     DEFAULT_MAX_COST,
     DEFAULT_MAX_STEPS,
@@ -101,6 +102,30 @@ def _get_run_args(
         pathlib.Path | None,
         typer.Option("--trace", help="Save the final state to a file."),
     ] = None,
+    audit: t.Annotated[
+        pathlib.Path | None,
+        typer.Option("--audit", help="Append a tamper-evident record of every executed tool to a file."),
+    ] = None,
+    checkpoint: t.Annotated[
+        pathlib.Path | None,
+        typer.Option("--checkpoint", help="Save the run to a file after every step, and resume it from there."),
+    ] = None,
+    replay: t.Annotated[
+        pathlib.Path | None,
+        typer.Option("--replay", help="Re-execute a run from its trace, without calling the model or the tools."),
+    ] = None,
+    health_check: t.Annotated[
+        bool,
+        typer.Option("--health-check", help="Verify that the generators are reachable before starting the run."),
+    ] = False,
+    events: t.Annotated[
+        str | None,
+        typer.Option("--events", help="Stream the events as Server-Sent Events on this host:port address."),
+    ] = None,
+    events_token: t.Annotated[
+        str | None,
+        typer.Option("--events-token", help="Token the clients of the events stream must provide."),
+    ] = DEFAULT_EVENTS_TOKEN,
     start_state: t.Annotated[
         str,
         typer.Option("--start-state", help="Pass the initial input state as a JSON string."),
@@ -123,6 +148,12 @@ def _get_run_args(
         timeout=timeout,
         log_path=log_path,
         trace=trace,
+        audit=audit,
+        checkpoint=checkpoint,
+        replay=replay,
+        health_check=health_check,
+        events=events,
+        events_token=events_token,
         # parse the start_state JSON string into a dictionary
         start_state=json.loads(start_state),
     )
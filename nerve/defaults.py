@@ -4,17 +4,71 @@ import pathlib
 DEFAULT_GENERATOR: str = os.getenv("NERVE_GENERATOR", "openai/gpt-4o-mini")
 DEFAULT_SERVE_HOST: str = os.getenv("NERVE_SERVE_HOST", "127.0.0.1")
 DEFAULT_SERVE_PORT: int = int(os.getenv("NERVE_SERVE_PORT", 8667))
+# token required by the events stream endpoint, unset by default
+DEFAULT_EVENTS_TOKEN: str | None = os.getenv("NERVE_EVENTS_TOKEN") or None
 DEFAULT_MAX_STEPS: int = int(os.getenv("NERVE_MAX_STEPS", 100))
+# fraction of the max steps after which the agent is told it is running out of steps, 0 to disable
+DEFAULT_MAX_STEPS_WARNING: float = float(os.getenv("NERVE_MAX_STEPS_WARNING", "0.8"))
 DEFAULT_MAX_COST: float = float(os.getenv("NERVE_MAX_COST", 10.0))
 DEFAULT_TIMEOUT: int | None = int(os.getenv("NERVE_TIMEOUT", 0)) or None
 DEFAULT_SHELL_TIMEOUT: int = int(os.getenv("NERVE_SHELL_TIMEOUT", "60"))
+DEFAULT_CODE_SANDBOX: str = os.getenv("NERVE_CODE_SANDBOX", "subprocess")
+DEFAULT_CODE_TIMEOUT: int = int(os.getenv("NERVE_CODE_TIMEOUT", "60"))
+DEFAULT_CODE_MAX_MEMORY: int = int(os.getenv("NERVE_CODE_MAX_MEMORY", "512"))
+DEFAULT_HTTP_MAX_RETRIES: int = int(os.getenv("NERVE_HTTP_MAX_RETRIES", "3"))
+DEFAULT_HTTP_RETRY_DELAY: int = int(os.getenv("NERVE_HTTP_RETRY_DELAY", "500"))
+DEFAULT_HTTP_TIMEOUT: int = int(os.getenv("NERVE_HTTP_TIMEOUT", "30"))
+# maximum size in bytes of a response body returned to the model, the rest is truncated
+DEFAULT_HTTP_MAX_RESPONSE_SIZE: int = int(os.getenv("NERVE_HTTP_MAX_RESPONSE_SIZE", str(1024 * 1024)))
+DEFAULT_HTTP_MAX_REDIRECTS: int = int(os.getenv("NERVE_HTTP_MAX_REDIRECTS", "5"))
+# proxy and custom root CA bundle for all outbound requests, unset by default
+DEFAULT_HTTP_PROXY: str | None = os.getenv("NERVE_HTTP_PROXY") or None
+DEFAULT_CA_BUNDLE: str | None = os.getenv("NERVE_CA_BUNDLE") or None
+# seconds to wait for the host application to answer a confirmation request
+DEFAULT_CONFIRMATION_TIMEOUT: float = float(os.getenv("NERVE_CONFIRMATION_TIMEOUT", "300"))
+# seconds to wait for the host application to answer a question of the agent
+DEFAULT_QUESTION_TIMEOUT: float = float(os.getenv("NERVE_QUESTION_TIMEOUT", "300"))
+DEFAULT_MAX_SUBTASK_DEPTH: int = int(os.getenv("NERVE_MAX_SUBTASK_DEPTH", "3"))
+DEFAULT_PROGRESS_STALL_STEPS: int = int(os.getenv("NERVE_PROGRESS_STALL_STEPS", "5"))
+# embedding model used by the rag namespace, independent from the chat generator
+DEFAULT_EMBEDDER: str = os.getenv("NERVE_EMBEDDER", "openai/text-embedding-3-small")
+# number of embedding requests sent concurrently while indexing documents
+DEFAULT_RAG_CONCURRENCY: int = int(os.getenv("NERVE_RAG_CONCURRENCY", "4"))
+# images returned by tools are downscaled to this maximum width and height before being sent to the model
+DEFAULT_IMAGE_MAX_SIZE: int = int(os.getenv("NERVE_IMAGE_MAX_SIZE", "1568"))
+DEFAULT_IMAGE_QUALITY: int = int(os.getenv("NERVE_IMAGE_QUALITY", "85"))
+# files returned by tools larger than this number of bytes are sent as text, if it can be extracted
+DEFAULT_FILE_MAX_SIZE: int = int(os.getenv("NERVE_FILE_MAX_SIZE", str(20 * 1024 * 1024)))
+# if set, the original of every downscaled image is saved in this folder
+DEFAULT_IMAGE_ORIGINALS_PATH: str | None = os.getenv("NERVE_IMAGE_ORIGINALS_PATH") or None
+# tool outputs longer than this number of characters are split in chunks the model can page through, 0 to disable
+DEFAULT_MAX_TOOL_OUTPUT_LENGTH: int = int(os.getenv("NERVE_MAX_TOOL_OUTPUT_LENGTH", "100000"))
+# where the API keys are read from: env (the environment variables), vault or aws
+DEFAULT_SECRETS_PROVIDER: str = os.getenv("NERVE_SECRETS", "env")
+DEFAULT_VAULT_PATH: str = os.getenv("NERVE_VAULT_PATH", "nerve")
+DEFAULT_VAULT_MOUNT: str = os.getenv("NERVE_VAULT_MOUNT", "secret")
+DEFAULT_AWS_SECRET_ID: str = os.getenv("NERVE_AWS_SECRET_ID", "nerve")
+# optional id of the run attached to every event, a random one is generated for each run if not set
+DEFAULT_RUN_ID: str | None = os.getenv("NERVE_RUN_ID") or None
 DEFAULT_CONVERSATION_STRATEGY: str = os.getenv("NERVE_CONVERSATION_STRATEGY", "full")
+# tokens are counted with the tokenizer of the model family if known, set to "estimate" to always estimate them
+DEFAULT_TOKENIZER: str = os.getenv("NERVE_TOKENIZER", "auto")
 
 DEFAULT_NERVE_HOME: pathlib.Path = pathlib.Path.home() / ".nerve"
 
 DEFAULT_AGENTS_LOAD_PATH: pathlib.Path = DEFAULT_NERVE_HOME / "agents"
 DEFAULT_PROMPTS_LOAD_PATH: pathlib.Path = DEFAULT_NERVE_HOME / "prompts"
 DEFAULT_RUNS_PATH: pathlib.Path = DEFAULT_NERVE_HOME / "runs"
+# opt-in cache of the generated responses, for development only
+DEFAULT_GENERATION_CACHE: bool = os.getenv("NERVE_GENERATION_CACHE", "").lower() in ("1", "true", "yes")
+DEFAULT_GENERATION_CACHE_TTL: int = int(os.getenv("NERVE_GENERATION_CACHE_TTL", "86400"))
+DEFAULT_GENERATION_CACHE_PATH: pathlib.Path = DEFAULT_NERVE_HOME / "cache"
+DEFAULT_RAG_INDEX_PATH: pathlib.Path = pathlib.Path(
+    os.getenv("NERVE_RAG_INDEX", str(DEFAULT_NERVE_HOME / "rag" / "index.json"))
+)
+DEFAULT_CALENDAR_PATH: pathlib.Path = pathlib.Path(
+    os.getenv("NERVE_CALENDAR_FILE", str(DEFAULT_NERVE_HOME / "calendar.ics"))
+)
 
 DEFAULT_AGENT_PATH: pathlib.Path = pathlib.Path("agent.yml")
 DEFAULT_AGENT_SYSTEM_PROMPT: str = "You are a helpful assistant."
@@ -1,14 +1,42 @@
 import asyncio
+import collections
+import hashlib
 import json
 import os
+import traceback
 import typing as t
 from abc import ABC, abstractmethod
 
+import jsonschema
 from loguru import logger
 
-from nerve.models import Usage
-from nerve.runtime import state
-from nerve.tools.protocol import get_tool_response, get_tool_schema
+from nerve.defaults import DEFAULT_MAX_TOOL_OUTPUT_LENGTH
+from nerve.generation.files import prepare_files
+from nerve.generation.images import describe_images, downscale_images
+from nerve.generation.repair import repair_json
+from nerve.generation.tokens import get_token_counter
+from nerve.generation.transform import MessageTransformer
+from nerve.models import Configuration, SupportedFeatures, Usage
+from nerve.runtime import secrets, state, template
+from nerve.tools.middleware import Middleware, ToolVetoed
+from nerve.tools.postprocess import PostProcessor, post_process
+from nerve.tools.protocol import get_tool_response, get_tool_schema, validate_tool_args
+
+
+def get_end_user_id(identity: str) -> str:
+    """Stable identifier of an end user sent to the providers for abuse monitoring, without disclosing who it is."""
+
+    return hashlib.sha256(identity.encode()).hexdigest()
+
+
+class ToolAbortedError(RuntimeError):
+    """A tool raised something that is not an exception, like SystemExit, it's reported with where it happened."""
+
+    def __init__(self, error: BaseException):
+        frames = traceback.extract_tb(error.__traceback__)
+        location = f" at {frames[-1].filename}:{frames[-1].lineno}" if frames else ""
+        super().__init__(f"tool aborted with {error!r}{location}")
+        self.error = error
 
 
 class WindowStrategy(ABC):
@@ -20,6 +48,10 @@ class WindowStrategy(ABC):
     def __str__(self) -> str:
         pass
 
+    def for_model(self, generator_id: str) -> "WindowStrategy":
+        """Return the strategy to use with the given model, by default the same one."""
+        return self
+
 
 class Engine(ABC):
     def __init__(
@@ -27,14 +59,51 @@ class Engine(ABC):
         generator_id: str,
         window_strategy: WindowStrategy,
         tools: list[t.Callable[..., t.Any]] | None = None,
+        response_schema: dict[str, t.Any] | None = None,
+        transformers: list[MessageTransformer] | None = None,
+        loop_detection: Configuration.LoopDetection | None = None,
+        max_tool_output_length: int | None = None,
+        argument_templates: t.Literal["error", "empty"] | None = None,
     ):
         self.generator_id = generator_id
         self.generator_params: dict[str, t.Any] = {}
+        # optional JSON schema the final response must conform to
+        self.response_schema = response_schema
+        # applied to everything sent to the model
+        self.transformers = transformers or []
+        # optional detection of repeated tool calls, with the most recent ones
+        self.loop_detection = loop_detection
+        self.recent_calls: collections.deque[str] = collections.deque(
+            maxlen=loop_detection.window if loop_detection else 0
+        )
+        # longer tool outputs are split in chunks, with the remaining chunks of each output by id
+        self.max_tool_output_length = (
+            DEFAULT_MAX_TOOL_OUTPUT_LENGTH if max_tool_output_length is None else max_tool_output_length
+        )
+        self.output_chunks: dict[str, collections.deque[str]] = {}
+        # if set, the references in the tool arguments are resolved, undefined ones either fail the call or are emptied
+        self.argument_templates = argument_templates
+        # executed around every tool call, in order
+        self.middlewares: list[Middleware] = []
+        # transformations of the successful tool outputs, run before the middlewares
+        self.post_processors: list[PostProcessor] = []
+        # set when another generator can take over, errors of an unavailable model are raised instead of ending the run
+        self.failover = False
+        # the system prompt is folded into the user one for the models that don't accept it
+        self.supports_system_prompt = True
+        # tools are sent in any case, the model might support them even if not known to
+        self.supports_tools = True
+        # files returned by the tools are sent as text to the models that don't accept them
+        self.supports_file_input = False
+        # images returned by the tools are replaced by their description for the models that can't see them
+        self.supports_vision = True
 
         self._parse_generator_params()
+        # tokenizer of the model family, or an estimate if unknown
+        self.token_counter = get_token_counter(self.generator_id)
 
         self.history: list[dict[str, t.Any]] = []
-        self.window_strategy = window_strategy
+        self.window_strategy = window_strategy.for_model(self.generator_id)
 
         self.tools = {fn.__name__: fn for fn in (tools or [])}
         self.tools_schemas = []
@@ -72,6 +141,9 @@ class Engine(ABC):
         else:
             self.api_base = None
 
+        # the API key of the provider is resolved through the secrets provider, unless given as a parameter
+        self.api_key: str | None = self.generator_params.pop("api_key", None) or secrets.get_api_key(self.generator_id)
+
     def _get_extended_tooling_schema(self, extra_tools: dict[str, t.Callable[..., t.Any]]) -> list[dict[str, t.Any]]:
         tools_schemas = self.tools_schemas.copy()
         extra_schemas = []
@@ -85,6 +157,10 @@ class Engine(ABC):
 
         tools_schemas.extend(extra_schemas)
 
+        # only offered while there's something to read
+        if self.output_chunks:
+            tools_schemas.append(get_tool_schema(self.generator_id, self.read_next_chunk))
+
         logger.trace(tools_schemas)
         logger.debug(f"{len(tools_schemas)} tools")
 
@@ -104,6 +180,37 @@ class Engine(ABC):
             "content": "None of the tools were used, interact with the user by executing the existing tools.",
         }
 
+    def _get_structured_response(self, content: str) -> list[dict[str, t.Any]]:
+        try:
+            jsonschema.validate(json.loads(content), self.response_schema)  # type: ignore
+        except json.JSONDecodeError as e:
+            error = f"the response is not valid JSON: {e}"
+        except jsonschema.ValidationError as e:
+            path = "/".join(str(p) for p in e.absolute_path)
+            error = f"{e.message} (at /{path})" if path else e.message
+        else:
+            # valid final response
+            self._get_text_response(content)
+            state.set_task_complete(content, json.loads(content))
+            return []
+
+        state.on_event(
+            "response_validation_error",
+            {
+                "generator": self.generator_id,
+                "response": content,
+                "error": error,
+            },
+        )
+
+        return [
+            {
+                "role": "user",
+                "content": f"Your response does not conform to the required JSON schema: {error}. "
+                "Reply again with only a valid JSON document.",
+            }
+        ]
+
     def _get_unknown_tool_response(self, tool_call_id: str, tool_name: str) -> dict[str, t.Any]:
         state.on_event(
             "unknown_tool",
@@ -119,6 +226,46 @@ class Engine(ABC):
             "content": f"The tool {tool_name} is not available.",
         }
 
+    def _get_invalid_tool_call_response(
+        self, tool_call_id: str, tool_name: str, args: str | dict[str, t.Any], error: str
+    ) -> dict[str, t.Any]:
+        state.on_event(
+            "invalid_tool_call",
+            {
+                "generator": self.generator_id,
+                "tool_name": tool_name,
+                "args": args,
+                "error": error,
+            },
+        )
+
+        return {
+            "tool_call_id": tool_call_id,
+            "role": "tool",
+            "name": tool_name,
+            "content": f"ERROR: invalid arguments for tool {tool_name}: {error}",
+        }
+
+    def _get_vetoed_tool_call_response(
+        self, tool_call_id: str, tool_name: str, args: dict[str, t.Any], reason: str
+    ) -> dict[str, t.Any]:
+        state.on_event(
+            "invalid_tool_call",
+            {
+                "generator": self.generator_id,
+                "tool_name": tool_name,
+                "args": args,
+                "error": f"vetoed: {reason}",
+            },
+        )
+
+        return {
+            "tool_call_id": tool_call_id,
+            "role": "tool",
+            "name": tool_name,
+            "content": f"ERROR: the execution of {tool_name} has been denied: {reason}",
+        }
+
     def _responses_for(self, tool_call_id: str, tool_name: str, response: t.Any) -> t.Any:
         if isinstance(response, str):
             return [
@@ -158,28 +305,103 @@ class Engine(ABC):
                 },
             ]
 
+    def _get_chunk_note(self, output_id: str) -> str:
+        chunks = self.output_chunks.get(output_id)
+        if not chunks:
+            self.output_chunks.pop(output_id, None)
+            return ""
+
+        remaining = sum(len(chunk) for chunk in chunks)
+        return (
+            f"\n\n[output truncated, {len(chunks)} more chunks and {remaining} characters left, "
+            f"use read_next_chunk with output_id {output_id} to read the next chunk]"
+        )
+
+    def _chunk_tool_output(self, output_id: str, tool_name: str, output: str) -> str:
+        if self.max_tool_output_length <= 0 or len(output) <= self.max_tool_output_length:
+            return output
+        elif tool_name == "read_next_chunk":
+            # the chunk fits, it's just followed by the note
+            return output
+
+        size = self.max_tool_output_length
+        chunks = [output[i : i + size] for i in range(0, len(output), size)]
+        self.output_chunks[output_id] = collections.deque(chunks[1:])
+
+        state.on_event(
+            "tool_output_chunked",
+            {"tool_name": tool_name, "output_id": output_id, "length": len(output), "chunks": len(chunks)},
+        )
+
+        return chunks[0] + self._get_chunk_note(output_id)
+
+    def read_next_chunk(
+        self,
+        output_id: t.Annotated[str, "The id of the truncated output"],
+    ) -> str:
+        """Read the next chunk of a tool output that was too long to be returned at once."""
+
+        chunks = self.output_chunks.get(output_id)
+        if not chunks:
+            return f"ERROR: there are no more chunks to read for output {output_id}"
+
+        return chunks.popleft() + self._get_chunk_note(output_id)
+
     async def _get_tool_response(
         self, tool_call_id: str, tool_name: str, tool_fn: t.Callable[..., t.Any], tool_args: dict[str, t.Any]
     ) -> list[dict[str, t.Any]]:
         logger.debug(f"calling tool: {tool_name} with args: {tool_args}")
+        replay = state.get_replay()
+        tool_error: Exception | None = None
+        # correlates the tool events with the tool_call_planned one
+        state.set_tool_call_id(tool_call_id)
         try:
-            tool_response = tool_fn(**tool_args)
-            # check if the tool function returned a coroutine
-            if asyncio.iscoroutine(tool_response):
-                tool_response = await tool_response
+            if replay is not None:
+                # return the recorded result instead of executing the tool
+                tool_response = replay.next_tool_result(tool_name, tool_args)
+            else:
+                if asyncio.iscoroutinefunction(tool_fn):
+                    tool_response = tool_fn(**tool_args)
+                else:
+                    # blocking tools run in a thread, so the run can still be cancelled while they execute
+                    tool_response = await asyncio.to_thread(tool_fn, **tool_args)
+                # check if the tool function returned a coroutine
+                if asyncio.iscoroutine(tool_response):
+                    tool_response = await tool_response
         except Exception as e:
+            tool_error = e
+        except (KeyboardInterrupt, asyncio.CancelledError):
+            # the run is being interrupted or cancelled
+            raise
+        except BaseException as e:
+            # a buggy tool calling sys.exit() or raising something that is not an exception must not end the run
+            tool_error = ToolAbortedError(e)
+        finally:
+            state.set_tool_call_id(None)
+
+        if tool_error is not None:
             state.on_event(
                 "tool_error",
                 {
                     "generator": self.generator_id,
                     "tool_name": tool_name,
                     "args": tool_args,
-                    "error": e,
+                    "error": tool_error,
                 },
             )
-            tool_response = f"ERROR while executing tool {tool_name}: {e}"
+            tool_response = f"ERROR while executing tool {tool_name}: {tool_error}"
+
+        if tool_error is None:
+            tool_response = post_process(self.post_processors, tool_name, tool_response)
 
-        generated_responses = get_tool_response(tool_response)
+        for middleware in self.middlewares:
+            tool_response = middleware.after_tool(tool_name, tool_args, tool_response, tool_error)
+
+        # large images would waste tokens or exceed the provider limits, and not every model accepts files
+        generated_responses = downscale_images(get_tool_response(tool_response))
+        if not self.supports_vision:
+            generated_responses = await describe_images(generated_responses, self.describe_image)
+        generated_responses = prepare_files(generated_responses, self.supports_file_input)
         if isinstance(generated_responses, str):
             # simple case, just set content
             return [
@@ -187,7 +409,7 @@ class Engine(ABC):
                     "tool_call_id": tool_call_id,
                     "role": "tool",
                     "name": tool_name,
-                    "content": generated_responses,
+                    "content": self._chunk_tool_output(tool_call_id, tool_name, generated_responses),
                 }
             ]
         elif isinstance(generated_responses, list):
@@ -198,7 +420,7 @@ class Engine(ABC):
                     "tool_call_id": tool_call_id,
                     "role": "tool",
                     "name": tool_name,
-                    "content": "\n".join(generated_responses),
+                    "content": self._chunk_tool_output(tool_call_id, tool_name, "\n".join(generated_responses)),
                 }
             ]
         else:
@@ -230,18 +452,151 @@ class Engine(ABC):
                 },
             ]
 
+    def _on_tool_calls_planned(self, calls: list[tuple[str, str, str | dict[str, t.Any]]]) -> None:
+        for position, (call_id, tool_name, args) in enumerate(calls, 1):
+            state.on_event(
+                "tool_call_planned",
+                {
+                    "generator": self.generator_id,
+                    "call_id": call_id,
+                    "tool_name": tool_name,
+                    "args": args,
+                    "position": position,
+                    "total": len(calls),
+                },
+            )
+
+    def _parse_tool_args(self, tool_name: str, args: str) -> t.Any:
+        try:
+            return json.loads(args)
+        except json.JSONDecodeError:
+            # smaller models often generate almost valid JSON, try to fix it before reporting the error
+            repaired = repair_json(args)
+            if repaired is None:
+                raise
+
+            logger.debug(f"repaired the arguments of {tool_name}: {args!r} -> {repaired!r}")
+            return json.loads(repaired)
+
     async def _process_tool_call(
         self, call_id: str, tool_name: str, args: str | dict[str, t.Any], extra_tools: dict[str, t.Callable[..., t.Any]]
     ) -> list[dict[str, t.Any]]:
         # resolve tool
         tool_fn = self.tools.get(tool_name, extra_tools.get(tool_name, None))
+        if tool_fn is None and tool_name == "read_next_chunk":
+            tool_fn = self.read_next_chunk
         if tool_fn is None:
             # unknown tool
             return [self._get_unknown_tool_response(call_id, tool_name)]
+        elif state.is_cancelled():
+            # every tool call must be followed by a response
+            return [
+                {
+                    "tool_call_id": call_id,
+                    "role": "tool",
+                    "name": tool_name,
+                    "content": "ERROR: the run has been cancelled",
+                }
+            ]
         else:
-            tool_call_args = json.loads(args) if isinstance(args, str) else args
+            try:
+                tool_call_args = self._parse_tool_args(tool_name, args) if isinstance(args, str) else args
+                if self.argument_templates is not None:
+                    tool_call_args = template.resolve(tool_call_args, self.argument_templates)
+                error = validate_tool_args(tool_fn, tool_call_args)
+            except json.JSONDecodeError as e:
+                tool_call_args = {}
+                error = f"the arguments are not valid JSON: {e}"
+            except template.UndefinedReferenceError as e:
+                error = str(e)
+
+            if error is not None:
+                return [self._get_invalid_tool_call_response(call_id, tool_name, args, error)]
+
+            try:
+                for middleware in self.middlewares:
+                    tool_call_args = middleware.before_tool(tool_name, tool_call_args)
+            except ToolVetoed as e:
+                return [self._get_vetoed_tool_call_response(call_id, tool_name, tool_call_args, str(e))]
+
             # execute tool and collect response
-            return await self._get_tool_response(call_id, tool_name, tool_fn, tool_call_args)
+            responses = await self._get_tool_response(call_id, tool_name, tool_fn, tool_call_args)
+
+            feedback = self._check_for_loop(tool_name, tool_call_args)
+            if feedback:
+                # the tool message must directly follow the tool call, so the feedback is appended to it
+                responses[0]["content"] += f"\n\n{feedback}"
+
+            return responses
+
+    def _check_for_loop(self, tool_name: str, tool_args: dict[str, t.Any]) -> str | None:
+        if self.loop_detection is None:
+            return None
+
+        call = json.dumps({"name": tool_name, "args": tool_args}, sort_keys=True, default=str)
+        self.recent_calls.append(call)
+
+        repetitions = self.recent_calls.count(call)
+        if repetitions <= self.loop_detection.threshold:
+            return None
+
+        state.on_event(
+            "loop_detected",
+            {
+                "generator": self.generator_id,
+                "tool_name": tool_name,
+                "args": tool_args,
+                "repetitions": repetitions,
+                "window": self.loop_detection.window,
+            },
+        )
+
+        max_repetitions = self.loop_detection.max_repetitions
+        if max_repetitions is not None and repetitions >= max_repetitions:
+            state.set_task_failed(f"stuck in a loop, {tool_name} called {repetitions} times with the same arguments")
+
+        return (
+            f"You called {tool_name} with the same arguments {repetitions} times in your last "
+            f"{len(self.recent_calls)} tool calls, stop repeating it and try a different approach."
+        )
+
+    def _get_summary_split(self, num_messages: int) -> int:
+        # never separate the tool calls from their responses
+        split = min(num_messages, len(self.history))
+        while split < len(self.history) and self.history[split].get("role") == "tool":
+            split += 1
+        return split
+
+    @abstractmethod
+    async def summarize_history(self, num_messages: int) -> Usage:
+        """Replace the oldest messages of the history with a summary, returning the usage of the summarization."""
+        pass
+
+    def count_tokens(self, text: str) -> int:
+        """Count the tokens of the text for the model of this engine, locally."""
+        return self.token_counter.count(text)
+
+    @property
+    def supported_features(self) -> SupportedFeatures:
+        return SupportedFeatures(
+            system_prompt=self.supports_system_prompt,
+            tools=self.supports_tools,
+            files=self.supports_file_input,
+            vision=self.supports_vision,
+        )
+
+    async def health_check(self) -> None:
+        """Raise a GeneratorError if the provider can't be reached or the model is not available."""
+        await self.complete("", "ping")
+
+    async def describe_image(self, url: str) -> str:
+        """Describe an image returned by a tool to a model that can't see it, by default it's only reported."""
+        return "[the tool returned an image, but the model can't see images]"
+
+    @abstractmethod
+    async def complete(self, system_prompt: str, prompt: str) -> tuple[Usage, str]:
+        """Generate a response to a single prompt, outside of the history and without tools."""
+        pass
 
     @abstractmethod
     async def step(
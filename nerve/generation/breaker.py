@@ -0,0 +1,113 @@
+import time
+import typing as t
+
+from loguru import logger
+
+from nerve.generation.errors import GeneratorError, OverloadedError
+from nerve.runtime import state
+
+State = t.Literal["closed", "open", "half_open"]
+
+
+class CircuitOpenError(OverloadedError):
+    """The provider failed too many times in a row, the call is not attempted until the cooldown is over."""
+
+    # retrying would fail the same way until the cooldown is over
+    retryable = False
+
+    def __init__(self, message: str, retry_in: float):
+        super().__init__(message)
+        # seconds until a call can be attempted again
+        self.retry_in = retry_in
+
+
+class CircuitBreaker:
+    """
+    Stops calling a provider after a number of consecutive failures: the circuit opens for a cooldown period
+    during which calls fail fast, then half-opens to let a single call probe whether the provider recovered.
+    """
+
+    def __init__(
+        self,
+        generator_id: str,
+        failures: int = 5,
+        cooldown: float = 60.0,
+        clock: t.Callable[[], float] = time.monotonic,
+    ):
+        self.generator_id = generator_id
+        # consecutive failures after which the circuit opens
+        self.max_failures = failures
+        # seconds the circuit stays open before probing the provider
+        self.cooldown = cooldown
+        self.clock = clock
+
+        self.state: State = "closed"
+        self.failures = 0
+        self.opened_at = 0.0
+        # set while the probe of an half-open circuit is in flight
+        self.probing = False
+
+    def _set_state(self, new_state: State) -> None:
+        if new_state == self.state:
+            return
+
+        logger.debug(f"circuit breaker of {self.generator_id}: {self.state} -> {new_state}")
+        state.on_event(
+            "circuit_breaker",
+            {
+                "generator": self.generator_id,
+                "from": self.state,
+                "to": new_state,
+                "failures": self.failures,
+                "cooldown": self.cooldown,
+            },
+        )
+        self.state = new_state
+
+    def before_call(self) -> bool:
+        """Raise a CircuitOpenError if the call must not be attempted, return whether the call is the probe."""
+
+        if self.state == "open":
+            remaining = self.opened_at + self.cooldown - self.clock()
+            if remaining > 0:
+                raise CircuitOpenError(
+                    f"{self.generator_id} failed {self.failures} times in a row, not calling it for {remaining:.0f}s",
+                    remaining,
+                )
+            self._set_state("half_open")
+
+        if self.probing:
+            # only one call probes the provider
+            raise CircuitOpenError(f"{self.generator_id} is being probed after {self.failures} failures", 1.0)
+
+        self.probing = self.state == "half_open"
+        return self.probing
+
+    def on_error(self, error: GeneratorError) -> None:
+        """Register the error of a call, only transient errors count as failures of the provider."""
+
+        if not error.retryable:
+            # the provider answered
+            self.on_success()
+            return
+
+        self.probing = False
+        self.failures += 1
+        if self.state == "half_open" or self.failures >= self.max_failures:
+            self.opened_at = self.clock()
+            self._set_state("open")
+
+    def on_success(self) -> None:
+        self.probing = False
+        self.failures = 0
+        self._set_state("closed")
+
+
+# shared by every engine using the same generator
+_breakers: dict[str, CircuitBreaker] = {}
+
+
+def get_breaker(generator_id: str, failures: int, cooldown: float) -> CircuitBreaker:
+    if generator_id not in _breakers:
+        _breakers[generator_id] = CircuitBreaker(generator_id, failures, cooldown)
+    return _breakers[generator_id]
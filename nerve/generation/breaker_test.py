@@ -0,0 +1,78 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.generation.breaker import CircuitBreaker, CircuitOpenError
+from nerve.generation.errors import InvalidRequestError, NetworkError
+
+
+class _Clock:
+    def __init__(self) -> None:
+        self.now = 0.0
+
+    def __call__(self) -> float:
+        return self.now
+
+
+@patch("nerve.generation.breaker.state")
+class TestCircuitBreaker(unittest.TestCase):
+    def setUp(self) -> None:
+        self.clock = _Clock()
+        self.breaker = CircuitBreaker("openai/gpt-4o", failures=2, cooldown=30, clock=self.clock)
+
+    def _fail(self) -> None:
+        self.breaker.before_call()
+        self.breaker.on_error(NetworkError("connection refused"))
+
+    def test_opens_after_consecutive_failures(self, mock_state: MagicMock) -> None:
+        self._fail()
+        self.assertEqual(self.breaker.state, "closed")
+        self._fail()
+        self.assertEqual(self.breaker.state, "open")
+
+        self.clock.now = 10
+        with self.assertRaises(CircuitOpenError) as ctx:
+            self.breaker.before_call()
+        self.assertEqual(ctx.exception.retry_in, 20)
+
+        mock_state.on_event.assert_called_once()
+        self.assertEqual(mock_state.on_event.call_args.args[0], "circuit_breaker")
+        self.assertEqual(mock_state.on_event.call_args.args[1]["to"], "open")
+
+    def test_successes_reset_the_failures(self, _: MagicMock) -> None:
+        self._fail()
+        self.breaker.on_success()
+        self._fail()
+
+        self.assertEqual(self.breaker.state, "closed")
+
+    def test_non_transient_errors_are_not_failures(self, _: MagicMock) -> None:
+        for _ in range(3):
+            self.breaker.before_call()
+            self.breaker.on_error(InvalidRequestError("invalid request"))
+
+        self.assertEqual(self.breaker.state, "closed")
+
+    def test_half_open_probe(self, _: MagicMock) -> None:
+        self._fail()
+        self._fail()
+        self.clock.now = 30
+
+        self.assertTrue(self.breaker.before_call())
+        self.assertEqual(self.breaker.state, "half_open")
+        # a single call probes the provider
+        with self.assertRaises(CircuitOpenError):
+            self.breaker.before_call()
+
+        self.breaker.on_success()
+        self.assertEqual(self.breaker.state, "closed")
+        self.assertFalse(self.breaker.before_call())
+
+    def test_failed_probe_opens_again(self, _: MagicMock) -> None:
+        self._fail()
+        self._fail()
+        self.clock.now = 30
+
+        self._fail()
+
+        self.assertEqual(self.breaker.state, "open")
+        self.assertEqual(self.breaker.opened_at, 30)
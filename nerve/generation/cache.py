@@ -0,0 +1,78 @@
+import hashlib
+import json
+import pathlib
+import time
+import typing as t
+from types import SimpleNamespace
+
+from loguru import logger
+
+from nerve.defaults import DEFAULT_GENERATION_CACHE_PATH, DEFAULT_GENERATION_CACHE_TTL
+from nerve.models import Usage
+
+
+def to_namespace(obj: t.Any) -> t.Any:
+    # cached messages must support attribute access like the litellm ones
+    if isinstance(obj, dict):
+        return SimpleNamespace(**{key: to_namespace(value) for key, value in obj.items()})
+    elif isinstance(obj, list):
+        return [to_namespace(item) for item in obj]
+    return obj
+
+
+class ResponseCache:
+    """
+    On-disk cache of the generated responses, keyed on the model, conversation and tools.
+    """
+
+    def __init__(self, path: pathlib.Path = DEFAULT_GENERATION_CACHE_PATH, ttl: int = DEFAULT_GENERATION_CACHE_TTL):
+        self.path = path
+        # time to live of the entries in seconds
+        self.ttl = ttl
+
+    @staticmethod
+    def get_key(
+        generator_id: str,
+        generator_params: dict[str, t.Any],
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+    ) -> str:
+        data = json.dumps(
+            {
+                "generator": generator_id,
+                "params": generator_params,
+                "conversation": conversation,
+                "tools": tools_schema,
+            },
+            sort_keys=True,
+            default=str,
+        )
+        return hashlib.sha256(data.encode()).hexdigest()
+
+    def _entry_path(self, key: str) -> pathlib.Path:
+        return self.path / f"{key}.json"
+
+    def get(self, key: str) -> tuple[Usage, t.Any] | None:
+        entry_path = self._entry_path(key)
+        if not entry_path.exists():
+            return None
+
+        try:
+            entry = json.loads(entry_path.read_text())
+        except json.JSONDecodeError:
+            logger.warning(f"removing invalid cache entry {entry_path}")
+            entry_path.unlink(missing_ok=True)
+            return None
+
+        if time.time() - entry["created_at"] > self.ttl:
+            logger.debug(f"cache entry {key} expired")
+            entry_path.unlink(missing_ok=True)
+            return None
+
+        return Usage(**entry["usage"]), to_namespace(entry["message"])
+
+    def set(self, key: str, usage: Usage, message: dict[str, t.Any]) -> None:
+        self.path.mkdir(parents=True, exist_ok=True)
+        self._entry_path(key).write_text(
+            json.dumps({"created_at": time.time(), "usage": usage.model_dump(), "message": message}, default=str)
+        )
@@ -0,0 +1,54 @@
+import pathlib
+import tempfile
+import time
+import unittest
+from unittest.mock import patch
+
+from nerve.generation.cache import ResponseCache
+from nerve.models import Usage
+
+CONVERSATION = [{"role": "system", "content": "You are a helpful assistant."}, {"role": "user", "content": "hi"}]
+MESSAGE = {
+    "content": None,
+    "role": "assistant",
+    "tool_calls": [{"id": "call_1", "function": {"name": "task_complete_success", "arguments": "{}"}}],
+}
+
+
+class TestResponseCache(unittest.TestCase):
+    def setUp(self) -> None:
+        self.temp_dir = tempfile.TemporaryDirectory()
+        self.cache = ResponseCache(pathlib.Path(self.temp_dir.name), ttl=60)
+
+    def tearDown(self) -> None:
+        self.temp_dir.cleanup()
+
+    def test_key_depends_on_all_inputs(self) -> None:
+        key = ResponseCache.get_key("openai/gpt-4o", {}, CONVERSATION, None)
+
+        self.assertEqual(key, ResponseCache.get_key("openai/gpt-4o", {}, list(CONVERSATION), None))
+        self.assertNotEqual(key, ResponseCache.get_key("openai/gpt-4o-mini", {}, CONVERSATION, None))
+        self.assertNotEqual(key, ResponseCache.get_key("openai/gpt-4o", {"temperature": 0.5}, CONVERSATION, None))
+        self.assertNotEqual(key, ResponseCache.get_key("openai/gpt-4o", {}, CONVERSATION[:1], None))
+        self.assertNotEqual(key, ResponseCache.get_key("openai/gpt-4o", {}, CONVERSATION, [{"type": "function"}]))
+
+    def test_roundtrip(self) -> None:
+        self.assertIsNone(self.cache.get("missing"))
+
+        self.cache.set("key", Usage(cost=0.1, prompt_tokens=10, completion_tokens=5, total_tokens=15), MESSAGE)
+
+        cached = self.cache.get("key")
+        assert cached is not None
+        usage, message = cached
+        self.assertEqual(usage.total_tokens, 15)
+        self.assertIsNone(message.content)
+        self.assertEqual(message.tool_calls[0].id, "call_1")
+        self.assertEqual(message.tool_calls[0].function.name, "task_complete_success")
+
+    def test_expired_entries_are_ignored(self) -> None:
+        self.cache.set("key", Usage(), MESSAGE)
+
+        with patch("nerve.generation.cache.time.time", return_value=time.time() + 120):
+            self.assertIsNone(self.cache.get("key"))
+
+        self.assertIsNone(self.cache.get("key"))
@@ -0,0 +1,25 @@
+import typing as t
+from abc import ABC, abstractmethod
+
+from nerve.models import Usage
+
+
+class Client(ABC):
+    """
+    Sends a conversation to a model and returns the next message, it can be passed to the engine to replace
+    the providers supported via litellm, for instance to use a mock model in tests.
+    """
+
+    @abstractmethod
+    async def generate(
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+    ) -> tuple[Usage, t.Any]:
+        """Return the usage and the assistant message, with attribute access to its fields like the litellm ones."""
+        pass
+
+    async def health_check(self) -> None:
+        """
+        Raise an error if the provider can't be reached or the model is not available. By default a response
+        to a minimal prompt is generated, clients can override it with a cheaper request.
+        """
+        await self.generate([{"role": "user", "content": "ping"}], None)
@@ -0,0 +1,81 @@
+import json
+import re
+import typing as t
+from collections import Counter
+
+# the tool setting the final answer of the agent
+_COMPLETION_TOOL = "task_complete_success"
+
+JUDGE_PROMPT = (
+    "You are given several answers that different attempts produced for the same task. Pick the answer that "
+    "agrees the most with the others and is best supported by them. Reply only with its number."
+)
+
+
+class Answer(t.NamedTuple):
+    # comparable representation of the answer
+    key: str
+    # whether the answer is a JSON document rather than free text
+    structured: bool
+
+
+def _canonical(value: t.Any) -> str:
+    return json.dumps(value, sort_keys=True, ensure_ascii=False)
+
+
+def get_final_answer(message: t.Any, has_schema: bool) -> Answer | None:
+    """
+    Return the final answer of a generated message, either a structured response or the completion of the task,
+    or None if the message does something else.
+    """
+
+    if message is None:
+        return None
+
+    tool_calls = getattr(message, "tool_calls", None) or []
+    if not tool_calls:
+        if not has_schema:
+            return None
+        try:
+            return Answer(_canonical(json.loads(message.content or "")), True)
+        except json.JSONDecodeError:
+            return None
+
+    if len(tool_calls) != 1 or tool_calls[0].function.name != _COMPLETION_TOOL:
+        return None
+
+    try:
+        args = tool_calls[0].function.arguments
+        args = json.loads(args) if isinstance(args, str) else args or {}
+    except json.JSONDecodeError:
+        return None
+
+    if args.get("result") is not None:
+        return Answer(_canonical(args["result"]), True)
+
+    return Answer(str(args.get("reason") or ""), False)
+
+
+def _normalize(text: str) -> str:
+    return " ".join(text.lower().split())
+
+
+def vote(answers: list[Answer]) -> tuple[int, int]:
+    """Return the index of the first answer of the majority and how many answers agree with it."""
+
+    counts = Counter(_normalize(answer.key) for answer in answers)
+    winner, votes = counts.most_common(1)[0]
+    return next(i for i, answer in enumerate(answers) if _normalize(answer.key) == winner), votes
+
+
+def get_judge_prompt(answers: list[Answer]) -> str:
+    return "\n\n".join(f"<answer number={i}>\n{answer.key}\n</answer>" for i, answer in enumerate(answers, 1))
+
+
+def parse_judge_choice(reply: str, num_answers: int) -> int | None:
+    """Return the index of the answer picked by the judge, or None if the reply is not a valid choice."""
+
+    match = re.search(r"\d+", reply)
+    if match is None or not 1 <= int(match.group()) <= num_answers:
+        return None
+    return int(match.group()) - 1
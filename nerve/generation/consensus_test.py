@@ -0,0 +1,41 @@
+import unittest
+
+from nerve.generation import consensus
+from nerve.generation.cache import to_namespace
+from nerve.generation.testing import text, tool_call
+
+
+class TestFinalAnswer(unittest.TestCase):
+    def test_structured_response(self) -> None:
+        answer = consensus.get_final_answer(to_namespace(text('{"b": 2, "a": 1}')), has_schema=True)
+
+        self.assertEqual(answer, consensus.Answer('{"a": 1, "b": 2}', True))
+        self.assertIsNone(consensus.get_final_answer(to_namespace(text("not json")), has_schema=True))
+        self.assertIsNone(consensus.get_final_answer(to_namespace(text("hello")), has_schema=False))
+
+    def test_task_completion(self) -> None:
+        reason = to_namespace(tool_call("task_complete_success", reason="the answer is 42"))
+        result = to_namespace(tool_call("task_complete_success", reason="done", result={"answer": 42}))
+
+        self.assertEqual(consensus.get_final_answer(reason, False), consensus.Answer("the answer is 42", False))
+        self.assertEqual(consensus.get_final_answer(result, False), consensus.Answer('{"answer": 42}', True))
+
+    def test_other_tool_calls_are_not_answers(self) -> None:
+        self.assertIsNone(consensus.get_final_answer(to_namespace(tool_call("read_file", path="a")), False))
+
+
+class TestAggregation(unittest.TestCase):
+    def test_vote(self) -> None:
+        answers = [
+            consensus.Answer("41", False),
+            consensus.Answer("The answer is 42", False),
+            consensus.Answer("the answer is  42", False),
+        ]
+
+        self.assertEqual(consensus.vote(answers), (1, 2))
+
+    def test_parse_judge_choice(self) -> None:
+        self.assertEqual(consensus.parse_judge_choice("2", 3), 1)
+        self.assertEqual(consensus.parse_judge_choice("Answer 3 is the best", 3), 2)
+        self.assertIsNone(consensus.parse_judge_choice("4", 3))
+        self.assertIsNone(consensus.parse_judge_choice("none of them", 3))
@@ -1,8 +1,60 @@
+import json
 import typing as t
 
 from loguru import logger
 
 from nerve.generation import WindowStrategy
+from nerve.generation.tokens import TokenCounter, TokenizerCounter, get_token_counter
+
+
+def _remove_orphans(window: list[dict[str, t.Any]]) -> list[dict[str, t.Any]]:
+    # NOTE: This is a "best effort" sliding window strategy.
+    #
+    # In theory we could just get the last N messages, however for each
+    # tool call from the AI, we need to add its tool call response or the
+    # API errors with:
+    #
+    #   Invalid parameter: messages with role 'tool' must be a response
+    #   to a preceeding message with 'tool_calls'.
+    orphans = []
+    for item in window:
+        if item.get("role") == "tool":
+            tool_call_id = item.get("tool_call_id", "")
+            found = False
+            for other in window:
+                # quick and dirty way to check if the tool call id is in the other item
+                if item != other and tool_call_id in str(other):
+                    found = True
+                    break
+
+            if not found:
+                orphans.append(item)
+
+    if orphans:
+        window = [item for item in window if item not in orphans]
+
+    return window
+
+
+def remove_duplicates(conversation: list[dict[str, t.Any]]) -> list[dict[str, t.Any]]:
+    """
+    Collapse identical consecutive messages, like the same feedback appended again by a retry, into one.
+    Only messages that are exactly the same, with the same role and content, are collapsed.
+    """
+
+    deduplicated: list[dict[str, t.Any]] = []
+    for message in conversation:
+        if not deduplicated or message != deduplicated[-1]:
+            deduplicated.append(message)
+
+    if len(deduplicated) < len(conversation):
+        logger.debug(f"collapsed {len(conversation) - len(deduplicated)} duplicated consecutive messages")
+
+    return deduplicated
+
+
+def _get_placeholder(num_removed: int) -> dict[str, t.Any]:
+    return {"role": "user", "content": f"<{num_removed} earlier messages were removed to fit the context window>"}
 
 
 class FullHistoryStrategy(WindowStrategy):
@@ -34,33 +86,7 @@ class SlidingWindowStrategy(WindowStrategy):
             logger.debug(" * " + str(msg))
         logger.debug("---")
 
-        # NOTE: This is a "best effort" sliding window strategy.
-        #
-        # In theory we could just get the last N messages, however for each
-        # tool call from the AI, we need to add its tool call response or the
-        # API errors with:
-        #
-        #   Invalid parameter: messages with role 'tool' must be a response
-        #   to a preceeding message with 'tool_calls'.
-        window = history[-self.window_size :]
-        orphans = []
-        for item in window:
-            if item.get("role") == "tool":
-                tool_call_id = item.get("tool_call_id", "")
-                found = False
-                for other in window:
-                    # quick and dirty way to check if the tool call id is in the other item
-                    if item != other and tool_call_id in str(other):
-                        found = True
-                        break
-
-                if not found:
-                    orphans.append(item)
-
-        if orphans:
-            window = [item for item in window if item not in orphans]
-
-        return window
+        return _remove_orphans(history[-self.window_size :])
 
     def __str__(self) -> str:
         return f"<sliding window of size {self.window_size}>"
@@ -104,11 +130,72 @@ class StrippedWindowStrategy(WindowStrategy):
         return f"<stripping window of size {self.window_size}>"
 
 
+class TrimmedWindowStrategy(WindowStrategy):
+    """
+    This strategy returns the last N messages, replacing the earlier ones with a placeholder
+    so that the model knows that part of the conversation is missing.
+    """
+
+    def __init__(self, window_size: int = 10) -> None:
+        self.window_size = window_size
+
+    async def get_window(self, history: list[dict[str, t.Any]]) -> list[dict[str, t.Any]]:
+        if len(history) <= self.window_size:
+            return history
+
+        window = _remove_orphans(history[-self.window_size :])
+        return [_get_placeholder(len(history) - len(window)), *window]
+
+    def __str__(self) -> str:
+        return f"<trimmed window of size {self.window_size}>"
+
+
+class TokenBudgetStrategy(WindowStrategy):
+    """
+    This strategy removes the earliest messages until the history fits in a budget of tokens,
+    replacing them with a placeholder.
+    """
+
+    def __init__(self, max_tokens: int = 32000, counter: TokenCounter | None = None) -> None:
+        self.max_tokens = max_tokens
+        self.counter = counter or TokenizerCounter()
+
+    def for_model(self, generator_id: str) -> WindowStrategy:
+        return TokenBudgetStrategy(self.max_tokens, get_token_counter(generator_id))
+
+    def _count_tokens(self, message: dict[str, t.Any]) -> int:
+        return self.counter.count(json.dumps(message, default=str))
+
+    async def get_window(self, history: list[dict[str, t.Any]]) -> list[dict[str, t.Any]]:
+        tokens = [self._count_tokens(message) for message in history]
+        total = sum(tokens)
+        if total <= self.max_tokens:
+            return history
+
+        start = 0
+        # always keep at least the last message
+        while total > self.max_tokens and start < len(history) - 1:
+            total -= tokens[start]
+            start += 1
+
+        window = _remove_orphans(history[start:])
+        logger.debug(f"removed {len(history) - len(window)} messages to fit {self.max_tokens} tokens")
+
+        return [_get_placeholder(len(history) - len(window)), *window]
+
+    def __str__(self) -> str:
+        return f"<token budget of {self.max_tokens}>"
+
+
 def strategy_from_string(strategy: str) -> WindowStrategy:
     if strategy == "full":
         return FullHistoryStrategy()
     elif strategy.startswith("strip-") and strategy.split("-")[1].isdigit():
         return StrippedWindowStrategy(int(strategy.split("-")[1]))
+    elif strategy.startswith("trim-") and strategy.split("-")[1].isdigit():
+        return TrimmedWindowStrategy(int(strategy.split("-")[1]))
+    elif strategy.startswith("tokens-") and strategy.split("-")[1].isdigit():
+        return TokenBudgetStrategy(int(strategy.split("-")[1]))
     elif strategy.isdigit():
         return SlidingWindowStrategy(int(strategy))
     else:
@@ -1,4 +1,5 @@
 import asyncio
+import json
 import typing as t
 import unittest
 from unittest.mock import patch
@@ -7,8 +8,12 @@ from nerve.generation.conversation import (
     FullHistoryStrategy,
     SlidingWindowStrategy,
     StrippedWindowStrategy,
+    TokenBudgetStrategy,
+    TrimmedWindowStrategy,
+    remove_duplicates,
     strategy_from_string,
 )
+from nerve.generation.tokens import EstimatedCounter
 
 
 class TestFullHistoryStrategy(unittest.TestCase):
@@ -125,6 +130,94 @@ class TestStrippedWindowStrategy(unittest.TestCase):
         self.assertEqual(t.cast(StrippedWindowStrategy, strategy).window_size, 8)
 
 
+class TestTrimmedWindowStrategy(unittest.TestCase):
+    def test_get_window_returns_full_history_when_smaller_than_window(self) -> None:
+        strategy = TrimmedWindowStrategy(window_size=5)
+        history = [{"role": "user", "content": "Hello"}, {"role": "assistant", "content": "Hi"}]
+
+        self.assertEqual(asyncio.run(strategy.get_window(history)), history)
+
+    def test_get_window_replaces_earlier_messages_with_placeholder(self) -> None:
+        strategy = TrimmedWindowStrategy(window_size=2)
+        history = [
+            {"role": "assistant", "content": "First"},
+            {"role": "assistant", "content": "", "tool_calls": [{"id": "call_1"}]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "Result"},
+            {"role": "assistant", "content": "Last"},
+        ]
+
+        result = asyncio.run(strategy.get_window(history))
+
+        # the orphan tool response is removed as well
+        self.assertEqual(
+            result,
+            [
+                {"role": "user", "content": "<3 earlier messages were removed to fit the context window>"},
+                {"role": "assistant", "content": "Last"},
+            ],
+        )
+        self.assertEqual(str(strategy), "<trimmed window of size 2>")
+
+
+@patch("nerve.generation.tokens.litellm.token_counter", side_effect=lambda text: len(text))
+class TestTokenBudgetStrategy(unittest.TestCase):
+    def test_get_window_returns_full_history_within_budget(self, _: t.Any) -> None:
+        strategy = TokenBudgetStrategy(max_tokens=1000)
+        history = [{"role": "user", "content": "Hello"}, {"role": "assistant", "content": "Hi"}]
+
+        self.assertEqual(asyncio.run(strategy.get_window(history)), history)
+
+    def test_get_window_removes_earliest_messages(self, _: t.Any) -> None:
+        history = [{"role": "assistant", "content": "x" * 100} for _ in range(5)]
+        message_size = len(json.dumps(history[0]))
+        strategy = TokenBudgetStrategy(max_tokens=message_size * 2)
+
+        result = asyncio.run(strategy.get_window(history))
+
+        self.assertEqual(len(result), 3)
+        self.assertEqual(result[0]["content"], "<3 earlier messages were removed to fit the context window>")
+        self.assertEqual(result[1:], history[-2:])
+
+    def test_get_window_keeps_last_message(self, _: t.Any) -> None:
+        history = [{"role": "assistant", "content": "x" * 100}]
+
+        result = asyncio.run(TokenBudgetStrategy(max_tokens=1).get_window(history))
+
+        self.assertEqual(result[-1], history[0])
+
+    def test_for_model_uses_the_model_counter(self, _: t.Any) -> None:
+        strategy = TokenBudgetStrategy(max_tokens=1000).for_model("ollama/qwen2.5")
+
+        self.assertIsInstance(strategy, TokenBudgetStrategy)
+        self.assertIsInstance(t.cast(TokenBudgetStrategy, strategy).counter, EstimatedCounter)
+        self.assertEqual(t.cast(TokenBudgetStrategy, strategy).max_tokens, 1000)
+
+
+class TestRemoveDuplicates(unittest.TestCase):
+    def test_identical_consecutive_messages_are_collapsed(self) -> None:
+        feedback = {"role": "user", "content": "try again"}
+        conversation = [
+            {"role": "user", "content": "task"},
+            feedback,
+            dict(feedback),
+            {"role": "assistant", "content": "ok"},
+        ]
+
+        self.assertEqual(remove_duplicates(conversation), [conversation[0], feedback, conversation[3]])
+
+    def test_similar_or_distant_messages_are_kept(self) -> None:
+        conversation = [
+            {"role": "user", "content": "try again"},
+            {"role": "assistant", "content": "try again"},
+            {"role": "assistant", "content": "ok"},
+            {"role": "assistant", "content": "try again"},
+            {"role": "tool", "tool_call_id": "call_1", "content": "42"},
+            {"role": "tool", "tool_call_id": "call_2", "content": "42"},
+        ]
+
+        self.assertEqual(remove_duplicates(conversation), conversation)
+
+
 class TestStrategyFromString(unittest.TestCase):
     def test_full_strategy(self) -> None:
         strategy = strategy_from_string("full")
@@ -140,6 +233,16 @@ class TestStrategyFromString(unittest.TestCase):
         self.assertIsInstance(strategy, StrippedWindowStrategy)
         self.assertEqual(t.cast(StrippedWindowStrategy, strategy).window_size, 2)
 
+    def test_trimmed_window_strategy(self) -> None:
+        strategy = strategy_from_string("trim-3")
+        self.assertIsInstance(strategy, TrimmedWindowStrategy)
+        self.assertEqual(t.cast(TrimmedWindowStrategy, strategy).window_size, 3)
+
+    def test_token_budget_strategy(self) -> None:
+        strategy = strategy_from_string("tokens-8000")
+        self.assertIsInstance(strategy, TokenBudgetStrategy)
+        self.assertEqual(t.cast(TokenBudgetStrategy, strategy).max_tokens, 8000)
+
     def test_invalid_strategy(self) -> None:
         with self.assertRaises(ValueError):
             strategy_from_string("invalid")
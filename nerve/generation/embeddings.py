@@ -0,0 +1,115 @@
+import asyncio
+import typing as t
+
+import litellm
+from loguru import logger
+
+from nerve.defaults import DEFAULT_EMBEDDER
+from nerve.generation import get_end_user_id
+from nerve.generation.errors import RateLimitedError, to_generator_error
+from nerve.runtime import secrets
+
+
+class Embedder:
+    """
+    Generates vector embeddings with an embedding model, separate from the chat generator.
+    """
+
+    def __init__(self, generator_id: str | None = None, batch_size: int = 100, end_user: str | None = None):
+        # never embed with the chat model, use the default embedding model instead
+        self.generator_id, _, query = (generator_id or DEFAULT_EMBEDDER).partition("?")
+        self.generator_params: dict[str, t.Any] = dict(param.split("=", 1) for param in query.split("&") if param)
+
+        # only the OpenAI embedding APIs accept the end user identifier
+        provider = self.generator_id.split("/")[0] if "/" in self.generator_id else "openai"
+        if end_user and provider in ("openai", "azure"):
+            self.generator_params["user"] = get_end_user_id(end_user)
+
+        # the API key of the provider is resolved through the secrets provider, unless given as a parameter
+        self.api_key: str | None = self.generator_params.pop("api_key", None) or secrets.get_api_key(self.generator_id)
+
+        # maximum number of texts embedded with a single request
+        self.batch_size = batch_size
+
+        logger.debug(f"embedder: {self.generator_id} {self.generator_params}")
+
+    async def embed(self, text: str) -> list[float]:
+        return (await self.embed_batch([text]))[0]
+
+    async def embed_batch(self, texts: list[str]) -> list[list[float]]:
+        embeddings: list[list[float]] = []
+        for i in range(0, len(texts), self.batch_size):
+            response = await asyncio.to_thread(
+                litellm.embedding,  # type: ignore
+                model=self.generator_id,
+                input=texts[i : i + self.batch_size],
+                **({"api_key": self.api_key} if self.api_key else {}),
+                **self.generator_params,
+            )
+            # make sure embeddings are in the same order as the inputs
+            data = sorted(response.data, key=lambda item: item.get("index", 0))
+            embeddings.extend(list(item["embedding"]) for item in data)
+
+        return embeddings
+
+    async def _embed_with_retries(self, texts: list[str], max_retries: int) -> list[list[float]] | None:
+        for attempt in range(max_retries + 1):
+            try:
+                return await self.embed_batch(texts)
+            except Exception as e:
+                error = to_generator_error(e)
+                # there's no point in trying the other texts with invalid credentials and so on
+                if error.fatal:
+                    raise error from e
+
+                if attempt == max_retries:
+                    logger.warning(f"can't embed {len(texts)} texts after {max_retries + 1} attempts: {e}")
+                    return None
+
+                delay = error.retry_after if isinstance(error, RateLimitedError) and error.retry_after else 2**attempt
+                logger.debug(f"embedding failed, retrying in {delay} seconds: {e}")
+                await asyncio.sleep(delay)
+
+        return None
+
+    async def embed_concurrently(
+        self,
+        texts: list[str],
+        concurrency: int = 4,
+        max_retries: int = 3,
+        on_progress: t.Callable[[int, int, int], None] | None = None,
+    ) -> list[list[float] | None]:
+        """
+        Embed the texts in batches sent concurrently, retrying the failed ones. The texts of a batch still failing
+        are embedded one by one, and the embedding of the texts that can't be embedded is None. on_progress is
+        called after every batch with the number of texts processed, the total and the number of failures.
+        """
+
+        semaphore = asyncio.Semaphore(max(1, concurrency))
+        embeddings: list[list[float] | None] = [None] * len(texts)
+        processed = failed = 0
+
+        async def embed_from(start: int) -> None:
+            nonlocal processed, failed
+
+            batch = texts[start : start + self.batch_size]
+            async with semaphore:
+                results: list[list[float] | None] | None = await self._embed_with_retries(batch, max_retries)
+                if results is None and len(batch) > 1:
+                    # isolate the texts causing the failure
+                    results = []
+                    for text in batch:
+                        single = await self._embed_with_retries([text], max_retries)
+                        results.append(single[0] if single else None)
+
+            for i, embedding in enumerate(results or [None] * len(batch)):
+                embeddings[start + i] = embedding
+                failed += embedding is None
+
+            processed += len(batch)
+            if on_progress is not None:
+                on_progress(processed, len(texts), failed)
+
+        await asyncio.gather(*(embed_from(start) for start in range(0, len(texts), self.batch_size)))
+
+        return embeddings
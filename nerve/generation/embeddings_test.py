@@ -0,0 +1,84 @@
+import unittest
+from types import SimpleNamespace
+from unittest.mock import MagicMock, patch
+
+from nerve.defaults import DEFAULT_EMBEDDER
+from nerve.generation.embeddings import Embedder
+from nerve.generation.errors import GeneratorError
+
+
+class TestEmbedder(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        # not affected by the API keys of the environment
+        patcher = patch("nerve.generation.embeddings.secrets.get_api_key", return_value=None)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def test_default_embedding_model(self) -> None:
+        self.assertEqual(Embedder().generator_id, DEFAULT_EMBEDDER)
+
+    def test_generator_params(self) -> None:
+        embedder = Embedder("ollama/nomic-embed-text?api_base=http://localhost:11434")
+
+        self.assertEqual(embedder.generator_id, "ollama/nomic-embed-text")
+        self.assertEqual(embedder.generator_params, {"api_base": "http://localhost:11434"})
+
+    def test_end_user(self) -> None:
+        embedder = Embedder("openai/text-embedding-3-small", end_user="alice@example.com")
+        self.assertEqual(len(embedder.generator_params["user"]), 64)
+        self.assertNotIn("alice", embedder.generator_params["user"])
+
+        # only sent to the providers supporting it
+        self.assertEqual(Embedder("ollama/nomic-embed-text", end_user="alice@example.com").generator_params, {})
+
+    @patch("nerve.generation.embeddings.litellm.embedding")
+    async def test_embed(self, mock_embedding: MagicMock) -> None:
+        mock_embedding.return_value = SimpleNamespace(data=[{"embedding": [0.1, 0.2]}])
+
+        embedding = await Embedder("openai/text-embedding-3-large").embed("hello")
+
+        self.assertEqual(embedding, [0.1, 0.2])
+        mock_embedding.assert_called_once_with(model="openai/text-embedding-3-large", input=["hello"])
+
+    @patch("nerve.generation.embeddings.litellm.embedding")
+    async def test_embed_batch(self, mock_embedding: MagicMock) -> None:
+        # return the embeddings out of order, they must be sorted by index
+        mock_embedding.side_effect = lambda model, input: SimpleNamespace(
+            data=[{"index": i, "embedding": [float(len(text))]} for i, text in reversed(list(enumerate(input)))]
+        )
+
+        embeddings = await Embedder(batch_size=2).embed_batch(["a", "bb", "ccc"])
+
+        self.assertEqual(embeddings, [[1.0], [2.0], [3.0]])
+        self.assertEqual(mock_embedding.call_count, 2)
+
+    @patch("nerve.generation.embeddings.asyncio.sleep")
+    @patch("nerve.generation.embeddings.litellm.embedding")
+    async def test_embed_concurrently(self, mock_embedding: MagicMock, mock_sleep: MagicMock) -> None:
+        attempts: dict[str, int] = {}
+
+        def embedding(model: str, input: list[str]) -> SimpleNamespace:
+            key = ",".join(input)
+            attempts[key] = attempts.get(key, 0) + 1
+            # the first batch fails once, the second one always fails because of a single text
+            if (key == "a,bb" and attempts[key] == 1) or "bad" in key:
+                raise ConnectionError("connection reset")
+            return SimpleNamespace(data=[{"index": i, "embedding": [float(len(text))]} for i, text in enumerate(input)])
+
+        mock_embedding.side_effect = embedding
+        progress: list[tuple[int, int, int]] = []
+
+        embeddings = await Embedder(batch_size=2).embed_concurrently(
+            ["a", "bb", "bad", "dddd"], concurrency=2, max_retries=1, on_progress=lambda *args: progress.append(args)
+        )
+
+        self.assertEqual(embeddings, [[1.0], [2.0], None, [4.0]])
+        self.assertEqual(attempts, {"a,bb": 2, "bad,dddd": 2, "bad": 2, "dddd": 1})
+        self.assertEqual(sorted(progress), [(2, 4, 0), (4, 4, 1)])
+
+    @patch("nerve.generation.embeddings.litellm.embedding")
+    async def test_fatal_errors_abort(self, mock_embedding: MagicMock) -> None:
+        mock_embedding.side_effect = GeneratorError("invalid api key", fatal=True)
+
+        with self.assertRaisesRegex(GeneratorError, "invalid api key"):
+            await Embedder().embed_concurrently(["a"])
@@ -0,0 +1,118 @@
+import json
+
+import litellm
+
+
+class GeneratorError(Exception):
+    """
+    Error returned while generating a response, the subclass tells whether the request can be retried
+    or the run must stop. Errors that don't fall in any specific category are instances of this class.
+    """
+
+    # the run can't continue if this error is raised
+    fatal: bool = False
+    # the same request can be retried
+    retryable: bool = False
+
+    def __init__(self, message: str, fatal: bool | None = None):
+        super().__init__(message)
+        if fatal is not None:
+            self.fatal = fatal
+
+
+class RateLimitedError(GeneratorError):
+    retryable = True
+
+    def __init__(self, message: str, retry_after: float | None = None):
+        super().__init__(message)
+        # seconds to wait before retrying, if provided by the server
+        self.retry_after = retry_after
+
+
+class AuthError(GeneratorError):
+    fatal = True
+
+
+class InvalidRequestError(GeneratorError):
+    fatal = True
+
+
+class ModelUnavailableError(GeneratorError):
+    fatal = True
+
+
+class ParseError(GeneratorError):
+    pass
+
+
+class NetworkError(GeneratorError):
+    retryable = True
+
+
+class OverloadedError(GeneratorError):
+    """The provider is temporarily overloaded or unavailable."""
+
+    retryable = True
+
+
+class RequestTimeoutError(OverloadedError):
+    """The provider did not respond within the request timeout, retried and failed over like an overloaded one."""
+
+    pass
+
+
+class InvalidResponseError(GeneratorError):
+    """The provider returned a response without any choice, usually a transient error."""
+
+    retryable = True
+
+
+class ContentFilteredError(GeneratorError):
+    """The response has been blocked by the content filter of the provider."""
+
+    pass
+
+
+def _get_retry_after(error: Exception) -> float | None:
+    response = getattr(error, "response", None)
+    headers = getattr(response, "headers", None) or {}
+    try:
+        return float(headers.get("retry-after", ""))
+    except (TypeError, ValueError):
+        return None
+
+
+def to_generator_error(error: Exception) -> GeneratorError:
+    """Convert an error raised by litellm or by a provider client to a GeneratorError, keeping it as the cause."""
+
+    if isinstance(error, GeneratorError):
+        return error
+
+    message = str(error)
+    converted: GeneratorError
+
+    # the order matters as some litellm errors are subclasses of others
+    if isinstance(error, litellm.RateLimitError):  # type: ignore
+        converted = RateLimitedError(message, _get_retry_after(error))
+    elif isinstance(error, litellm.AuthenticationError | litellm.PermissionDeniedError):  # type: ignore
+        converted = AuthError(message)
+    elif isinstance(error, litellm.NotFoundError):  # type: ignore
+        converted = ModelUnavailableError(message)
+    elif isinstance(error, litellm.BadRequestError):  # type: ignore
+        converted = InvalidRequestError(message)
+    elif isinstance(error, litellm.Timeout):  # type: ignore
+        converted = RequestTimeoutError(message)
+    elif isinstance(error, litellm.APIConnectionError):  # type: ignore
+        converted = NetworkError(message)
+    elif isinstance(error, litellm.ServiceUnavailableError | litellm.InternalServerError):  # type: ignore
+        converted = OverloadedError(message)
+    elif isinstance(error, ConnectionError | TimeoutError):
+        converted = NetworkError(message)
+    elif isinstance(error, json.JSONDecodeError):
+        converted = ParseError(message)
+    else:
+        # generic provider errors can't be recovered from
+        converted = GeneratorError(message, fatal=isinstance(error, litellm.APIError))  # type: ignore
+
+    converted.__cause__ = error
+    return converted
@@ -0,0 +1,61 @@
+import json
+import unittest
+from types import SimpleNamespace
+
+import litellm
+
+from nerve.generation.errors import (
+    AuthError,
+    GeneratorError,
+    InvalidRequestError,
+    ModelUnavailableError,
+    NetworkError,
+    OverloadedError,
+    ParseError,
+    RateLimitedError,
+    RequestTimeoutError,
+    to_generator_error,
+)
+
+
+class TestToGeneratorError(unittest.TestCase):
+    def test_conversions(self) -> None:
+        cases: list[tuple[Exception, type[GeneratorError]]] = [
+            (litellm.RateLimitError("slow down"), RateLimitedError),  # type: ignore
+            (litellm.AuthenticationError("invalid key"), AuthError),  # type: ignore
+            (litellm.NotFoundError("no such model"), ModelUnavailableError),  # type: ignore
+            (litellm.BadRequestError("invalid messages"), InvalidRequestError),  # type: ignore
+            (litellm.APIConnectionError("connection refused"), NetworkError),  # type: ignore
+            (litellm.Timeout("request timed out"), RequestTimeoutError),  # type: ignore
+            (litellm.ServiceUnavailableError("overloaded"), OverloadedError),  # type: ignore
+            (json.JSONDecodeError("invalid", "{", 0), ParseError),
+        ]
+
+        for error, kind in cases:
+            converted = to_generator_error(error)
+            self.assertIs(type(converted), kind)
+            self.assertIs(converted.__cause__, error)
+
+    def test_fatal_and_retryable(self) -> None:
+        self.assertTrue(to_generator_error(litellm.AuthenticationError("invalid key")).fatal)  # type: ignore
+        self.assertTrue(to_generator_error(litellm.RateLimitError("slow down")).retryable)  # type: ignore
+        self.assertTrue(to_generator_error(litellm.APIError("provider error")).fatal)  # type: ignore
+
+        other = to_generator_error(ValueError("unexpected"))
+        self.assertIs(type(other), GeneratorError)
+        self.assertFalse(other.fatal)
+        self.assertFalse(other.retryable)
+
+    def test_retry_after(self) -> None:
+        error = litellm.RateLimitError("slow down")  # type: ignore
+        error.response = SimpleNamespace(headers={"retry-after": "12"})
+
+        converted = to_generator_error(error)
+
+        self.assertIsInstance(converted, RateLimitedError)
+        self.assertEqual(converted.retry_after, 12.0)  # type: ignore
+
+    def test_already_converted(self) -> None:
+        error = NetworkError("timeout")
+
+        self.assertIs(to_generator_error(error), error)
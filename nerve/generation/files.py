@@ -0,0 +1,79 @@
+import base64
+import io
+import typing as t
+
+from loguru import logger
+
+from nerve.defaults import DEFAULT_FILE_MAX_SIZE
+
+# maximum size in bytes of the files sent to the model, larger ones are replaced by a note
+max_size: int = DEFAULT_FILE_MAX_SIZE
+
+# types that can be decoded as text for the models that don't accept files
+_TEXT_TYPES = ("application/json", "application/xml", "application/x-yaml", "application/yaml")
+
+
+def _decode(part: dict[str, t.Any]) -> tuple[str, str, bytes] | None:
+    file = part.get("file")
+    if not isinstance(file, dict) or not isinstance(file.get("file_data"), str):
+        return None
+
+    url = file["file_data"]
+    if not url.startswith("data:") or ";base64," not in url:
+        return None
+
+    header, encoded = url.split(",", 1)
+    return str(file.get("filename") or "file"), header[5:].split(";")[0], base64.b64decode(encoded)
+
+
+def _extract_pdf_text(data: bytes) -> str | None:
+    try:
+        from pypdf import PdfReader
+    except ImportError:
+        logger.debug("pypdf is not installed, text can't be extracted from PDF files")
+        return None
+
+    try:
+        return "\n\n".join(page.extract_text() or "" for page in PdfReader(io.BytesIO(data)).pages)
+    except Exception as e:
+        logger.warning(f"can't extract text from PDF file: {e}")
+        return None
+
+
+def _to_text(name: str, mime_type: str, data: bytes) -> str:
+    text = None
+    if mime_type.startswith("text/") or mime_type in _TEXT_TYPES:
+        text = data.decode("utf-8", errors="replace")
+    elif mime_type == "application/pdf":
+        text = _extract_pdf_text(data)
+
+    if text is None:
+        return f"[file {name} ({mime_type}, {len(data)} bytes) can't be read by this model]"
+
+    return f"[text extracted from file {name}]\n\n{text}"
+
+
+def prepare_files(response: t.Any, supports_files: bool) -> t.Any:
+    """
+    Prepare the files of a tool response, if any: they are sent as they are to the models accepting files,
+    otherwise, or if they are too large, they are replaced by their text when it can be extracted.
+    """
+
+    if isinstance(response, list):
+        return [prepare_files(item, supports_files) for item in response]
+
+    if not isinstance(response, dict) or response.get("type") != "file":
+        return response
+
+    decoded = _decode(response)
+    if decoded is None:
+        return response
+
+    name, mime_type, data = decoded
+    if supports_files and len(data) <= max_size:
+        return response
+
+    if len(data) > max_size:
+        logger.warning(f"file {name} is larger than {max_size} bytes, sending its text instead")
+
+    return _to_text(name, mime_type, data)
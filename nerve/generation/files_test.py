@@ -0,0 +1,44 @@
+import unittest
+from unittest.mock import patch
+
+from nerve.generation import files
+from nerve.generation.files import prepare_files
+from nerve.tools.utils import file_part
+
+
+class TestPrepareFiles(unittest.TestCase):
+    def test_files_are_kept_for_models_accepting_them(self) -> None:
+        part = file_part(b"%PDF-1.7", "application/pdf", "report.pdf")
+
+        self.assertEqual(prepare_files(part, True), part)
+
+    def test_text_files_are_decoded_otherwise(self) -> None:
+        part = file_part(b"a,b\n1,2", "text/csv", "data.csv")
+
+        self.assertEqual(prepare_files(part, False), "[text extracted from file data.csv]\n\na,b\n1,2")
+
+    def test_unreadable_files_are_described(self) -> None:
+        part = file_part(b"\x00\x01", "application/octet-stream", "blob.bin")
+
+        self.assertEqual(
+            prepare_files([part, "text"], False),
+            ["[file blob.bin (application/octet-stream, 2 bytes) can't be read by this model]", "text"],
+        )
+
+    @patch("nerve.generation.files._extract_pdf_text", return_value="page one")
+    def test_pdf_text_is_extracted(self, _: object) -> None:
+        part = file_part(b"%PDF-1.7", "application/pdf", "report.pdf")
+
+        self.assertEqual(prepare_files(part, False), "[text extracted from file report.pdf]\n\npage one")
+
+    @patch.object(files, "max_size", 4)
+    def test_large_files_are_sent_as_text(self) -> None:
+        part = file_part(b'{"a": 1}', "application/json", "data.json")
+
+        self.assertEqual(prepare_files(part, True), '[text extracted from file data.json]\n\n{"a": 1}')
+
+    def test_other_responses_are_unchanged(self) -> None:
+        image = {"type": "image_url", "image_url": {"url": "data:image/png;base64,AA=="}}
+
+        self.assertEqual(prepare_files(image, False), image)
+        self.assertEqual(prepare_files("text", False), "text")
@@ -0,0 +1,103 @@
+import base64
+import hashlib
+import io
+import pathlib
+import typing as t
+
+from loguru import logger
+
+from nerve.defaults import DEFAULT_IMAGE_MAX_SIZE, DEFAULT_IMAGE_ORIGINALS_PATH, DEFAULT_IMAGE_QUALITY
+
+# maximum width and height of the images sent to the model, 0 to disable downscaling
+max_size: int = DEFAULT_IMAGE_MAX_SIZE
+# quality of the re-encoded JPEG images
+quality: int = DEFAULT_IMAGE_QUALITY
+# optional folder where the originals of the downscaled images are saved
+originals_path: str | None = DEFAULT_IMAGE_ORIGINALS_PATH
+
+
+def _save_original(data: bytes, mime_type: str) -> None:
+    if not originals_path:
+        return
+
+    folder = pathlib.Path(originals_path)
+    folder.mkdir(parents=True, exist_ok=True)
+    path = folder / f"{hashlib.sha256(data).hexdigest()[:16]}.{mime_type.split('/')[-1]}"
+    path.write_bytes(data)
+    logger.debug(f"original image saved to {path}")
+
+
+def downscale_image_url(url: str) -> str:
+    """
+    Downscale a data URL image to the maximum size, preserving the aspect ratio.
+    Any other URL, or an image that is already small enough, is returned as it is.
+    """
+
+    if max_size <= 0 or not url.startswith("data:image/") or ";base64," not in url:
+        return url
+
+    try:
+        from PIL import Image
+    except ImportError:
+        logger.debug("pillow is not installed, images won't be downscaled")
+        return url
+
+    header, encoded = url.split(",", 1)
+    mime_type = header[5:].split(";")[0]
+    data = base64.b64decode(encoded)
+
+    try:
+        image = Image.open(io.BytesIO(data))
+        image.load()
+    except Exception as e:
+        logger.warning(f"can't decode {mime_type} image: {e}")
+        return url
+
+    width, height = image.size
+    if max(width, height) <= max_size:
+        return url
+
+    _save_original(data, mime_type)
+
+    image.thumbnail((max_size, max_size), Image.Resampling.LANCZOS)
+
+    buffer = io.BytesIO()
+    if image.mode in ("RGBA", "LA", "P"):
+        # keep the transparency
+        image.save(buffer, format="PNG", optimize=True)
+        mime_type = "image/png"
+    else:
+        image.convert("RGB").save(buffer, format="JPEG", quality=quality, optimize=True)
+        mime_type = "image/jpeg"
+
+    logger.debug(f"image downscaled from {width}x{height} to {image.size[0]}x{image.size[1]}")
+
+    return f"data:{mime_type};base64,{base64.b64encode(buffer.getvalue()).decode()}"
+
+
+def downscale_images(response: t.Any) -> t.Any:
+    """Downscale the images of a tool response, if any."""
+
+    if isinstance(response, list):
+        return [downscale_images(item) for item in response]
+
+    if isinstance(response, dict) and response.get("type") == "image_url":
+        image_url = response.get("image_url")
+        if isinstance(image_url, dict) and isinstance(image_url.get("url"), str):
+            return {**response, "image_url": {**image_url, "url": downscale_image_url(image_url["url"])}}
+
+    return response
+
+
+async def describe_images(response: t.Any, describe: t.Callable[[str], t.Awaitable[str]]) -> t.Any:
+    """Replace the images of a tool response, if any, with their description, for the models without vision."""
+
+    if isinstance(response, list):
+        return [await describe_images(item, describe) for item in response]
+
+    if isinstance(response, dict) and response.get("type") == "image_url":
+        image_url = response.get("image_url")
+        if isinstance(image_url, dict) and isinstance(image_url.get("url"), str):
+            return await describe(image_url["url"])
+
+    return response
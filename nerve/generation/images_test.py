@@ -0,0 +1,72 @@
+import base64
+import importlib.util
+import io
+import pathlib
+import tempfile
+import unittest
+from unittest.mock import patch
+
+from nerve.generation import images
+
+HAS_PIL = importlib.util.find_spec("PIL") is not None
+
+
+def _image_url(width: int, height: int, mode: str = "RGB", format: str = "PNG") -> str:
+    from PIL import Image
+
+    buffer = io.BytesIO()
+    Image.new(mode, (width, height)).save(buffer, format=format)
+    return f"data:image/{format.lower()};base64,{base64.b64encode(buffer.getvalue()).decode()}"
+
+
+def _image_size(url: str) -> tuple[int, int]:
+    from PIL import Image
+
+    return Image.open(io.BytesIO(base64.b64decode(url.split(",", 1)[1]))).size  # type: ignore
+
+
+class TestDownscaleImages(unittest.TestCase):
+    def test_non_image_responses_are_untouched(self) -> None:
+        self.assertEqual(images.downscale_images("hello"), "hello")
+        self.assertEqual(images.downscale_images({"type": "text", "text": "hi"}), {"type": "text", "text": "hi"})
+
+    def test_remote_urls_are_untouched(self) -> None:
+        response = {"type": "image_url", "image_url": {"url": "https://example.com/image.png"}}
+
+        self.assertEqual(images.downscale_images(response), response)
+
+    @unittest.skipUnless(HAS_PIL, "pillow is not installed")
+    def test_large_images_are_downscaled(self) -> None:
+        response = {"type": "image_url", "image_url": {"url": _image_url(4000, 2000)}}
+
+        with patch.object(images, "max_size", 1000):
+            downscaled = images.downscale_images(response)
+
+        url = downscaled["image_url"]["url"]
+        self.assertTrue(url.startswith("data:image/jpeg;base64,"))
+        self.assertEqual(_image_size(url), (1000, 500))
+
+    @unittest.skipUnless(HAS_PIL, "pillow is not installed")
+    def test_transparency_is_preserved(self) -> None:
+        with patch.object(images, "max_size", 100):
+            url = images.downscale_image_url(_image_url(400, 200, mode="RGBA"))
+
+        self.assertTrue(url.startswith("data:image/png;base64,"))
+        self.assertEqual(_image_size(url), (100, 50))
+
+    @unittest.skipUnless(HAS_PIL, "pillow is not installed")
+    def test_small_images_are_untouched(self) -> None:
+        url = _image_url(640, 480)
+
+        with patch.object(images, "max_size", 1000):
+            self.assertEqual(images.downscale_image_url(url), url)
+
+    @unittest.skipUnless(HAS_PIL, "pillow is not installed")
+    def test_originals_are_saved(self) -> None:
+        with tempfile.TemporaryDirectory() as temp_dir:
+            with patch.object(images, "max_size", 100), patch.object(images, "originals_path", temp_dir):
+                images.downscale_image_url(_image_url(400, 200))
+
+            saved = list(pathlib.Path(temp_dir).iterdir())
+            self.assertEqual(len(saved), 1)
+            self.assertEqual(saved[0].suffix, ".png")
@@ -1,17 +1,51 @@
 import asyncio
 import json
+import math
 import traceback
 import typing as t
 import uuid
 
+import httpx
 import litellm
 from loguru import logger
 
-from nerve.generation import Engine, WindowStrategy
-from nerve.generation.conversation import SlidingWindowStrategy
+from nerve.defaults import DEFAULT_GENERATION_CACHE
+from nerve.generation import Engine, WindowStrategy, breaker, consensus, get_end_user_id
+from nerve.generation.cache import ResponseCache
+from nerve.generation.client import Client
+from nerve.generation.conversation import SlidingWindowStrategy, remove_duplicates
+from nerve.generation.errors import (
+    ContentFilteredError,
+    GeneratorError,
+    InvalidResponseError,
+    ModelUnavailableError,
+    OverloadedError,
+    RateLimitedError,
+    RequestTimeoutError,
+    to_generator_error,
+)
 from nerve.generation.ollama import OllamaGlue
-from nerve.models import Usage
-from nerve.runtime import state
+from nerve.generation.transform import MessageTransformer, transform_conversation
+from nerve.models import Configuration, Pricing, Usage
+from nerve.runtime import network, secrets, state
+
+_T = t.TypeVar("_T")
+
+
+class _Request:
+    """Options of a single request to the model."""
+
+    def __init__(self, plain: bool = False, sample: bool = False):
+        # requests that are not steps of the agent, like summaries, are sent without the response format,
+        # the stop sequences and the seed of the agent
+        self.plain = plain
+        # additional samples of the final answer, generated in parallel and never cached
+        self.sample = sample
+
+        # metadata of the response, the engine only reports the one of the requests that are not samples
+        self.truncated = False
+        self.logprobs: list[dict[str, t.Any]] | None = None
+        self.system_fingerprint: str | None = None
 
 
 def _convert_to_serializable(obj: t.Any) -> t.Any:
@@ -31,73 +65,697 @@ def _convert_to_serializable(obj: t.Any) -> t.Any:
         return obj
 
 
+def _without_binary_parts(message: dict[str, t.Any]) -> dict[str, t.Any]:
+    # images and documents would end up in the prompt as base64 text
+    content = message.get("content")
+    if not isinstance(content, list):
+        return message
+
+    parts = [
+        part
+        if not isinstance(part, dict) or part.get("type") == "text"
+        else {"type": "text", "text": f"<{part.get('type')} omitted>"}
+        for part in content
+    ]
+    return {**message, "content": parts}
+
+
+def _get_field(obj: t.Any, name: str) -> t.Any:
+    return obj.get(name) if isinstance(obj, dict) else getattr(obj, name, None)
+
+
+def _get_token_logprobs(choice: t.Any) -> list[dict[str, t.Any]]:
+    logprobs = _get_field(choice, "logprobs")
+    return [
+        {
+            "token": _get_field(item, "token"),
+            "logprob": _get_field(item, "logprob"),
+            "top_logprobs": [
+                {"token": _get_field(top, "token"), "logprob": _get_field(top, "logprob")}
+                for top in _get_field(item, "top_logprobs") or []
+            ],
+        }
+        for item in (_get_field(logprobs, "content") if logprobs is not None else None) or []
+    ]
+
+
+def _summarize_logprobs(tokens: list[dict[str, t.Any]]) -> dict[str, t.Any]:
+    values = [token["logprob"] for token in tokens if token["logprob"] is not None]
+    mean = sum(values) / len(values) if values else None
+    return {
+        "tokens": tokens,
+        "mean": mean,
+        "min": min(values) if values else None,
+        # geometric mean of the probabilities of the tokens, a rough estimate of how confident the model is
+        "confidence": math.exp(mean) if mean is not None else None,
+    }
+
+
+_SUMMARIZATION_PROMPT = (
+    "Summarize the following messages of a conversation between an agent and its tools, one JSON message per line. "
+    "Keep every fact, result and decision that is relevant to complete the task, and what is left to do."
+)
+
+# asked to continue a response truncated because of the output tokens limit
+_CONTINUATION_PROMPT = "Your response was truncated, continue exactly where you left off."
+_MAX_CONTINUATIONS = 3
+# how many times a response without choices is requested again
+_MAX_INVALID_RESPONSE_RETRIES = 2
+# how many times an overloaded provider is retried, with exponential backoff
+_MAX_OVERLOADED_RETRIES = 2
+# how many times a request exceeding the request timeout is retried
+_MAX_TIMEOUT_RETRIES = 2
+# sent back to the model when it returns an empty response
+_EMPTY_RESPONSE_FEEDBACK = "You returned no content, take an action or explain why you can not."
+# the tool_choice values supported by every provider, anything else is the name of a tool
+_TOOL_CHOICES = ("auto", "none", "required")
+# providers accepting a limited number of stop sequences
+_MAX_STOP_SEQUENCES = {"openai": 4, "azure": 4, "groq": 4}
+# providers accepting a maximum number of thinking tokens, the others only accept a reasoning effort
+_THINKING_BUDGET_PROVIDERS = ("anthropic", "bedrock", "vertex_ai", "gemini")
+# providers caching the prompt up to explicit cache_control breakpoints, the others cache it automatically if at all
+_CACHE_CONTROL_PROVIDERS = ("anthropic", "bedrock", "vertex_ai")
+_CACHE_CONTROL = {"type": "ephemeral"}
+# asks the vision generator to describe an image for a model that can't see it
+_DESCRIBE_IMAGE_PROMPT = (
+    "Describe this image for someone who can't see it. Transcribe any text exactly and report every detail "
+    "that could be relevant, like the layout, the elements and their state, without any introduction."
+)
+# sent back to the model when a truncated response is reported
+_TRUNCATION_FEEDBACK = (
+    "Your last response was truncated because it exceeded the maximum output length and has been ignored, "
+    "try again with a shorter response or split the work in smaller steps."
+)
+
+
 class LiteLLMEngine(Engine):
     def __init__(
         self,
         generator_id: str,
         window_strategy: WindowStrategy,
         tools: list[t.Callable[..., t.Any]] | None = None,
+        response_schema: dict[str, t.Any] | None = None,
+        pricing: dict[str, Pricing] | None = None,
+        transformers: list[MessageTransformer] | None = None,
+        on_truncation: t.Literal["report", "continue"] = "report",
+        stop: list[str] | None = None,
+        tool_choice: str | None = None,
+        max_empty_responses: int = 3,
+        seed: int | None = None,
+        reasoning_effort: t.Literal["low", "medium", "high"] | None = None,
+        thinking_budget: int | None = None,
+        loop_detection: Configuration.LoopDetection | None = None,
+        client: Client | None = None,
+        max_tool_output_length: int | None = None,
+        argument_templates: t.Literal["error", "empty"] | None = None,
+        logprobs: int | None = None,
+        self_consistency: Configuration.SelfConsistency | None = None,
+        end_user: str | None = None,
+        circuit_breaker: Configuration.CircuitBreaker | None = None,
+        prompt_caching: bool = True,
+        vision_generator: str | None = None,
+        endpoint: Configuration.Endpoint | None = None,
+        examples: list[Configuration.Example] | None = None,
+        request_timeout: Configuration.RequestTimeout | None = None,
     ):
-        super().__init__(generator_id, window_strategy, tools)
+        super().__init__(
+            generator_id,
+            window_strategy,
+            tools,
+            response_schema,
+            transformers,
+            loop_detection,
+            max_tool_output_length,
+            argument_templates,
+        )
+
+        # optional client replacing the providers supported via litellm
+        self.client = client
+
+        # optional OpenAI compatible endpoint, like an LLM gateway, replacing the provider of the model
+        if endpoint is not None:
+            self._use_endpoint(endpoint)
+
+        # optional seed for reproducible outputs, can also be set as a generator parameter
+        self.seed = seed if seed is not None else self.generator_params.pop("seed", None)
+        # identifies the backend configuration that generated the last response, if provided
+        self.system_fingerprint: str | None = None
+
+        # optional number of alternatives to request the log probabilities of, for each generated token
+        if logprobs is not None and not 0 <= logprobs <= 20:
+            raise ValueError(f"logprobs must be between 0 and 20, got {logprobs}")
+        self.logprobs = logprobs
+        # log probabilities of the tokens of the last response, if requested and returned by the provider
+        self.last_logprobs: list[dict[str, t.Any]] | None = None
+
+        # optional circuit breaker failing fast while the provider is down
+        self.breaker = (
+            breaker.get_breaker(self.generator_id, circuit_breaker.failures, circuit_breaker.cooldown)
+            if circuit_breaker
+            else None
+        )
+
+        # optional timeout of every request to the provider, so that a hung call doesn't stall the run
+        self.request_timeout = request_timeout
+
+        # optional few-shot examples, as user and assistant turns following the system prompt
+        self.examples = [
+            message
+            for example in examples or []
+            for message in (
+                {"role": "user", "content": example.user},
+                {"role": "assistant", "content": example.assistant},
+            )
+        ]
+
+        # optional sampling of the final answer multiple times
+        self.self_consistency = self_consistency
+
+        # how many consecutive empty responses are retried before failing the task
+        self.max_empty_responses = max_empty_responses
+        self.empty_responses = 0
+
+        # auto, none, required or the name of a tool to call at the first step
+        self.tool_choice = tool_choice or "auto"
+        if self.tool_choice not in _TOOL_CHOICES and self.tool_choice not in self.tools:
+            raise ValueError(f"tool_choice is set to {self.tool_choice} but there is no tool with this name")
+
+        # optional sequences where the model stops generating
+        self.stop = self._get_stop_sequences(stop or [])
+
+        # what to do when a response is truncated because of the output tokens limit
+        self.on_truncation = on_truncation
+        # set if the last generated response is truncated
+        self.truncated = False
+
+        # optional pricing of this model, if not set the cost from the litellm database is used
+        self.pricing: Pricing | None = (pricing or {}).get(self.generator_id)
 
         # until this is not fixed, ollama needs special treatment: https://github.com/BerriAI/litellm/issues/6353
         self.is_ollama = "ollama" in self.generator_id
         self.reduced_window_size = 25
+        network.configure_litellm()
+        # optional responses cache
+        self.cache: ResponseCache | None = ResponseCache() if DEFAULT_GENERATION_CACHE else None
+
+        # some models, like the o1 family, reject system messages
+        self.supports_system_prompt = self.is_ollama or self._supports_system_prompt()
+        # documents are passed as they are to custom clients and to the models accepting them
+        self.supports_file_input = client is not None or (not self.is_ollama and self._supports_file_input())
+        # images are described by the optional vision generator for the models that can't see them
+        self.supports_vision = client is not None or self.is_ollama or self._supports_vision()
+        self.vision_generator = vision_generator
+
+        # optional hashed identifier of the end user, for the providers monitoring abuses by user
+        self.user_params = self._get_user_params(end_user)
+
+        # provider specific parameters controlling how much reasoning models think
+        self.reasoning_params = self._get_reasoning_params(reasoning_effort, thinking_budget)
+
+        # the system prompt and the tools are the same at every step, caching them cuts the cost of the prompt
+        self.prompt_caching = prompt_caching and not self.is_ollama and self._supports_prompt_caching()
 
         if not self.is_ollama:
-            if self.tools and not litellm.supports_function_calling(model=self.generator_id):  # type: ignore
+            self.supports_tools = client is not None or bool(
+                litellm.supports_function_calling(model=self.generator_id)  # type: ignore
+            )
+            if self.tools and not self.supports_tools:
                 logger.warning(
                     f"model {self.generator_id} does not support function calling or not listed in litellm database"
                 )
         else:
-            self._ollama = OllamaGlue(self.api_base, self.generator_id, self.generator_params)
+            self._ollama = OllamaGlue(self.api_base, self.generator_id, self.generator_params, self.stop, self.seed)
 
-    async def _litellm_generate(
+        # native structured output, the response is validated in any case
+        self.response_format: dict[str, t.Any] | None = None
+        if self.response_schema is not None:
+            if not self.is_ollama and litellm.supports_response_schema(model=self.generator_id):  # type: ignore
+                self.response_format = {
+                    "type": "json_schema",
+                    "json_schema": {"name": "response", "schema": self.response_schema},
+                }
+            else:
+                logger.warning(f"model {self.generator_id} does not support response_format, ignoring it")
+
+    def _use_endpoint(self, endpoint: Configuration.Endpoint) -> None:
+        self.api_base = endpoint.base_url
+        if endpoint.no_auth:
+            # the openai client refuses to send requests without a key
+            self.api_key = "no-auth"
+            return
+
+        key_name = endpoint.api_key_env or "OPENAI_API_KEY"
+        api_key = secrets.get_provider().get(key_name)
+        if not api_key:
+            raise ValueError(f"the API key of the endpoint {endpoint.base_url} is not set, set {key_name} or no_auth")
+        self.api_key = api_key
+
+    def _supports_system_prompt(self) -> bool:
+        try:
+            info = litellm.get_model_info(model=self.generator_id)  # type: ignore
+        except Exception:
+            # models that are not in the litellm database are assumed to support it
+            return True
+
+        if info.get("supports_system_messages") is False:
+            logger.warning(f"model {self.generator_id} does not support system prompts, folding it into the user one")
+            return False
+
+        return True
+
+    def _supports_file_input(self) -> bool:
+        try:
+            return bool(litellm.supports_pdf_input(model=self.generator_id))  # type: ignore
+        except Exception:
+            # models that are not in the litellm database get the text of the files
+            return False
+
+    def _supports_prompt_caching(self) -> bool:
+        if self._get_provider() not in _CACHE_CONTROL_PROVIDERS:
+            return False
+
+        try:
+            return bool(litellm.supports_prompt_caching(model=self.generator_id))  # type: ignore
+        except Exception:
+            return False
+
+    def _add_cache_hints(
         self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
-    ) -> tuple[Usage, t.Any]:
+    ) -> tuple[list[dict[str, t.Any]], list[dict[str, t.Any]] | None]:
+        if not self.prompt_caching:
+            return conversation, tools_schema
+
+        # everything up to a breakpoint is cached: the tools come first, then the system prompt
+        if tools_schema:
+            tools_schema = [*tools_schema[:-1], {**tools_schema[-1], "cache_control": _CACHE_CONTROL}]
+
+        if conversation and conversation[0]["role"] == "system":
+            system_prompt, *rest = conversation
+            content = system_prompt["content"]
+            if isinstance(content, str):
+                content = [{"type": "text", "text": content}]
+            if content:
+                content = [*content[:-1], {**content[-1], "cache_control": _CACHE_CONTROL}]
+            conversation = [{**system_prompt, "content": content}, *rest]
+
+        return conversation, tools_schema
+
+    def _supports_vision(self) -> bool:
+        try:
+            info = litellm.get_model_info(model=self.generator_id)  # type: ignore
+        except Exception:
+            # models that are not in the litellm database are assumed to support it
+            return True
+
+        supported = bool(info.get("supports_vision"))
+
+        if not supported:
+            logger.warning(f"model {self.generator_id} does not support images, they will be described as text")
+        return supported
+
+    def _fold_system_prompt(self, conversation: list[dict[str, t.Any]]) -> list[dict[str, t.Any]]:
+        if self.supports_system_prompt or not conversation or conversation[0]["role"] != "system":
+            return conversation
+
+        # the system prompt is prepended to the first user message
+        system_prompt, user_message, *rest = conversation
+        content = user_message["content"]
+        if isinstance(content, list):
+            content = [{"type": "text", "text": system_prompt["content"]}, *content]
+        else:
+            content = f"{system_prompt['content']}\n\n{content}"
+
+        return [{**user_message, "content": content}, *rest]
+
+    def _get_instruction_conversation(self, system_prompt: str, prompt: str) -> list[dict[str, t.Any]]:
+        # requests that are not part of the history, like summaries and judgements
+        conversation = [{"role": "system", "content": system_prompt}, {"role": "user", "content": prompt}]
+        return self._fold_system_prompt(transform_conversation(conversation, self.transformers))
+
+    def _get_provider(self) -> str:
+        # models without a provider prefix are served by openai
+        return self.generator_id.split("/")[0] if "/" in self.generator_id else "openai"
+
+    def _get_user_params(self, end_user: str | None) -> dict[str, t.Any]:
+        if not end_user:
+            return {}
+
+        try:
+            supported = "user" in (litellm.get_supported_openai_params(model=self.generator_id) or [])  # type: ignore
+        except Exception:
+            supported = False
+
+        if not supported:
+            logger.debug(f"model {self.generator_id} does not support the end user identifier")
+            return {}
+
+        return {"user": get_end_user_id(end_user)}
+
+    def _get_logprobs_params(self) -> dict[str, t.Any]:
+        if self.logprobs is None:
+            return {}
+
+        return {"logprobs": True, **({"top_logprobs": self.logprobs} if self.logprobs > 0 else {})}
+
+    def _get_output_params(self) -> dict[str, t.Any]:
+        # the parameters shaping the responses of the agent
+        return {
+            **({"response_format": self.response_format} if self.response_format else {}),
+            **({"stop": self.stop} if self.stop else {}),
+            **({"seed": self.seed} if self.seed is not None else {}),
+            **self._get_logprobs_params(),
+        }
+
+    def _get_reasoning_params(self, reasoning_effort: str | None, thinking_budget: int | None) -> dict[str, t.Any]:
+        if reasoning_effort is None and thinking_budget is None:
+            return {}
+
+        if self.is_ollama or not litellm.supports_reasoning(model=self.generator_id):  # type: ignore
+            logger.warning(f"model {self.generator_id} does not support reasoning, ignoring the reasoning options")
+            return {}
+
+        provider = self._get_provider()
+        if thinking_budget is not None:
+            if provider in _THINKING_BUDGET_PROVIDERS:
+                return {"thinking": {"type": "enabled", "budget_tokens": thinking_budget}}
+
+            logger.warning(f"{provider} does not support a thinking budget, ignoring it")
+
+        return {"reasoning_effort": reasoning_effort} if reasoning_effort else {}
+
+    def _get_stop_sequences(self, stop: list[str]) -> list[str]:
+        provider = self._get_provider()
+        max_stop = _MAX_STOP_SEQUENCES.get(provider)
+        if max_stop is not None and len(stop) > max_stop:
+            logger.warning(f"{provider} supports up to {max_stop} stop sequences, ignoring {stop[max_stop:]}")
+            return stop[:max_stop]
+
+        return stop
+
+    def _get_tool_choice(self) -> str | dict[str, t.Any]:
+        if self.tool_choice in _TOOL_CHOICES:
+            return self.tool_choice
+
+        # a specific tool is only forced as the opening move, or the model would never be able to do anything else
+        if not self.history:
+            return {"type": "function", "function": {"name": self.tool_choice}}
+
+        return "auto"
+
+    def _get_timeout_params(self) -> dict[str, t.Any]:
+        if self.request_timeout is None:
+            return {}
+
+        return {"timeout": httpx.Timeout(self.request_timeout.total, connect=self.request_timeout.connect)}
+
+    async def _with_timeout(self, call: t.Awaitable[_T]) -> _T:
+        """Await a request to the provider, raising a RequestTimeoutError if it takes longer than the timeout."""
+
+        if self.request_timeout is None:
+            return await call
+
         try:
-            logger.debug(f"litellm.api_base: {self.api_base}")
-            logger.debug(f"litellm.conversation: {json.dumps(conversation, indent=2)}")
+            return await asyncio.wait_for(call, timeout=self.request_timeout.total)
+        except asyncio.TimeoutError as e:
+            error = RequestTimeoutError(f"{self.generator_id} did not respond within {self.request_timeout.total}s")
+            cause: Exception = e
+        except litellm.Timeout as e:  # type: ignore
+            # the connection timed out
+            error = RequestTimeoutError(str(e))
+            cause = e
+
+        state.on_event(
+            "request_timeout",
+            {
+                "generator": self.generator_id,
+                "connect": self.request_timeout.connect,
+                "total": self.request_timeout.total,
+                "error": str(error),
+            },
+        )
+        raise error from cause
+
+    async def _litellm_completion(
+        self,
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+        request: _Request | None = None,
+    ) -> tuple[Usage, t.Any, bool]:
+        request = request or _Request()
+        logger.debug(f"litellm.api_base: {self.api_base}")
+        logger.debug(f"litellm.conversation: {json.dumps(conversation, indent=2)}")
+
+        conversation, tools_schema = self._add_cache_hints(conversation, tools_schema)
 
-            # litellm.set_verbose = True
-            response = litellm.completion(
+        # litellm.set_verbose = True
+        # run in a thread so that the call can be interrupted if the run is cancelled
+        response = await self._with_timeout(
+            asyncio.to_thread(
+                litellm.completion,
                 model=self.generator_id,
                 messages=conversation,
                 tools=tools_schema,
-                tool_choice="auto" if tools_schema else None,
+                tool_choice=self._get_tool_choice() if tools_schema else None,
                 verbose=False,
                 api_base=self.api_base,
+                **({"api_key": self.api_key} if self.api_key else {}),
+                **self._get_timeout_params(),
+                **(self._get_output_params() if not request.plain else {}),
+                **self.user_params,
+                **self.reasoning_params,
                 **self.generator_params,
+                **(
+                    {"temperature": self.self_consistency.temperature}
+                    if request.sample and self.self_consistency
+                    else {}
+                ),
             )
+        )
 
-            logger.debug(f"litellm.response: {response}")
+        logger.debug(f"litellm.response: {response}")
 
-            return Usage(
-                prompt_tokens=response.usage.prompt_tokens,
-                completion_tokens=response.usage.completion_tokens,
-                total_tokens=response.usage.total_tokens,
-                cost=response._hidden_params.get("response_cost", None),
-            ), response.choices[0].message
-        except litellm.RateLimitError as e:  # type: ignore
-            logger.warning(f"rate limit exceeded, sleeping for 5 seconds: {e}")
-            await asyncio.sleep(5)
-            return await self._litellm_generate(conversation, tools_schema)
+        # returned by some providers on content filtering or internal errors
+        if not getattr(response, "choices", None):
+            raise InvalidResponseError("the model returned a response without choices")
+
+        finish_reason = getattr(response.choices[0], "finish_reason", None)
+        if finish_reason == "content_filter":
+            raise ContentFilteredError(
+                "the response has been blocked by the content filter of the provider, "
+                "rephrase the prompt or use a different model"
+            )
+
+        # changes when the provider updates the backend, responses generated with the same seed might differ
+        fingerprint = getattr(response, "system_fingerprint", None)
+        if fingerprint and self.system_fingerprint and fingerprint != self.system_fingerprint:
+            logger.warning(f"system fingerprint changed from {self.system_fingerprint} to {fingerprint}")
+        if fingerprint:
+            request.system_fingerprint = fingerprint
+            if not request.sample:
+                self.system_fingerprint = fingerprint
+
+        if self.logprobs is not None and not request.plain:
+            # continuations add to the tokens of the same response
+            request.logprobs = (request.logprobs or []) + _get_token_logprobs(response.choices[0])
+            if not request.sample:
+                self.last_logprobs = request.logprobs
+
+        # reported separately by some providers
+        details = getattr(response.usage, "completion_tokens_details", None)
+        prompt_details = getattr(response.usage, "prompt_tokens_details", None)
+        usage = Usage(
+            prompt_tokens=response.usage.prompt_tokens,
+            completion_tokens=response.usage.completion_tokens,
+            total_tokens=response.usage.total_tokens,
+            reasoning_tokens=getattr(details, "reasoning_tokens", None) or 0,
+            # anthropic style fields first, then the openai style ones
+            cache_read_tokens=getattr(response.usage, "cache_read_input_tokens", None)
+            or getattr(prompt_details, "cached_tokens", None)
+            or 0,
+            cache_write_tokens=getattr(response.usage, "cache_creation_input_tokens", None) or 0,
+            cost=response._hidden_params.get("response_cost", None),
+        )
+        # the maximum number of output tokens has been reached
+        truncated = finish_reason == "length"
+
+        return usage, response.choices[0].message, truncated
+
+    async def _litellm_generate(
+        self,
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+        request: _Request | None = None,
+        attempt: int = 0,
+        timeouts: int = 0,
+    ) -> tuple[Usage, t.Any]:
+        request = request or _Request()
+        try:
+            usage, message, truncated = await self._litellm_completion(conversation, tools_schema, request)
+
+            # partial tool calls can't be continued
+            continuations = 0
+            while (
+                truncated
+                and self.on_truncation == "continue"
+                and not message.tool_calls
+                and continuations < _MAX_CONTINUATIONS
+            ):
+                logger.warning("response truncated, asking the model to continue")
+                continuation = [
+                    *conversation,
+                    {"role": "assistant", "content": message.content or ""},
+                    {"role": "user", "content": _CONTINUATION_PROMPT},
+                ]
+                more_usage, more, truncated = await self._litellm_completion(continuation, tools_schema, request)
+                usage += more_usage
+                message.content = (message.content or "") + (more.content or "")
+                continuations += 1
+
+            request.truncated = truncated
+            if not request.sample:
+                self.truncated = truncated
+
+            return usage, message
+        except Exception as e:
+            error = to_generator_error(e)
+            if isinstance(error, InvalidResponseError) and attempt < _MAX_INVALID_RESPONSE_RETRIES:
+                state.on_event(
+                    "invalid_response",
+                    {"generator": self.generator_id, "error": str(error), "attempt": attempt + 1},
+                )
+                return await self._litellm_generate(conversation, tools_schema, request, attempt + 1, timeouts)
+
+            # timeouts have their own retries, so that they don't use the ones of the overloaded provider
+            if isinstance(error, RequestTimeoutError):
+                if timeouts >= _MAX_TIMEOUT_RETRIES:
+                    raise
+
+                logger.warning(f"{self.generator_id} timed out, retrying: {e}")
+                return await self._litellm_generate(conversation, tools_schema, request, attempt, timeouts + 1)
+
+            if isinstance(error, OverloadedError) and attempt < _MAX_OVERLOADED_RETRIES:
+                delay = 2**attempt
+                logger.warning(f"{self.generator_id} is overloaded, retrying in {delay} seconds: {e}")
+                await asyncio.sleep(delay)
+                return await self._litellm_generate(conversation, tools_schema, request, attempt + 1, timeouts)
+
+            if not isinstance(error, RateLimitedError):
+                raise
+
+            delay = error.retry_after or 5
+            logger.warning(f"rate limit exceeded, sleeping for {delay} seconds: {e}")
+            await asyncio.sleep(delay)
+            return await self._litellm_generate(conversation, tools_schema, request)
 
     async def _generate(
-        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+        self,
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+        sample_answer: bool = False,
+        plain: bool = False,
     ) -> tuple[Usage, t.Any]:
-        logger.debug(f"tools schema: {json.dumps(tools_schema, indent=2)}")
-        if self.is_ollama:
-            # https://github.com/BerriAI/litellm/issues/6353
-            return await self._ollama.generate(conversation, tools_schema)
+        self.truncated = False
+        self.last_logprobs = None
+
+        replay = state.get_replay()
+        if replay is not None:
+            # re-execute a recorded run without calling the model, the trace contains the chosen answer
+            usage, message = replay.next_generation()
         else:
-            return await self._litellm_generate(conversation, tools_schema)
+            usage, message = await self._generate_or_get_cached(conversation, tools_schema, _Request(plain=plain))
+            if sample_answer and self.self_consistency is not None and self.self_consistency.samples > 1:
+                # only complete final answers are sampled again
+                answer = (
+                    consensus.get_final_answer(message, self.response_schema is not None)
+                    if not self.truncated
+                    else None
+                )
+                if answer is not None:
+                    usage, message = await self._get_consensus(conversation, tools_schema, usage, message, answer)
+
+        # record the response so that the run can be replayed
+        state.on_event(
+            "generation",
+            {
+                "generator": self.generator_id,
+                "usage": usage,
+                "message": _convert_to_serializable(message),
+                "system_fingerprint": self.system_fingerprint,
+                **({"logprobs": _summarize_logprobs(self.last_logprobs)} if self.last_logprobs else {}),
+            },
+        )
+
+        return usage, message
+
+    async def _generate_or_get_cached(
+        self,
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+        request: _Request | None = None,
+    ) -> tuple[Usage, t.Any]:
+        logger.debug(f"tools schema: {json.dumps(tools_schema, indent=2)}")
+
+        request = request or _Request()
+        cache_key = None
+        # cached samples would all be the same
+        if self.cache is not None and not request.sample:
+            output_params = {
+                "response_format": self.response_format,
+                "stop": self.stop,
+                "seed": self.seed,
+                **self._get_logprobs_params(),
+            }
+            params = {
+                **self.generator_params,
+                **(output_params if not request.plain else {"plain": True}),
+                **self.reasoning_params,
+                "tool_choice": self._get_tool_choice(),
+            }
+            cache_key = self.cache.get_key(self.generator_id, params, conversation, tools_schema)
+            cached = self.cache.get(cache_key)
+            if cached is not None:
+                state.on_event("generation_cache_hit", {"generator": self.generator_id, "key": cache_key})
+                return cached
+
+        probe = self.breaker.before_call() if self.breaker is not None else False
+
+        try:
+            if self.client is not None:
+                usage, message = await self._with_timeout(self.client.generate(conversation, tools_schema))
+            elif self.is_ollama:
+                # https://github.com/BerriAI/litellm/issues/6353
+                usage, message = await self._with_timeout(
+                    self._ollama.generate(conversation, tools_schema, plain=request.plain)
+                )
+            else:
+                usage, message = await self._litellm_generate(conversation, tools_schema, request)
+        except Exception as e:
+            if self.breaker is not None:
+                self.breaker.on_error(to_generator_error(e))
+            raise
+        finally:
+            if probe and self.breaker is not None:
+                # a cancelled probe must not refuse every later call
+                self.breaker.probing = False
+
+        if self.breaker is not None:
+            self.breaker.on_success()
+
+        if self.pricing is not None:
+            usage.cost = self.pricing.get_cost(usage)
+
+        if cache_key is not None and message is not None:
+            self.cache.set(cache_key, usage, _convert_to_serializable(message))  # type: ignore
+
+        return usage, message
 
     async def _get_conversation(
         self, system_prompt: str | None, user_prompt: str, extra_message: str | None
     ) -> list[dict[str, t.Any]]:
         # @ system prompt and user prompt always included
         conversation = [{"role": "system", "content": system_prompt}] if system_prompt else []
+        # examples are not part of the history, so they're never trimmed
+        conversation.extend(self.examples)
         conversation.append({"role": "user", "content": user_prompt})
         conversation.extend(await self.window_strategy.get_window(self.history))
 
@@ -106,7 +764,9 @@ class LiteLLMEngine(Engine):
 
         logger.debug(f"{self.window_strategy} | conv size: {len(conversation)}")
 
-        return conversation
+        # single place where everything is sent to the model, regardless of the provider
+        conversation = remove_duplicates(conversation)
+        return self._fold_system_prompt(transform_conversation(conversation, self.transformers))
 
     async def _generate_next_message(
         self,
@@ -124,7 +784,7 @@ class LiteLLMEngine(Engine):
         try:
             # TODO: implement forced rate limit
             # get next message
-            return await self._generate(conversation, tools_schema)
+            return await self._generate(conversation, tools_schema, sample_answer=True)
 
         except litellm.ContextWindowExceededError as e:  # type: ignore
             logger.debug(e)
@@ -141,34 +801,220 @@ class LiteLLMEngine(Engine):
                     completion_tokens=0,
                     total_tokens=0,
                 ), None
-        except litellm.AuthenticationError as e:  # type: ignore
-            logger.error(e)
-            exit(1)
-        except litellm.NotFoundError as e:  # type: ignore
-            logger.error(e)
-            exit(1)
-        except litellm.BadRequestError as e:  # type: ignore
-            logger.error(e)
-            # logger.error(f"{traceback.format_exc()}")
-            exit(1)
-        except litellm.APIError as e:  # type: ignore
-            logger.error(e)
-            logger.error(f"{traceback.format_exc()}")
-            exit(1)
+        except Exception as e:
+            error = to_generator_error(e)
+            if self.failover and isinstance(error, OverloadedError | ModelUnavailableError):
+                # the run continues with another generator
+                raise error
+
+            if isinstance(error, breaker.CircuitOpenError):
+                # rather than failing every step, wait for the provider to be probed again
+                logger.warning(f"{error}, waiting {error.retry_in:.0f}s")
+                await asyncio.sleep(error.retry_in)
+                return Usage(prompt_tokens=0, completion_tokens=0, total_tokens=0), None
+
+            logger.error(error)
+            if type(error) is GeneratorError:
+                # unexpected error
+                logger.error(f"{traceback.format_exc()}")
+
+            # authentication errors, invalid requests and so on can't be recovered from
+            if error.fatal:
+                exit(1)
+
             return Usage(
                 prompt_tokens=0,
                 completion_tokens=0,
                 total_tokens=0,
             ), None
 
+    async def _get_consensus(
+        self,
+        conversation: list[dict[str, t.Any]],
+        tools_schema: list[dict[str, t.Any]] | None,
+        usage: Usage,
+        message: t.Any,
+        answer: consensus.Answer,
+    ) -> tuple[Usage, t.Any]:
+        config = t.cast(Configuration.SelfConsistency, self.self_consistency)
+
+        async def get_sample() -> tuple[Usage, t.Any, _Request]:
+            # each sample carries its own metadata, so that they don't overwrite each other
+            request = _Request(sample=True)
+            sample_usage, sample = await self._generate_or_get_cached(conversation, tools_schema, request)
+            return sample_usage, sample, request
+
+        samples = await asyncio.gather(*(get_sample() for _ in range(config.samples - 1)), return_exceptions=True)
+
+        messages, answers, metadata = [message], [answer], [(self.last_logprobs, self.system_fingerprint)]
+        for result in samples:
+            if isinstance(result, BaseException):
+                if not isinstance(result, Exception):
+                    raise result
+                logger.warning(f"can't sample an answer: {result}")
+                continue
+
+            # every sample counts, even the ones that are discarded
+            sample_usage, sample, request = result
+            usage += sample_usage
+            if request.truncated:
+                continue
+
+            sample_answer = consensus.get_final_answer(sample, self.response_schema is not None)
+            if sample_answer is not None:
+                messages.append(sample)
+                answers.append(sample_answer)
+                metadata.append((request.logprobs, request.system_fingerprint or self.system_fingerprint))
+
+        strategy = config.strategy
+        if strategy == "auto":
+            strategy = "vote" if answer.structured else "judge"
+
+        chosen, majority = consensus.vote(answers)
+        votes: int | None = majority
+        if strategy == "judge" and len(answers) > 1:
+            judge = self._get_instruction_conversation(consensus.JUDGE_PROMPT, consensus.get_judge_prompt(answers))
+            # not recorded as a generation, the trace only contains the chosen answer
+            judge_usage, reply = await self._generate_or_get_cached(judge, None, _Request(plain=True))
+            usage += judge_usage
+            choice = consensus.parse_judge_choice(str(reply.content or "") if reply is not None else "", len(answers))
+            if choice is not None:
+                chosen, votes = choice, None
+
+        state.on_event(
+            "self_consistency",
+            {
+                "generator": self.generator_id,
+                "samples": config.samples,
+                "answers": len(answers),
+                "strategy": strategy,
+                "chosen": chosen,
+                "votes": votes,
+                "usage": usage,
+            },
+        )
+
+        # the engine reports the metadata of the chosen answer
+        self.truncated = False
+        self.last_logprobs, self.system_fingerprint = metadata[chosen]
+
+        return usage, messages[chosen]
+
+    def _get_history_message(self, message: t.Any) -> dict[str, t.Any]:
+        serializable = _convert_to_serializable(message)
+        # the reasoning trace must not be sent back to the model
+        # https://api-docs.deepseek.com/guides/reasoning_model
+        if isinstance(serializable, dict):
+            serializable.pop("reasoning_content", None)
+        return serializable  # type: ignore
+
+    async def summarize_history(self, num_messages: int) -> Usage:
+        split = self._get_summary_split(num_messages)
+        if split == 0:
+            return Usage()
+
+        messages = "\n".join(
+            json.dumps(_without_binary_parts(message), default=str) for message in self.history[:split]
+        )
+        conversation = self._get_instruction_conversation(_SUMMARIZATION_PROMPT, messages)
+
+        usage, message = await self._generate(conversation, None, plain=True)
+        summary = str(message.content) if message is not None else ""
+        if not summary:
+            logger.warning("empty summary, history not summarized")
+            return usage
+
+        self.history[:split] = [{"role": "user", "content": f"<summary of {split} earlier messages>\n{summary}"}]
+
+        state.on_event("history_summarized", {"generator": self.generator_id, "messages": split, "summary": summary})
+
+        return usage
+
+    async def health_check(self) -> None:
+        try:
+            if self.client is not None:
+                await self._with_timeout(self.client.health_check())
+            elif self.is_ollama:
+                await self._with_timeout(self._ollama.health_check())
+            else:
+                # the cheapest request that needs a valid key and an existing model
+                await self._with_timeout(
+                    asyncio.to_thread(
+                        litellm.completion,
+                        model=self.generator_id,
+                        messages=[{"role": "user", "content": "ping"}],
+                        max_tokens=1,
+                        verbose=False,
+                        api_base=self.api_base,
+                        **({"api_key": self.api_key} if self.api_key else {}),
+                        **self._get_timeout_params(),
+                    )
+                )
         except Exception as e:
-            logger.error(e)
-            logger.error(f"{traceback.format_exc()}")
-            return Usage(
-                prompt_tokens=0,
-                completion_tokens=0,
-                total_tokens=0,
-            ), None
+            raise to_generator_error(e) from e
+
+    async def describe_image(self, url: str) -> str:
+        if not self.vision_generator:
+            return await super().describe_image(url)
+
+        api_key = secrets.get_api_key(self.vision_generator)
+        try:
+            response = await self._with_timeout(
+                asyncio.to_thread(
+                    litellm.completion,
+                    model=self.vision_generator,
+                    messages=[
+                        {
+                            "role": "user",
+                            "content": [
+                                {"type": "text", "text": _DESCRIBE_IMAGE_PROMPT},
+                                {"type": "image_url", "image_url": {"url": url}},
+                            ],
+                        }
+                    ],
+                    verbose=False,
+                    **({"api_key": api_key} if api_key else {}),
+                    **self._get_timeout_params(),
+                )
+            )
+        except Exception as e:
+            logger.warning(f"can't describe image with {self.vision_generator}: {e}")
+            return f"[the tool returned an image that can't be described: {e}]"
+
+        # the request is not part of the step of the agent
+        state.update_usage(
+            Usage(
+                prompt_tokens=response.usage.prompt_tokens,
+                completion_tokens=response.usage.completion_tokens,
+                total_tokens=response.usage.total_tokens,
+                cost=response._hidden_params.get("response_cost", None),
+            )
+        )
+
+        description = response.choices[0].message.content or "no description"
+        return f"[the tool returned an image, described by {self.vision_generator}]\n\n{description}"
+
+    async def complete(self, system_prompt: str, prompt: str) -> tuple[Usage, str]:
+        conversation = self._get_instruction_conversation(system_prompt, prompt)
+
+        usage, message = await self._generate(conversation, None, plain=True)
+
+        return usage, str(message.content or "") if message is not None else ""
+
+    def _on_empty_response(self) -> None:
+        self.empty_responses += 1
+        state.on_event(
+            "empty_response",
+            {"generator": self.generator_id, "count": self.empty_responses, "max": self.max_empty_responses},
+        )
+
+        if self.empty_responses > self.max_empty_responses:
+            state.set_task_failed(f"the model returned {self.empty_responses} empty responses in a row")
+            return
+
+        # nudge the model towards either an action or a final answer, the empty turn itself is not added since
+        # most providers reject assistant messages without content
+        self.history.append({"role": "user", "content": _EMPTY_RESPONSE_FEEDBACK})
 
     async def step(
         self,
@@ -184,27 +1030,56 @@ class LiteLLMEngine(Engine):
         if message is None:
             return usage
 
+        # reasoning models like deepseek-reasoner return their chain of thought separately
+        reasoning = getattr(message, "reasoning_content", None)
+        if reasoning:
+            state.on_event("thinking", {"generator": self.generator_id, "thinking": reasoning})
+
         # collect responses
         has_tools = len(extra_tools) > 0 or len(self.tools) > 0
         responses: list[dict[str, t.Any]] = []
 
-        if has_tools and not message.tool_calls:
+        if self.truncated:
+            # never act on a partial response, tool call arguments are likely invalid
+            state.on_event(
+                "response_truncated",
+                {"generator": self.generator_id, "content": message.content, "tool_calls": bool(message.tool_calls)},
+            )
+            self.history.append({"role": "assistant", "content": message.content or ""})
+            self.history.append({"role": "user", "content": _TRUNCATION_FEEDBACK})
+            return usage
+
+        if not message.tool_calls and not (message.content or "").strip():
+            self._on_empty_response()
+            return usage
+
+        self.empty_responses = 0
+
+        if self.response_schema is not None and not message.tool_calls:
+            # validate the final structured response
+            responses = self._get_structured_response(str(message.content))
+
+        elif has_tools and not message.tool_calls:
             # no tool calls, just return the text response
             responses = [self._get_text_response(str(message.content))]
 
         elif message.tool_calls:
             logger.debug(message.tool_calls)
+            calls = [
+                (
+                    tool_call.id if hasattr(tool_call, "id") else str(uuid.uuid4()),
+                    tool_call.function.name or "",
+                    tool_call.function.arguments,
+                )
+                for tool_call in message.tool_calls
+            ]
+            # let the observers know about every call before the first one is executed
+            self._on_tool_calls_planned(calls)
+
             # for each tool call
-            for tool_call in message.tool_calls:
+            for call_id, tool_name, tool_args in calls:
                 # resolve and execute the tool call
-                responses.extend(
-                    await self._process_tool_call(
-                        tool_call.id if hasattr(tool_call, "id") else str(uuid.uuid4()),
-                        tool_call.function.name or "",
-                        tool_call.function.arguments,
-                        extra_tools,
-                    )
-                )
+                responses.extend(await self._process_tool_call(call_id, tool_name, tool_args, extra_tools))
 
                 # break early from multiple tool calls if the task is complete
                 if state.is_active_task_done():
@@ -213,7 +1088,7 @@ class LiteLLMEngine(Engine):
 
         # add tool call + per-call response messages
         # https://github.com/evilsocket/nerve/issues/41
-        self.history.append(_convert_to_serializable(message))
+        self.history.append(self._get_history_message(message))
         self.history.extend(responses)
 
         return usage
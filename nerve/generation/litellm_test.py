@@ -0,0 +1,1430 @@
+import asyncio
+import json
+import os
+import threading
+import time
+import typing as t
+import unittest
+from types import SimpleNamespace
+from unittest.mock import AsyncMock, MagicMock, patch
+
+import httpx
+import litellm
+
+from nerve.generation import breaker
+from nerve.generation.cache import to_namespace
+from nerve.generation.client import Client
+from nerve.generation.conversation import FullHistoryStrategy, SlidingWindowStrategy
+from nerve.generation.errors import ContentFilteredError, NetworkError, RequestTimeoutError
+from nerve.generation.litellm import LiteLLMEngine
+from nerve.generation.testing import ScriptedClient, text, tool_call
+from nerve.models import Configuration, Pricing, Usage
+
+SCHEMA = {
+    "type": "object",
+    "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+    "required": ["name", "age"],
+}
+
+
+def _text_message(content: str) -> SimpleNamespace:
+    return SimpleNamespace(role="assistant", content=content, tool_calls=None)
+
+
+@patch("nerve.generation.state")
+class TestStructuredResponse(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        self.engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), response_schema=SCHEMA)
+
+    def test_response_format(self, _: MagicMock) -> None:
+        self.assertEqual(self.engine.response_format["type"], "json_schema")  # type: ignore
+        self.assertEqual(self.engine.response_format["json_schema"]["schema"], SCHEMA)  # type: ignore
+
+    async def test_valid_response_completes_the_task(self, mock_state: MagicMock) -> None:
+        self.engine._generate_next_message = AsyncMock(  # type: ignore
+            return_value=(Usage(), _text_message('{"name": "Bob", "age": 42}'))
+        )
+
+        await self.engine.step(None, "who are you?")
+
+        mock_state.set_task_complete.assert_called_once_with('{"name": "Bob", "age": 42}', {"name": "Bob", "age": 42})
+        self.assertEqual(len(self.engine.history), 1)
+
+    async def test_invalid_response_is_retried(self, mock_state: MagicMock) -> None:
+        for content, error in (
+            ('{"name": "Bob"}', "'age' is a required property"),
+            ('{"name": "Bob", "age": "old"}', "(at /age)"),
+            ("my name is Bob", "not valid JSON"),
+        ):
+            self.engine.history.clear()
+            self.engine._generate_next_message = AsyncMock(return_value=(Usage(), _text_message(content)))  # type: ignore
+
+            await self.engine.step(None, "who are you?")
+
+            mock_state.set_task_complete.assert_not_called()
+            self.assertEqual(self.engine.history[-1]["role"], "user")
+            self.assertIn(error, self.engine.history[-1]["content"])
+
+
+@patch("nerve.generation.litellm.state")
+class TestReasoningContent(unittest.IsolatedAsyncioTestCase):
+    async def test_reasoning_is_emitted_and_not_sent_back(self, mock_state: MagicMock) -> None:
+        mock_state.is_active_task_done.return_value = False
+        engine = LiteLLMEngine("deepseek/deepseek-reasoner", FullHistoryStrategy())
+        message = SimpleNamespace(role="assistant", content="42", reasoning_content="6 times 7 is 42", tool_calls=None)
+        engine._generate_next_message = AsyncMock(return_value=(Usage(), message))  # type: ignore
+
+        await engine.step(None, "what is 6 times 7?")
+
+        mock_state.on_event.assert_any_call(
+            "thinking", {"generator": "deepseek/deepseek-reasoner", "thinking": "6 times 7 is 42"}
+        )
+        self.assertEqual(engine.history, [{"role": "assistant", "content": "42", "tool_calls": None}])
+
+    async def test_models_without_reasoning(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+        engine._generate_next_message = AsyncMock(  # type: ignore
+            return_value=(Usage(), SimpleNamespace(role="assistant", content="42", tool_calls=None))
+        )
+
+        await engine.step(None, "what is 6 times 7?")
+
+        mock_state.on_event.assert_not_called()
+        self.assertEqual(engine.history, [{"role": "assistant", "content": "42", "tool_calls": None}])
+
+
+class TestPricing(unittest.IsolatedAsyncioTestCase):
+    async def test_pricing_overrides_the_litellm_cost(self) -> None:
+        engine = LiteLLMEngine(
+            "openai/my-finetune?temperature=0.5",
+            FullHistoryStrategy(),
+            pricing={"openai/my-finetune": Pricing(input=0.01, output=0.03)},
+        )
+        engine._litellm_generate = AsyncMock(  # type: ignore
+            return_value=(Usage(cost=None, prompt_tokens=2000, completion_tokens=1000), _text_message("hi"))
+        )
+
+        usage, _ = await engine._generate([], None)
+
+        self.assertAlmostEqual(usage.cost, 0.05)  # type: ignore
+
+    async def test_unknown_models_have_no_cost(self) -> None:
+        engine = LiteLLMEngine(
+            "openai/my-finetune", FullHistoryStrategy(), pricing={"openai/gpt-4o": Pricing(input=1, output=1)}
+        )
+        engine._litellm_generate = AsyncMock(  # type: ignore
+            return_value=(Usage(cost=None, prompt_tokens=2000, completion_tokens=1000), _text_message("hi"))
+        )
+
+        usage, _ = await engine._generate([], None)
+
+        self.assertIsNone(usage.cost)
+
+
+@patch("nerve.generation.state")
+class TestToolCallValidation(unittest.IsolatedAsyncioTestCase):
+    async def test_invalid_arguments_are_reported_to_the_model(self, mock_state: MagicMock) -> None:
+        mock_state.is_cancelled.return_value = False
+        mock_state.get_replay.return_value = None
+        called = []
+
+        def read_file(path: str) -> str:
+            """Read a file."""
+            called.append(path)
+            return "content"
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_file])
+
+        for args, error in (('{"file": "a.txt"}', "missing a required argument: 'path'"), ("{path", "not valid JSON")):
+            responses = await engine._process_tool_call("call_1", "read_file", args, {})
+
+            self.assertEqual(len(responses), 1)
+            self.assertIn("ERROR: invalid arguments for tool read_file", responses[0]["content"])
+            self.assertIn(error, responses[0]["content"])
+
+        self.assertEqual(called, [])
+        self.assertEqual(mock_state.on_event.call_args[0][0], "invalid_tool_call")
+
+        responses = await engine._process_tool_call("call_2", "read_file", '{"path": "a.txt"}', {})
+        self.assertEqual(responses[0]["content"], "content")
+
+    async def test_malformed_arguments_are_repaired(self, mock_state: MagicMock) -> None:
+        mock_state.is_cancelled.return_value = False
+        mock_state.get_replay.return_value = None
+
+        def read_file(path: str) -> str:
+            """Read a file."""
+            return f"content of {path}"
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_file])
+
+        responses = await engine._process_tool_call("call_1", "read_file", '{"path": "a.txt",', {})
+
+        self.assertEqual(responses[0]["content"], "content of a.txt")
+
+    async def test_aborting_tools_do_not_end_the_run(self, mock_state: MagicMock) -> None:
+        mock_state.is_cancelled.return_value = False
+        mock_state.get_replay.return_value = None
+
+        def read_file(path: str) -> str:
+            """Read a file."""
+            raise SystemExit(3)
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_file])
+
+        responses = await engine._process_tool_call("call_1", "read_file", '{"path": "a.txt"}', {})
+
+        self.assertRegex(
+            responses[0]["content"],
+            r"ERROR while executing tool read_file: tool aborted with SystemExit\(3\) at .+litellm_test\.py:\d+",
+        )
+        self.assertEqual(mock_state.on_event.call_args[0][0], "tool_error")
+
+    async def test_interruptions_are_not_caught(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+
+        def read_file(path: str) -> str:
+            """Read a file."""
+            raise KeyboardInterrupt()
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_file])
+
+        with self.assertRaises(KeyboardInterrupt):
+            await engine._get_tool_response("call_1", "read_file", read_file, {"path": "a.txt"})
+
+    async def test_blocking_tools_can_be_cancelled(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+
+        def read_file(path: str) -> str:
+            """Read a file."""
+            time.sleep(2)
+            return "content"
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_file])
+
+        started_at = time.monotonic()
+        with self.assertRaises(asyncio.TimeoutError):
+            await asyncio.wait_for(engine._get_tool_response("call_1", "read_file", read_file, {"path": "a.txt"}), 0.1)
+
+        self.assertLess(time.monotonic() - started_at, 1.5)
+
+
+def screenshot() -> dict[str, t.Any]:
+    """Take a screenshot."""
+    return {"type": "image_url", "image_url": {"url": "data:image/png;base64,cG5n"}}
+
+
+@patch("nerve.generation.litellm.litellm.get_model_info", return_value={"supports_vision": False})
+@patch("nerve.generation.state")
+class TestImagesOnTextOnlyModels(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.state")
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_images_are_described_by_the_vision_generator(
+        self, mock_completion: MagicMock, mock_litellm_state: MagicMock, mock_state: MagicMock, _: MagicMock
+    ) -> None:
+        mock_state.is_cancelled.return_value = False
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=100, completion_tokens=10, total_tokens=110),
+            choices=[SimpleNamespace(message=_text_message("a login form"), finish_reason="stop")],
+            _hidden_params={"response_cost": 0.01},
+        )
+        engine = LiteLLMEngine(
+            "deepseek/deepseek-chat", FullHistoryStrategy(), [screenshot], vision_generator="openai/gpt-4o"
+        )
+
+        responses = await engine._process_tool_call("call_1", "screenshot", {}, {})
+
+        self.assertFalse(engine.supports_vision)
+        self.assertEqual(len(responses), 1)
+        self.assertEqual(
+            responses[0]["content"], "[the tool returned an image, described by openai/gpt-4o]\n\na login form"
+        )
+        kwargs = mock_completion.call_args.kwargs
+        self.assertEqual(kwargs["model"], "openai/gpt-4o")
+        self.assertEqual(kwargs["messages"][0]["content"][1]["image_url"]["url"], "data:image/png;base64,cG5n")
+        self.assertEqual(mock_litellm_state.update_usage.call_args.args[0].cost, 0.01)
+
+    def test_vision_is_detected(self, _: MagicMock, mock_model_info: MagicMock) -> None:
+        self.assertFalse(LiteLLMEngine("deepseek/deepseek-chat", FullHistoryStrategy()).supported_features.vision)
+
+        # models that are not in the litellm database get the images as they are
+        mock_model_info.side_effect = Exception("model not mapped")
+        self.assertTrue(LiteLLMEngine("openai/my-finetune", FullHistoryStrategy()).supported_features.vision)
+
+    async def test_images_are_reported_without_a_vision_generator(self, mock_state: MagicMock, _: MagicMock) -> None:
+        mock_state.is_cancelled.return_value = False
+        mock_state.get_replay.return_value = None
+        engine = LiteLLMEngine("deepseek/deepseek-chat", FullHistoryStrategy(), [screenshot])
+
+        responses = await engine._process_tool_call("call_1", "screenshot", {}, {})
+
+        self.assertEqual(responses[0]["content"], "[the tool returned an image, but the model can't see images]")
+
+
+@patch("nerve.generation.litellm.state")
+class TestSummarizeHistory(unittest.IsolatedAsyncioTestCase):
+    async def test_oldest_messages_are_replaced_by_a_summary(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+        engine.history = [
+            {"role": "assistant", "content": "let me check"},
+            {"role": "assistant", "content": None, "tool_calls": [{"id": "call_1"}]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "42"},
+            {"role": "assistant", "content": "the answer is 42"},
+        ]
+        engine._generate = AsyncMock(return_value=(Usage(total_tokens=10), _text_message("found 42")))  # type: ignore
+
+        usage = await engine.summarize_history(2)
+
+        self.assertEqual(usage.total_tokens, 10)
+        # the tool response is summarized together with its tool call
+        self.assertEqual(
+            engine.history,
+            [
+                {"role": "user", "content": "<summary of 3 earlier messages>\nfound 42"},
+                {"role": "assistant", "content": "the answer is 42"},
+            ],
+        )
+        mock_state.on_event.assert_called_once_with(
+            "history_summarized", {"generator": "openai/gpt-4o", "messages": 3, "summary": "found 42"}
+        )
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_summary_is_a_plain_request(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = _response(
+            [SimpleNamespace(message=_text_message("took a screenshot"), finish_reason="stop")]
+        )
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), response_schema=SCHEMA, stop=["</answer>"], seed=42
+        )
+        engine.history = [
+            {
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "the screenshot"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,c2VjcmV0"}},
+                ],
+            },
+            {"role": "assistant", "content": "nice"},
+        ]
+
+        await engine.summarize_history(2)
+
+        kwargs = mock_completion.call_args.kwargs
+        # the summary must not be shaped like the responses of the agent
+        for param in ("response_format", "stop", "seed"):
+            self.assertNotIn(param, kwargs)
+        prompt = kwargs["messages"][-1]["content"]
+        self.assertNotIn("c2VjcmV0", prompt)
+        self.assertIn("<image_url omitted>", prompt)
+
+    async def test_empty_summary_keeps_the_history(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+        engine.history = [{"role": "assistant", "content": "hello"}]
+        engine._generate = AsyncMock(return_value=(Usage(total_tokens=3), _text_message("")))  # type: ignore
+
+        usage = await engine.summarize_history(5)
+
+        self.assertEqual(usage.total_tokens, 3)
+        self.assertEqual(engine.history, [{"role": "assistant", "content": "hello"}])
+
+
+class TestTruncatedResponses(unittest.IsolatedAsyncioTestCase):
+    async def test_truncated_response_is_continued(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), on_truncation="continue")
+        engine._litellm_completion = AsyncMock(  # type: ignore
+            side_effect=[
+                (Usage(total_tokens=10), _text_message("the quick brown "), True),
+                (Usage(total_tokens=5), _text_message("fox"), False),
+            ]
+        )
+
+        usage, message = await engine._litellm_generate([{"role": "user", "content": "say it"}], None)
+
+        self.assertEqual(message.content, "the quick brown fox")
+        self.assertEqual(usage.total_tokens, 15)
+        self.assertFalse(engine.truncated)
+        continuation = engine._litellm_completion.call_args_list[1].args[0]  # type: ignore
+        self.assertEqual(continuation[1], {"role": "assistant", "content": "the quick brown "})
+
+    @patch("nerve.generation.litellm.state")
+    async def test_truncated_response_is_reported(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[])
+        tool_call = SimpleNamespace(id="call_1", function=SimpleNamespace(name="write_file", arguments='{"content": "'))
+        message = SimpleNamespace(role="assistant", content=None, tool_calls=[tool_call])
+        engine._litellm_completion = AsyncMock(return_value=(Usage(), message, True))  # type: ignore
+        mock_state.get_replay.return_value = None
+
+        await engine.step(None, "write the file")
+
+        # the partial tool call is never executed nor sent back to the model
+        self.assertTrue(engine.truncated)
+        self.assertEqual(engine.history[0], {"role": "assistant", "content": ""})
+        self.assertEqual(engine.history[1]["role"], "user")
+        self.assertIn("truncated", engine.history[1]["content"])
+        mock_state.on_event.assert_any_call(
+            "response_truncated", {"generator": "openai/gpt-4o", "content": None, "tool_calls": True}
+        )
+
+
+class TestStopSequences(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_stop_sequences_are_sent(self, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine("deepseek/deepseek-chat", FullHistoryStrategy(), stop=["</answer>"])
+
+        await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(mock_completion.call_args.kwargs["stop"], ["</answer>"])
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_no_stop_sequences(self, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        self.assertNotIn("stop", mock_completion.call_args.kwargs)
+
+    def test_stop_sequences_are_truncated_to_the_provider_limit(self) -> None:
+        stop = ["a", "b", "c", "d", "e"]
+
+        self.assertEqual(LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), stop=stop).stop, stop[:4])
+        self.assertEqual(LiteLLMEngine("gpt-4o", FullHistoryStrategy(), stop=stop).stop, stop[:4])
+        self.assertEqual(LiteLLMEngine("anthropic/claude", FullHistoryStrategy(), stop=stop).stop, stop)
+
+
+def read_plan() -> str:
+    """Read the plan."""
+    return "plan"
+
+
+def _response(choices: list[SimpleNamespace]) -> SimpleNamespace:
+    return SimpleNamespace(
+        usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+        choices=choices,
+        _hidden_params={},
+    )
+
+
+@patch("nerve.generation.litellm.state")
+@patch("nerve.generation.litellm.litellm.completion")
+class TestInvalidResponses(unittest.IsolatedAsyncioTestCase):
+    async def test_response_without_choices_is_retried(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_completion.side_effect = [
+            _response([]),
+            _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")]),
+        ]
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        _, message = await engine._litellm_generate([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(message.content, "hi")
+        self.assertEqual(mock_completion.call_count, 2)
+        mock_state.on_event.assert_called_once_with(
+            "invalid_response",
+            {"generator": "openai/gpt-4o", "error": "the model returned a response without choices", "attempt": 1},
+        )
+
+    async def test_persistent_invalid_responses_do_not_crash(
+        self, mock_completion: MagicMock, mock_state: MagicMock
+    ) -> None:
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = _response([])
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        _, message = await engine._generate_next_message(None, "hi")
+
+        self.assertIsNone(message)
+        self.assertEqual(mock_completion.call_count, 3)
+
+    async def test_content_filter(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_completion.return_value = _response(
+            [SimpleNamespace(message=_text_message(None), finish_reason="content_filter")]  # type: ignore
+        )
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        with self.assertRaisesRegex(ContentFilteredError, "blocked by the content filter"):
+            await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+
+class _HangingClient(Client):
+    async def generate(
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+    ) -> tuple[Usage, t.Any]:
+        await asyncio.Event().wait()
+        raise AssertionError("unreachable")
+
+
+@patch("nerve.generation.litellm.state")
+class TestRequestTimeout(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_timeout_is_sent_to_the_provider(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.return_value = _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")])
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), request_timeout=Configuration.RequestTimeout(connect=5, total=30)
+        )
+
+        await engine._litellm_generate([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(mock_completion.call_args.kwargs["timeout"], httpx.Timeout(30, connect=5))
+
+    @patch("nerve.generation.litellm.asyncio.sleep", new_callable=AsyncMock)
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_each_attempt_has_its_own_timeout(
+        self, mock_completion: MagicMock, _: AsyncMock, mock_state: MagicMock
+    ) -> None:
+        responses = [None, _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")])]
+
+        def completion(**kwargs: t.Any) -> t.Any:
+            response = responses.pop(0)
+            if response is None:
+                # the first request hangs
+                time.sleep(0.2)
+            return response
+
+        mock_completion.side_effect = completion
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), request_timeout=Configuration.RequestTimeout(total=0.05)
+        )
+
+        _, message = await engine._litellm_generate([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(message.content, "hi")
+        self.assertEqual(mock_completion.call_count, 2)
+        mock_state.on_event.assert_called_once_with(
+            "request_timeout",
+            {
+                "generator": "openai/gpt-4o",
+                "connect": 10.0,
+                "total": 0.05,
+                "error": "openai/gpt-4o did not respond within 0.05s",
+            },
+        )
+
+    @patch("nerve.generation.litellm.asyncio.sleep", new_callable=AsyncMock)
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_timeouts_have_their_own_retries(
+        self, mock_completion: MagicMock, _: AsyncMock, mock_state: MagicMock
+    ) -> None:
+        mock_completion.side_effect = [
+            litellm.ServiceUnavailableError("overloaded"),  # type: ignore
+            litellm.Timeout("timed out"),  # type: ignore
+            litellm.ServiceUnavailableError("overloaded"),  # type: ignore
+            litellm.Timeout("timed out"),  # type: ignore
+            _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")]),
+        ]
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), request_timeout=Configuration.RequestTimeout(total=30)
+        )
+
+        _, message = await engine._litellm_generate([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(message.content, "hi")
+        self.assertEqual(mock_completion.call_count, 5)
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_health_check_times_out(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.side_effect = lambda **kwargs: time.sleep(0.2)
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), request_timeout=Configuration.RequestTimeout(connect=5, total=0.05)
+        )
+
+        with self.assertRaises(RequestTimeoutError):
+            await engine.health_check()
+
+        self.assertEqual(mock_completion.call_args.kwargs["timeout"], httpx.Timeout(0.05, connect=5))
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_image_description_times_out(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.side_effect = lambda **kwargs: time.sleep(0.2)
+        engine = LiteLLMEngine(
+            "deepseek/deepseek-chat",
+            FullHistoryStrategy(),
+            vision_generator="openai/gpt-4o",
+            request_timeout=Configuration.RequestTimeout(total=0.05),
+        )
+
+        description = await engine.describe_image("data:image/png;base64,cG5n")
+
+        self.assertIn("can't be described", description)
+
+    async def test_hanging_client_times_out(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            client=_HangingClient(),
+            request_timeout=Configuration.RequestTimeout(total=0.05),
+        )
+
+        with self.assertRaises(RequestTimeoutError):
+            await engine._generate_or_get_cached([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(mock_state.on_event.call_args[0][0], "request_timeout")
+
+    async def test_no_timeout_by_default(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), client=ScriptedClient([text("hi")]))
+
+        _, message = await engine._generate_or_get_cached([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(message.content, "hi")
+
+
+class TestToolChoice(unittest.TestCase):
+    def test_default_is_auto(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_plan])
+
+        self.assertEqual(engine._get_tool_choice(), "auto")
+
+    def test_required(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_plan], tool_choice="required")
+        engine.history.append({"role": "assistant", "content": "hi"})
+
+        self.assertEqual(engine._get_tool_choice(), "required")
+
+    def test_specific_tool_is_forced_at_the_first_step(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_plan], tool_choice="read_plan")
+
+        self.assertEqual(engine._get_tool_choice(), {"type": "function", "function": {"name": "read_plan"}})
+
+        engine.history.append({"role": "assistant", "content": "hi"})
+        self.assertEqual(engine._get_tool_choice(), "auto")
+
+    def test_unknown_tool(self) -> None:
+        with self.assertRaisesRegex(ValueError, "no tool with this name"):
+            LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), tools=[read_plan], tool_choice="write_plan")
+
+
+@patch("nerve.generation.litellm.state")
+class TestEmptyResponses(unittest.IsolatedAsyncioTestCase):
+    async def test_empty_response_is_retried(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), max_empty_responses=2)
+        engine._litellm_completion = AsyncMock(  # type: ignore
+            return_value=(Usage(total_tokens=3), _text_message(" "), False)
+        )
+        mock_state.get_replay.return_value = None
+
+        usage = await engine.step(None, "do it")
+
+        # counted towards the usage
+        self.assertEqual(usage.total_tokens, 3)
+        # empty assistant turns are rejected by most providers
+        self.assertEqual(len(engine.history), 1)
+        self.assertEqual(engine.history[0]["role"], "user")
+        self.assertIn("no content", engine.history[0]["content"])
+        mock_state.on_event.assert_any_call("empty_response", {"generator": "openai/gpt-4o", "count": 1, "max": 2})
+        mock_state.set_task_failed.assert_not_called()
+
+    async def test_task_fails_after_max_empty_responses(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), max_empty_responses=2)
+        engine._litellm_completion = AsyncMock(return_value=(Usage(), _text_message(""), False))  # type: ignore
+        mock_state.get_replay.return_value = None
+
+        for _ in range(3):
+            await engine.step(None, "do it")
+
+        mock_state.set_task_failed.assert_called_once()
+        self.assertIn("3 empty responses", mock_state.set_task_failed.call_args.args[0])
+
+    async def test_counter_is_reset_by_a_valid_response(self, mock_state: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), max_empty_responses=1)
+        engine._litellm_completion = AsyncMock(  # type: ignore
+            side_effect=[
+                (Usage(), _text_message(""), False),
+                (Usage(), _text_message("hello"), False),
+                (Usage(), _text_message(""), False),
+            ]
+        )
+        mock_state.get_replay.return_value = None
+
+        for _ in range(3):
+            await engine.step(None, "do it")
+
+        self.assertEqual(engine.empty_responses, 1)
+        mock_state.set_task_failed.assert_not_called()
+
+
+class TestSystemPromptFolding(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.get_model_info", return_value={"supports_system_messages": False})
+    async def test_system_prompt_is_folded_into_the_user_prompt(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/o1-mini", FullHistoryStrategy())
+
+        conversation = await engine._get_conversation("you are an agent", "do something", None)
+
+        self.assertFalse(engine.supports_system_prompt)
+        self.assertEqual(conversation, [{"role": "user", "content": "you are an agent\n\ndo something"}])
+
+    @patch("nerve.generation.litellm.litellm.get_model_info", return_value={"supports_system_messages": False})
+    async def test_multi_part_user_prompt(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/o1-mini", FullHistoryStrategy())
+        folded = engine._fold_system_prompt(
+            [{"role": "system", "content": "be brief"}, {"role": "user", "content": [{"type": "text", "text": "hi"}]}]
+        )
+
+        self.assertEqual(
+            folded,
+            [{"role": "user", "content": [{"type": "text", "text": "be brief"}, {"type": "text", "text": "hi"}]}],
+        )
+
+    @patch("nerve.generation.litellm.state")
+    @patch("nerve.generation.litellm.litellm.get_model_info", return_value={"supports_system_messages": False})
+    async def test_instructions_are_folded(self, _: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        client = ScriptedClient([text("short"), text("they said hi")])
+        engine = LiteLLMEngine("openai/o1-mini", FullHistoryStrategy(), client=client)
+        engine.history = [{"role": "user", "content": "hi"}]
+
+        await engine.complete("be brief", "say something")
+        await engine.summarize_history(1)
+
+        # neither the completions nor the summaries send a system prompt
+        self.assertEqual(client.conversations[0], [{"role": "user", "content": "be brief\n\nsay something"}])
+        self.assertEqual([m["role"] for m in client.conversations[1]], ["user"])
+
+    @patch("nerve.generation.litellm.state")
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_completions_are_plain_requests(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+            choices=[SimpleNamespace(message=_text_message("a summary"), finish_reason="stop")],
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine(
+            "openai/gpt-4o", FullHistoryStrategy(), response_schema=SCHEMA, stop=["</answer>"], seed=42
+        )
+
+        _, result = await engine.complete("be brief", "summarize this")
+
+        self.assertEqual(result, "a summary")
+        for param in ("response_format", "stop", "seed"):
+            self.assertNotIn(param, mock_completion.call_args.kwargs)
+
+    @patch("nerve.generation.litellm.litellm.get_model_info", side_effect=Exception("model not mapped"))
+    async def test_unknown_models_keep_the_system_prompt(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/custom-model", FullHistoryStrategy())
+
+        conversation = await engine._get_conversation("you are an agent", "do something", None)
+
+        self.assertTrue(engine.supports_system_prompt)
+        self.assertEqual(conversation[0], {"role": "system", "content": "you are an agent"})
+
+
+class TestExamples(unittest.IsolatedAsyncioTestCase):
+    def _engine(self, history: list[dict[str, t.Any]]) -> LiteLLMEngine:
+        engine = LiteLLMEngine(
+            "openai/gpt-4o",
+            SlidingWindowStrategy(1),
+            examples=[
+                Configuration.Example(user="fix the typo", assistant="I'll read the file first."),
+                Configuration.Example(user="rename it", assistant="I'll search for its usages first."),
+            ],
+        )
+        engine.history = history
+        return engine
+
+    async def test_examples_follow_the_system_prompt(self) -> None:
+        engine = self._engine([])
+
+        conversation = await engine._get_conversation("you are an agent", "do something", None)
+
+        self.assertEqual(
+            conversation,
+            [
+                {"role": "system", "content": "you are an agent"},
+                {"role": "user", "content": "fix the typo"},
+                {"role": "assistant", "content": "I'll read the file first."},
+                {"role": "user", "content": "rename it"},
+                {"role": "assistant", "content": "I'll search for its usages first."},
+                {"role": "user", "content": "do something"},
+            ],
+        )
+
+    async def test_examples_are_never_trimmed(self) -> None:
+        history = [{"role": "assistant", "content": f"step {i}"} for i in range(5)]
+        engine = self._engine(history)
+
+        conversation = await engine._get_conversation("you are an agent", "do something", None)
+
+        self.assertEqual(len([m for m in conversation if m["content"] in ("fix the typo", "rename it")]), 2)
+        self.assertEqual(conversation[-1], {"role": "assistant", "content": "step 4"})
+        self.assertNotIn({"role": "assistant", "content": "step 0"}, conversation)
+
+
+class TestSeed(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_seed_is_sent_and_fingerprint_is_captured(self, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            system_fingerprint="fp_44709d6fcb",
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), seed=42)
+
+        await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(mock_completion.call_args.kwargs["seed"], 42)
+        self.assertEqual(engine.system_fingerprint, "fp_44709d6fcb")
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_no_seed(self, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        self.assertNotIn("seed", mock_completion.call_args.kwargs)
+        self.assertIsNone(engine.system_fingerprint)
+
+    def test_seed_generator_parameter(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o?seed=7", FullHistoryStrategy())
+
+        self.assertEqual(engine.seed, 7)
+        self.assertNotIn("seed", engine.generator_params)
+
+
+@patch("nerve.generation.litellm.litellm.supports_reasoning", return_value=True)
+class TestReasoningOptions(unittest.IsolatedAsyncioTestCase):
+    def test_reasoning_effort(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/o3-mini", FullHistoryStrategy(), reasoning_effort="high")
+
+        self.assertEqual(engine.reasoning_params, {"reasoning_effort": "high"})
+
+    def test_thinking_budget(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine(
+            "anthropic/claude-3-7-sonnet-latest", FullHistoryStrategy(), reasoning_effort="low", thinking_budget=2048
+        )
+
+        self.assertEqual(engine.reasoning_params, {"thinking": {"type": "enabled", "budget_tokens": 2048}})
+
+    def test_thinking_budget_falls_back_to_the_reasoning_effort(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/o3-mini", FullHistoryStrategy(), reasoning_effort="low", thinking_budget=2048)
+
+        self.assertEqual(engine.reasoning_params, {"reasoning_effort": "low"})
+
+    def test_models_without_reasoning_ignore_the_options(self, mock_supports_reasoning: MagicMock) -> None:
+        mock_supports_reasoning.return_value = False
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), reasoning_effort="high")
+
+        self.assertEqual(engine.reasoning_params, {})
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_reasoning_tokens_are_reported(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.return_value = SimpleNamespace(
+            usage=SimpleNamespace(
+                prompt_tokens=10,
+                completion_tokens=50,
+                total_tokens=60,
+                completion_tokens_details=SimpleNamespace(reasoning_tokens=40),
+            ),
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            _hidden_params={},
+        )
+        engine = LiteLLMEngine("openai/o3-mini", FullHistoryStrategy(), reasoning_effort="medium")
+
+        usage, _, _ = await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        self.assertEqual(mock_completion.call_args.kwargs["reasoning_effort"], "medium")
+        self.assertEqual(usage.reasoning_tokens, 40)
+
+
+class TestEndpoint(unittest.TestCase):
+    def test_api_key_from_the_given_variable(self) -> None:
+        with patch.dict(os.environ, {"GATEWAY_KEY": "gw-123"}):
+            engine = LiteLLMEngine(
+                "openai/llama-3.3-70b",
+                FullHistoryStrategy(),
+                endpoint=Configuration.Endpoint(base_url="https://gateway.corp/v1", api_key_env="GATEWAY_KEY"),
+            )
+
+        self.assertEqual((engine.api_base, engine.api_key), ("https://gateway.corp/v1", "gw-123"))
+
+    def test_no_auth(self) -> None:
+        engine = LiteLLMEngine(
+            "openai/qwen3",
+            FullHistoryStrategy(),
+            endpoint=Configuration.Endpoint(base_url="http://localhost:8000/v1", no_auth=True),
+        )
+
+        self.assertEqual((engine.api_base, engine.api_key), ("http://localhost:8000/v1", "no-auth"))
+
+    def test_missing_api_key(self) -> None:
+        with patch.dict(os.environ, {}, clear=True):
+            with self.assertRaisesRegex(ValueError, "set GATEWAY_KEY or no_auth"):
+                LiteLLMEngine(
+                    "openai/llama-3.3-70b",
+                    FullHistoryStrategy(),
+                    endpoint=Configuration.Endpoint(base_url="https://gateway.corp/v1", api_key_env="GATEWAY_KEY"),
+                )
+
+
+@patch("nerve.generation.litellm.litellm.supports_prompt_caching", return_value=True)
+class TestPromptCaching(unittest.IsolatedAsyncioTestCase):
+    def _completion(self, usage: SimpleNamespace) -> SimpleNamespace:
+        return SimpleNamespace(
+            usage=usage,
+            choices=[SimpleNamespace(message=_text_message("hi"), finish_reason="stop")],
+            _hidden_params={},
+        )
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_system_prompt_and_tools_are_marked(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.return_value = self._completion(
+            SimpleNamespace(
+                prompt_tokens=100,
+                completion_tokens=5,
+                total_tokens=105,
+                cache_read_input_tokens=80,
+                cache_creation_input_tokens=20,
+            )
+        )
+        engine = LiteLLMEngine("anthropic/claude-3-5-sonnet-latest", FullHistoryStrategy())
+        tools = [{"type": "function", "function": {"name": "a"}}, {"type": "function", "function": {"name": "b"}}]
+
+        usage, _, _ = await engine._litellm_completion(
+            [{"role": "system", "content": "be helpful"}, {"role": "user", "content": "hi"}], tools
+        )
+
+        kwargs = mock_completion.call_args.kwargs
+        self.assertEqual(
+            kwargs["messages"][0]["content"],
+            [{"type": "text", "text": "be helpful", "cache_control": {"type": "ephemeral"}}],
+        )
+        self.assertEqual(kwargs["messages"][1], {"role": "user", "content": "hi"})
+        self.assertNotIn("cache_control", kwargs["tools"][0])
+        self.assertEqual(kwargs["tools"][1]["cache_control"], {"type": "ephemeral"})
+        # the original tools are not modified
+        self.assertNotIn("cache_control", tools[1])
+        self.assertEqual((usage.cache_read_tokens, usage.cache_write_tokens), (80, 20))
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_no_hints_for_automatic_caching(self, mock_completion: MagicMock, _: MagicMock) -> None:
+        mock_completion.return_value = self._completion(
+            SimpleNamespace(
+                prompt_tokens=100,
+                completion_tokens=5,
+                total_tokens=105,
+                prompt_tokens_details=SimpleNamespace(cached_tokens=64),
+            )
+        )
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        usage, _, _ = await engine._litellm_completion([{"role": "system", "content": "be helpful"}], None)
+
+        self.assertFalse(engine.prompt_caching)
+        self.assertEqual(mock_completion.call_args.kwargs["messages"], [{"role": "system", "content": "be helpful"}])
+        self.assertEqual((usage.cache_read_tokens, usage.cache_write_tokens), (64, 0))
+
+    def test_prompt_caching_can_be_disabled(self, _: MagicMock) -> None:
+        engine = LiteLLMEngine("anthropic/claude-3-5-sonnet-latest", FullHistoryStrategy(), prompt_caching=False)
+
+        self.assertFalse(engine.prompt_caching)
+
+
+def read_file(path: str) -> str:
+    """Read a file."""
+    return "content"
+
+
+@patch("nerve.generation.state")
+class TestLoopDetection(unittest.IsolatedAsyncioTestCase):
+    def _engine(self, **kwargs: t.Any) -> LiteLLMEngine:
+        return LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            [read_file],
+            loop_detection=Configuration.LoopDetection(**kwargs),
+        )
+
+    async def test_repeated_calls_get_feedback(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = self._engine(window=5, threshold=2)
+
+        for _ in range(2):
+            responses = await engine._process_tool_call("id", "read_file", {"path": "a.txt"}, {})
+            self.assertEqual(responses[0]["content"], "content")
+
+        responses = await engine._process_tool_call("id", "read_file", {"path": "a.txt"}, {})
+
+        self.assertIn("try a different approach", responses[0]["content"])
+        self.assertEqual(mock_state.on_event.call_args.args[0], "loop_detected")
+        self.assertEqual(mock_state.on_event.call_args.args[1]["repetitions"], 3)
+        mock_state.set_task_failed.assert_not_called()
+
+    async def test_different_arguments_are_not_a_loop(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = self._engine(window=5, threshold=1)
+
+        for path in ("a.txt", "b.txt", "a.txt"):
+            responses = await engine._process_tool_call("id", "read_file", {"path": path}, {})
+
+        self.assertIn("try a different approach", responses[0]["content"])
+
+        # the oldest calls are out of the window
+        engine = self._engine(window=2, threshold=1)
+        for path in ("a.txt", "b.txt", "c.txt", "a.txt"):
+            responses = await engine._process_tool_call("id", "read_file", {"path": path}, {})
+
+        self.assertEqual(responses[0]["content"], "content")
+
+    async def test_task_fails_after_max_repetitions(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = self._engine(window=10, threshold=1, max_repetitions=3)
+
+        for _ in range(3):
+            await engine._process_tool_call("id", "read_file", {"path": "a.txt"}, {})
+
+        mock_state.set_task_failed.assert_called_once()
+
+
+def dump_logs() -> str:
+    """Dump the logs."""
+    return "0123456789" * 3
+
+
+@patch("nerve.generation.state")
+class TestChunkedToolOutput(unittest.IsolatedAsyncioTestCase):
+    async def test_long_output_is_paged(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [dump_logs], max_tool_output_length=12)
+
+        responses = await engine._process_tool_call("call_1", "dump_logs", {}, {})
+
+        self.assertTrue(responses[0]["content"].startswith("012345678901\n\n[output truncated, 2 more chunks"))
+        self.assertIn("read_next_chunk", [s["function"]["name"] for s in engine._get_extended_tooling_schema({})])
+        self.assertEqual(mock_state.on_event.call_args.args[0], "tool_output_chunked")
+
+        responses = await engine._process_tool_call("call_2", "read_next_chunk", {"output_id": "call_1"}, {})
+        self.assertTrue(responses[0]["content"].startswith("234567890123\n\n[output truncated, 1 more chunks"))
+
+        responses = await engine._process_tool_call("call_3", "read_next_chunk", {"output_id": "call_1"}, {})
+        self.assertEqual(responses[0]["content"], "456789")
+
+        # nothing left to read
+        self.assertNotIn("read_next_chunk", [s["function"]["name"] for s in engine._get_extended_tooling_schema({})])
+        responses = await engine._process_tool_call("call_4", "read_next_chunk", {"output_id": "call_1"}, {})
+        self.assertTrue(responses[0]["content"].startswith("ERROR"))
+
+    async def test_short_output_is_returned_at_once(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [dump_logs], max_tool_output_length=0)
+
+        responses = await engine._process_tool_call("call_1", "dump_logs", {}, {})
+
+        self.assertEqual(responses[0]["content"], "0123456789" * 3)
+        self.assertEqual(engine.output_chunks, {})
+
+
+def echo(text: str) -> str:
+    """Echo the text."""
+    return text
+
+
+@patch("nerve.runtime.template.state")
+@patch("nerve.generation.state")
+class TestArgumentTemplates(unittest.IsolatedAsyncioTestCase):
+    async def test_references_are_resolved(self, mock_state: MagicMock, template_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        template_state.get_variables.return_value = {"target": "example.com"}
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [echo], argument_templates="error")
+
+        responses = await engine._process_tool_call("call_1", "echo", '{"text": "ping {{ target }}"}', {})
+
+        self.assertEqual(responses[0]["content"], "ping example.com")
+
+    async def test_undefined_reference(self, mock_state: MagicMock, template_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        template_state.get_variables.return_value = {}
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [echo], argument_templates="error")
+
+        responses = await engine._process_tool_call("call_1", "echo", {"text": "ping {{ target }}"}, {})
+
+        self.assertIn("undefined reference {{ target }}", responses[0]["content"])
+        self.assertEqual(mock_state.on_event.call_args.args[0], "invalid_tool_call")
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [echo], argument_templates="empty")
+        responses = await engine._process_tool_call("call_1", "echo", {"text": "ping {{ target }}"}, {})
+
+        self.assertEqual(responses[0]["content"], "ping ")
+
+    async def test_disabled_by_default(self, mock_state: MagicMock, template_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [echo])
+
+        responses = await engine._process_tool_call("call_1", "echo", {"text": "{{ target }}"}, {})
+
+        self.assertEqual(responses[0]["content"], "{{ target }}")
+
+
+def _tool_call(call_id: str, text: str) -> SimpleNamespace:
+    return SimpleNamespace(id=call_id, function=SimpleNamespace(name="echo", arguments=f'{{"text": "{text}"}}'))
+
+
+class TestPlannedToolCalls(unittest.IsolatedAsyncioTestCase):
+    async def test_calls_are_announced_before_being_executed(self) -> None:
+        mock_state = MagicMock()
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+        mock_state.is_active_task_done.return_value = False
+
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), [echo])
+        message = SimpleNamespace(
+            role="assistant", content=None, tool_calls=[_tool_call("call_1", "a"), _tool_call("call_2", "b")]
+        )
+        engine._generate_next_message = AsyncMock(return_value=(Usage(), message))  # type: ignore
+
+        with patch("nerve.generation.state", mock_state), patch("nerve.generation.litellm.state", mock_state):
+            await engine.step(None, "echo twice")
+
+        calls = [
+            (c[0], c.args[1]["call_id"] if c[0] == "on_event" else c.args[0])
+            for c in mock_state.mock_calls
+            if c[0] == "set_tool_call_id" or (c[0] == "on_event" and c.args[0] == "tool_call_planned")
+        ]
+        self.assertEqual(
+            calls,
+            [
+                ("on_event", "call_1"),
+                ("on_event", "call_2"),
+                ("set_tool_call_id", "call_1"),
+                ("set_tool_call_id", None),
+                ("set_tool_call_id", "call_2"),
+                ("set_tool_call_id", None),
+            ],
+        )
+        mock_state.on_event.assert_any_call(
+            "tool_call_planned",
+            {
+                "generator": "openai/gpt-4o",
+                "call_id": "call_2",
+                "tool_name": "echo",
+                "args": '{"text": "b"}',
+                "position": 2,
+                "total": 2,
+            },
+        )
+
+
+@patch("nerve.generation.litellm.state")
+@patch("nerve.generation.litellm.litellm.completion")
+class TestLogprobs(unittest.IsolatedAsyncioTestCase):
+    def _choice(self) -> SimpleNamespace:
+        logprobs = SimpleNamespace(
+            content=[
+                SimpleNamespace(token="4", logprob=-0.1, top_logprobs=[SimpleNamespace(token="5", logprob=-2.5)]),
+                SimpleNamespace(token="2", logprob=-0.3, top_logprobs=[]),
+            ]
+        )
+        return SimpleNamespace(message=_text_message("42"), finish_reason="stop", logprobs=logprobs)
+
+    async def test_logprobs_are_requested_and_emitted(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = _response([self._choice()])
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), logprobs=3)
+
+        await engine._generate([{"role": "user", "content": "6 * 7?"}], None)
+
+        self.assertTrue(mock_completion.call_args.kwargs["logprobs"])
+        self.assertEqual(mock_completion.call_args.kwargs["top_logprobs"], 3)
+        self.assertEqual(
+            engine.last_logprobs,
+            [
+                {"token": "4", "logprob": -0.1, "top_logprobs": [{"token": "5", "logprob": -2.5}]},
+                {"token": "2", "logprob": -0.3, "top_logprobs": []},
+            ],
+        )
+
+        summary = mock_state.on_event.call_args.args[1]["logprobs"]
+        self.assertAlmostEqual(summary["mean"], -0.2)
+        self.assertEqual(summary["min"], -0.3)
+        self.assertAlmostEqual(summary["confidence"], 0.8187, places=4)
+
+    async def test_not_requested_by_default(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_completion.return_value = _response([self._choice()])
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        await engine._generate([{"role": "user", "content": "6 * 7?"}], None)
+
+        self.assertNotIn("logprobs", mock_completion.call_args.kwargs)
+        self.assertIsNone(engine.last_logprobs)
+        self.assertNotIn("logprobs", mock_state.on_event.call_args.args[1])
+
+    def test_invalid_number_of_alternatives(self, *_: MagicMock) -> None:
+        with self.assertRaisesRegex(ValueError, "between 0 and 20"):
+            LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), logprobs=21)
+
+
+@patch("nerve.generation.litellm.state")
+class TestSelfConsistency(unittest.IsolatedAsyncioTestCase):
+    def _engine(self, client: ScriptedClient, strategy: str, schema: dict[str, t.Any] | None = None) -> LiteLLMEngine:
+        return LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            response_schema=schema,
+            client=client,
+            self_consistency=Configuration.SelfConsistency(samples=3, strategy=strategy),  # type: ignore
+        )
+
+    async def test_structured_answers_are_voted(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        client = ScriptedClient(
+            [
+                text('{"name": "bob", "age": 41}'),
+                text('{"age": 42, "name": "bob"}'),
+                text('{"name": "bob", "age": 42}'),
+            ],
+            Usage(total_tokens=10),
+        )
+        engine = self._engine(client, "auto", SCHEMA)
+
+        usage, message = await engine._generate_next_message(None, "who is bob?")
+
+        self.assertEqual(json.loads(message.content), {"name": "bob", "age": 42})
+        # every sample counts toward the usage
+        self.assertEqual(usage.total_tokens, 30)
+        event = next(c.args[1] for c in mock_state.on_event.call_args_list if c.args[0] == "self_consistency")
+        self.assertEqual((event["strategy"], event["answers"], event["votes"]), ("vote", 3, 2))
+
+    async def test_only_the_chosen_answer_is_recorded(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        client = ScriptedClient(
+            [
+                tool_call("task_complete_success", reason="it is 41"),
+                tool_call("task_complete_success", reason="the answer is 42"),
+                tool_call("task_complete_success", reason="42"),
+                text("2"),
+            ]
+        )
+        engine = self._engine(client, "judge")
+
+        _, message = await engine._generate_next_message(None, "what is the answer?")
+
+        # a replayed trace contains a single generation for the step, with the chosen answer
+        generations = [c.args[1] for c in mock_state.on_event.call_args_list if c.args[0] == "generation"]
+        self.assertEqual(len(generations), 1)
+        self.assertIn("the answer is 42", json.dumps(generations[0]["message"]))
+        self.assertIn("the answer is 42", message.tool_calls[0].function.arguments)
+
+    async def test_replayed_answers_are_not_sampled(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value.next_generation.return_value = (
+            Usage(total_tokens=10),
+            to_namespace(tool_call("task_complete_success", reason="42")),
+        )
+        client = ScriptedClient([])
+        engine = self._engine(client, "judge")
+
+        _, message = await engine._generate_next_message(None, "what is the answer?")
+
+        self.assertIn("42", message.tool_calls[0].function.arguments)
+        self.assertEqual(client.conversations, [])
+
+    async def test_free_text_answers_are_judged(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        client = ScriptedClient(
+            [
+                tool_call("task_complete_success", reason="it is 41"),
+                tool_call("task_complete_success", reason="the answer is 42"),
+                tool_call("read_file", path="notes.txt"),
+                text("2"),
+            ],
+            Usage(total_tokens=10),
+        )
+        engine = self._engine(client, "auto")
+
+        usage, message = await engine._generate_next_message(None, "what is the answer?")
+
+        self.assertIn("the answer is 42", message.tool_calls[0].function.arguments)
+        # three samples and the judge
+        self.assertEqual(usage.total_tokens, 40)
+        self.assertIn("<answer number=2>\nthe answer is 42\n</answer>", client.conversations[-1][-1]["content"])
+
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_samples_are_generated_in_parallel(self, mock_completion: MagicMock, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        barrier = threading.Barrier(2, timeout=5)
+
+        def complete(**kwargs: t.Any) -> SimpleNamespace:
+            if "temperature" in kwargs:
+                # fails unless both samples are in flight at the same time
+                barrier.wait()
+                content, fingerprint = '{"name": "bob", "age": 42}', "fp_sample"
+            elif kwargs["tools"] is None:
+                content, fingerprint = "2", "fp_judge"
+            else:
+                content, fingerprint = '{"name": "bob", "age": 41}', "fp_first"
+
+            return SimpleNamespace(
+                usage=SimpleNamespace(prompt_tokens=1, completion_tokens=1, total_tokens=2),
+                choices=[SimpleNamespace(message=_text_message(content), finish_reason="stop")],
+                system_fingerprint=fingerprint,
+                _hidden_params={},
+            )
+
+        mock_completion.side_effect = complete
+        engine = LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            response_schema=SCHEMA,
+            stop=["</answer>"],
+            seed=42,
+            self_consistency=Configuration.SelfConsistency(samples=3, strategy="judge"),
+        )
+
+        _, message = await engine._generate_next_message(None, "who is bob?")
+
+        self.assertEqual(json.loads(message.content)["age"], 42)
+        event = next(c.args[1] for c in mock_state.on_event.call_args_list if c.args[0] == "self_consistency")
+        self.assertEqual(event["answers"], 3)
+        # the metadata of the chosen sample is reported
+        self.assertEqual(engine.system_fingerprint, "fp_sample")
+        # the judge is not shaped like the responses of the agent
+        judge = mock_completion.call_args_list[-1].kwargs
+        for param in ("response_format", "stop", "seed"):
+            self.assertNotIn(param, judge)
+
+    async def test_intermediate_steps_are_not_sampled(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        client = ScriptedClient([tool_call("read_file", path="notes.txt")])
+        engine = self._engine(client, "vote")
+
+        _, message = await engine._generate_next_message(None, "read the notes")
+
+        self.assertEqual(message.tool_calls[0].function.name, "read_file")
+        self.assertEqual(len(client.conversations), 1)
+
+
+@patch("nerve.generation.litellm.litellm.completion")
+class TestEndUser(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.get_supported_openai_params", return_value=["user", "seed"])
+    async def test_end_user_is_hashed(self, _: MagicMock, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")])
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), end_user="alice@example.com")
+
+        await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+
+        user = mock_completion.call_args.kwargs["user"]
+        # stable and not disclosing the identity
+        other = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), end_user="alice@example.com")
+        self.assertEqual(user, other.user_params["user"])
+        self.assertNotIn("alice", user)
+
+    @patch("nerve.generation.litellm.litellm.get_supported_openai_params", return_value=["seed"])
+    async def test_unsupported_or_unset(self, _: MagicMock, mock_completion: MagicMock) -> None:
+        mock_completion.return_value = _response([SimpleNamespace(message=_text_message("hi"), finish_reason="stop")])
+
+        for engine in (
+            LiteLLMEngine("groq/llama3-70b-8192", FullHistoryStrategy(), end_user="alice@example.com"),
+            LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy()),
+        ):
+            await engine._litellm_completion([{"role": "user", "content": "hi"}], None)
+            self.assertNotIn("user", mock_completion.call_args.kwargs)
+
+
+class _UnreachableClient(Client):
+    async def generate(
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+    ) -> tuple[Usage, t.Any]:
+        raise ConnectionError("connection refused")
+
+
+class TestHealthCheck(unittest.IsolatedAsyncioTestCase):
+    @patch("nerve.generation.litellm.litellm.completion")
+    async def test_minimal_request(self, mock_completion: MagicMock) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy())
+
+        await engine.health_check()
+
+        self.assertEqual(mock_completion.call_args.kwargs["max_tokens"], 1)
+        self.assertEqual(mock_completion.call_args.kwargs["model"], "openai/gpt-4o")
+
+    async def test_errors_are_converted(self) -> None:
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), client=_UnreachableClient())
+
+        with self.assertRaisesRegex(NetworkError, "connection refused"):
+            await engine.health_check()
+
+    async def test_scripted_messages_are_not_consumed(self) -> None:
+        client = ScriptedClient([text("hi")])
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), client=client)
+
+        await engine.health_check()
+
+        self.assertEqual(len(client.messages), 1)
+
+
+@patch("nerve.generation.breaker.state")
+class TestCircuitBreaker(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        breaker._breakers.clear()
+
+    async def test_open_circuit_fails_fast(self, _: MagicMock) -> None:
+        client = _UnreachableClient()
+        client.generate = AsyncMock(side_effect=ConnectionError("connection refused"))  # type: ignore
+        engine = LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            client=client,
+            circuit_breaker=Configuration.CircuitBreaker(failures=2, cooldown=60),
+        )
+
+        for _ in range(2):
+            with self.assertRaises(ConnectionError):
+                await engine._generate([], None)
+
+        with self.assertRaises(breaker.CircuitOpenError):
+            await engine._generate([], None)
+
+        self.assertEqual(client.generate.call_count, 2)
+
+    async def test_engines_of_the_same_generator_share_the_breaker(self, _: MagicMock) -> None:
+        config = Configuration.CircuitBreaker()
+
+        first = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), circuit_breaker=config)
+        second = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), circuit_breaker=config)
+
+        self.assertIs(first.breaker, second.breaker)
+        self.assertIsNone(LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy()).breaker)
+
+    async def test_cancelled_probe_does_not_block_the_circuit(self, _: MagicMock) -> None:
+        client = _UnreachableClient()
+        client.generate = AsyncMock(side_effect=asyncio.CancelledError())  # type: ignore
+        engine = LiteLLMEngine(
+            "openai/gpt-4o",
+            FullHistoryStrategy(),
+            client=client,
+            circuit_breaker=Configuration.CircuitBreaker(failures=2, cooldown=60),
+        )
+        circuit = t.cast(breaker.CircuitBreaker, engine.breaker)
+        circuit.state, circuit.failures = "half_open", 2
+
+        with self.assertRaises(asyncio.CancelledError):
+            await engine._generate([], None)
+
+        # the next call probes the provider again
+        self.assertFalse(circuit.probing)
+        client.generate = AsyncMock(return_value=(Usage(), None))  # type: ignore
+        await engine._generate([], None)
+        self.assertEqual(circuit.state, "closed")
@@ -2,17 +2,29 @@ import typing as t
 
 from loguru import logger
 
+from nerve.generation.client import Client
+from nerve.generation.errors import ModelUnavailableError
 from nerve.models import Usage
+from nerve.runtime import network
 
 
 # until this is not fixed, ollama needs special treatment: https://github.com/BerriAI/litellm/issues/6353
-class OllamaGlue:
-    def __init__(self, api_base: str, generator_id: str, generator_params: dict[str, t.Any]) -> None:
+class OllamaGlue(Client):
+    def __init__(
+        self,
+        api_base: str,
+        generator_id: str,
+        generator_params: dict[str, t.Any],
+        stop: list[str] | None = None,
+        seed: int | None = None,
+    ) -> None:
         import ollama
 
         self.model = "/".join(generator_id.split("/")[1:])
-        self.client = ollama.AsyncClient(host=api_base)
+        self.client = ollama.AsyncClient(host=api_base, **network.get_httpx_options())
         self.generator_params = generator_params
+        self.stop = stop or []
+        self.seed = seed
 
         logger.debug(f"using ollama client for model {self.model}")
 
@@ -59,16 +71,31 @@ class OllamaGlue:
 
         return fixed
 
+    async def health_check(self) -> None:
+        # listing the local models is cheaper than generating
+        response = await self.client.list()
+        names = {getattr(model, "model", None) or model.get("name") for model in response.models}
+        if self.model not in names and f"{self.model}:latest" not in names:
+            raise ModelUnavailableError(f"model {self.model} is not available on the ollama server, pull it first")
+
     async def generate(
-        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None, plain: bool = False
     ) -> tuple[Usage, t.Any]:
         conversation = await self._process_conversation(conversation)
 
+        # plain requests, like summaries, don't use the stop sequences and the seed of the agent
+        options: dict[str, t.Any] = {}
+        if self.stop and not plain:
+            options["stop"] = self.stop
+        if self.seed is not None and not plain:
+            options["seed"] = self.seed
+
         logger.debug(f"ollama.conversation: {conversation}")
         response = await self.client.chat(
             model=self.model,
             messages=conversation,
             tools=tools_schema,
+            **({"options": options} if options else {}),
             **self.generator_params,
         )
         logger.debug(f"ollama.response: {response}")
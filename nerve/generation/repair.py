@@ -0,0 +1,85 @@
+import ast
+import json
+import re
+
+# the arguments are sometimes wrapped in a markdown code block
+_CODE_BLOCK = re.compile(r"```(?:json)?\s*(.*?)\s*```", re.DOTALL)
+# control characters that must be escaped within strings
+_ESCAPES = {"\n": "\\n", "\r": "\\r", "\t": "\\t"}
+
+
+def _without_trailing_comma(out: list[str]) -> list[str]:
+    text = "".join(out).rstrip()
+    return [text[:-1] if text.endswith(",") else text]
+
+
+def _fix_structure(text: str) -> str:
+    out: list[str] = []
+    closers: list[str] = []
+    in_string = False
+    escaped = False
+
+    for i, char in enumerate(text):
+        if in_string:
+            if escaped:
+                escaped = False
+            elif char == "\\":
+                escaped = True
+            elif char == '"':
+                # a quote only ends the string if followed by a structural character, otherwise it was not escaped
+                rest = text[i + 1 :].lstrip()
+                if rest and rest[0] not in ",:}]":
+                    out.append('\\"')
+                    continue
+                in_string = False
+            elif char in _ESCAPES:
+                out.append(_ESCAPES[char])
+                continue
+        elif char == '"':
+            in_string = True
+        elif char in "{[":
+            closers.append("}" if char == "{" else "]")
+        elif char in "}]":
+            out = _without_trailing_comma(out)
+            if closers:
+                closers.pop()
+
+        out.append(char)
+
+    # close whatever a truncated response left open
+    if in_string:
+        out.append('"')
+    return "".join(_without_trailing_comma(out) + closers[::-1])
+
+
+def repair_json(text: str) -> str | None:
+    """
+    Try to repair almost valid JSON as generated by smaller models, with trailing commas, unescaped quotes
+    and newlines, missing closing brackets or Python literals. Return the repaired JSON or None if it can't be fixed.
+    """
+
+    text = text.strip()
+    match = _CODE_BLOCK.fullmatch(text)
+    if match:
+        text = match.group(1)
+
+    repaired = _fix_structure(text)
+    try:
+        json.loads(repaired)
+        return repaired
+    except json.JSONDecodeError:
+        pass
+
+    try:
+        # single quotes, True, False and None
+        value = ast.literal_eval(text)
+    except (ValueError, SyntaxError, MemoryError, RecursionError):
+        return None
+
+    if not isinstance(value, dict | list):
+        return None
+
+    try:
+        return json.dumps(value)
+    except (TypeError, ValueError):
+        return None
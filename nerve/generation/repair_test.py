@@ -0,0 +1,39 @@
+import json
+import unittest
+
+from nerve.generation.repair import repair_json
+
+
+class TestRepairJSON(unittest.TestCase):
+    def _repaired(self, text: str) -> object:
+        repaired = repair_json(text)
+        self.assertIsNotNone(repaired)
+        return json.loads(repaired or "")
+
+    def test_trailing_commas(self) -> None:
+        self.assertEqual(self._repaired('{"a": [1, 2,], "b": 3,}'), {"a": [1, 2], "b": 3})
+
+    def test_unescaped_quotes(self) -> None:
+        self.assertEqual(
+            self._repaired('{"command": "echo "hello world"", "timeout": 5}'),
+            {"command": 'echo "hello world"', "timeout": 5},
+        )
+
+    def test_unescaped_newlines(self) -> None:
+        self.assertEqual(self._repaired('{"code": "a = 1\nprint(a)"}'), {"code": "a = 1\nprint(a)"})
+
+    def test_missing_closing_brackets(self) -> None:
+        self.assertEqual(self._repaired('{"path": "/tmp", "items": ["a", "b"'), {"path": "/tmp", "items": ["a", "b"]})
+
+    def test_code_block(self) -> None:
+        self.assertEqual(self._repaired('```json\n{"a": 1}\n```'), {"a": 1})
+
+    def test_python_literals(self) -> None:
+        self.assertEqual(self._repaired("{'recursive': True, 'path': None}"), {"recursive": True, "path": None})
+
+    def test_valid_json_is_unchanged(self) -> None:
+        self.assertEqual(repair_json('{"a": "b, c"}'), '{"a": "b, c"}')
+
+    def test_unrecoverable(self) -> None:
+        self.assertIsNone(repair_json("not json at all"))
+        self.assertIsNone(repair_json('{"a" 1}'))
@@ -0,0 +1,55 @@
+import json
+import typing as t
+from collections import deque
+
+from nerve.generation.cache import to_namespace
+from nerve.generation.client import Client
+from nerve.generation.errors import GeneratorError
+from nerve.models import Usage
+
+
+def text(content: str) -> dict[str, t.Any]:
+    """An assistant message with a text response."""
+
+    return {"role": "assistant", "content": content, "tool_calls": None}
+
+
+def tool_call(name: str, **args: t.Any) -> dict[str, t.Any]:
+    """An assistant message calling a tool with the given arguments."""
+
+    return {
+        "role": "assistant",
+        "content": None,
+        "tool_calls": [
+            {
+                "id": f"call_{name}",
+                "type": "function",
+                "function": {"name": name, "arguments": json.dumps(args)},
+            }
+        ],
+    }
+
+
+class ScriptedClient(Client):
+    """
+    Deterministic client returning pre-seeded messages, one per generation, in order to test agents offline.
+    """
+
+    def __init__(self, messages: list[dict[str, t.Any]], usage: Usage | None = None):
+        self.messages = deque(messages)
+        self.usage = usage or Usage()
+        # the conversations received, one per generation
+        self.conversations: list[list[dict[str, t.Any]]] = []
+
+    async def generate(
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+    ) -> tuple[Usage, t.Any]:
+        self.conversations.append(conversation)
+        if not self.messages:
+            raise GeneratorError("no more scripted messages", fatal=True)
+
+        return self.usage.model_copy(), to_namespace(self.messages.popleft())
+
+    async def health_check(self) -> None:
+        # the scripted messages are reserved to the run
+        pass
@@ -0,0 +1,45 @@
+import unittest
+
+from nerve.generation.testing import ScriptedClient, text, tool_call
+from nerve.models import Configuration, Status
+from nerve.runtime import state
+from nerve.runtime.agent import Agent
+
+
+class TestScriptedClient(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        state.reset()
+        state._extra_tools = {}
+
+    def tearDown(self) -> None:
+        state.reset()
+
+    async def test_agent_runs_offline(self) -> None:
+        client = ScriptedClient(
+            [
+                text("let me think about it"),
+                tool_call("task_complete_success", reason="the answer is 42"),
+            ]
+        )
+        agent = await Agent.create(
+            "openai/gpt-4o",
+            Configuration(agent="You are a helpful assistant.", task="What is the answer?", using=["task"]),
+            client=client,
+        )
+
+        await agent.step()
+        self.assertFalse(state.is_active_task_done())
+
+        await agent.step()
+        self.assertTrue(state.is_active_task_done())
+        self.assertEqual(state._task_status, Status.COMPLETED)
+        self.assertEqual(state._reason, "the answer is 42")
+
+        self.assertEqual(len(client.conversations), 2)
+        self.assertEqual(client.conversations[0][-1], {"role": "user", "content": "What is the answer?"})
+
+    async def test_exhausted_script(self) -> None:
+        client = ScriptedClient([])
+
+        with self.assertRaisesRegex(Exception, "no more scripted messages"):
+            await client.generate([], None)
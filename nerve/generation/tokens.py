@@ -0,0 +1,56 @@
+import functools
+from abc import ABC, abstractmethod
+
+import litellm
+from loguru import logger
+
+from nerve.defaults import DEFAULT_TOKENIZER
+
+# model families whose tokenizer is known to litellm, matched against the model name without the provider
+_KNOWN_FAMILIES = ("gpt-", "chatgpt-", "o1", "o3", "o4", "text-embedding-", "claude", "llama", "command")
+# average number of characters per token used to estimate the others
+_CHARS_PER_TOKEN = 4
+
+
+class TokenCounter(ABC):
+    @abstractmethod
+    def count(self, text: str) -> int:
+        pass
+
+
+class TokenizerCounter(TokenCounter):
+    """Count tokens with the tokenizer of the model, or the default OpenAI one if no model is given."""
+
+    def __init__(self, model: str | None = None) -> None:
+        self.model = model
+
+    def count(self, text: str) -> int:
+        if self.model is None:
+            return int(litellm.token_counter(text=text))  # type: ignore
+        return int(litellm.token_counter(model=self.model, text=text))  # type: ignore
+
+
+class EstimatedCounter(TokenCounter):
+    """Estimate the number of tokens from the number of characters."""
+
+    def count(self, text: str) -> int:
+        return -(-len(text) // _CHARS_PER_TOKEN)
+
+
+def has_known_tokenizer(model: str) -> bool:
+    name = model.split("/")[-1].lower()
+    return name.startswith(_KNOWN_FAMILIES)
+
+
+@functools.cache
+def get_token_counter(model: str) -> TokenCounter:
+    """Return the token counter for the model, cached so that the fallback is only reported once per model."""
+
+    if DEFAULT_TOKENIZER != "estimate" and has_known_tokenizer(model):
+        return TokenizerCounter(model)
+
+    if DEFAULT_TOKENIZER != "estimate":
+        logger.warning(f"no known tokenizer for {model}, token counts are estimated from the length of the text")
+
+    return EstimatedCounter()
+
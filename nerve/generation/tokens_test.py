@@ -0,0 +1,47 @@
+import unittest
+from unittest.mock import patch
+
+from nerve.generation.tokens import (
+    EstimatedCounter,
+    TokenizerCounter,
+    get_token_counter,
+    has_known_tokenizer,
+)
+
+
+class TestTokenCounters(unittest.TestCase):
+    def test_known_families(self) -> None:
+        self.assertTrue(has_known_tokenizer("openai/gpt-4o-mini"))
+        self.assertTrue(has_known_tokenizer("anthropic/claude-3-5-sonnet-20241022"))
+        self.assertTrue(has_known_tokenizer("groq/llama3-70b-8192"))
+        self.assertTrue(has_known_tokenizer("o3-mini"))
+        self.assertFalse(has_known_tokenizer("ollama/qwen2.5"))
+        self.assertFalse(has_known_tokenizer("gemini/gemini-2.0-flash"))
+
+    def test_uses_the_model_tokenizer(self) -> None:
+        counter = get_token_counter("openai/gpt-4o")
+
+        self.assertIsInstance(counter, TokenizerCounter)
+        with patch("nerve.generation.tokens.litellm.token_counter", return_value=7) as token_counter:
+            self.assertEqual(counter.count("hello world"), 7)
+
+        token_counter.assert_called_once_with(model="openai/gpt-4o", text="hello world")
+
+    def test_unknown_models_are_estimated_and_reported_once(self) -> None:
+        with patch("nerve.generation.tokens.logger.warning") as warning:
+            counter = get_token_counter("ollama/mistral-tokens-test")
+            get_token_counter("ollama/mistral-tokens-test")
+
+        self.assertIsInstance(counter, EstimatedCounter)
+        self.assertEqual(warning.call_count, 1)
+
+    def test_estimate_rounds_up(self) -> None:
+        counter = EstimatedCounter()
+
+        self.assertEqual(counter.count(""), 0)
+        self.assertEqual(counter.count("abcd"), 1)
+        self.assertEqual(counter.count("abcde"), 2)
+
+    @patch("nerve.generation.tokens.DEFAULT_TOKENIZER", "estimate")
+    def test_estimate_can_be_forced(self) -> None:
+        self.assertIsInstance(get_token_counter.__wrapped__("openai/gpt-4o"), EstimatedCounter)
@@ -0,0 +1,77 @@
+import copy
+import re
+import typing as t
+from abc import ABC, abstractmethod
+
+
+class MessageTransformer(ABC):
+    """
+    Transforms the text sent to the model, for instance to redact secrets and personal data.
+    """
+
+    @abstractmethod
+    def transform(self, text: str) -> str:
+        pass
+
+
+class RegexRedactor(MessageTransformer):
+    """
+    Replaces every match of the given regular expressions.
+    """
+
+    def __init__(self, patterns: list[str], replacement: str = "[REDACTED]"):
+        self.patterns = [re.compile(pattern) for pattern in patterns]
+        self.replacement = replacement
+
+    def transform(self, text: str) -> str:
+        for pattern in self.patterns:
+            text = pattern.sub(self.replacement, text)
+        return text
+
+
+def _transform_text(text: str, transformers: list[MessageTransformer]) -> str:
+    for transformer in transformers:
+        text = transformer.transform(text)
+    return text
+
+
+def _transform_content(content: t.Any, transformers: list[MessageTransformer]) -> t.Any:
+    if isinstance(content, str):
+        return _transform_text(content, transformers)
+
+    elif isinstance(content, list):
+        # only the text parts, images and files are sent as they are since their data urls would be corrupted
+        return [
+            {**part, "text": _transform_text(part["text"], transformers)}
+            if isinstance(part, dict) and part.get("type") == "text" and isinstance(part.get("text"), str)
+            else part
+            for part in content
+        ]
+
+    return content
+
+
+def transform_conversation(
+    conversation: list[dict[str, t.Any]], transformers: list[MessageTransformer]
+) -> list[dict[str, t.Any]]:
+    """
+    Apply the transformers to the text of every message, without altering the original conversation.
+    """
+
+    if not transformers:
+        return conversation
+
+    transformed = []
+    for message in copy.deepcopy(conversation):
+        if message.get("content"):
+            message["content"] = _transform_content(message["content"], transformers)
+
+        # only the arguments, names and ids must stay untouched
+        for tool_call in message.get("tool_calls") or []:
+            function = tool_call.get("function") or {}
+            if isinstance(function.get("arguments"), str):
+                function["arguments"] = _transform_text(function["arguments"], transformers)
+
+        transformed.append(message)
+
+    return transformed
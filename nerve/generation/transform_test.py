@@ -0,0 +1,58 @@
+import unittest
+
+from nerve.generation.transform import MessageTransformer, RegexRedactor, transform_conversation
+
+
+class Upper(MessageTransformer):
+    def transform(self, text: str) -> str:
+        return text.upper()
+
+
+class TestTransformConversation(unittest.TestCase):
+    def test_no_transformers(self) -> None:
+        conversation = [{"role": "user", "content": "hello"}]
+
+        self.assertIs(transform_conversation(conversation, []), conversation)
+
+    def test_redact_text_and_tool_calls(self) -> None:
+        conversation = [
+            {"role": "system", "content": "the api key is sk-12345"},
+            {
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "mail bob@example.com"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,sk-999"}},
+                    {"type": "file", "file": {"file_data": "data:application/pdf;base64,sk-999"}},
+                ],
+            },
+            {
+                "role": "assistant",
+                "content": None,
+                "tool_calls": [
+                    {
+                        "id": "sk-call",
+                        "type": "function",
+                        "function": {"name": "send", "arguments": '{"to": "bob@example.com"}'},
+                    }
+                ],
+            },
+        ]
+
+        redactor = RegexRedactor([r"sk-\d+", r"[\w.]+@[\w.]+"])
+        transformed = transform_conversation(conversation, [redactor])
+
+        self.assertEqual(transformed[0]["content"], "the api key is [REDACTED]")
+        self.assertEqual(transformed[1]["content"][0], {"type": "text", "text": "mail [REDACTED]"})
+        # the data of images and files would be corrupted
+        self.assertEqual(transformed[1]["content"][1:], conversation[1]["content"][1:])
+        self.assertEqual(transformed[2]["tool_calls"][0]["function"]["arguments"], '{"to": "[REDACTED]"}')
+        self.assertEqual(transformed[2]["tool_calls"][0]["id"], "sk-call")
+        # the original conversation is not altered
+        self.assertEqual(conversation[0]["content"], "the api key is sk-12345")
+
+    def test_transformers_are_chained(self) -> None:
+        transformed = transform_conversation(
+            [{"role": "user", "content": "token abc"}], [RegexRedactor(["abc"], "xyz"), Upper()]
+        )
+
+        self.assertEqual(transformed[0]["content"], "TOKEN XYZ")
@@ -37,16 +37,69 @@ class Usage(BaseModel):
     prompt_tokens: int = 0
     completion_tokens: int = 0
     total_tokens: int = 0
+    # part of the completion tokens used for reasoning, for the providers reporting it
+    reasoning_tokens: int = 0
+    # part of the prompt tokens read from or written to the prompt cache, for the providers reporting it
+    cache_read_tokens: int = 0
+    cache_write_tokens: int = 0
 
     def __add__(self, other: "Usage") -> "Usage":
         return Usage(
-            cost=(self.cost or 0) + (other.cost or 0),
+            # the cost stays unknown until at least one of the two is known
+            cost=None if self.cost is None and other.cost is None else (self.cost or 0) + (other.cost or 0),
             prompt_tokens=self.prompt_tokens + other.prompt_tokens,
             completion_tokens=self.completion_tokens + other.completion_tokens,
             total_tokens=self.total_tokens + other.total_tokens,
+            reasoning_tokens=self.reasoning_tokens + other.reasoning_tokens,
+            cache_read_tokens=self.cache_read_tokens + other.cache_read_tokens,
+            cache_write_tokens=self.cache_write_tokens + other.cache_write_tokens,
         )
 
 
+class SupportedFeatures(BaseModel):
+    """
+    What the model of a generator supports, what it doesn't is degraded or left out.
+    """
+
+    system_prompt: bool = True
+    tools: bool = True
+    files: bool = False
+    vision: bool = True
+
+
+class ToolStats(BaseModel):
+    """
+    Execution time statistics of a tool, in seconds.
+    """
+
+    count: int = 0
+    errors: int = 0
+    total_time: float = 0.0
+    min_time: float | None = None
+    max_time: float | None = None
+    mean_time: float = 0.0
+
+    def add(self, elapsed: float, error: bool = False) -> None:
+        self.count += 1
+        self.errors += int(error)
+        self.total_time += elapsed
+        self.min_time = elapsed if self.min_time is None else min(self.min_time, elapsed)
+        self.max_time = elapsed if self.max_time is None else max(self.max_time, elapsed)
+        self.mean_time = self.total_time / self.count
+
+
+class Pricing(BaseModel):
+    """
+    Cost in dollars per 1000 tokens of a model.
+    """
+
+    input: float
+    output: float
+
+    def get_cost(self, usage: Usage) -> float:
+        return (usage.prompt_tokens * self.input + usage.completion_tokens * self.output) / 1000
+
+
 class Tool(BaseModel):
     """
     A tool is a function that can be called by the agent.
@@ -72,6 +125,30 @@ class Tool(BaseModel):
     print: bool = False
     mime: str | None = None
     tool: str | None = None
+    # maximum number of concurrent calls, unlimited by default
+    max_concurrency: int | None = None
+    # maximum number of calls per second, unlimited by default
+    rate_limit: float | None = None
+
+
+class ToolDescriptor(BaseModel):
+    """
+    Serializable description of a tool available to the agent, for hosts rendering the toolset
+    or validating invocations.
+    """
+
+    name: str
+    description: str
+    # namespace the tool belongs to, if any
+    namespace: str | None = None
+    # JSON schema of the arguments
+    parameters: dict[str, t.Any] = {}
+    # example arguments, from the examples and defaults of the parameters when available
+    example: dict[str, t.Any] = {}
+    # the user is asked for confirmation before the tool is executed
+    requires_confirmation: bool = False
+    # calling the tool completes the task
+    completes_task: bool = False
 
 
 def _check_required_version(required: str | None) -> str | None:
@@ -125,6 +202,74 @@ class Configuration(BaseModel):
         max_cost: float | None = None
         timeout: int | None = None
 
+    class Summarization(BaseModel):
+        # summarize when the history has more than this number of messages
+        threshold: int = 50
+        # how many of the oldest messages are condensed into a single summary
+        messages: int = 30
+
+    class LoopDetection(BaseModel):
+        # how many of the most recent tool calls are checked for repetitions
+        window: int = 10
+        # the model is asked to change approach when the same call is repeated more than this in the window
+        threshold: int = 3
+        # optional number of identical calls in the window after which the task fails
+        max_repetitions: int | None = None
+
+    class SelfConsistency(BaseModel):
+        # how many answers are sampled in parallel for the final response, including the first one
+        samples: int = 5
+        # temperature of the samples, must be greater than 0 for the answers to differ
+        temperature: float = 0.7
+        # vote the most common answer, ask the model to pick the consensus among the answers,
+        # or vote structured answers and judge free text ones
+        strategy: t.Literal["auto", "vote", "judge"] = "auto"
+
+    class CircuitBreaker(BaseModel):
+        # consecutive failures of the provider after which the calls fail fast
+        failures: int = 5
+        # seconds before a single call probes whether the provider recovered
+        cooldown: float = 60.0
+
+    class RequestTimeout(BaseModel):
+        # seconds to establish the connection to the provider
+        connect: float = 10.0
+        # seconds for the whole request, each retry has its own timeout
+        total: float = 300.0
+
+    class PostProcessor(BaseModel):
+        # strip_ansi removes the terminal escape sequences, mask replaces the patterns and truncate cuts the output
+        type: t.Literal["strip_ansi", "mask", "truncate"]
+        # optional names of the tools to transform the output of, all of them if empty
+        tools: list[str] = []
+        # regular expressions to replace, for the mask type
+        patterns: list[str] = []
+        # maximum number of characters of the output, for the truncate type
+        max_length: int = 0
+
+    class Endpoint(BaseModel):
+        # base URL of an OpenAI compatible API, like an LLM gateway or a local server
+        base_url: str
+        # optional model served by the endpoint, replacing the generator
+        model: str | None = None
+        # environment variable, or secret, with the API key of the endpoint, OPENAI_API_KEY if not set
+        api_key_env: str | None = None
+        # for local servers that don't require authentication
+        no_auth: bool = False
+
+    class Example(BaseModel):
+        # a worked example, sent as a user message followed by the assistant response
+        user: str
+        assistant: str
+
+    class CompletionCheck(BaseModel):
+        # every step of the plan created with the planning namespace must be completed
+        plan: bool = False
+        # knowledge entries that must be set and not empty
+        knowledge: list[str] = []
+        # ask the model to verify that the task has actually been solved
+        self_check: bool = False
+
     # legacy field used to detect if the user is loading a legacy file
     system_prompt: str | None = Field(default=None, exclude=True)
 
@@ -144,12 +289,80 @@ class Configuration(BaseModel):
     defaults: dict[str, t.Any] = {}
     # builtin namespaces
     using: list[str] = []
+    # builtin namespaces the agent can activate at runtime with the toolbox namespace
+    optional_using: list[str] = []
     # jail mechanism for each namespace
     jail: dict[str, list[str]] = {}
+    # optional list of enabled tools for each namespace
+    allow: dict[str, list[str]] = {}
+    # optional list of disabled tools for each namespace
+    deny: dict[str, list[str]] = {}
+    # optional namespaces and tools, by name or as namespace.name, to list first in the given order
+    tool_priority: list[str] = []
+    # optional settings for each namespace, validated against the types of its variables
+    settings: dict[str, dict[str, t.Any]] = {}
     # MCP ( https://modelcontextprotocol.io/ ) servers.
     mcp: dict[str, MCPServer] = {}
     # optional limits
     limits: Limits | None = None
+    # optional summarization of the oldest messages of the history
+    summarize: Summarization | None = None
+    # optional detection of the same tool being called with the same arguments over and over
+    loop_detection: LoopDetection | None = None
+    # optional few-shot examples sent after the system prompt, never removed from the conversation window
+    examples: list[Example] = []
+    # optional checks that must pass before the task_complete_success tool can complete the task
+    completion_check: CompletionCheck | None = None
+    # tool outputs longer than this number of characters are split in chunks, NERVE_MAX_TOOL_OUTPUT_LENGTH if not set
+    max_tool_output_length: int | None = None
+    # if set, {{ variable }}, {{ global.NAME }} and {{ env.NAME }} references in the tool arguments are resolved
+    # before the execution: undefined references either fail the call or are replaced with an empty string
+    argument_templates: t.Literal["error", "empty"] | None = None
+    # what to do when a response is truncated because of the output tokens limit:
+    # report it to the model and ask to try again, or ask the model to continue it
+    on_truncation: t.Literal["report", "continue"] = "report"
+    # how many consecutive empty responses are retried before the task fails
+    max_empty_responses: int = 3
+    # optional sequences where the model stops generating
+    stop: list[str] = []
+    # auto (default), none, required or the name of a tool the model must call at the first step
+    tool_choice: str | None = None
+    # optional seed for reproducible outputs, for the providers supporting it
+    seed: int | None = None
+    # how much reasoning models should think before answering, ignored by the other models
+    reasoning_effort: t.Literal["low", "medium", "high"] | None = None
+    # maximum number of tokens reasoning models can use to think, for the providers supporting it
+    thinking_budget: int | None = None
+    # if set, the log probabilities of the generated tokens are requested along with this number of the most
+    # likely alternatives for each token, from 0 to 20, for the providers supporting it
+    logprobs: int | None = None
+    # optional sampling of the final answer multiple times, returning the consensus
+    self_consistency: SelfConsistency | None = None
+    # optional JSON schema the final response of the agent must conform to
+    response_format: dict[str, t.Any] | None = None
+    # optional OpenAI compatible endpoint serving the generator
+    endpoint: Endpoint | None = None
+    # generators to switch to, in order, when the generator is overloaded or unavailable
+    fallback_generators: list[str] = []
+    # optional timeout of the requests to the generator, timed out requests are retried and failed over
+    request_timeout: RequestTimeout | None = None
+    # optional protection against sustained outages of the provider, shared by the agents using the same generator
+    circuit_breaker: CircuitBreaker | None = None
+    # optional identity of the end user the agent is serving, sent hashed to the providers supporting it
+    # (like OpenAI) for abuse monitoring
+    end_user: str | None = None
+    # mark the system prompt and the tools as cacheable, for the providers supporting explicit prompt caching
+    prompt_caching: bool = True
+    # optional vision model describing the images returned by the tools when the generator can't see them
+    vision_generator: str | None = None
+    # optional embedding model used by the rag namespace, NERVE_EMBEDDER if not set
+    embedder: str | None = None
+    # optional per-model pricing, overrides the litellm database
+    pricing: dict[str, Pricing] = {}
+    # optional transformations of the successful tool outputs, applied in order before they are sent to the model
+    post_processors: list[PostProcessor] = []
+    # optional regular expressions of secrets and personal data to redact from anything sent to the model
+    redact: list[str] = []
     # custom tooling
     tools: list[Tool | t.Callable[..., t.Any]] = []
 
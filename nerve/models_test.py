@@ -2,7 +2,7 @@ import pathlib
 import tempfile
 import unittest
 
-from nerve.models import Configuration, Tool
+from nerve.models import Configuration, Tool, Usage
 
 
 class TestConfiguration(unittest.TestCase):
@@ -137,3 +137,11 @@ class TestConfiguration(unittest.TestCase):
         self.assertIn("input1", inputs)
         self.assertNotIn("tool_name", inputs)  # Should be excluded as it's a tool
         self.assertNotIn("param", inputs)  # Should be excluded as it's a parameter to a tool call
+
+
+class TestUsage(unittest.TestCase):
+    def test_unknown_cost_is_preserved(self) -> None:
+        self.assertIsNone((Usage(total_tokens=1) + Usage(total_tokens=2)).cost)
+
+    def test_known_cost_is_summed(self) -> None:
+        self.assertEqual((Usage(cost=1.5) + Usage() + Usage(cost=0.5)).cost, 2.0)
@@ -37,12 +37,34 @@ class Runtime:
         )
         runtime = cls(name=name, generator=configuration.generator or "", working_dir=working_dir)
 
+        # settings of the namespaces activated at runtime are validated now, so that errors fail the load
+        for namespace, settings in configuration.settings.items():
+            if namespace not in configuration.using + configuration.optional_using:
+                raise ValueError(f"settings are given for namespace {namespace}, which is not used by the agent")
+            if namespace not in configuration.using:
+                compiler.validate_namespace_settings(namespace, settings)
+
         # import tools from builtin namespaces
-        ns_tools = compiler.get_tools_from_namespaces(configuration.using, configuration.jail)
+        ns_tools = compiler.get_tools_from_namespaces(
+            configuration.using,
+            configuration.jail,
+            configuration.allow,
+            configuration.deny,
+            configuration.settings,
+        )
         if ns_tools:
             logger.debug(f"🧰 importing {len(ns_tools)} tools from: {configuration.using}")
             runtime.tools.extend(ns_tools)
 
+        # the rag namespace embeds with its own model rather than with the chat generator,
+        # including when it is activated at runtime
+        if "rag" in configuration.using + configuration.optional_using:
+            from nerve.tools.namespaces import rag
+
+            if configuration.embedder:
+                rag.embedder = configuration.embedder
+            rag.end_user = configuration.end_user
+
         # import custom tools from yaml definition
         yml_tools = compiler.get_tools_from_yml(
             working_dir,
@@ -79,6 +101,12 @@ class Runtime:
             logger.info(f"🧰 importing {len(server_tools)} tools from MCP server {name}")
             runtime.tools.extend(server_tools)
 
+        # the order of the tools influences which one some models pick
+        if configuration.tool_priority:
+            runtime.tools = compiler.prioritize_tools(
+                runtime.tools, configuration.tool_priority, configuration.optional_using
+            )
+
         logger.debug(f"all runtime tools: {runtime.tools}")
 
         return runtime
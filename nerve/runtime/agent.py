@@ -1,14 +1,22 @@
+import json
 import pathlib
+import typing as t
 
 import click
 from loguru import logger
 
 import nerve.runtime.state as state
 from nerve.generation import Engine, WindowStrategy
+from nerve.generation.client import Client
 from nerve.generation.conversation import FullHistoryStrategy
+from nerve.generation.errors import ModelUnavailableError, OverloadedError
 from nerve.generation.litellm import LiteLLMEngine
+from nerve.generation.transform import RegexRedactor
 from nerve.models import Configuration, Tool, Usage
 from nerve.runtime import Runtime
+from nerve.tools import postprocess
+from nerve.tools.middleware import Middleware
+from nerve.tools.postprocess import PostProcessor
 
 
 class Agent:
@@ -18,6 +26,7 @@ class Agent:
         configuration: Configuration,
         generation_engine: Engine,
         conv_window_strategy: WindowStrategy,
+        engine_factory: t.Callable[[str], Engine] | None = None,
     ):
         # runtime data
         self.runtime = runtime
@@ -29,8 +38,22 @@ class Agent:
         self.conv_window_strategy = conv_window_strategy
         # user message to be added to the conversation at runtime if in interactive mode
         self._extra_message: str | None = None
+        # creates the engine of the next fallback generator when the current one is unavailable
+        self._engine_factory = engine_factory
+        self._fallback_generators = list(configuration.fallback_generators) if engine_factory else []
+        self.generation_engine.failover = bool(self._fallback_generators)
 
         state.on_event("agent_created", {"agent": self})
+        self._on_generator_features()
+
+    def _on_generator_features(self) -> None:
+        state.on_event(
+            "generator_features",
+            {
+                "generator": self.generation_engine.generator_id,
+                "features": self.generation_engine.supported_features.model_dump(),
+            },
+        )
 
     @classmethod
     async def create(
@@ -41,6 +64,7 @@ class Agent:
         window_strategy: WindowStrategy = FullHistoryStrategy(),
         working_dir: pathlib.Path = pathlib.Path.cwd(),
         name: str = "agent",
+        client: Client | None = None,
     ) -> "Agent":
         """
         Create an agent from a generator and configuration.
@@ -52,6 +76,7 @@ class Agent:
             window_strategy: How to handle conversation history.
             working_dir: The working directory to use.
             name: The name of the agent.
+            client: Optional client to use instead of the generator provider, like a ScriptedClient in tests.
         """
 
         if start_state:
@@ -70,6 +95,9 @@ class Agent:
             )
 
         configuration.generator = configuration.generator or generator
+        if configuration.endpoint and configuration.endpoint.model:
+            # served by an OpenAI compatible API
+            configuration.generator = f"openai/{configuration.endpoint.model}"
 
         runtime = await Runtime.build(
             working_dir=working_dir,
@@ -77,13 +105,56 @@ class Agent:
             configuration=configuration,
         )
 
-        return cls(
+        redactor = RegexRedactor(configuration.redact) if configuration.redact else None
+        audit_trail = state.get_audit_trail()
+        if redactor and audit_trail:
+            # secrets must not end up in the audit trail either
+            audit_trail.add_transformer(redactor)
+
+        def create_engine(generator: str) -> Engine:
+            return LiteLLMEngine(
+                generator,
+                window_strategy,
+                runtime.tools,
+                configuration.response_format,
+                configuration.pricing,
+                [redactor] if redactor else None,
+                configuration.on_truncation,
+                configuration.stop,
+                configuration.tool_choice,
+                configuration.max_empty_responses,
+                configuration.seed,
+                configuration.reasoning_effort,
+                configuration.thinking_budget,
+                configuration.loop_detection,
+                client,
+                configuration.max_tool_output_length,
+                configuration.argument_templates,
+                configuration.logprobs,
+                configuration.self_consistency,
+                configuration.end_user,
+                configuration.circuit_breaker,
+                configuration.prompt_caching,
+                configuration.vision_generator,
+                # the fallback generators are served by their own providers
+                configuration.endpoint if generator == configuration.generator else None,
+                configuration.examples,
+                configuration.request_timeout,
+            )
+
+        agent = cls(
             runtime=runtime,
             configuration=configuration,
-            generation_engine=LiteLLMEngine(configuration.generator, window_strategy, runtime.tools),
+            generation_engine=create_engine(configuration.generator),
             conv_window_strategy=window_strategy,
+            engine_factory=create_engine,
         )
 
+        for processor in configuration.post_processors:
+            agent.add_post_processor(postprocess.from_config(processor))
+
+        return agent
+
     @classmethod
     async def create_from_file(
         cls,
@@ -91,6 +162,7 @@ class Agent:
         config_file_path: pathlib.Path,
         window_strategy: WindowStrategy = FullHistoryStrategy(),
         start_state: dict[str, str] | None = None,
+        client: Client | None = None,
     ) -> "Agent":
         config = Configuration.from_path(config_file_path)
         if config.is_legacy:
@@ -106,6 +178,7 @@ class Agent:
             window_strategy,
             working_dir,
             stem if stem not in ("task", "agent") else working_dir.stem,
+            client,
         )
 
     def _get_system_prompt(self) -> str | None:
@@ -115,7 +188,9 @@ class Agent:
         raw = self.configuration.agent
 
         for name, value in state.get_knowledge().items():
-            raw += f"\n\n## {name.capitalize()}\n\n{value}"
+            # typed values are rendered as JSON
+            rendered = value if isinstance(value, str) else json.dumps(value, indent=2, default=str)
+            raw += f"\n\n## {name.capitalize()}\n\n{rendered}"
 
         return state.interpolate(raw, working_dir=self.runtime.working_dir)
 
@@ -128,6 +203,83 @@ class Agent:
     def add_extra_message(self, message: str) -> None:
         self._extra_message = message
 
+    async def health_check(self) -> None:
+        """Raise a GeneratorError if the generator of the agent can't be used, in order to fail before the run."""
+        await self.generation_engine.health_check()
+
+    def add_middleware(self, middleware: Middleware) -> None:
+        """Run the given middleware around every tool call, after the ones already registered."""
+
+        self.generation_engine.middlewares.append(middleware)
+
+    def add_post_processor(self, processor: PostProcessor) -> None:
+        """Transform the successful tool outputs with the given processor, after the ones already registered."""
+
+        self.generation_engine.post_processors.append(processor)
+
+    def _switch_generator(self, error: Exception) -> None:
+        previous = self.generation_engine
+        generator = self._fallback_generators.pop(0)
+        engine = self._engine_factory(generator)  # type: ignore
+
+        # the run continues where it was
+        engine.history = previous.history
+        engine.middlewares = previous.middlewares
+        engine.post_processors = previous.post_processors
+        engine.recent_calls = previous.recent_calls
+        engine.output_chunks = previous.output_chunks
+        engine.failover = bool(self._fallback_generators)
+
+        self.generation_engine = engine
+        self.runtime.generator = engine.generator_id
+
+        state.on_event(
+            "generator_switched",
+            {"from": previous.generator_id, "to": engine.generator_id, "error": str(error)},
+        )
+        self._on_generator_features()
+
+    async def _step_with_fallback(
+        self,
+        system_prompt: str | None,
+        prompt: str,
+        extra_tools: dict[str, t.Callable[..., t.Any]],
+        extra_message: str | None,
+    ) -> Usage:
+        while True:
+            try:
+                return await self.generation_engine.step(system_prompt, prompt, extra_tools, extra_message)
+            except (OverloadedError, ModelUnavailableError) as e:
+                if not self._fallback_generators:
+                    raise
+
+                self._switch_generator(e)
+
+    def cancel(self, reason: str = "cancelled") -> None:
+        """Cancel the agent run, can be called from any thread."""
+
+        state.cancel(reason)
+
+    async def _summarize_if_needed(self) -> Usage:
+        summarize = self.configuration.summarize
+        if summarize is None or len(self.generation_engine.history) <= summarize.threshold:
+            return Usage()
+
+        logger.debug(f"summarizing the oldest {summarize.messages} messages")
+        while True:
+            try:
+                return await self.generation_engine.summarize_history(summarize.messages)
+            except (OverloadedError, ModelUnavailableError) as e:
+                if not self._fallback_generators:
+                    logger.warning(f"can't summarize the history, continuing without summarizing it: {e}")
+                    return Usage()
+
+                self._switch_generator(e)
+            except Exception as e:
+                # the step can still be executed with the whole history
+                logger.warning(f"can't summarize the history, continuing without summarizing it: {e}")
+                return Usage()
+
     async def step(self) -> Usage:
         logger.debug(f"agent {self.runtime.name} step")
 
@@ -158,7 +310,8 @@ class Agent:
                 },
             )
 
-            usage = await self.generation_engine.step(system_prompt, prompt, extra_tools, extra_message)
+            usage = await self._summarize_if_needed()
+            usage += await self._step_with_fallback(system_prompt, prompt, extra_tools, extra_message)
             logger.debug(f"usage: {usage}")
             return usage
         except click.exceptions.MissingParameter as e:
@@ -0,0 +1,127 @@
+import typing as t
+import unittest
+from unittest.mock import AsyncMock
+
+from nerve.generation.errors import GeneratorError, OverloadedError
+from nerve.generation.testing import ScriptedClient, text, tool_call
+from nerve.models import Configuration, Usage
+from nerve.runtime import state
+from nerve.runtime.agent import Agent
+
+
+class OverloadedClient(ScriptedClient):
+    """Fails while the generator is the first one."""
+
+    def __init__(self, messages: list[dict[str, t.Any]]):
+        super().__init__(messages, Usage(prompt_tokens=10, completion_tokens=5, total_tokens=15))
+        self.agent: Agent | None = None
+
+    async def generate(
+        self, conversation: list[dict[str, t.Any]], tools_schema: list[dict[str, t.Any]] | None
+    ) -> tuple[Usage, t.Any]:
+        if self.agent is not None and self.agent.generation_engine.generator_id == "openai/gpt-4o":
+            raise OverloadedError("the model is overloaded")
+        return await super().generate(conversation, tools_schema)
+
+
+class TestFallbackGenerators(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        state.reset()
+        state._extra_tools = {}
+        state._events = []
+
+    def tearDown(self) -> None:
+        state.reset()
+
+    async def _create(
+        self, client: ScriptedClient, fallbacks: list[str], summarize: Configuration.Summarization | None = None
+    ) -> Agent:
+        return await Agent.create(
+            "openai/gpt-4o",
+            Configuration(
+                agent="You are a helpful assistant.",
+                task="What is the answer?",
+                using=["task"],
+                fallback_generators=fallbacks,
+                summarize=summarize,
+            ),
+            client=client,
+        )
+
+    async def test_switch_to_fallback(self) -> None:
+        client = OverloadedClient([text("thinking"), tool_call("task_complete_success", reason="42")])
+        agent = await self._create(client, ["anthropic/claude-sonnet-4", "groq/llama-3.3-70b"])
+        client.agent = agent
+
+        usage = await agent.step()
+
+        self.assertEqual(agent.generation_engine.generator_id, "anthropic/claude-sonnet-4")
+        self.assertEqual(agent.runtime.generator, "anthropic/claude-sonnet-4")
+        self.assertEqual(usage.total_tokens, 15)
+        self.assertTrue(agent.generation_engine.failover)
+        switched = [event for event in state._events if event.name == "generator_switched"]
+        self.assertEqual(
+            switched[0].data,
+            {"from": "openai/gpt-4o", "to": "anthropic/claude-sonnet-4", "error": "the model is overloaded"},
+        )
+
+        # the history carries over
+        history = agent.generation_engine.history
+        await agent.step()
+        self.assertIs(agent.generation_engine.history, history)
+        self.assertTrue(state.is_active_task_done())
+
+    async def test_summarization_uses_the_fallback(self) -> None:
+        client = OverloadedClient([text("they talked"), tool_call("task_complete_success", reason="42")])
+        summarize = Configuration.Summarization(threshold=1, messages=2)
+        agent = await self._create(client, ["anthropic/claude-sonnet-4"], summarize)
+        client.agent = agent
+        agent.generation_engine.history = [{"role": "user", "content": "hi"}, {"role": "assistant", "content": "hey"}]
+
+        await agent.step()
+
+        self.assertEqual(agent.generation_engine.generator_id, "anthropic/claude-sonnet-4")
+        self.assertIn("they talked", agent.generation_engine.history[0]["content"])
+        self.assertTrue(state.is_active_task_done())
+
+    async def test_failed_summarization_does_not_end_the_run(self) -> None:
+        client = ScriptedClient([tool_call("task_complete_success", reason="42")])
+        agent = await self._create(client, [], Configuration.Summarization(threshold=1, messages=2))
+        history = [{"role": "user", "content": "hi"}, {"role": "assistant", "content": "hey"}]
+        agent.generation_engine.history = list(history)
+        agent.generation_engine.summarize_history = AsyncMock(  # type: ignore
+            side_effect=GeneratorError("bad response")
+        )
+
+        await agent.step()
+
+        # the step is executed with the whole history
+        self.assertEqual(agent.generation_engine.history[:2], history)
+        self.assertTrue(state.is_active_task_done())
+
+    async def test_failover_is_disabled_without_fallbacks(self) -> None:
+        agent = await self._create(ScriptedClient([]), [])
+
+        self.assertFalse(agent.generation_engine.failover)
+
+
+class TestGeneratorFeatures(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        state.reset()
+        state._extra_tools = {}
+        state._events = []
+
+    def tearDown(self) -> None:
+        state.reset()
+
+    async def test_features_are_reported(self) -> None:
+        await Agent.create("openai/gpt-4o", Configuration(agent="a", task="b"), client=ScriptedClient([]))
+
+        features = [event for event in state._events if event.name == "generator_features"]
+        self.assertEqual(
+            features[0].data,
+            {
+                "generator": "openai/gpt-4o",
+                "features": {"system_prompt": True, "tools": True, "files": True, "vision": True},
+            },
+        )
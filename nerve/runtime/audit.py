@@ -0,0 +1,114 @@
+import hashlib
+import json
+import pathlib
+import time
+import typing as t
+
+from nerve.generation.transform import MessageTransformer
+
+# hash of the (virtual) entry preceding the first one
+GENESIS_HASH = "0" * 64
+
+
+def _get_hash(entry: dict[str, t.Any]) -> str:
+    # the hash covers every field of the entry, including the hash of the previous one
+    data = json.dumps({k: v for k, v in entry.items() if k != "hash"}, sort_keys=True, default=str)
+    return hashlib.sha256(data.encode()).hexdigest()
+
+
+def _redact(value: t.Any, transformers: list[MessageTransformer]) -> t.Any:
+    if isinstance(value, str):
+        for transformer in transformers:
+            value = transformer.transform(value)
+        return value
+
+    elif isinstance(value, dict):
+        return {key: _redact(item, transformers) for key, item in value.items()}
+
+    elif isinstance(value, list | tuple):
+        return [_redact(item, transformers) for item in value]
+
+    elif value is None or isinstance(value, bool | int | float):
+        return value
+
+    return _redact(str(value), transformers)
+
+
+class AuditTrail:
+    """
+    Append-only record of every tool executed, where each entry is chained to the previous one
+    by its hash so that any modification of the file can be detected with verify_audit_trail.
+    """
+
+    def __init__(self, path: pathlib.Path, transformers: list[MessageTransformer] | None = None):
+        self.path = path.absolute()
+        self.transformers = transformers or []
+        self.last_hash = GENESIS_HASH
+
+        # continue an existing trail
+        if self.path.exists():
+            with open(self.path) as f:
+                for line in f:
+                    if line.strip():
+                        self.last_hash = json.loads(line)["hash"]
+
+    def add_transformer(self, transformer: MessageTransformer) -> None:
+        """Redact the arguments and results of the next entries with the given transformer."""
+
+        self.transformers.append(transformer)
+
+    def record(
+        self,
+        name: str,
+        args: t.Any | None,
+        result: t.Any | None,
+        error: t.Any | None,
+        elapsed: float,
+    ) -> dict[str, t.Any]:
+        """Append an entry for an executed tool."""
+
+        entry: dict[str, t.Any] = {
+            "timestamp": time.time(),
+            "name": name,
+            "args": _redact(args, self.transformers),
+            "result": _redact(result, self.transformers),
+            "status": "error" if error is not None else "ok",
+            "error": _redact(error, self.transformers),
+            "elapsed": elapsed,
+            "previous_hash": self.last_hash,
+        }
+        entry["hash"] = _get_hash(entry)
+
+        self.path.parent.mkdir(parents=True, exist_ok=True)
+        with open(self.path, "a+t") as f:
+            f.write(json.dumps(entry) + "\n")
+
+        self.last_hash = entry["hash"]
+
+        return entry
+
+
+def verify_audit_trail(path: pathlib.Path) -> int | None:
+    """
+    Walk the chain of entries and return the index of the first broken one, or None if the trail is intact.
+    """
+
+    previous_hash = GENESIS_HASH
+    with open(path) as f:
+        lines = [line for line in f if line.strip()]
+
+    for index, line in enumerate(lines):
+        try:
+            entry = json.loads(line)
+        except json.JSONDecodeError:
+            return index
+
+        if not isinstance(entry, dict) or entry.get("previous_hash") != previous_hash:
+            return index
+
+        if entry.get("hash") != _get_hash(entry):
+            return index
+
+        previous_hash = entry["hash"]
+
+    return None
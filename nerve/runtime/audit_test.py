@@ -0,0 +1,82 @@
+import json
+import pathlib
+import tempfile
+import unittest
+
+from nerve.generation.transform import RegexRedactor
+from nerve.runtime.audit import GENESIS_HASH, AuditTrail, verify_audit_trail
+
+
+class TestAuditTrail(unittest.TestCase):
+    def setUp(self) -> None:
+        self.temp_dir = tempfile.TemporaryDirectory()
+        self.path = pathlib.Path(self.temp_dir.name) / "audit.jsonl"
+
+    def tearDown(self) -> None:
+        self.temp_dir.cleanup()
+
+    def _read_entries(self) -> list[dict]:
+        return [json.loads(line) for line in self.path.read_text().splitlines()]
+
+    def _write_entries(self, entries: list[dict]) -> None:
+        self.path.write_text("".join(json.dumps(entry) + "\n" for entry in entries))
+
+    def test_entries_are_chained(self) -> None:
+        trail = AuditTrail(self.path)
+        trail.record("read_file", {"path": "a.txt"}, "content", None, 0.5)
+        trail.record("delete_file", {"path": "b.txt"}, None, "not found", 0.1)
+
+        entries = self._read_entries()
+        self.assertEqual(len(entries), 2)
+        self.assertEqual(entries[0]["previous_hash"], GENESIS_HASH)
+        self.assertEqual(entries[1]["previous_hash"], entries[0]["hash"])
+        self.assertEqual(entries[0]["status"], "ok")
+        self.assertEqual(entries[1]["status"], "error")
+        self.assertEqual(entries[1]["elapsed"], 0.1)
+        self.assertIsNone(verify_audit_trail(self.path))
+
+    def test_continues_existing_trail(self) -> None:
+        AuditTrail(self.path).record("first", {}, "ok", None, 0.0)
+        AuditTrail(self.path).record("second", {}, "ok", None, 0.0)
+
+        entries = self._read_entries()
+        self.assertEqual(entries[1]["previous_hash"], entries[0]["hash"])
+        self.assertIsNone(verify_audit_trail(self.path))
+
+    def test_redaction(self) -> None:
+        trail = AuditTrail(self.path)
+        trail.add_transformer(RegexRedactor([r"sk-\w+"]))
+        trail.record("http_get", {"headers": {"Authorization": "Bearer sk-secret"}}, ["sk-other"], None, 0.0)
+
+        entry = self._read_entries()[0]
+        self.assertEqual(entry["args"], {"headers": {"Authorization": "Bearer [REDACTED]"}})
+        self.assertEqual(entry["result"], ["[REDACTED]"])
+
+    def test_detects_modified_entry(self) -> None:
+        trail = AuditTrail(self.path)
+        for i in range(3):
+            trail.record("tool", {"i": i}, "ok", None, 0.0)
+
+        entries = self._read_entries()
+        entries[1]["args"] = {"i": 42}
+        self._write_entries(entries)
+
+        self.assertEqual(verify_audit_trail(self.path), 1)
+
+    def test_detects_removed_entry(self) -> None:
+        trail = AuditTrail(self.path)
+        for i in range(3):
+            trail.record("tool", {"i": i}, "ok", None, 0.0)
+
+        entries = self._read_entries()
+        del entries[1]
+        self._write_entries(entries)
+
+        self.assertEqual(verify_audit_trail(self.path), 1)
+
+    def test_detects_invalid_line(self) -> None:
+        AuditTrail(self.path).record("tool", {}, "ok", None, 0.0)
+        with open(self.path, "a") as f:
+            f.write("not json\n")
+
+        self.assertEqual(verify_audit_trail(self.path), 1)
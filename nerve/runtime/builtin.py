@@ -7,10 +7,16 @@ import string
 import time
 import typing as t
 
-import requests
 from loguru import logger
 
 
+def _get_public_ip() -> str:
+    # imported here to avoid a circular import with the state module
+    from nerve.runtime import network
+
+    return str(network.request("GET", "https://api.ipify.org?format=json", timeout=10).json()["ip"])
+
+
 def _read_clipboard() -> str:
     try:
         import pyperclip
@@ -50,7 +56,7 @@ _builtins: dict[str, t.Callable[[], str]] = {
     "WORKING_DIR": lambda: os.getcwd(),
     # network
     "LOCAL_IP": lambda: socket.gethostbyname(socket.gethostname()),
-    "PUBLIC_IP": lambda: requests.get("https://api.ipify.org?format=json").json()["ip"],
+    "PUBLIC_IP": _get_public_ip,
     "HOSTNAME": lambda: socket.gethostname(),
     # misc
     "RANDOM_INT": lambda: str(random.randint(0, 10000)),
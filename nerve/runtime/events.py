@@ -8,3 +8,6 @@ class Event(BaseModel):
     timestamp: float = Field(default_factory=time.time)
     name: str
     data: t.Any | None = None
+    # the run emitting the event and the step it was emitted at, unset outside of a run
+    run_id: str | None = None
+    step: int | None = None
@@ -1,16 +1,23 @@
+import asyncio
+import json
+import math
 import pathlib
 import time
+import typing as t
 
 from loguru import logger
 
 import nerve.runtime.state as state
+from nerve.defaults import DEFAULT_MAX_STEPS_WARNING
 from nerve.generation import WindowStrategy
 from nerve.generation.conversation import FullHistoryStrategy
-from nerve.models import Workflow
+from nerve.models import Usage, Workflow
 from nerve.runtime.agent import Agent
 from nerve.runtime.shell import Shell
 
 IS_ACTIVE: bool = False
+# the flow currently running in this process
+ACTIVE_FLOW: "Flow | None" = None
 
 
 class Flow:
@@ -22,12 +29,13 @@ class Flow:
         max_cost: float = 10.0,
         timeout: int | None = None,
     ):
-        global IS_ACTIVE
+        global IS_ACTIVE, ACTIVE_FLOW
 
         if IS_ACTIVE:
             raise RuntimeError("A flow is already running")
 
         IS_ACTIVE = True
+        ACTIVE_FLOW = self
 
         # all actors in the flow
         self.actors = actors
@@ -38,8 +46,14 @@ class Flow:
         self.curr_actor: Agent | None = None
         # current step from the beginning of the flow
         self.curr_step: int = 1
+        # steps taken by the subtasks, they count towards the max steps
+        self.consumed_steps: int = 0
         # optional max steps to run
         self.max_steps: int = max_steps
+        # fraction of the max steps after which the agent is warned, 0 to disable
+        self.max_steps_warning: float = DEFAULT_MAX_STEPS_WARNING
+        # set once the agent has been warned
+        self.max_steps_warned: bool = False
         # max cost to run the flow
         self.max_cost: float = max_cost
         # optional timeout to run the flow
@@ -48,6 +62,10 @@ class Flow:
         self.started_at: float | None = None
         # interactive shell
         self.shell: Shell = Shell()
+        # optional file where the flow is checkpointed after every step
+        self.checkpoint: pathlib.Path | None = None
+        # attached to every event, to tell apart the events of concurrent runs
+        self.run_id: str = state.start_run()
 
     @classmethod
     async def build(
@@ -113,6 +131,31 @@ class Flow:
             state.set_defaults(self.curr_actor.configuration.defaults)
             state.on_task_started(self.curr_actor)
 
+    def get_steps_left(self) -> int:
+        """Get the number of steps left, including the current one, or 0 if there's no limit."""
+
+        if self.max_steps <= 0:
+            return 0
+
+        return max(0, self.max_steps - self.curr_step - self.consumed_steps + 1)
+
+    def _warn_if_running_out_of_steps(self) -> None:
+        if self.max_steps <= 0 or self.max_steps_warning <= 0 or self.curr_actor is None or self.max_steps_warned:
+            return
+
+        # warn once, at the first step past the threshold, that subtasks might have jumped over
+        step = self.curr_step + self.consumed_steps
+        if step < max(1, math.ceil(self.max_steps * self.max_steps_warning)):
+            return
+
+        self.max_steps_warned = True
+        remaining = self.get_steps_left()
+        state.on_event("max_steps_approaching", {"step": step, "max_steps": self.max_steps, "remaining": remaining})
+        self.curr_actor.add_extra_message(
+            f"You have only {remaining} steps left before the task is stopped, "
+            "focus on completing it or report what you found so far."
+        )
+
     async def step(self) -> None:
         logger.debug("flow.step")
 
@@ -123,29 +166,130 @@ class Flow:
             state.on_event("flow_complete", {"steps": self.curr_step - 1, "usage": state.get_usage()})
             return
 
+        state.set_step(self.curr_step)
         state.on_event("step_started", {"step": self.curr_step, "usage": state.get_usage()})
+        self._warn_if_running_out_of_steps()
+
+        step_usage = await self._run_cancellable(self.curr_actor.step())  # type: ignore
+        if step_usage is None:
+            logger.debug("step cancelled")
+            return
 
-        step_usage = await self.curr_actor.step()  # type: ignore
         logger.debug(f"step usage: {step_usage}")
 
         # increment total usage
         state.update_usage(step_usage)
-        state.on_event("step_complete", {"step": self.curr_step, "usage": state.get_usage()})
+        state.on_event(
+            "step_complete",
+            {
+                "step": self.curr_step,
+                "step_usage": step_usage,
+                "usage": state.get_usage(),
+                "tool_stats": state.get_tool_stats(),
+            },
+        )
 
         if state.is_active_task_done():
             logger.debug(f"task {self.curr_actor.runtime.name} complete")  # type: ignore
             self.curr_actor_idx += 1
             self.curr_actor = None
-            state.reset()
+            # the tool stats, the audit trail and the replay are kept for the next task
+            state.reset_task()
 
         self.curr_step += 1
 
+        if self.checkpoint:
+            self.save_snapshot(self.checkpoint)
+
+    def get_snapshot(self) -> dict[str, t.Any]:
+        """Get a serializable snapshot of the flow, used to resume it later in another process."""
+
+        return {
+            "run_id": self.run_id,
+            "step": self.curr_step,
+            "consumed_steps": self.consumed_steps,
+            "actor": self.curr_actor_idx,
+            "state": state.get_snapshot(),
+            "histories": [actor.generation_engine.history for actor in self.actors],
+        }
+
+    def restore_snapshot(self, snapshot: dict[str, t.Any]) -> None:
+        """Resume the flow from a snapshot, the agents must be the same of the snapshotted flow."""
+
+        if len(snapshot["histories"]) != len(self.actors):
+            raise ValueError(f"the snapshot has {len(snapshot['histories'])} agents, the flow has {len(self.actors)}")
+
+        # resumed runs keep their id, older snapshots have none
+        if snapshot.get("run_id"):
+            self.run_id = state.start_run(snapshot["run_id"])
+        self.curr_step = snapshot["step"]
+        # older snapshots don't have it
+        self.consumed_steps = snapshot.get("consumed_steps", 0)
+        self.curr_actor_idx = snapshot["actor"]
+        self.curr_actor = None
+        for actor, history in zip(self.actors, snapshot["histories"]):
+            actor.generation_engine.history = history
+
+        state.restore_snapshot(snapshot["state"])
+
+    def save_snapshot(self, path: pathlib.Path) -> None:
+        """Save a snapshot of the flow to a file."""
+
+        # write and rename so that a crash never leaves a partial snapshot behind
+        temp_path = path.with_suffix(path.suffix + ".tmp")
+        with open(temp_path, "w") as f:
+            json.dump(self.get_snapshot(), f, cls=state.CustomJSONEncoder)
+        temp_path.replace(path)
+
+    def enable_checkpoints(self, path: pathlib.Path) -> None:
+        """Save a snapshot after every step, resuming from it if it already exists."""
+
+        if path.exists():
+            logger.info(f"⏯️  resuming from {path} ...")
+            with open(path) as f:
+                self.restore_snapshot(json.load(f))
+
+        self.checkpoint = path
+
+    def _is_timed_out(self) -> bool:
+        return self.timeout is not None and self.started_at is not None and time.time() - self.started_at > self.timeout
+
+    async def _run_cancellable(self, coro: t.Coroutine[t.Any, t.Any, Usage]) -> Usage | None:
+        """Run a step, interrupting it if the run is cancelled or times out."""
+
+        task = asyncio.ensure_future(coro)
+        while True:
+            done, _ = await asyncio.wait({task}, timeout=0.1)
+            if done:
+                return task.result()
+
+            if self._is_timed_out():
+                state.cancel("timeout reached")
+
+            if state.is_cancelled():
+                task.cancel()
+                try:
+                    await task
+                except asyncio.CancelledError:
+                    pass
+                return None
+
+    def cancel(self, reason: str = "cancelled") -> None:
+        """Cancel the flow, interrupting the current step."""
+
+        state.cancel(reason)
+
     def done(self) -> bool:
         if self.curr_actor_idx >= len(self.actors):
             logger.debug("all actors done")
             return True
 
-        if self.max_steps > 0 and self.curr_step > self.max_steps:
+        if state.is_cancelled():
+            logger.debug("flow cancelled")
+            state.on_cancelled()
+            return True
+
+        if self.max_steps > 0 and self.curr_step + self.consumed_steps > self.max_steps:
             logger.debug("max steps reached")
             state.on_max_steps_reached()
             return True
@@ -156,7 +300,7 @@ class Flow:
             state.on_max_cost_reached()
             return True
 
-        if self.timeout is not None and self.started_at is not None and time.time() - self.started_at > self.timeout:
+        if self._is_timed_out():
             logger.debug("timeout reached")
             state.on_timeout()
             return True
@@ -165,44 +309,53 @@ class Flow:
 
     async def _reset(self) -> None:
         logger.debug("flow reset")
-        state.reset()
+        state.reset_task()
+        state.clear_cancellation()
         await self.shell.reset()
         self.curr_actor_idx = 0
         self.curr_actor = None
 
     async def run(self, task_override: str | None = None) -> None:
-        state.on_event(
-            "flow_started",
-            {
-                "flow": self,
-                "state": state.as_dict(),
-            },
-        )
-
-        while not self.done():
-            await self._setup_if_needed(task_override)
-
-            if self.curr_actor:
-                logger.debug("interact if needed")
-                await self.shell.interact_if_needed(self.curr_actor)
-            else:
-                logger.debug("no actor, can't interact")
-
-            await self.step()
-
-            # in interactive mode, we reset and restart when we're done
-            # to let the user quit or change the task
-            if self.done() and state.is_interactive():
-                await self._reset()
-
-        logger.debug("flow complete")
-
-        state.on_event(
-            "flow_complete",
-            {
-                "workflow": self.workflow,
-                "steps": self.curr_step - 1,
-                "usage": state.get_usage(),
-                "state": state.as_dict(),
-            },
-        )
+        global IS_ACTIVE, ACTIVE_FLOW
+
+        try:
+            state.on_event(
+                "flow_started",
+                {
+                    "flow": self,
+                    "state": state.as_dict(),
+                },
+            )
+
+            while not self.done():
+                await self._setup_if_needed(task_override)
+
+                if self.curr_actor:
+                    logger.debug("interact if needed")
+                    await self.shell.interact_if_needed(self.curr_actor)
+                else:
+                    logger.debug("no actor, can't interact")
+
+                await self.step()
+
+                # in interactive mode, we reset and restart when we're done
+                # to let the user quit or change the task
+                if self.done() and state.is_interactive():
+                    await self._reset()
+
+            logger.debug("flow complete")
+
+            state.on_event(
+                "flow_complete",
+                {
+                    "workflow": self.workflow,
+                    "steps": self.curr_step - 1,
+                    "usage": state.get_usage(),
+                    "tool_stats": state.get_tool_stats(),
+                    "state": state.as_dict(),
+                },
+            )
+        finally:
+            # the flow is over, another one can be created in this process
+            IS_ACTIVE = False
+            ACTIVE_FLOW = None
@@ -1,8 +1,15 @@
+import asyncio
+import pathlib
+import time
 from unittest.mock import MagicMock
 
 import pytest
 
+import nerve.runtime.flow as flow_module
+from nerve.models import Usage
+from nerve.runtime import state
 from nerve.runtime.flow import Flow
+from nerve.tools.compiler import wrap_tool_function
 
 
 def test_flow_singleton() -> None:
@@ -19,3 +26,224 @@ def test_flow_singleton() -> None:
 
     # Verify the error message
     assert "A flow is already running" in str(excinfo.value)
+
+
+def _create_flow_with_slow_agent(timeout: int | None = None) -> Flow:
+    flow_module.IS_ACTIVE = False
+
+    async def slow_step() -> Usage:
+        await asyncio.sleep(30)
+        return Usage()
+
+    mock_agent = MagicMock()
+    mock_agent.step = slow_step
+
+    return Flow(actors=[mock_agent], max_steps=10, timeout=timeout)
+
+
+async def test_flow_cancel_interrupts_step() -> None:
+    flow = _create_flow_with_slow_agent()
+    try:
+        asyncio.get_running_loop().call_later(0.1, flow.cancel)
+
+        started_at = time.time()
+        await flow.step()
+
+        assert time.time() - started_at < 5
+        assert state.is_cancelled()
+        assert flow.done()
+        assert state._reason == "cancelled"
+    finally:
+        state.clear_cancellation()
+        state.reset()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_flow_timeout_interrupts_step() -> None:
+    flow = _create_flow_with_slow_agent(timeout=1)
+    try:
+        started_at = time.time()
+        await flow.step()
+
+        assert time.time() - started_at < 5
+        assert flow.done()
+        assert state._reason == "timeout reached"
+    finally:
+        state.clear_cancellation()
+        state.reset()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_flow_cancel_interrupts_blocking_tool() -> None:
+    flow_module.IS_ACTIVE = False
+
+    def blocking_tool() -> str:
+        """Block the calling thread."""
+        time.sleep(2)
+        return "done"
+
+    tool = wrap_tool_function(blocking_tool)
+
+    async def step() -> Usage:
+        await tool()
+        return Usage()
+
+    mock_agent = MagicMock()
+    mock_agent.step = step
+
+    flow = Flow(actors=[mock_agent], max_steps=10)
+    try:
+        asyncio.get_running_loop().call_later(0.1, flow.cancel)
+
+        started_at = time.time()
+        await flow.step()
+
+        assert time.time() - started_at < 1.5
+        assert state.is_cancelled()
+    finally:
+        state.clear_cancellation()
+        state.reset()
+        flow_module.IS_ACTIVE = False
+
+
+def _create_flow_with_counting_agent(completes_at: int) -> Flow:
+    flow_module.IS_ACTIVE = False
+
+    mock_agent = MagicMock()
+    mock_agent.generation_engine.history = []
+
+    async def step() -> Usage:
+        history = mock_agent.generation_engine.history
+        history.append({"role": "assistant", "content": f"step {len(history) + 1}"})
+        if len(history) == completes_at:
+            state.set_task_complete("done")
+        return Usage(total_tokens=10)
+
+    mock_agent.step = step
+
+    return Flow(actors=[mock_agent], max_steps=10)
+
+
+async def test_flow_resumes_from_checkpoint(tmp_path: pathlib.Path) -> None:
+    checkpoint = tmp_path / "checkpoint.json"
+    try:
+        # run two steps and stop, as if the process was terminated
+        flow = _create_flow_with_counting_agent(completes_at=4)
+        flow.enable_checkpoints(checkpoint)
+        await flow.step()
+        await flow.step()
+        assert checkpoint.exists()
+
+        state.reset()
+        state._usage = Usage()
+
+        # resume in a fresh flow
+        resumed = _create_flow_with_counting_agent(completes_at=4)
+        resumed.enable_checkpoints(checkpoint)
+        assert resumed.curr_step == 3
+        assert state.get_usage().total_tokens == 20
+        assert resumed.run_id == flow.run_id
+
+        await resumed.run()
+
+        assert resumed.curr_actor_idx == 1
+        assert resumed.curr_step == 5
+        assert [m["content"] for m in resumed.actors[0].generation_engine.history] == [
+            "step 1",
+            "step 2",
+            "step 3",
+            "step 4",
+        ]
+        assert state.get_usage().total_tokens == 40
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_agent_is_warned_before_max_steps() -> None:
+    try:
+        flow = _create_flow_with_counting_agent(completes_at=20)
+        flow.max_steps_warning = 0.8
+        for _ in range(10):
+            await flow.step()
+
+        # warned once at step 8 of 10
+        flow.actors[0].add_extra_message.assert_called_once()
+        assert "only 3 steps left" in flow.actors[0].add_extra_message.call_args.args[0]
+
+        assert flow.done()
+        assert state._reason == "max steps reached"
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_max_steps_warning_can_be_disabled() -> None:
+    try:
+        flow = _create_flow_with_counting_agent(completes_at=20)
+        flow.max_steps_warning = 0
+        for _ in range(10):
+            await flow.step()
+
+        flow.actors[0].add_extra_message.assert_not_called()
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_steps_of_subtasks_count_towards_max_steps() -> None:
+    try:
+        flow = _create_flow_with_counting_agent(completes_at=20)
+        flow.max_steps_warning = 0.8
+        await flow.step()
+        await flow.step()
+
+        # a subtask took 6 steps during the second one, jumping over the warning threshold
+        flow.consumed_steps = 6
+        await flow.step()
+
+        flow.actors[0].add_extra_message.assert_called_once()
+        assert "only 2 steps left" in flow.actors[0].add_extra_message.call_args.args[0]
+
+        await flow.step()
+        assert flow.done()
+        assert state._reason == "max steps reached"
+        assert flow.curr_step == 5
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_flow_is_inactive_after_running() -> None:
+    try:
+        flow = _create_flow_with_counting_agent(completes_at=1)
+        assert flow_module.ACTIVE_FLOW is flow
+
+        await flow.run()
+
+        assert not flow_module.IS_ACTIVE
+        assert flow_module.ACTIVE_FLOW is None
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
+
+
+async def test_events_carry_the_run_and_step() -> None:
+    try:
+        flow = _create_flow_with_counting_agent(completes_at=2)
+        start = len(state._events)
+        await flow.step()
+        await flow.step()
+
+        events = state._events[start:]
+        assert {event.run_id for event in events} == {flow.run_id}
+        assert [event.step for event in events if event.name == "step_complete"] == [1, 2]
+    finally:
+        state.reset()
+        state._usage = Usage()
+        flow_module.IS_ACTIVE = False
@@ -1,3 +1,4 @@
+import json
 import pathlib
 import sys
 import typing as t
@@ -122,18 +123,43 @@ def log_event_to_terminal(event: Event) -> None:
 
         logger.info(colored(f" ↳ {data['name']} -> {ret} in {elapsed_time:.4f}s", "dark_grey"))
 
+    elif event.name == "request_timeout":
+        logger.warning(f"⏱️ request to {data['generator']} timed out: {data['error']}")
+
+    elif event.name == "circuit_breaker":
+        if data["to"] == "open":
+            logger.warning(f"🔌 {data['generator']} failed {data['failures']} times, no calls for {data['cooldown']}s")
+        else:
+            logger.info(f"🔌 circuit breaker of {data['generator']} is {data['to'].replace('_', '-')}")
+
+    elif event.name == "tool_output":
+        logger.debug(f"📜 {data['output'].rstrip()}")
+
     elif event.name == "task_complete":
         logger.info(colored(f"✅ task {data['actor']} completed", "green", attrs=["bold"]))
 
+    elif event.name == "task_completion_blocked":
+        logger.warning(f"🚧 task completion blocked: {'; '.join(data['unmet'])}")
+
     elif event.name == "task_failed":
         logger.error(colored(f"❌ task {data['actor']} failed: {data['reason']}", "red", attrs=["bold"]))
 
     elif event.name == "tool_created":
         logger.info(f"🧰 registered tool: {data['name']}")
 
+    elif event.name == "self_consistency":
+        agreement = f"{data['votes']}/{data['answers']} agreeing" if data["votes"] is not None else "judged"
+        logger.info(f"🗳️  answer {data['chosen'] + 1} of {data['answers']} chosen ({data['strategy']}, {agreement})")
+
+    elif event.name == "tool_call_planned":
+        logger.debug(f"📋 planned tool call {data['position']}/{data['total']}: {data['tool_name']}")
+
     elif event.name == "unknown_tool":
         logger.warning(f"❌ model called unknown tool: {data['tool_name']}")
 
+    elif event.name == "invalid_tool_call":
+        logger.warning(f"❌ model called {data['tool_name']} with invalid arguments {data['args']}: {data['error']}")
+
     elif event.name == "tool_error":
         logger.error(f"❌ error executing {data['tool_name']}({data['args']}): {data['error']}")
 
@@ -146,6 +172,17 @@ def log_event_to_terminal(event: Event) -> None:
         else:
             logger.info(f"⚙️  flow complete in {data['steps']} steps")
 
+        # the slowest tools first
+        tool_stats = {
+            name: DictWrapper(stats) if isinstance(stats, dict) else stats
+            for name, stats in (data.get("tool_stats") or {}).items()
+        }
+        for name, stats in sorted(tool_stats.items(), key=lambda item: -item[1].total_time)[:5]:
+            logger.info(
+                f"⏱️  {name}: {stats.count} calls, {stats.total_time:.2f}s total, "
+                f"{stats.mean_time:.2f}s mean, {stats.max_time:.2f}s max"
+            )
+
     elif event.name == "text_response":
         logger.info(f"💬 {colored(data['response'], 'black', 'on_white')}")
 
@@ -158,10 +195,130 @@ def log_event_to_terminal(event: Event) -> None:
         else:
             logger.info(f"📊 [step {data['step']}]")
 
+    elif event.name == "confirmation_requested":
+        logger.debug(f"❓ confirmation requested: {data['prompt']}")
+
+    elif event.name == "question_asked":
+        logger.debug(f"❓ question asked: {data['question']}")
+
+    elif event.name == "question_answered":
+        if data["answer"] is None:
+            logger.warning("🙊 the question has not been answered")
+        else:
+            logger.info(f"🗨️  answer: {data['answer']}")
+
+    elif event.name == "confirmation_resolved":
+        if data["approved"]:
+            logger.info(f"👍 execution of {data['tool_name']} approved by the user")
+        else:
+            logger.warning(f"👎 execution of {data['tool_name']} denied by the user")
+
+    elif event.name == "response_truncated":
+        logger.warning("✂️  response truncated because of the output tokens limit, asking the model to try again")
+
+    elif event.name == "history_summarized":
+        logger.info(f"🗜️  summarized {data['messages']} messages")
+
+    elif event.name == "empty_response":
+        logger.warning(f"🫥 {data['generator']} returned an empty response ({data['count']}/{data['max']})")
+
+    elif event.name == "invalid_response":
+        logger.warning(f"🫥 {data['generator']}: {data['error']}, retrying ({data['attempt']})")
+
+    elif event.name == "state_snapshot":
+        logger.debug(f"🔎 state: {json.dumps(data, indent=2)}")
+
+    elif event.name == "state_restored":
+        logger.info(f"⏯️  state restored, usage so far: {data['usage']}")
+
+    elif event.name == "plan_revised":
+        for change in data["changes"]:
+            description = f": {change['description']}" if change.get("description") else ""
+            logger.info(f"🗺️  plan step {change['step']} {change['action']}{description}")
+
+    elif event.name == "tool_output_chunked":
+        logger.info(f"✂️  {data['tool_name']} output of {data['length']} chars split in {data['chunks']} chunks")
+
+    elif event.name == "loop_detected":
+        logger.warning(f"🔁 {data['tool_name']} called {data['repetitions']} times with the same arguments")
+
+    elif event.name == "rag_indexing_progress":
+        failed = f", {data['failed']} failed" if data["failed"] else ""
+        logger.info(f"📚 embedded {data['processed']}/{data['total']} chunks{failed}")
+
+    elif event.name == "generator_switched":
+        logger.warning(f"🔀 {data['from']} is unavailable, switching to {data['to']}: {data['error']}")
+
+    elif event.name == "generator_features":
+        unsupported = [feature for feature, supported in data["features"].items() if not supported]
+        if unsupported:
+            logger.debug(f"🧩 {data['generator']} does not support: {', '.join(unsupported)}")
+
+    elif event.name == "namespace_activated":
+        logger.info(f"🧰 namespace {data['namespace']} activated with {len(data['tools'])} tools")
+
+    elif event.name == "message_sent":
+        logger.info(f"📨 {data['from']} -> {data['to']}: {data['message']}")
+
+    elif event.name == "memories_consolidated":
+        logger.info(f"💾 {len(data['removed'])} duplicated memories merged, {data['memories']} left")
+        for a, b in data["contradictions"]:
+            logger.warning(f"💾 contradicting memories: {a} <-> {b}")
+
+    elif event.name == "text_summarized":
+        logger.info(f"📝 {data['length']} characters processed: {data['instruction']}")
+
+    elif event.name == "progress_update":
+        note = f" ({data['note']})" if data.get("note") else ""
+        logger.info(f"📈 progress {data['previous'] or 0}% -> {data['current']}%{note}")
+
+    elif event.name == "progress_stalled":
+        logger.warning(f"📉 progress stalled at {data['current']}% for {data['steps']} steps")
+
+    elif event.name == "subtask_started":
+        logger.info(f"🪆 [depth {data['depth']}] subtask {data['id']} started: {data['task']}")
+
+    elif event.name == "subtask_complete":
+        status = "completed" if data["success"] else "failed"
+        logger.info(f"🪆 [depth {data['depth']}] subtask {data['id']} {status} in {data['steps']} steps")
+
+    elif event.name == "thinking":
+        logger.info(f"🤔 {colored(data['thinking'], 'dark_grey')}")
+
+    elif event.name == "response_validation_error":
+        logger.warning(f"❌ invalid structured response: {data['error']}")
+
+    elif event.name == "generation_cache_hit":
+        logger.info(colored(f"♻️  using cached response for {data['generator']} ({data['key'][:12]})", "yellow"))
+
+    elif event.name == "cancel_requested":
+        logger.warning(f"🛑 cancelling run: {data['reason']}")
+
+    elif event.name == "sleeping":
+        logger.info(f"💤 sleeping for {data['seconds']:.1f} seconds")
+
+    elif event.name == "network_retry":
+        logger.debug(
+            f"🔁 {data['method']} {data['url']} failed ({data['reason']}), "
+            f"retry {data['attempt']}/{data['max_retries']} in {data['delay']:.2f}s"
+        )
+
+    elif event.name == "max_steps_approaching":
+        logger.warning(f"⏳ [step {data['step']}] {data['remaining']} of {data['max_steps']} steps left")
+
+    elif event.name == "step_complete":
+        # older traces don't have the step usage
+        step_usage = data.get("step_usage")
+        if isinstance(step_usage, dict):
+            step_usage = DictWrapper(step_usage)
+
+        if step_usage is not None and step_usage.cost is not None:
+            logger.info(f"💸 [step {data['step']}] cost {step_usage.cost:.4f} $")
+
     elif event.name in (
         "task_started",
         "agent_step",
-        "step_complete",
+        "generation",
         "variable_change",
         "knowledge_change",
         "mode_change",
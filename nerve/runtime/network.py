@@ -0,0 +1,154 @@
+import contextlib
+import os
+import random
+import time
+import typing as t
+
+import requests
+from pydantic import BaseModel
+
+from nerve.defaults import DEFAULT_CA_BUNDLE, DEFAULT_HTTP_MAX_RETRIES, DEFAULT_HTTP_PROXY, DEFAULT_HTTP_RETRY_DELAY
+from nerve.runtime import state
+
+# proxy url used by every outbound request, if None the standard HTTP(S)_PROXY variables are honored
+proxy: str | None = DEFAULT_HTTP_PROXY
+# custom root CA bundle used to verify every outbound request, if None the system one is used
+ca_bundle: str | None = DEFAULT_CA_BUNDLE
+
+# methods that can be safely repeated without side effects
+IDEMPOTENT_METHODS = {"GET", "HEAD", "OPTIONS", "PUT", "DELETE", "TRACE"}
+# status codes that indicate a transient error
+RETRY_STATUS_CODES = {429, 500, 502, 503, 504}
+
+
+class RetryPolicy(BaseModel):
+    """
+    Exponential backoff with full jitter for transient network errors.
+    """
+
+    # how many times to retry after the first attempt
+    max_retries: int = DEFAULT_HTTP_MAX_RETRIES
+    # base delay in milliseconds, doubled at every attempt
+    base_delay_ms: int = DEFAULT_HTTP_RETRY_DELAY
+    # upper bound for a single delay in milliseconds
+    max_delay_ms: int = 30_000
+    # allow retrying non idempotent requests like POST
+    retry_non_idempotent: bool = False
+
+    def can_retry_method(self, method: str) -> bool:
+        return self.retry_non_idempotent or method.upper() in IDEMPOTENT_METHODS
+
+    def should_retry(
+        self,
+        attempt: int,
+        method: str,
+        response: requests.Response | None = None,
+        error: Exception | None = None,
+    ) -> bool:
+        if attempt >= self.max_retries or not self.can_retry_method(method):
+            return False
+
+        if error is not None:
+            return isinstance(error, requests.ConnectionError | requests.Timeout)
+
+        return response is not None and response.status_code in RETRY_STATUS_CODES
+
+    def get_delay(self, attempt: int, response: requests.Response | None = None) -> float:
+        """Get the delay in seconds before the given retry attempt (starting from 0)."""
+
+        cap = min(self.max_delay_ms, self.base_delay_ms * (2**attempt))
+        delay = random.uniform(0, cap) / 1000.0
+
+        # honor the server provided delay if any
+        if response is not None:
+            retry_after = response.headers.get("Retry-After", "")
+            if retry_after.isdigit():
+                delay = max(delay, min(float(retry_after), self.max_delay_ms / 1000.0))
+
+        return delay
+
+
+def get_request_options() -> dict[str, t.Any]:
+    """Get the proxy and TLS options for the requests library."""
+
+    options: dict[str, t.Any] = {}
+    if proxy:
+        options["proxies"] = {"http": proxy, "https": proxy}
+    if ca_bundle:
+        options["verify"] = ca_bundle
+    return options
+
+
+def get_httpx_options() -> dict[str, t.Any]:
+    """Get the proxy and TLS options for httpx based clients."""
+
+    options: dict[str, t.Any] = {}
+    if proxy:
+        options["proxy"] = proxy
+    if ca_bundle:
+        options["verify"] = ca_bundle
+    return options
+
+
+def configure_litellm() -> None:
+    """Make the litellm clients honor the proxy and TLS settings."""
+
+    import litellm
+
+    if ca_bundle:
+        litellm.ssl_verify = ca_bundle
+    if proxy:
+        # litellm creates its own httpx clients that read the proxy from the environment
+        os.environ["HTTP_PROXY"] = os.environ["HTTPS_PROXY"] = proxy
+
+
+def request(
+    method: str,
+    url: str,
+    policy: RetryPolicy | None = None,
+    max_redirects: int | None = None,
+    **kwargs: t.Any,
+) -> requests.Response:
+    """Perform an HTTP request retrying transient errors according to the policy."""
+
+    policy = policy or RetryPolicy()
+    kwargs = {**get_request_options(), **kwargs}
+    attempt = 0
+
+    # the redirects limit can only be set on a session, closed once done like the ones of requests.request
+    with requests.Session() if max_redirects is not None else contextlib.nullcontext() as session:
+        send: t.Callable[..., requests.Response] = requests.request
+        if session is not None:
+            session.max_redirects = max_redirects
+            send = session.request
+
+        while True:
+            response = None
+            error = None
+            try:
+                response = send(method, url, **kwargs)
+            except requests.RequestException as e:
+                error = e
+
+            if not policy.should_retry(attempt, method, response, error):
+                if error is not None:
+                    raise error
+                return response  # type: ignore
+
+            delay = policy.get_delay(attempt, response)
+            reason = str(error) if error is not None else f"HTTP {response.status_code}"  # type: ignore
+
+            state.on_event(
+                "network_retry",
+                {
+                    "method": method,
+                    "url": url,
+                    "reason": reason,
+                    "attempt": attempt + 1,
+                    "max_retries": policy.max_retries,
+                    "delay": delay,
+                },
+            )
+
+            time.sleep(delay)
+            attempt += 1
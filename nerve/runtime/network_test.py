@@ -0,0 +1,134 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+import requests
+
+from nerve.runtime import network
+
+
+def _response(status_code: int, headers: dict[str, str] | None = None) -> MagicMock:
+    response = MagicMock()
+    response.status_code = status_code
+    response.headers = headers or {}
+    return response
+
+
+class TestRetryPolicy(unittest.TestCase):
+    def test_retries_transient_errors_on_idempotent_methods(self) -> None:
+        policy = network.RetryPolicy(max_retries=3)
+
+        self.assertTrue(policy.should_retry(0, "GET", response=_response(503)))
+        self.assertTrue(policy.should_retry(0, "get", response=_response(429)))
+        self.assertTrue(policy.should_retry(0, "GET", error=requests.Timeout()))
+        self.assertTrue(policy.should_retry(0, "GET", error=requests.ConnectionError()))
+
+    def test_does_not_retry_client_errors(self) -> None:
+        policy = network.RetryPolicy(max_retries=3)
+
+        self.assertFalse(policy.should_retry(0, "GET", response=_response(200)))
+        self.assertFalse(policy.should_retry(0, "GET", response=_response(404)))
+        self.assertFalse(policy.should_retry(0, "GET", error=requests.exceptions.InvalidURL()))
+
+    def test_does_not_retry_post_unless_allowed(self) -> None:
+        self.assertFalse(network.RetryPolicy().should_retry(0, "POST", response=_response(503)))
+        self.assertTrue(
+            network.RetryPolicy(retry_non_idempotent=True).should_retry(0, "POST", response=_response(503))
+        )
+
+    def test_stops_after_max_retries(self) -> None:
+        policy = network.RetryPolicy(max_retries=2)
+
+        self.assertTrue(policy.should_retry(1, "GET", response=_response(503)))
+        self.assertFalse(policy.should_retry(2, "GET", response=_response(503)))
+
+    def test_exponential_delay_with_jitter(self) -> None:
+        policy = network.RetryPolicy(base_delay_ms=100, max_delay_ms=1000)
+
+        for attempt, cap in ((0, 0.1), (1, 0.2), (2, 0.4), (3, 0.8), (10, 1.0)):
+            for _ in range(20):
+                delay = policy.get_delay(attempt)
+                self.assertGreaterEqual(delay, 0)
+                self.assertLessEqual(delay, cap)
+
+    def test_honors_retry_after(self) -> None:
+        policy = network.RetryPolicy(base_delay_ms=1, max_delay_ms=10_000)
+
+        self.assertGreaterEqual(policy.get_delay(0, _response(429, {"Retry-After": "3"})), 3.0)
+
+
+class TestRequest(unittest.TestCase):
+    @patch("nerve.runtime.network.time.sleep")
+    @patch("nerve.runtime.network.requests.request")
+    def test_retries_until_success(self, mock_request: MagicMock, mock_sleep: MagicMock) -> None:
+        mock_request.side_effect = [_response(503), requests.Timeout(), _response(200)]
+
+        response = network.request("GET", "http://example.com", network.RetryPolicy(max_retries=3))
+
+        self.assertEqual(response.status_code, 200)
+        self.assertEqual(mock_request.call_count, 3)
+        self.assertEqual(mock_sleep.call_count, 2)
+
+    @patch("nerve.runtime.network.time.sleep")
+    @patch("nerve.runtime.network.requests.request")
+    def test_raises_last_error(self, mock_request: MagicMock, mock_sleep: MagicMock) -> None:
+        mock_request.side_effect = requests.ConnectionError()
+
+        with self.assertRaises(requests.ConnectionError):
+            network.request("GET", "http://example.com", network.RetryPolicy(max_retries=2))
+
+        self.assertEqual(mock_request.call_count, 3)
+
+    @patch("nerve.runtime.network.time.sleep")
+    @patch("nerve.runtime.network.requests.request")
+    def test_post_is_not_retried(self, mock_request: MagicMock, mock_sleep: MagicMock) -> None:
+        mock_request.return_value = _response(503)
+
+        response = network.request("POST", "http://example.com", network.RetryPolicy(max_retries=3))
+
+        self.assertEqual(response.status_code, 503)
+        self.assertEqual(mock_request.call_count, 1)
+        mock_sleep.assert_not_called()
+
+    @patch.object(requests.Session, "close")
+    @patch.object(requests.Session, "request")
+    def test_redirects_session_is_closed(self, mock_request: MagicMock, mock_close: MagicMock) -> None:
+        mock_request.return_value = _response(200)
+
+        response = network.request("GET", "http://example.com", max_redirects=3)
+
+        self.assertEqual(response.status_code, 200)
+        mock_close.assert_called_once()
+
+
+class TestProxyAndCA(unittest.TestCase):
+    def tearDown(self) -> None:
+        network.proxy = None
+        network.ca_bundle = None
+
+    def test_no_configuration(self) -> None:
+        self.assertEqual(network.get_request_options(), {})
+        self.assertEqual(network.get_httpx_options(), {})
+
+    def test_proxy_and_ca_bundle(self) -> None:
+        network.proxy = "http://proxy.corp:3128"
+        network.ca_bundle = "/etc/ssl/corp.pem"
+
+        self.assertEqual(
+            network.get_request_options(),
+            {
+                "proxies": {"http": "http://proxy.corp:3128", "https": "http://proxy.corp:3128"},
+                "verify": "/etc/ssl/corp.pem",
+            },
+        )
+        self.assertEqual(
+            network.get_httpx_options(), {"proxy": "http://proxy.corp:3128", "verify": "/etc/ssl/corp.pem"}
+        )
+
+    @patch("nerve.runtime.network.requests.request")
+    def test_requests_use_the_configuration(self, mock_request: MagicMock) -> None:
+        network.ca_bundle = "/etc/ssl/corp.pem"
+        mock_request.return_value = MagicMock(status_code=200)
+
+        network.request("GET", "https://example.com", timeout=5)
+
+        mock_request.assert_called_once_with("GET", "https://example.com", verify="/etc/ssl/corp.pem", timeout=5)
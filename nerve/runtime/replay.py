@@ -0,0 +1,71 @@
+import collections
+import json
+import pathlib
+import typing as t
+
+import nerve.runtime.state as state
+from nerve.generation.cache import to_namespace
+from nerve.models import Usage
+from nerve.runtime.events import Event
+
+# events recorded while a tool is executing that must be applied again when replaying it
+_TOOL_EFFECTS = ("task_complete", "task_failed")
+
+
+class ReplayError(Exception):
+    pass
+
+
+class Replay:
+    """
+    Serves the model responses and tool results recorded in a trace, in order, in order to re-execute
+    a run deterministically without calling the model or the tools.
+    """
+
+    def __init__(self, events: list[Event]):
+        self.generations: collections.deque[dict[str, t.Any]] = collections.deque()
+        self.tool_calls: collections.deque[dict[str, t.Any]] = collections.deque()
+
+        effects: list[Event] | None = None
+        for event in events:
+            if event.name == "generation":
+                self.generations.append(event.data)
+            elif event.name == "before_tool_called":
+                effects = []
+            elif event.name in _TOOL_EFFECTS and effects is not None:
+                effects.append(event)
+            elif event.name == "tool_called":
+                self.tool_calls.append({**event.data, "effects": effects or []})
+                effects = None
+
+    @classmethod
+    def from_path(cls, trace_path: pathlib.Path) -> "Replay":
+        with open(trace_path) as f:
+            return cls([Event(**json.loads(line)) for line in f if line.strip()])
+
+    def next_generation(self) -> tuple[Usage, t.Any]:
+        if not self.generations:
+            raise ReplayError("no more recorded model responses in the trace")
+
+        data = self.generations.popleft()
+        return Usage(**data["usage"]), to_namespace(data["message"])
+
+    def next_tool_result(self, tool_name: str, args: dict[str, t.Any]) -> t.Any:
+        if not self.tool_calls:
+            raise ReplayError(f"no more recorded tool calls in the trace, got a call to {tool_name}")
+
+        call = self.tool_calls.popleft()
+        if call["name"] != tool_name:
+            raise ReplayError(f"the run diverged from the trace: expected a call to {call['name']}, got {tool_name}")
+
+        state.on_before_tool_called(tool_name, args)
+
+        for effect in call["effects"]:
+            if effect.name == "task_complete":
+                state.set_task_complete(effect.data["reason"], effect.data.get("result"))
+            else:
+                state.set_task_failed(effect.data["reason"])
+
+        state.on_tool_called(call["started_at"], call["finished_at"], tool_name, args, call["result"], call["error"])
+
+        return call["result"]
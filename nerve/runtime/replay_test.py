@@ -0,0 +1,70 @@
+import json
+import pathlib
+import tempfile
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.runtime.events import Event
+from nerve.runtime.replay import Replay, ReplayError
+
+USAGE = {"cost": 0.1, "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+TOOL_CALL = {"id": "call_1", "function": {"name": "task_complete_success", "arguments": '{"reason": "done"}'}}
+
+EVENTS = [
+    Event(name="agent_step", data={"prompt": "do it"}),
+    Event(
+        name="generation",
+        data={"usage": USAGE, "message": {"role": "assistant", "content": None, "tool_calls": [TOOL_CALL]}},
+    ),
+    Event(name="before_tool_called", data={"name": "task_complete_success", "args": {"reason": "done"}}),
+    Event(name="task_complete", data={"actor": "agent", "reason": "done"}),
+    Event(
+        name="tool_called",
+        data={
+            "started_at": 1.0,
+            "finished_at": 2.0,
+            "name": "task_complete_success",
+            "args": {"reason": "done"},
+            "result": "task complete",
+            "error": None,
+        },
+    ),
+]
+
+
+@patch("nerve.runtime.replay.state")
+class TestReplay(unittest.TestCase):
+    def test_generations_are_served_in_order(self, _: MagicMock) -> None:
+        replay = Replay(EVENTS)
+
+        usage, message = replay.next_generation()
+
+        self.assertEqual(usage.total_tokens, 15)
+        self.assertEqual(message.tool_calls[0].function.name, "task_complete_success")
+        with self.assertRaises(ReplayError):
+            replay.next_generation()
+
+    def test_tool_results_and_effects_are_replayed(self, mock_state: MagicMock) -> None:
+        replay = Replay(EVENTS)
+
+        result = replay.next_tool_result("task_complete_success", {"reason": "done"})
+
+        self.assertEqual(result, "task complete")
+        mock_state.set_task_complete.assert_called_once_with("done", None)
+        mock_state.on_tool_called.assert_called_once_with(
+            1.0, 2.0, "task_complete_success", {"reason": "done"}, "task complete", None
+        )
+
+    def test_diverging_tool_call(self, mock_state: MagicMock) -> None:
+        with self.assertRaisesRegex(ReplayError, "expected a call to task_complete_success, got read_file"):
+            Replay(EVENTS).next_tool_result("read_file", {"path": "/etc/passwd"})
+
+    def test_from_path(self, _: MagicMock) -> None:
+        with tempfile.TemporaryDirectory() as temp_dir:
+            trace_path = pathlib.Path(temp_dir) / "trace.jsonl"
+            trace_path.write_text("\n".join(json.dumps(event.model_dump()) for event in EVENTS) + "\n")
+
+            replay = Replay.from_path(trace_path)
+
+        self.assertEqual(len(replay.generations), 1)
+        self.assertEqual(len(replay.tool_calls), 1)
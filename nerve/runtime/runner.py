@@ -30,13 +30,25 @@ class Arguments(BaseModel):
     timeout: int | None
     log_path: pathlib.Path | None
     trace: pathlib.Path | None
+    # optional tamper-evident audit trail of the executed tools
+    audit: pathlib.Path | None = None
+    # optional file to checkpoint the run to after every step, and to resume it from
+    checkpoint: pathlib.Path | None = None
+    # optional trace of a previous run to replay
+    replay: pathlib.Path | None = None
+    # verify that the generators can be used before starting the run
+    health_check: bool = False
+    # optional host:port address to stream the events to browsers from, and the token required to connect
+    events: str | None = None
+    events_token: str | None = None
     start_state: dict[str, t.Any]
 
     def to_serializable(self) -> dict[str, t.Any]:
         return {
             k: v if not isinstance(v, pathlib.Path) else v.as_posix()
             for k, v in self.model_dump().items()
-            if k != "conversation_strategy"
+            # the token is a secret
+            if k not in ("conversation_strategy", "events_token")
         }
 
 
@@ -126,8 +138,11 @@ def _parse_events(inputs: dict[str, str], events: list[dict[str, t.Any]]) -> Par
     if task_completed is not None:
         data = task_completed.get("data", {})
         reason = data.get("reason", {})
-        if reason:
+        result = data.get("result")
+        if reason or result is not None:
             parsed.output_object = {"reason": reason}
+            if result is not None:
+                parsed.output_object["result"] = result
         else:
             parsed.output_object = data
 
@@ -199,6 +214,7 @@ class Runner:
         id: str | None = None,
         base_path: pathlib.Path = DEFAULT_RUNS_PATH,
         clean_at_exit: bool = True,
+        env: dict[str, str] | None = None,
     ):
         if not base_path.exists():
             base_path.mkdir(parents=True, exist_ok=True)
@@ -206,6 +222,8 @@ class Runner:
         self.id = id or str(uuid.uuid4())
         self.events_file = base_path / f"run-{self.id}.jsonl"
         self.input_state = input_state or {}
+        # extra environment variables for the child process
+        self.env = env or {}
         self.command_line = _create_command_line(
             args,
             self.input_state,
@@ -260,7 +278,8 @@ class Runner:
             *self.command_line,
             stdout=asyncio.subprocess.PIPE,
             stderr=asyncio.subprocess.PIPE,
-            env=os.environ.copy(),
+            # the events of the child process are correlated to this run
+            env={**os.environ, "NERVE_RUN_ID": self.id, **self.env},
         )
         stdout_task = asyncio.create_task(read_stream(self._process.stdout, "stdout"))
         stderr_task = asyncio.create_task(read_stream(self._process.stderr, "stderr"))
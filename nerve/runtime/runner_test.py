@@ -38,6 +38,20 @@ class TestParseEvents(unittest.TestCase):
         self.assertEqual(result.time, 2.0)
         self.assertEqual(result.usage, {"tokens": 50})
 
+    def test_task_completed_with_result(self) -> None:
+        """Test when task is completed with a structured result."""
+        inputs = {"input1": "value1"}
+        events = [
+            {"name": "flow_start", "timestamp": 0.0},
+            {"name": "task_complete", "timestamp": 1.0, "data": {"reason": None, "result": {"answer": 42}}},
+            {"name": "flow_complete", "timestamp": 2.0, "data": {"steps": 2, "usage": {"tokens": 50}}},
+        ]
+
+        result = _parse_events(inputs, events)
+
+        self.assertTrue(result.task_success)
+        self.assertEqual(result.output_object, {"reason": None, "result": {"answer": 42}})
+
     def test_task_failed(self) -> None:
         """Test when task fails."""
         inputs = {"input1": "value1"}
@@ -0,0 +1,149 @@
+import json
+import os
+import typing as t
+from abc import ABC, abstractmethod
+
+from loguru import logger
+
+from nerve.defaults import DEFAULT_AWS_SECRET_ID, DEFAULT_SECRETS_PROVIDER, DEFAULT_VAULT_MOUNT, DEFAULT_VAULT_PATH
+
+# environment variables of the API keys of the providers, as read by litellm
+API_KEYS: dict[str, str] = {
+    "openai": "OPENAI_API_KEY",
+    "azure": "AZURE_API_KEY",
+    "anthropic": "ANTHROPIC_API_KEY",
+    "groq": "GROQ_API_KEY",
+    "mistral": "MISTRAL_API_KEY",
+    "deepseek": "DEEPSEEK_API_KEY",
+    "gemini": "GEMINI_API_KEY",
+    "xai": "XAI_API_KEY",
+    "cohere": "COHERE_API_KEY",
+    "together_ai": "TOGETHERAI_API_KEY",
+    "fireworks_ai": "FIREWORKS_API_KEY",
+    "openrouter": "OPENROUTER_API_KEY",
+    "perplexity": "PERPLEXITYAI_API_KEY",
+}
+
+
+class SecretProvider(ABC):
+    """
+    Resolves secrets, like the API keys of the providers, by the name of their environment variable.
+    """
+
+    @abstractmethod
+    def get(self, name: str) -> str | None:
+        pass
+
+
+class EnvSecretProvider(SecretProvider):
+    """Reads the secrets from the environment variables, the default."""
+
+    def get(self, name: str) -> str | None:
+        return os.environ.get(name) or None
+
+
+class _StoredSecretProvider(SecretProvider):
+    """Reads all the secrets at once from a single entry of a secrets manager, mapping names to values."""
+
+    def __init__(self) -> None:
+        self._secrets: dict[str, t.Any] | None = None
+
+    @abstractmethod
+    def _load(self) -> dict[str, t.Any]:
+        pass
+
+    def get(self, name: str) -> str | None:
+        if self._secrets is None:
+            self._secrets = self._load()
+            logger.debug(f"loaded {len(self._secrets)} secrets from {self}")
+
+        value = self._secrets.get(name)
+        return str(value) if value else None
+
+
+class VaultSecretProvider(_StoredSecretProvider):
+    """Reads the secrets from a KV v2 secret of HashiCorp Vault, requires the hvac package."""
+
+    def __init__(self, path: str = DEFAULT_VAULT_PATH, mount_point: str = DEFAULT_VAULT_MOUNT):
+        super().__init__()
+        self.path = path
+        self.mount_point = mount_point
+
+    def _load(self) -> dict[str, t.Any]:
+        try:
+            import hvac  # type: ignore
+        except ImportError as e:
+            raise ImportError(
+                "the hvac package is required to read secrets from Vault, install nerve-adk[vault]"
+            ) from e
+
+        # the address and the token are read from VAULT_ADDR and VAULT_TOKEN
+        client = hvac.Client(url=os.getenv("VAULT_ADDR"), token=os.getenv("VAULT_TOKEN"))
+        response = client.secrets.kv.v2.read_secret_version(path=self.path, mount_point=self.mount_point)
+        return dict(response["data"]["data"])
+
+    def __str__(self) -> str:
+        return f"vault:{self.mount_point}/{self.path}"
+
+
+class AWSSecretProvider(_StoredSecretProvider):
+    """Reads the secrets from a JSON secret of AWS Secrets Manager, requires the boto3 package."""
+
+    def __init__(self, secret_id: str = DEFAULT_AWS_SECRET_ID):
+        super().__init__()
+        self.secret_id = secret_id
+
+    def _load(self) -> dict[str, t.Any]:
+        try:
+            import boto3  # type: ignore
+        except ImportError as e:
+            raise ImportError(
+                "the boto3 package is required to read secrets from AWS Secrets Manager, install nerve-adk[aws]"
+            ) from e
+
+        # credentials and region are resolved by boto3 as usual
+        response = boto3.client("secretsmanager").get_secret_value(SecretId=self.secret_id)
+        return dict(json.loads(response["SecretString"]))
+
+    def __str__(self) -> str:
+        return f"aws:{self.secret_id}"
+
+
+_PROVIDERS: dict[str, t.Callable[[], SecretProvider]] = {
+    "env": EnvSecretProvider,
+    "vault": VaultSecretProvider,
+    "aws": AWSSecretProvider,
+}
+
+_provider: SecretProvider | None = None
+
+
+def set_provider(provider: SecretProvider) -> None:
+    """Set the provider resolving the secrets, for applications embedding nerve."""
+
+    global _provider
+    _provider = provider
+
+
+def get_provider() -> SecretProvider:
+    global _provider
+
+    if _provider is None:
+        if DEFAULT_SECRETS_PROVIDER not in _PROVIDERS:
+            raise ValueError(
+                f"unknown secrets provider {DEFAULT_SECRETS_PROVIDER}, available providers: {list(_PROVIDERS)}"
+            )
+        _provider = _PROVIDERS[DEFAULT_SECRETS_PROVIDER]()
+
+    return _provider
+
+
+def get_api_key(generator_id: str) -> str | None:
+    """Return the API key of the provider of a model, if known and set."""
+
+    # models without a provider prefix are served by openai
+    provider = generator_id.split("/")[0] if "/" in generator_id else "openai"
+    if provider not in API_KEYS:
+        return None
+
+    return get_provider().get(API_KEYS[provider])
@@ -0,0 +1,68 @@
+import os
+import typing as t
+import unittest
+from unittest.mock import patch
+
+from nerve.generation.conversation import FullHistoryStrategy
+from nerve.generation.embeddings import Embedder
+from nerve.generation.litellm import LiteLLMEngine
+from nerve.runtime import secrets
+
+
+class _DictSecretProvider(secrets.SecretProvider):
+    def __init__(self, values: dict[str, str]):
+        self.values = values
+
+    def get(self, name: str) -> str | None:
+        return self.values.get(name)
+
+
+class _FakeStore(secrets._StoredSecretProvider):
+    def __init__(self) -> None:
+        super().__init__()
+        self.loads = 0
+
+    def _load(self) -> dict[str, t.Any]:
+        self.loads += 1
+        return {"OPENAI_API_KEY": "sk-stored", "EMPTY": ""}
+
+
+class TestSecrets(unittest.TestCase):
+    def tearDown(self) -> None:
+        secrets._provider = None
+
+    def test_environment_by_default(self) -> None:
+        with patch.dict(os.environ, {"ANTHROPIC_API_KEY": "sk-ant"}):
+            self.assertIsInstance(secrets.get_provider(), secrets.EnvSecretProvider)
+            self.assertEqual(secrets.get_api_key("anthropic/claude-3-5-sonnet-latest"), "sk-ant")
+
+    def test_api_key_of_the_provider(self) -> None:
+        secrets.set_provider(_DictSecretProvider({"OPENAI_API_KEY": "sk-openai", "GROQ_API_KEY": "gsk"}))
+
+        self.assertEqual(secrets.get_api_key("gpt-4o"), "sk-openai")
+        self.assertEqual(secrets.get_api_key("groq/llama3-70b-8192"), "gsk")
+        self.assertIsNone(secrets.get_api_key("mistral/mistral-large-latest"))
+        # providers authenticating differently are left to litellm
+        self.assertIsNone(secrets.get_api_key("ollama/llama3"))
+
+    def test_stored_secrets_are_loaded_once(self) -> None:
+        store = _FakeStore()
+        secrets.set_provider(store)
+
+        self.assertEqual(secrets.get_api_key("openai/gpt-4o"), "sk-stored")
+        self.assertEqual(secrets.get_api_key("openai/gpt-4o-mini"), "sk-stored")
+        self.assertIsNone(store.get("EMPTY"))
+        self.assertEqual(store.loads, 1)
+
+    def test_unknown_provider(self) -> None:
+        with patch("nerve.runtime.secrets.DEFAULT_SECRETS_PROVIDER", "keychain"):
+            with self.assertRaisesRegex(ValueError, "unknown secrets provider keychain"):
+                secrets.get_provider()
+
+    def test_engines_use_the_provider(self) -> None:
+        secrets.set_provider(_DictSecretProvider({"OPENAI_API_KEY": "sk-openai"}))
+
+        self.assertEqual(LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy()).api_key, "sk-openai")
+        self.assertEqual(Embedder("openai/text-embedding-3-small").api_key, "sk-openai")
+        # explicit parameters win
+        self.assertEqual(LiteLLMEngine("openai/gpt-4o?api_key=sk-param", FullHistoryStrategy()).api_key, "sk-param")
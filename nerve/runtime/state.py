@@ -1,27 +1,48 @@
 import asyncio
+import contextvars
 import json
 import os
 import pathlib
 import threading
 import typing as t
+import uuid
 
 import click
 import jinja2
 from loguru import logger
 
-from nerve.models import Mode, Status, Usage
+from nerve.defaults import DEFAULT_CONFIRMATION_TIMEOUT, DEFAULT_QUESTION_TIMEOUT, DEFAULT_RUN_ID
+from nerve.models import Mode, Status, ToolDescriptor, ToolStats, Usage
 from nerve.runtime.builtin import get_builtin_variable_value, is_builtin_variable
 from nerve.runtime.events import Event
 from nerve.runtime.thread_pool import ThreadPool
 
+if t.TYPE_CHECKING:
+    from nerve.runtime.audit import AuditTrail
+    from nerve.runtime.replay import Replay
+
+# types a piece of knowledge can be declared as
+KNOWLEDGE_TYPES: dict[str, tuple[type, ...]] = {
+    "string": (str,),
+    "number": (int, float),
+    "boolean": (bool,),
+    "json": (dict, list),
+}
+
 # the current actor
 _current_actor: t.Any | None = None
 # total usage
 _usage: Usage = Usage()
+# execution time statistics of each tool
+_tool_stats: dict[str, ToolStats] = {}
 # event log
 _events: list[Event] = []
 # trace file
 _trace_file: pathlib.Path | None = None
+# optional audit trail of the executed tools
+_audit_trail: "AuditTrail | None" = None
+# optional recorded run to replay
+_replay: "Replay | None" = None
 # working mode
 _mode: Mode = Mode.AUTOMATIC
 # the status of the active task
@@ -34,12 +55,34 @@ _variables: dict[str, t.Any] = {}
 _defaults: dict[str, t.Any] = {}
 # similar to variables but used by tools
 _knowledge: dict[str, t.Any] = {}
+# declared types of the knowledge keys, values of undeclared keys are not checked
+_knowledge_types: dict[str, str] = {}
+# variables and knowledge can be updated by tools running in other threads
+_storage_lock: threading.RLock = threading.RLock()
 # tools
 _tools: dict[str, t.Callable[..., t.Any]] = {}
 # extra tools defined at runtime
 _extra_tools: dict[str, t.Callable[..., t.Any]] = {}
+# set when the current run has been cancelled, can be set from any thread
+_cancelled: threading.Event = threading.Event()
+# the reason for the cancellation
+_cancel_reason: str = "cancelled"
+# if set, confirmations are answered by the host application within this number of seconds
+_confirmation_timeout: float | None = None
+# confirmations waiting for an answer from the host, by request id
+_pending_confirmations: dict[str, tuple[threading.Event, list[bool]]] = {}
+# if set, questions of the agent are answered by the host application within this number of seconds
+_question_timeout: float | None = None
+# questions waiting for an answer from the host, by request id
+_pending_questions: dict[str, tuple[threading.Event, list[str]]] = {}
 # listeners for events
 _listeners: list[t.Callable[[Event], None]] = []
+# id of the run, attached to every event
+_run_id: str | None = DEFAULT_RUN_ID
+# step being executed, attached to every event
+_step: int | None = None
+# id of the tool call being executed, as generated by the model
+_tool_call_id: contextvars.ContextVar[str | None] = contextvars.ContextVar("tool_call_id", default=None)
 # thread pool for asynchronous event dispatching
 _thread_pool: ThreadPool = ThreadPool()
 
@@ -64,6 +107,33 @@ def set_trace_file(trace_file: pathlib.Path) -> None:
     logger.info(f"🔍 tracing to {_trace_file}")
 
 
+def set_audit_trail(audit_trail: "AuditTrail") -> None:
+    """Record every executed tool to a tamper-evident audit trail."""
+
+    global _audit_trail
+    _audit_trail = audit_trail
+    logger.info(f"🔏 auditing to {audit_trail.path}")
+
+
+def get_audit_trail() -> "AuditTrail | None":
+    """Get the audit trail, if any."""
+
+    return _audit_trail
+
+
+def set_replay(replay: "Replay") -> None:
+    """Serve the model responses and tool results from a recorded run."""
+
+    global _replay
+    _replay = replay
+
+
+def get_replay() -> "Replay | None":
+    """Get the recorded run being replayed, if any."""
+
+    return _replay
+
+
 class CustomJSONEncoder(json.JSONEncoder):
     def default(self, o: t.Any) -> t.Any:
         logger.debug("serializing", o)
@@ -98,12 +168,41 @@ def get_usage() -> Usage:
     return _usage
 
 
+def get_tool_stats() -> dict[str, ToolStats]:
+    """Get the execution time statistics of each tool."""
+
+    return _tool_stats
+
+
+def start_run(run_id: str | None = None) -> str:
+    """Assign the id of the run attached to every event, NERVE_RUN_ID or a random one if not given."""
+
+    global _run_id, _step
+
+    _run_id = run_id or DEFAULT_RUN_ID or uuid.uuid4().hex
+    _step = None
+    return _run_id
+
+
+def get_run_id() -> str | None:
+    """Get the id of the current run."""
+
+    return _run_id
+
+
+def set_step(step: int) -> None:
+    """Set the step of the run attached to the events."""
+
+    global _step
+    _step = step
+
+
 def on_event(name: str, data: t.Any | None = None) -> None:
     """Register an event."""
 
     global _events
 
-    event = Event(name=name, data=data)
+    event = Event(name=name, data=data, run_id=_run_id, step=_step)
     _events.append(event)
 
     for listener in _listeners:
@@ -131,6 +230,12 @@ def get_current_actor() -> t.Any:
     return _current_actor
 
 
+def set_tool_call_id(call_id: str | None) -> None:
+    """Set the id of the tool call being executed, included in the tool events."""
+
+    _tool_call_id.set(call_id)
+
+
 def on_before_tool_called(
     name: str,
     args: t.Any | None = None,
@@ -140,12 +245,29 @@ def on_before_tool_called(
     on_event(
         "before_tool_called",
         {
+            "call_id": _tool_call_id.get(),
             "name": name,
             "args": args,
         },
     )
 
 
+def on_tool_output(output: str, stream: str = "stdout") -> None:
+    """
+    Report the output a long running tool produced so far, so that it can be followed while the tool is still
+    running. The tool result is not affected and must still contain the complete output.
+    """
+
+    on_event(
+        "tool_output",
+        {
+            "call_id": _tool_call_id.get(),
+            "stream": stream,
+            "output": output,
+        },
+    )
+
+
 def on_tool_called(
     started_at: float,
     finished_at: float,
@@ -156,9 +278,16 @@ def on_tool_called(
 ) -> None:
     """Register a tool call (after it is executed)."""
 
+    _tool_stats.setdefault(name, ToolStats()).add(finished_at - started_at, error is not None)
+
+    # recorded regardless of the events log
+    if _audit_trail:
+        _audit_trail.record(name, args, result, error, finished_at - started_at)
+
     on_event(
         "tool_called",
         {
+            "call_id": _tool_call_id.get(),
             "started_at": started_at,
             "finished_at": finished_at,
             "name": name,
@@ -206,14 +335,19 @@ def is_active_task_done() -> bool:
     return _task_status.is_done()
 
 
-def set_task_complete(the_reason: str | None = None) -> None:
-    """Set the task as complete."""
+def set_task_complete(the_reason: str | None = None, result: t.Any | None = None) -> None:
+    """Set the task as complete, optionally with a structured result that can be consumed programmatically."""
 
     global _task_status, _reason
     _task_status = Status.COMPLETED
     _reason = the_reason
     on_event(
-        "task_complete", {"actor": _current_actor.runtime.name if _current_actor else "unknown", "reason": the_reason}
+        "task_complete",
+        {
+            "actor": _current_actor.runtime.name if _current_actor else "unknown",
+            "reason": the_reason,
+            "result": result,
+        },
     )
 
 
@@ -253,6 +387,36 @@ def on_timeout() -> None:
         set_task_failed("timeout reached")
 
 
+def cancel(reason: str = "cancelled") -> None:
+    """Cancel the current run, the in-flight step is interrupted as soon as possible."""
+
+    global _cancel_reason
+    if not _cancelled.is_set():
+        _cancel_reason = reason
+        _cancelled.set()
+        on_event("cancel_requested", {"reason": reason})
+
+
+def is_cancelled() -> bool:
+    """Check if the current run has been cancelled."""
+
+    return _cancelled.is_set()
+
+
+def clear_cancellation() -> None:
+    """Clear the cancellation flag."""
+
+    _cancelled.clear()
+
+
+def on_cancelled() -> None:
+    """Set the task as failed due to cancellation."""
+
+    global _task_status, _reason
+    if _task_status == Status.RUNNING:
+        set_task_failed(_cancel_reason)
+
+
 def as_dict() -> dict[str, t.Any]:
     """Get the current state as a dictionary."""
 
@@ -267,6 +431,62 @@ def as_dict() -> dict[str, t.Any]:
     }
 
 
+def get_snapshot() -> dict[str, t.Any]:
+    """Get a serializable snapshot of the state that can be restored in another process."""
+
+    return {
+        "usage": _usage.model_dump(),
+        "current_task": {
+            "status": _task_status.value,
+            "reason": _reason,
+        },
+        "variables": _variables,
+        "knowledge": _knowledge,
+        "knowledge_types": _knowledge_types,
+    }
+
+
+def peek() -> dict[str, t.Any]:
+    """
+    Get a serializable copy of the variables and of every knowledge entry with its type, for debugging.
+    Unlike get_snapshot, nothing in it can be used to modify the state.
+    """
+
+    with _storage_lock:
+        data = {
+            "current_task": {"status": _task_status.value, "reason": _reason},
+            "variables": _variables,
+            "knowledge": {
+                key: {"type": _knowledge_types.get(key), "value": value} for key, value in _knowledge.items()
+            },
+        }
+        # serializing is cheaper than a deep copy and makes sure the result can be sent anywhere
+        return t.cast(dict[str, t.Any], json.loads(json.dumps(data, cls=CustomJSONEncoder)))
+
+
+def emit_state_snapshot() -> None:
+    """Emit a state_snapshot event with the current state as returned by peek."""
+
+    on_event("state_snapshot", peek())
+
+
+def restore_snapshot(snapshot: dict[str, t.Any]) -> None:
+    """Restore the state from a snapshot."""
+
+    global _usage, _task_status, _reason, _variables, _knowledge, _knowledge_types
+
+    _usage = Usage(**snapshot["usage"])
+    _task_status = Status(snapshot["current_task"]["status"])
+    _reason = snapshot["current_task"]["reason"]
+    _variables = dict(snapshot["variables"])
+    _knowledge = dict(snapshot["knowledge"])
+    # snapshots created before typed knowledge have no types
+    _knowledge_types = dict(snapshot.get("knowledge_types", {}))
+
+    # let the listeners resync
+    on_event("state_restored", {"state": as_dict(), "usage": _usage})
+
+
 def set_tools(tools: dict[str, t.Callable[..., t.Any]]) -> None:
     """Set all tools."""
 
@@ -283,6 +503,15 @@ def get_tools() -> dict[str, t.Callable[..., t.Any]]:
     return _tools
 
 
+def get_tool_descriptors() -> list[ToolDescriptor]:
+    """Get a serializable description of every tool available to the agent, including the extra ones."""
+
+    # import here to avoid circular import
+    from nerve.tools.protocol import get_tool_descriptor
+
+    return [get_tool_descriptor(tool) for tool in {**_tools, **_extra_tools}.values()]
+
+
 def get_extra_tools() -> dict[str, t.Callable[..., t.Any]]:
     """Get any extra tool registered at runtime."""
 
@@ -319,52 +548,114 @@ def get_knowledge() -> dict[str, t.Any]:
     return _knowledge
 
 
+def _check_knowledge_type(key: str, value: t.Any) -> None:
+    type_name = _knowledge_types.get(key)
+    if type_name is None:
+        return
+
+    # bool is a subclass of int but it's not a number
+    valid = isinstance(value, KNOWLEDGE_TYPES[type_name]) and not (type_name == "number" and isinstance(value, bool))
+    if not valid:
+        raise TypeError(f"knowledge {key} must be a {type_name}, got {type(value).__name__}")
+
+
+def declare_knowledge(key: str, type_name: str) -> None:
+    """Declare the type of a piece of knowledge, the following writes are checked against it."""
+
+    if type_name not in KNOWLEDGE_TYPES:
+        raise ValueError(f"unknown knowledge type {type_name}, valid types are: {', '.join(KNOWLEDGE_TYPES)}")
+
+    with _storage_lock:
+        _knowledge_types[key] = type_name
+        if key in _knowledge:
+            try:
+                _check_knowledge_type(key, _knowledge[key])
+            except TypeError:
+                del _knowledge_types[key]
+                raise
+
+
 def write_knowledge(key: str, value: t.Any) -> None:
     """Write a piece of knowledge that will be used in the system prompt."""
 
     global _knowledge
 
-    on_event("knowledge_change", {"name": key, "from": _knowledge.get(key), "to": value})
+    with _storage_lock:
+        _check_knowledge_type(key, value)
 
-    _knowledge[key] = value
+        on_event(
+            "knowledge_change",
+            {"name": key, "from": _knowledge.get(key), "to": value, "type": _knowledge_types.get(key)},
+        )
+
+        _knowledge[key] = value
 
 
 def append_to_knowledge(key: str, value: t.Any) -> None:
     """Append a piece of knowledge to the existing knowledge."""
 
     global _knowledge
-    if key not in _knowledge:
-        write_knowledge(key, value)
-    else:
-        write_knowledge(key, _knowledge[key] + "\n" + value)
+
+    with _storage_lock:
+        if key not in _knowledge:
+            write_knowledge(key, value)
+        elif isinstance(_knowledge[key], list):
+            write_knowledge(key, [*_knowledge[key], value])
+        elif isinstance(_knowledge[key], str):
+            write_knowledge(key, _knowledge[key] + "\n" + value)
+        else:
+            raise TypeError(f"can't append to knowledge {key} of type {type(_knowledge[key]).__name__}")
 
 
 def clear_knowledge(key: str) -> None:
     """Remove a piece of knowledge."""
 
     global _knowledge
-    if key in _knowledge:
-        on_event("knowledge_change", {"name": key, "from": _knowledge[key], "to": None})
-        del _knowledge[key]
+
+    with _storage_lock:
+        if key in _knowledge:
+            on_event(
+                "knowledge_change",
+                {"name": key, "from": _knowledge[key], "to": None, "type": _knowledge_types.get(key)},
+            )
+            del _knowledge[key]
 
 
 def update_variables(update: dict[str, t.Any]) -> None:
     """Update variables."""
 
     global _variables
-    for key, value in update.items():
-        on_event("variable_change", {"name": key, "from": _variables.get(key), "to": value})
-    _variables.update(update)
 
+    with _storage_lock:
+        for key, value in update.items():
+            on_event("variable_change", {"name": key, "from": _variables.get(key), "to": value})
+        _variables.update(update)
 
-def reset() -> None:
-    """Reset the state."""
 
-    global _task_status, _reason, _knowledge
+def reset_task() -> None:
+    """Reset the status of the active task, before the next one of the flow starts."""
+
+    global _task_status, _reason
     _task_status = Status.RUNNING
     _reason = None
 
 
+def reset() -> None:
+    """Reset the state."""
+
+    global _tool_stats, _knowledge_types, _cancel_reason, _pending_confirmations, _pending_questions
+    global _replay, _audit_trail
+    reset_task()
+    _tool_stats = {}
+    _knowledge_types = {}
+    _cancelled.clear()
+    _cancel_reason = "cancelled"
+    _pending_confirmations = {}
+    _pending_questions = {}
+    _replay = None
+    _audit_trail = None
+
+
 def on_user_input_needed(input_name: str, prompt: str) -> str:
     """Get user input."""
 
@@ -398,6 +689,148 @@ def on_user_input_needed(input_name: str, prompt: str) -> str:
         )
 
 
+def enable_host_confirmations(timeout: float = DEFAULT_CONFIRMATION_TIMEOUT) -> None:
+    """
+    Let the host application answer the confirmation requests via respond_to_confirmation instead of
+    asking on the terminal, requests not answered within the timeout are denied.
+    """
+
+    global _confirmation_timeout
+    _confirmation_timeout = timeout
+
+
+def respond_to_confirmation(request_id: str, approved: bool) -> bool:
+    """Answer a pending confirmation request, can be called from any thread."""
+
+    pending = _pending_confirmations.get(request_id)
+    if pending is None:
+        logger.warning(f"unknown or expired confirmation request: {request_id}")
+        return False
+
+    answered, result = pending
+    result.append(approved)
+    answered.set()
+    return True
+
+
+def _wait_for_host_confirmation(request_id: str, timeout: float) -> bool:
+    answered, result = _pending_confirmations[request_id]
+    try:
+        if not answered.wait(timeout):
+            logger.warning(f"confirmation request {request_id} timed out")
+            return False
+        return result[0]
+    finally:
+        del _pending_confirmations[request_id]
+
+
+def on_user_confirmation_needed(prompt: str, tool_name: str | None = None, args: t.Any | None = None) -> bool:
+    """Ask the user to confirm an action, always denied in non interactive mode."""
+
+    logger.debug(f"on_user_confirmation_needed: {prompt}")
+
+    request_id = str(uuid.uuid4())
+    if _confirmation_timeout is not None:
+        # register the request before notifying the host, it might answer right away
+        _pending_confirmations[request_id] = (threading.Event(), [])
+
+    on_event("confirmation_requested", {"id": request_id, "prompt": prompt, "tool_name": tool_name, "args": args})
+
+    if _confirmation_timeout is not None:
+        approved = _wait_for_host_confirmation(request_id, _confirmation_timeout)
+    elif not is_interactive():
+        logger.warning(f"denied in non interactive mode: {prompt}")
+        approved = False
+    else:
+        # wait for all events to be logged
+        wait_for_events_logs()
+        # ask the user
+        approved = input(f"{prompt} [y/N] ").strip().lower() in ("y", "yes")
+
+    on_event("confirmation_resolved", {"id": request_id, "tool_name": tool_name, "approved": approved})
+
+    return approved
+
+
+def enable_host_questions(timeout: float = DEFAULT_QUESTION_TIMEOUT) -> None:
+    """
+    Let the host application answer the questions of the agent via respond_to_question instead of
+    asking on the terminal, questions not answered within the timeout get no response.
+    """
+
+    global _question_timeout
+    _question_timeout = timeout
+
+
+def respond_to_question(request_id: str, answer: str) -> bool:
+    """Answer a pending question, can be called from any thread."""
+
+    pending = _pending_questions.get(request_id)
+    if pending is None:
+        logger.warning(f"unknown or expired question: {request_id}")
+        return False
+
+    answered, result = pending
+    result.append(answer)
+    answered.set()
+    return True
+
+
+def _wait_for_host_answer(request_id: str, timeout: float) -> str | None:
+    answered, result = _pending_questions[request_id]
+    try:
+        if not answered.wait(timeout):
+            logger.warning(f"question {request_id} timed out")
+            return None
+        return result[0]
+    finally:
+        del _pending_questions[request_id]
+
+
+def _ask_on_terminal(question: str, choices: list[str] | None) -> str | None:
+    # wait for all events to be logged
+    wait_for_events_logs()
+
+    prompt = question
+    if choices:
+        prompt += "\n" + "\n".join(f"  {i}. {choice}" for i, choice in enumerate(choices, 1))
+
+    answer = input(f"{prompt}\n> ").strip()
+    # choices can be selected by number
+    if choices and answer.isdigit() and 1 <= int(answer) <= len(choices):
+        answer = choices[int(answer) - 1]
+
+    return answer or None
+
+
+def on_user_question(question: str, choices: list[str] | None = None) -> str | None:
+    """
+    Ask the user an open question, optionally suggesting some choices, and return the answer or None if
+    nobody answered: in non interactive mode, or if the host didn't answer within the timeout.
+    """
+
+    logger.debug(f"on_user_question: {question} {choices}")
+
+    request_id = str(uuid.uuid4())
+    if _question_timeout is not None:
+        # register the question before notifying the host, it might answer right away
+        _pending_questions[request_id] = (threading.Event(), [])
+
+    on_event("question_asked", {"id": request_id, "question": question, "choices": choices})
+
+    answer: str | None = None
+    if _question_timeout is not None:
+        answer = _wait_for_host_answer(request_id, _question_timeout)
+    elif is_interactive():
+        answer = _ask_on_terminal(question, choices)
+    else:
+        logger.warning(f"not answered in non interactive mode: {question}")
+
+    on_event("question_answered", {"id": request_id, "answer": answer})
+
+    return answer
+
+
 def _create_jinja_env(working_dir: pathlib.Path) -> jinja2.Environment:
     logger.debug(f"creating jinja environment for working dir: {working_dir}")
 
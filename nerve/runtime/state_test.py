@@ -1,11 +1,14 @@
 import datetime
+import json
 import os
 import re
-from unittest.mock import MagicMock, patch
+import threading
+import typing as t
+from unittest.mock import ANY, MagicMock, patch
 
 import pytest
 
-from nerve.models import Mode
+from nerve.models import Mode, Status, Usage
 from nerve.runtime import state
 
 
@@ -123,3 +126,316 @@ class TestInterpolate:
             r"Builtin: \d+, Custom: custom_value",
             state.interpolate("Builtin: {{ RANDOM_INT }}, Custom: {{ custom_var }}"),
         )
+
+
+class TestConfirmations:
+    def setup_method(self) -> None:
+        state._mode = Mode.AUTOMATIC
+        state._confirmation_timeout = None
+
+    def teardown_method(self) -> None:
+        state._confirmation_timeout = None
+
+    @patch("nerve.runtime.state.on_event")
+    def test_denied_in_non_interactive_mode(self, mock_on_event: MagicMock) -> None:
+        assert not state.on_user_confirmation_needed("delete everything?", "rm", {"path": "/"})
+
+        names = [call.args[0] for call in mock_on_event.call_args_list]
+        assert names == ["confirmation_requested", "confirmation_resolved"]
+        assert mock_on_event.call_args_list[0].args[1]["tool_name"] == "rm"
+
+    @patch("nerve.runtime.state.on_event")
+    def test_approved_by_the_host(self, mock_on_event: MagicMock) -> None:
+        # the host answers as soon as it is notified
+        def on_event(name: str, data: dict[str, t.Any]) -> None:
+            if name == "confirmation_requested":
+                threading.Thread(target=state.respond_to_confirmation, args=(data["id"], True)).start()
+
+        mock_on_event.side_effect = on_event
+        state.enable_host_confirmations(timeout=5)
+
+        assert state.on_user_confirmation_needed("send the email?", "send_email", {})
+        assert state._pending_confirmations == {}
+
+    @patch("nerve.runtime.state.on_event")
+    def test_denied_on_timeout(self, _: MagicMock) -> None:
+        state.enable_host_confirmations(timeout=0.01)
+
+        assert not state.on_user_confirmation_needed("send the email?", "send_email", {})
+        assert not state.respond_to_confirmation("expired", True)
+
+
+class TestQuestions:
+    def setup_method(self) -> None:
+        state._mode = Mode.AUTOMATIC
+        state._question_timeout = None
+
+    def teardown_method(self) -> None:
+        self.setup_method()
+
+    @patch("nerve.runtime.state.on_event")
+    def test_no_answer_in_non_interactive_mode(self, mock_on_event: MagicMock) -> None:
+        assert state.on_user_question("which environment?") is None
+
+        names = [call.args[0] for call in mock_on_event.call_args_list]
+        assert names == ["question_asked", "question_answered"]
+
+    @patch("nerve.runtime.state.on_event")
+    def test_answered_by_the_host(self, mock_on_event: MagicMock) -> None:
+        def on_event(name: str, data: dict[str, t.Any]) -> None:
+            if name == "question_asked":
+                threading.Thread(target=state.respond_to_question, args=(data["id"], "staging")).start()
+
+        mock_on_event.side_effect = on_event
+        state.enable_host_questions(timeout=5)
+
+        assert state.on_user_question("which environment?", ["staging", "production"]) == "staging"
+        assert mock_on_event.call_args_list[0].args[1]["choices"] == ["staging", "production"]
+        assert state._pending_questions == {}
+
+    @patch("nerve.runtime.state.on_event")
+    def test_no_answer_on_timeout(self, mock_on_event: MagicMock) -> None:
+        state.enable_host_questions(timeout=0.01)
+
+        assert state.on_user_question("which environment?") is None
+        assert mock_on_event.call_args.args == ("question_answered", {"id": ANY, "answer": None})
+        assert not state.respond_to_question("expired", "staging")
+
+    @patch("builtins.input", return_value="2")
+    @patch("nerve.runtime.state.on_event")
+    def test_choice_by_number_in_interactive_mode(self, _: MagicMock, __: MagicMock) -> None:
+        state._mode = Mode.INTERACTIVE
+
+        assert state.on_user_question("which environment?", ["staging", "production"]) == "production"
+
+
+class TestSnapshot:
+    def setup_method(self) -> None:
+        state.reset()
+        state._usage = Usage()
+        state._variables = {}
+        state._knowledge = {}
+        state._knowledge_types = {}
+
+    def teardown_method(self) -> None:
+        self.setup_method()
+
+    @patch("nerve.runtime.state.on_event")
+    def test_round_trip(self, mock_on_event: MagicMock) -> None:
+        state._variables = {"target": "example.com"}
+        state._knowledge = {"plan": "1. [x] scan"}
+        state.update_usage(Usage(prompt_tokens=10, completion_tokens=5, total_tokens=15, cost=0.1))
+        state.set_task_failed("boom")
+
+        snapshot = json.loads(json.dumps(state.get_snapshot()))
+        self.setup_method()
+        state.restore_snapshot(snapshot)
+
+        assert state.get_variables() == {"target": "example.com"}
+        assert state.get_knowledge() == {"plan": "1. [x] scan"}
+        assert state.get_usage().total_tokens == 15
+        assert state.get_usage().cost == 0.1
+        assert state._task_status == Status.FAILED
+        assert state._reason == "boom"
+        assert mock_on_event.call_args.args[0] == "state_restored"
+
+    @patch("nerve.runtime.state.on_event")
+    def test_knowledge_types_are_restored(self, _: MagicMock) -> None:
+        state.declare_knowledge("attempts", "number")
+        state.write_knowledge("attempts", 3)
+
+        snapshot = json.loads(json.dumps(state.get_snapshot()))
+        self.setup_method()
+        state.restore_snapshot(snapshot)
+
+        assert state.get_knowledge() == {"attempts": 3}
+        with pytest.raises(TypeError):
+            state.write_knowledge("attempts", "4")
+
+    @patch("nerve.runtime.state.on_event")
+    def test_peek(self, mock_on_event: MagicMock) -> None:
+        state._variables = {"target": "example.com", "started": datetime.date(2025, 1, 1)}
+        state.declare_knowledge("ports", "json")
+        state.write_knowledge("ports", [22, 80])
+        state.write_knowledge("plan", "1. [ ] scan")
+        mock_on_event.reset_mock()
+
+        peeked = state.peek()
+
+        assert peeked == {
+            "current_task": {"status": "running", "reason": None},
+            "variables": {"target": "example.com", "started": "2025-01-01"},
+            "knowledge": {
+                "ports": {"type": "json", "value": [22, 80]},
+                "plan": {"type": None, "value": "1. [ ] scan"},
+            },
+        }
+        mock_on_event.assert_not_called()
+
+        # the result is a copy
+        peeked["knowledge"]["ports"]["value"].append(443)
+        assert state.get_knowledge()["ports"] == [22, 80]
+
+    @patch("nerve.runtime.state.on_event")
+    def test_emit_state_snapshot(self, mock_on_event: MagicMock) -> None:
+        state._variables = {"target": "example.com"}
+
+        state.emit_state_snapshot()
+
+        mock_on_event.assert_called_once_with("state_snapshot", state.peek())
+
+
+class TestTypedKnowledge:
+    def setup_method(self) -> None:
+        state._knowledge = {}
+        state._knowledge_types = {}
+
+    def teardown_method(self) -> None:
+        self.setup_method()
+
+    @patch("nerve.runtime.state.on_event")
+    def test_undeclared_knowledge_is_not_checked(self, _: MagicMock) -> None:
+        state.write_knowledge("notes", "first")
+        state.append_to_knowledge("notes", "second")
+        state.write_knowledge("notes", 42)
+
+        assert state.get_knowledge() == {"notes": 42}
+
+    @patch("nerve.runtime.state.on_event")
+    def test_typed_knowledge_is_checked(self, mock_on_event: MagicMock) -> None:
+        state.declare_knowledge("progress", "number")
+        state.write_knowledge("progress", 40)
+
+        for invalid in ("40", True, None):
+            with pytest.raises(TypeError):
+                state.write_knowledge("progress", invalid)
+
+        assert state.get_knowledge() == {"progress": 40}
+        mock_on_event.assert_called_once_with(
+            "knowledge_change", {"name": "progress", "from": None, "to": 40, "type": "number"}
+        )
+
+    @patch("nerve.runtime.state.on_event")
+    def test_append_to_json_list(self, _: MagicMock) -> None:
+        state.declare_knowledge("findings", "json")
+        state.write_knowledge("findings", [])
+        state.append_to_knowledge("findings", {"port": 22})
+
+        assert state.get_knowledge() == {"findings": [{"port": 22}]}
+
+    @patch("nerve.runtime.state.on_event")
+    def test_declare_with_existing_value_of_another_type(self, _: MagicMock) -> None:
+        state.write_knowledge("done", "yes")
+
+        with pytest.raises(TypeError):
+            state.declare_knowledge("done", "boolean")
+        with pytest.raises(ValueError):
+            state.declare_knowledge("done", "date")
+
+        # the key is still untyped
+        state.write_knowledge("done", "no")
+
+
+class TestToolStats:
+    def setup_method(self) -> None:
+        state._tool_stats = {}
+
+    def teardown_method(self) -> None:
+        state._tool_stats = {}
+
+    @patch("nerve.runtime.state.on_event")
+    def test_aggregates(self, _: MagicMock) -> None:
+        state.on_tool_called(0.0, 1.0, "http_get")
+        state.on_tool_called(5.0, 8.0, "http_get", error="timeout")
+        state.on_tool_called(10.0, 12.0, "http_get")
+        state.on_tool_called(0.0, 0.5, "read_file")
+
+        stats = state.get_tool_stats()
+
+        assert stats["http_get"].count == 3
+        assert stats["http_get"].errors == 1
+        assert stats["http_get"].total_time == 6.0
+        assert stats["http_get"].min_time == 1.0
+        assert stats["http_get"].max_time == 3.0
+        assert stats["http_get"].mean_time == 2.0
+        assert stats["read_file"].count == 1
+        # serializable through the events
+        assert json.loads(json.dumps(stats, cls=state.CustomJSONEncoder))["read_file"]["mean_time"] == 0.5
+
+    @patch("nerve.runtime.state.on_event")
+    def test_events_carry_the_call_id(self, mock_on_event: MagicMock) -> None:
+        state.set_tool_call_id("call_1")
+        try:
+            state.on_before_tool_called("http_get", {"url": "http://example.com"})
+            state.on_tool_called(0.0, 1.0, "http_get")
+        finally:
+            state.set_tool_call_id(None)
+        state.on_tool_called(0.0, 1.0, "http_get")
+
+        ids = [c.args[1]["call_id"] for c in mock_on_event.call_args_list]
+        assert ids == ["call_1", "call_1", None]
+
+
+class TestRunCorrelation:
+    def setup_method(self) -> None:
+        self.run_id, self.step = state._run_id, state._step
+
+    def teardown_method(self) -> None:
+        state._run_id, state._step = self.run_id, self.step
+
+    def test_events_carry_the_run_and_step(self) -> None:
+        assert state.start_run("run-1") == "run-1"
+        state.on_event("before")
+        state.set_step(3)
+        state.on_event("during")
+
+        before, during = state._events[-2:]
+        assert (before.run_id, before.step) == ("run-1", None)
+        assert (during.run_id, during.step) == ("run-1", 3)
+
+    def test_runs_get_a_random_id(self) -> None:
+        state.set_step(3)
+
+        # the id set by the environment is read once, at import time
+        environ = {k: v for k, v in os.environ.items() if k != "NERVE_RUN_ID"}
+        with patch.dict(os.environ, environ, clear=True), patch.object(state, "DEFAULT_RUN_ID", None):
+            first, second = state.start_run(), state.start_run()
+
+        assert first and second and first != second
+        assert state._step is None
+
+
+class TestReset:
+    @patch("nerve.runtime.state.on_event")
+    def test_reset_clears_the_run(self, _: MagicMock) -> None:
+        state.on_tool_called(0.0, 1.0, "http_get")
+        state.declare_knowledge("findings", "json")
+        state.cancel("timeout reached")
+        state._pending_confirmations["1"] = (threading.Event(), [])
+        state._pending_questions["1"] = (threading.Event(), [])
+        state.set_replay(MagicMock())
+        state.set_audit_trail(MagicMock())
+        state.set_task_failed("failed")
+
+        state.reset()
+
+        assert state.get_tool_stats() == {}
+        assert state._knowledge_types == {}
+        assert not state.is_cancelled()
+        assert state._cancel_reason == "cancelled"
+        assert state._pending_confirmations == {} and state._pending_questions == {}
+        assert state.get_replay() is None and state.get_audit_trail() is None
+        assert not state.is_active_task_done()
+
+    @patch("nerve.runtime.state.on_event")
+    def test_reset_task_keeps_the_run(self, _: MagicMock) -> None:
+        try:
+            state.on_tool_called(0.0, 1.0, "http_get")
+            state.set_task_failed("failed")
+
+            state.reset_task()
+
+            assert not state.is_active_task_done()
+            assert state.get_tool_stats()["http_get"].count == 1
+        finally:
+            state.reset()
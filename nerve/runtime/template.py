@@ -0,0 +1,76 @@
+import json
+import os
+import re
+import typing as t
+
+import nerve.runtime.state as state
+from nerve.runtime.builtin import get_builtin_variable_value, is_builtin_variable
+
+# {{ name }}, {{ name.key }}, {{ global.NAME }} or {{ env.NAME }}
+_REFERENCE = re.compile(r"\{\{\s*([A-Za-z_][\w-]*(?:\.[\w-]+)*)\s*\}\}")
+
+
+class UndefinedReferenceError(ValueError):
+    """Raised when a template references an undefined variable."""
+
+    def __init__(self, reference: str):
+        super().__init__(f"undefined reference {{{{ {reference} }}}}")
+        self.reference = reference
+
+
+def _lookup(reference: str) -> t.Any:
+    scope, _, rest = reference.partition(".")
+    if scope == "env" and rest:
+        return os.environ.get(rest)
+
+    elif scope == "global" and rest:
+        return get_builtin_variable_value(rest) if is_builtin_variable(rest) else None
+
+    variables = state.get_variables()
+    if scope not in variables:
+        return get_builtin_variable_value(scope) if not rest and is_builtin_variable(scope) else None
+
+    # nested keys of a structured variable
+    value = variables[scope]
+    for key in rest.split(".") if rest else []:
+        if isinstance(value, dict) and key in value:
+            value = value[key]
+        elif isinstance(value, list) and key.isdigit() and int(key) < len(value):
+            value = value[int(key)]
+        else:
+            return None
+
+    return value
+
+
+def _resolve_string(raw: str, undefined: t.Literal["error", "empty"]) -> str:
+    def replace(match: re.Match[str]) -> str:
+        value = _lookup(match.group(1))
+        if value is None:
+            if undefined == "error":
+                raise UndefinedReferenceError(match.group(1))
+            return ""
+
+        return value if isinstance(value, str) else json.dumps(value, default=str)
+
+    # the resolved values are not resolved again, so that they can't inject references
+    return _REFERENCE.sub(replace, raw)
+
+
+def resolve(value: t.Any, undefined: t.Literal["error", "empty"] = "error") -> t.Any:
+    """
+    Resolve the {{ variable }}, {{ global.NAME }} and {{ env.NAME }} references of every string in the value,
+    recursing into dictionaries and lists. Undefined references either raise an UndefinedReferenceError
+    or are replaced with an empty string.
+    """
+
+    if isinstance(value, str):
+        return _resolve_string(value, undefined)
+
+    elif isinstance(value, dict):
+        return {key: resolve(item, undefined) for key, item in value.items()}
+
+    elif isinstance(value, list):
+        return [resolve(item, undefined) for item in value]
+
+    return value
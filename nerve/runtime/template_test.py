@@ -0,0 +1,53 @@
+import os
+import unittest
+from unittest.mock import patch
+
+from nerve.runtime import state
+from nerve.runtime.template import UndefinedReferenceError, resolve
+
+
+class TestResolve(unittest.TestCase):
+    def setUp(self) -> None:
+        state.reset()
+        state.update_variables({"target": "example.com", "ports": [22, 80], "creds": {"user": {"name": "admin"}}})
+
+    def tearDown(self) -> None:
+        state.reset()
+
+    def test_variables(self) -> None:
+        self.assertEqual(resolve("scan {{ target }} and {{target}}"), "scan example.com and example.com")
+
+    def test_nested_references(self) -> None:
+        self.assertEqual(resolve("{{ creds.user.name }}"), "admin")
+        self.assertEqual(resolve("{{ ports.1 }}"), "80")
+        self.assertEqual(resolve("{{ ports }}"), "[22, 80]")
+
+    def test_nested_values(self) -> None:
+        args = {"command": "ping {{ target }}", "options": [{"host": "{{ target }}"}, 3], "quiet": True}
+
+        self.assertEqual(
+            resolve(args),
+            {"command": "ping example.com", "options": [{"host": "example.com"}, 3], "quiet": True},
+        )
+
+    def test_global_and_env(self) -> None:
+        with patch.dict(os.environ, {"NERVE_TEST_VAR": "from env"}):
+            self.assertEqual(resolve("{{ env.NERVE_TEST_VAR }}"), "from env")
+
+        self.assertEqual(resolve("{{ global.CURRENT_YEAR }}"), resolve("{{ CURRENT_YEAR }}"))
+
+    def test_undefined_error(self) -> None:
+        for raw in ("{{ missing }}", "{{ creds.user.password }}", "{{ env.NERVE_MISSING_VAR }}", "{{ global.NOPE }}"):
+            with self.assertRaises(UndefinedReferenceError):
+                resolve({"args": [raw]})
+
+    def test_undefined_empty(self) -> None:
+        self.assertEqual(resolve("a{{ missing }}b", "empty"), "ab")
+
+    def test_resolved_values_are_not_resolved_again(self) -> None:
+        state.update_variables({"injected": "{{ env.HOME }}"})
+
+        self.assertEqual(resolve("{{ injected }}"), "{{ env.HOME }}")
+
+    def test_text_without_references(self) -> None:
+        self.assertEqual(resolve("{ not a reference } {{ }}"), "{ not a reference } {{ }}")
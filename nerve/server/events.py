@@ -0,0 +1,146 @@
+import asyncio
+import collections
+import hmac
+import json
+import threading
+import typing as t
+
+import uvicorn
+from loguru import logger
+from starlette.applications import Starlette
+from starlette.requests import Request
+from starlette.responses import PlainTextResponse, Response, StreamingResponse
+from starlette.routing import Route
+
+import nerve.runtime.state as state
+from nerve.defaults import DEFAULT_SERVE_HOST
+from nerve.runtime.events import Event
+
+# seconds of inactivity after which a comment is sent to keep the connections alive
+HEARTBEAT_INTERVAL: float = 15.0
+# number of recent events kept for the clients resuming with a last event id
+BUFFER_SIZE: int = 1000
+
+
+def format_event(event_id: int, event: Event) -> str:
+    """Serialize an event as a Server-Sent Events message."""
+
+    data = json.dumps(event.model_dump(), cls=state.CustomJSONEncoder)
+    return f"id: {event_id}\nevent: {event.name}\ndata: {data}\n\n"
+
+
+class EventStream:
+    """
+    Broadcasts the events of the runtime to any number of Server-Sent Events clients, keeping the most
+    recent ones so that a client reconnecting with its last event id doesn't miss any.
+    """
+
+    def __init__(self, buffer_size: int = BUFFER_SIZE):
+        self._lock = threading.Lock()
+        self._last_id = 0
+        self._buffer: collections.deque[tuple[int, Event]] = collections.deque(maxlen=buffer_size)
+        self._subscribers: list[tuple[asyncio.AbstractEventLoop, asyncio.Queue[tuple[int, Event]]]] = []
+
+    def attach(self) -> None:
+        """Start receiving the events of the runtime."""
+
+        state.add_event_listener(self.publish)
+
+    def publish(self, event: Event) -> None:
+        """Send an event to every client, can be called from any thread."""
+
+        with self._lock:
+            self._last_id += 1
+            item = (self._last_id, event)
+            self._buffer.append(item)
+            subscribers = list(self._subscribers)
+
+        for loop, queue in subscribers:
+            try:
+                loop.call_soon_threadsafe(queue.put_nowait, item)
+            except RuntimeError:
+                # the loop of the client is closed
+                pass
+
+    async def subscribe(
+        self,
+        last_event_id: int | None = None,
+        types: set[str] | None = None,
+        heartbeat: float = HEARTBEAT_INTERVAL,
+    ) -> t.AsyncIterator[str]:
+        """
+        Yield the SSE messages of the events with the given names, or of every event if not set, starting
+        after last_event_id if provided.
+        """
+
+        queue: asyncio.Queue[tuple[int, Event]] = asyncio.Queue()
+        subscriber = (asyncio.get_running_loop(), queue)
+
+        with self._lock:
+            backlog = [item for item in self._buffer if last_event_id is not None and item[0] > last_event_id]
+            self._subscribers.append(subscriber)
+
+        try:
+            for event_id, event in backlog:
+                if types is None or event.name in types:
+                    yield format_event(event_id, event)
+
+            while True:
+                try:
+                    event_id, event = await asyncio.wait_for(queue.get(), heartbeat)
+                except asyncio.TimeoutError:
+                    yield ": heartbeat\n\n"
+                    continue
+
+                if types is None or event.name in types:
+                    yield format_event(event_id, event)
+        finally:
+            with self._lock:
+                self._subscribers.remove(subscriber)
+
+
+def _is_authorized(request: Request, token: str) -> bool:
+    # browsers can't set headers on an EventSource, so the token can also be passed as query parameter
+    provided = request.query_params.get("token", "")
+    authorization = request.headers.get("authorization", "")
+    if authorization.lower().startswith("bearer "):
+        provided = authorization[len("bearer ") :]
+
+    return hmac.compare_digest(provided.encode(), token.encode())
+
+
+def create_events_app(stream: EventStream, token: str | None = None) -> Starlette:
+    """Create the app streaming the events on /events, optionally filtered with ?types=name1,name2."""
+
+    async def handle_events(request: Request) -> Response:
+        if token is not None and not _is_authorized(request, token):
+            return PlainTextResponse("unauthorized", status_code=401)
+
+        types = {name.strip() for name in request.query_params.get("types", "").split(",") if name.strip()}
+        last_event_id = request.headers.get("last-event-id") or request.query_params.get("last_event_id")
+        try:
+            resume_from = int(last_event_id) if last_event_id else None
+        except ValueError:
+            return PlainTextResponse("invalid last event id", status_code=400)
+
+        return StreamingResponse(
+            stream.subscribe(resume_from, types or None),
+            media_type="text/event-stream",
+            headers={"Cache-Control": "no-cache", "X-Accel-Buffering": "no"},
+        )
+
+    return Starlette(routes=[Route("/events", endpoint=handle_events)])
+
+
+async def serve_events(stream: EventStream, address: str, token: str | None = None) -> None:
+    """Serve the events stream on the given host:port address."""
+
+    host, _, port = address.rpartition(":")
+    host = host or DEFAULT_SERVE_HOST
+    if not token:
+        logger.warning("🔓 the events stream is not protected by a token")
+
+    logger.info(f"📡 streaming events on http://{host}:{port}/events")
+
+    config = uvicorn.Config(create_events_app(stream, token), host=host, port=int(port), log_level="warning")
+    await uvicorn.Server(config).serve()
@@ -0,0 +1,81 @@
+import asyncio
+import json
+import threading
+import unittest
+
+from starlette.requests import Request
+
+from nerve.runtime.events import Event
+from nerve.server.events import EventStream, _is_authorized, format_event
+
+
+def _get_request(query: str = "", headers: dict[str, str] | None = None) -> Request:
+    return Request(
+        {
+            "type": "http",
+            "query_string": query.encode(),
+            "headers": [(k.lower().encode(), v.encode()) for k, v in (headers or {}).items()],
+        }
+    )
+
+
+class TestEventStream(unittest.IsolatedAsyncioTestCase):
+    def test_format_event(self) -> None:
+        message = format_event(3, Event(name="step_started", data={"step": 1}))
+
+        lines = message.split("\n")
+        self.assertEqual(lines[0], "id: 3")
+        self.assertEqual(lines[1], "event: step_started")
+        self.assertEqual(json.loads(lines[2].removeprefix("data: "))["data"], {"step": 1})
+        self.assertTrue(message.endswith("\n\n"))
+
+    async def test_live_events_are_filtered(self) -> None:
+        stream = EventStream()
+        messages = stream.subscribe(types={"tool_called"})
+        next_message = asyncio.ensure_future(messages.__anext__())
+        await asyncio.sleep(0)
+
+        # events are published from the listeners thread pool
+        thread = threading.Thread(
+            target=lambda: [stream.publish(Event(name=name)) for name in ("step_started", "tool_called")]
+        )
+        thread.start()
+        thread.join()
+
+        message = await asyncio.wait_for(next_message, 1)
+        self.assertIn("id: 2\nevent: tool_called\n", message)
+        await messages.aclose()
+        self.assertEqual(stream._subscribers, [])
+
+    async def test_resume_from_last_event_id(self) -> None:
+        stream = EventStream()
+        for name in ("a", "b", "c"):
+            stream.publish(Event(name=name))
+
+        messages = stream.subscribe(last_event_id=1)
+
+        self.assertIn("event: b", await messages.__anext__())
+        self.assertIn("event: c", await messages.__anext__())
+        await messages.aclose()
+
+    async def test_no_backlog_without_last_event_id(self) -> None:
+        stream = EventStream()
+        stream.publish(Event(name="a"))
+
+        messages = stream.subscribe(heartbeat=0.01)
+
+        self.assertEqual(await messages.__anext__(), ": heartbeat\n\n")
+        await messages.aclose()
+
+    async def test_buffer_is_bounded(self) -> None:
+        stream = EventStream(buffer_size=2)
+        for name in ("a", "b", "c"):
+            stream.publish(Event(name=name))
+
+        self.assertEqual([event_id for event_id, _ in stream._buffer], [2, 3])
+
+    def test_authorization(self) -> None:
+        self.assertTrue(_is_authorized(_get_request(headers={"Authorization": "Bearer secret"}), "secret"))
+        self.assertTrue(_is_authorized(_get_request("token=secret"), "secret"))
+        self.assertFalse(_is_authorized(_get_request("token=wrong"), "secret"))
+        self.assertFalse(_is_authorized(_get_request(), "secret"))
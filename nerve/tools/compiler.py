@@ -1,8 +1,10 @@
 import asyncio
 import base64
+import contextlib
 import functools
 import importlib
 import inspect
+import mimetypes
 import os
 import pathlib
 import time
@@ -10,11 +12,13 @@ import traceback
 import typing as t
 
 import jinja2
+import pydantic
 from loguru import logger
 from termcolor import colored
 
 from nerve.models import Tool
 from nerve.runtime import state
+from nerve.tools import limits, utils
 
 
 def wrap_tool_function(func: t.Callable[..., t.Any], mime: str | None = None) -> t.Callable[..., t.Any]:
@@ -22,6 +26,8 @@ def wrap_tool_function(func: t.Callable[..., t.Any], mime: str | None = None) ->
     Creates a wrapper around a function that logs the function call and its result.
     """
 
+    limiter = limits.get_limiter(func)
+
     async def wrapper(*args: t.Any, **kwargs: t.Any) -> t.Any:
         logger.debug(f"calling {func.__name__} ...")
 
@@ -30,10 +36,16 @@ def wrap_tool_function(func: t.Callable[..., t.Any], mime: str | None = None) ->
         started_at = time.time()
         error = None
         try:
-            result = func(*args, **kwargs)
-            # check if the tool function returned a coroutine
-            if asyncio.iscoroutine(result):
-                result = await result
+            # calls exceeding the tool limits are queued
+            async with limiter.acquire() if limiter else contextlib.nullcontext():
+                if asyncio.iscoroutinefunction(func):
+                    result = func(*args, **kwargs)
+                else:
+                    # blocking tools run in a thread, so the run can still be cancelled while they execute
+                    result = await asyncio.to_thread(func, *args, **kwargs)
+                # check if the tool function returned a coroutine
+                if asyncio.iscoroutine(result):
+                    result = await result
 
             logger.debug(f"tool {func.__name__} returned: {result}")
 
@@ -53,6 +65,9 @@ def wrap_tool_function(func: t.Callable[..., t.Any], mime: str | None = None) ->
                     "type": "image_url",
                     "image_url": {"url": f"data:{mime};base64,{base64.b64encode(result).decode('utf-8')}"},
                 }
+            elif mimetypes.guess_extension(mime):
+                # documents like PDFs
+                result = utils.file_part(result, mime, f"{func.__name__}{mimetypes.guess_extension(mime)}")
             else:
                 logger.error(f"tool {func.__name__} references an unsupported mime type: {mime}")
                 exit(1)
@@ -65,42 +80,147 @@ def wrap_tool_function(func: t.Callable[..., t.Any], mime: str | None = None) ->
     return wrapper
 
 
-def get_tools_from_namespace(namespace: str, jail: list[str]) -> list[t.Callable[..., t.Any]]:
+def _import_namespace(namespace: str) -> t.Any:
     try:
         importlib.util.find_spec(f"nerve.tools.namespaces.{namespace}")
     except ImportError as err:
         raise ImportError(f"namespace {namespace} not found") from err
 
     try:
-        module = __import__(f"nerve.tools.namespaces.{namespace}", fromlist=[""])
-        if jail:
-            for jailed_path in jail:
-                module.jail.append(jailed_path)
-                logger.debug(f"namespace {namespace} jailed to: {jailed_path}")
-
-        module_tools = [
-            wrap_tool_function(func)
-            for (name, func) in inspect.getmembers(module, inspect.isfunction)
-            if name[0] != "_" and func.__module__ == module.__name__
-        ]
-        logger.debug(f"importing {len(module_tools)} tools from {namespace} namespace")
-        return module_tools
+        return __import__(f"nerve.tools.namespaces.{namespace}", fromlist=[""])
     except ImportError as err:
         raise ImportError(f"could not import {namespace}: {err}") from err
 
 
+def _validate_settings(namespace: str, module: t.Any, settings: dict[str, t.Any]) -> dict[str, t.Any]:
+    # the settings of a namespace are its public module variables with a type annotation,
+    # the jail has its own directive
+    fields = {
+        name: hint
+        for name, hint in t.get_type_hints(module).items()
+        if name[0] != "_" and name != "jail" and hasattr(module, name)
+    }
+
+    validated = {}
+    for name, value in settings.items():
+        if name not in fields:
+            raise ValueError(f"unknown setting {name} for namespace {namespace}, available settings: {sorted(fields)}")
+        try:
+            validated[name] = pydantic.TypeAdapter(fields[name]).validate_python(value)
+        except pydantic.ValidationError as err:
+            raise ValueError(f"invalid setting {namespace}.{name}: {err.errors()[0]['msg']} (got {value!r})") from None
+
+    return validated
+
+
+def validate_namespace_settings(namespace: str, settings: dict[str, t.Any]) -> dict[str, t.Any]:
+    """Validate the settings of a namespace against the types of its variables, without applying them."""
+
+    return _validate_settings(namespace, _import_namespace(namespace), settings)
+
+
+def get_tools_from_namespace(
+    namespace: str,
+    jail: list[str],
+    allow: list[str] | None = None,
+    deny: list[str] | None = None,
+    settings: dict[str, t.Any] | None = None,
+) -> list[t.Callable[..., t.Any]]:
+    module = _import_namespace(namespace)
+
+    for name, value in _validate_settings(namespace, module, settings or {}).items():
+        setattr(module, name, value)
+        logger.debug(f"namespace {namespace} setting {name} set to {value!r}")
+
+    if jail:
+        for jailed_path in jail:
+            module.jail.append(jailed_path)
+            logger.debug(f"namespace {namespace} jailed to: {jailed_path}")
+
+    functions = {
+        name: func
+        for (name, func) in inspect.getmembers(module, inspect.isfunction)
+        if name[0] != "_" and func.__module__ == module.__name__
+    }
+
+    # make sure that typos don't silently enable tools
+    unknown = [name for name in (allow or []) + (deny or []) if name not in functions]
+    if unknown:
+        raise ValueError(f"unknown tools {unknown} in namespace {namespace}, available tools: {list(functions)}")
+
+    module_tools = []
+    for name, func in functions.items():
+        if (allow is not None and name not in allow) or (deny is not None and name in deny):
+            logger.debug(f"tool {namespace}.{name} disabled")
+            continue
+
+        module_tools.append(wrap_tool_function(func))
+
+    logger.debug(f"importing {len(module_tools)} tools from {namespace} namespace")
+    return module_tools
+
+
 def get_tools_from_namespaces(
     namespaces: list[str],
     jail: dict[str, list[str]],
+    allow: dict[str, list[str]] | None = None,
+    deny: dict[str, list[str]] | None = None,
+    settings: dict[str, dict[str, t.Any]] | None = None,
 ) -> list[t.Callable[..., t.Any]]:
     tools = []
+    allow = allow or {}
+    deny = deny or {}
+    settings = settings or {}
 
     for namespace in namespaces:
-        tools.extend(get_tools_from_namespace(namespace, jail.get(namespace, [])))
+        tools.extend(
+            get_tools_from_namespace(
+                namespace,
+                jail.get(namespace, []),
+                allow.get(namespace),
+                deny.get(namespace),
+                settings.get(namespace),
+            )
+        )
 
     return tools
 
 
+def prioritize_tools(
+    tools: list[t.Callable[..., t.Any]], priority: list[str], optional_namespaces: list[str] | None = None
+) -> list[t.Callable[..., t.Any]]:
+    """
+    Move the tools matching the priority list, by namespace, by name or as namespace.name, first and in the same
+    order, the others keep their order.
+    """
+
+    def get_names(tool: t.Callable[..., t.Any]) -> tuple[str, ...]:
+        module = getattr(tool, "__module__", "") or ""
+        if not module.startswith("nerve.tools.namespaces."):
+            return (tool.__name__,)
+
+        namespace = module.removeprefix("nerve.tools.namespaces.")
+        return (tool.__name__, namespace, f"{namespace}.{tool.__name__}")
+
+    names = {tool.__name__: get_names(tool) for tool in tools}
+
+    # make sure that typos don't silently leave the order unchanged, optional namespaces are activated later
+    known = {name for tool_names in names.values() for name in tool_names}
+    unknown = [
+        entry
+        for entry in priority
+        if entry not in known and entry.split(".")[0] not in (optional_namespaces or [])
+    ]
+    if unknown:
+        raise ValueError(f"unknown tools or namespaces {unknown} in the tools priority")
+
+    def get_rank(tool: t.Callable[..., t.Any]) -> int:
+        return next((i for i, entry in enumerate(priority) if entry in names[tool.__name__]), len(priority))
+
+    # sorting is stable
+    return sorted(tools, key=get_rank)
+
+
 def get_tools_from_file(working_dir: pathlib.Path, file: str) -> list[t.Callable[..., t.Any]]:
     tool_path = pathlib.Path(file)
     if not tool_path.is_absolute():
@@ -151,7 +271,11 @@ def get_tool_from_yml(working_dir: pathlib.Path, tool: Tool) -> t.Callable[...,
     func_namespace: dict[str, t.Any] = {}
     exec(func_body, func_namespace)
 
-    return wrap_tool_function(func_namespace[tool.name], tool.mime)
+    func = limits.limited(tool.max_concurrency, tool.rate_limit)(func_namespace[tool.name])
+    if tool.complete_task:
+        func.__nerve_completes_task__ = True  # type: ignore
+
+    return wrap_tool_function(func, tool.mime)
 
 
 def get_tools_from_yml(working_dir: pathlib.Path, yml_tools: list[Tool]) -> list[t.Callable[..., t.Any]]:
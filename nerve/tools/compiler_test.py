@@ -2,7 +2,14 @@ import base64
 import unittest
 from unittest.mock import MagicMock, patch
 
-from nerve.tools.compiler import wrap_tool_function
+from nerve.tools.compiler import (
+    get_tools_from_namespace,
+    get_tools_from_namespaces,
+    prioritize_tools,
+    validate_namespace_settings,
+    wrap_tool_function,
+)
+from nerve.tools.namespaces import feeds
 
 
 class TestWrapToolFunction(unittest.IsolatedAsyncioTestCase):
@@ -40,6 +47,16 @@ class TestWrapToolFunction(unittest.IsolatedAsyncioTestCase):
         self.assertEqual(result["type"], "image_url")
         self.assertEqual(result["image_url"]["url"], f"data:{mime};base64,{expected_b64}")
 
+    async def test_document_mime_returns_file_dict(self) -> None:
+        def report() -> bytes:
+            return b"%PDF-1.7"
+
+        result = await wrap_tool_function(report, mime="application/pdf")()
+
+        self.assertEqual(result["type"], "file")
+        self.assertEqual(result["file"]["filename"], "report.pdf")
+        self.assertEqual(result["file"]["file_data"], "data:application/pdf;base64,JVBERi0xLjc=")
+
     @patch("nerve.tools.compiler.logger")
     @patch("nerve.tools.compiler.exit")
     async def test_invalid_mime_raises_exception(self, mock_exit: MagicMock, mock_logger: MagicMock) -> None:
@@ -52,3 +69,87 @@ class TestWrapToolFunction(unittest.IsolatedAsyncioTestCase):
 
         mock_logger.error.assert_called_once_with("tool test_func references an unsupported mime type: invalid/mime")
         mock_exit.assert_called_once_with(1)
+
+
+class TestGetToolsFromNamespace(unittest.TestCase):
+    def test_all_tools_by_default(self) -> None:
+        tools = get_tools_from_namespace("filesystem", [])
+
+        self.assertEqual(sorted(tool.__name__ for tool in tools), ["list_folder_contents", "read_file", "read_many"])
+
+    def test_allow(self) -> None:
+        tools = get_tools_from_namespace("filesystem", [], allow=["read_file"])
+
+        self.assertEqual([tool.__name__ for tool in tools], ["read_file"])
+
+    def test_deny(self) -> None:
+        tools = get_tools_from_namespaces(["filesystem", "task"], {}, deny={"filesystem": ["read_file"]})
+
+        names = [tool.__name__ for tool in tools]
+        self.assertIn("list_folder_contents", names)
+        self.assertNotIn("read_file", names)
+        self.assertIn("task_complete_success", names)
+
+    def test_unknown_tool_names_are_rejected(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unknown tools \\['rm'\\]"):
+            get_tools_from_namespace("filesystem", [], deny=["rm"])
+
+
+class TestPrioritizeTools(unittest.TestCase):
+    def setUp(self) -> None:
+        def custom_tool() -> str:
+            """A custom tool."""
+            return ""
+
+        self.tools = get_tools_from_namespaces(["filesystem", "time"], {}) + [custom_tool]
+
+    def _names(self, priority: list[str]) -> list[str]:
+        return [tool.__name__ for tool in prioritize_tools(self.tools, priority, ["planning"])]
+
+    def test_order_is_preserved_by_default(self) -> None:
+        self.assertEqual(self._names([]), [tool.__name__ for tool in self.tools])
+
+    def test_namespaces_and_tools_come_first(self) -> None:
+        time_tools = [tool.__name__ for tool in get_tools_from_namespace("time", [])]
+
+        self.assertEqual(
+            self._names(["custom_tool", "time", "filesystem.read_many"]),
+            ["custom_tool", *time_tools, "read_many", "list_folder_contents", "read_file"],
+        )
+
+    def test_unknown_entries_are_rejected(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unknown tools or namespaces \\['shell'\\]"):
+            self._names(["shell", "planning.add_step"])
+
+
+class TestNamespaceSettings(unittest.TestCase):
+    def setUp(self) -> None:
+        self.max_entries = feeds.max_entries
+
+    def tearDown(self) -> None:
+        feeds.max_entries = self.max_entries
+
+    def test_settings_are_applied(self) -> None:
+        get_tools_from_namespace("feeds", [], settings={"max_entries": "5"})
+
+        self.assertEqual(feeds.max_entries, 5)
+
+    def test_unknown_settings_are_rejected(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unknown setting max_items for namespace feeds"):
+            get_tools_from_namespace("feeds", [], settings={"max_items": 5})
+
+    def test_invalid_settings_are_rejected(self) -> None:
+        with self.assertRaisesRegex(ValueError, "invalid setting feeds.max_entries: .*integer.* \\(got 'many'\\)"):
+            validate_namespace_settings("feeds", {"max_entries": "many"})
+
+        self.assertEqual(feeds.max_entries, self.max_entries)
+
+    def test_limits_are_settings(self) -> None:
+        self.assertEqual(validate_namespace_settings("email", {"max_body_length": "100"}), {"max_body_length": 100})
+        self.assertEqual(
+            validate_namespace_settings("planning", {"max_visible_revisions": 3}), {"max_visible_revisions": 3}
+        )
+
+    def test_jail_is_not_a_setting(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unknown setting jail"):
+            validate_namespace_settings("filesystem", {"jail": ["/tmp"]})
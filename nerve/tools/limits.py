@@ -0,0 +1,107 @@
+import asyncio
+import contextlib
+import time
+import typing as t
+
+from loguru import logger
+
+
+class Limiter:
+    """
+    Limits the number of concurrent calls and the calls per second of a tool. Calls exceeding the
+    limits are queued rather than failed.
+    """
+
+    def __init__(self, name: str, max_concurrency: int | None = None, rate_limit: float | None = None) -> None:
+        self.name = name
+        self.max_concurrency = max_concurrency
+        self.rate_limit = rate_limit
+
+        # token bucket allowing bursts of up to rate_limit calls
+        self._capacity = max(1.0, rate_limit or 1.0)
+        self._tokens = self._capacity
+        self._updated_at = time.monotonic()
+
+        self._loop: asyncio.AbstractEventLoop | None = None
+        self._semaphore: asyncio.Semaphore | None = None
+
+    def _get_semaphore(self) -> asyncio.Semaphore | None:
+        if self.max_concurrency is None:
+            return None
+
+        # asyncio primitives are bound to the loop they are first used in
+        loop = asyncio.get_running_loop()
+        if self._semaphore is None or self._loop is not loop:
+            self._loop = loop
+            self._semaphore = asyncio.Semaphore(self.max_concurrency)
+
+        return self._semaphore
+
+    async def _wait_for_token(self) -> None:
+        if not self.rate_limit:
+            return
+
+        while True:
+            now = time.monotonic()
+            self._tokens = min(self._capacity, self._tokens + (now - self._updated_at) * self.rate_limit)
+            self._updated_at = now
+            if self._tokens >= 1:
+                self._tokens -= 1
+                return
+
+            delay = (1 - self._tokens) / self.rate_limit
+            logger.debug(f"tool {self.name} is rate limited, waiting {delay:.2f}s")
+            await asyncio.sleep(delay)
+
+    @contextlib.asynccontextmanager
+    async def acquire(self) -> t.AsyncIterator[None]:
+        """Wait for a call to be allowed by the limits, for the duration of the context."""
+
+        # the semaphore acquired by this call is released, even if it's replaced in the meantime
+        semaphore = self._get_semaphore()
+        if semaphore is not None:
+            await semaphore.acquire()
+
+        try:
+            await self._wait_for_token()
+            yield
+        finally:
+            if semaphore is not None:
+                semaphore.release()
+
+
+# limiters shared by all the tools with the same module and name
+_limiters: dict[tuple[str | None, str], Limiter] = {}
+
+
+def limited(
+    max_concurrency: int | None = None, rate_limit: float | None = None
+) -> t.Callable[[t.Callable[..., t.Any]], t.Callable[..., t.Any]]:
+    """
+    Decorator to declare the maximum number of concurrent calls and/or the maximum number of calls per
+    second of a tool. Both default to unlimited.
+    """
+
+    def decorator(func: t.Callable[..., t.Any]) -> t.Callable[..., t.Any]:
+        func.__nerve_max_concurrency__ = max_concurrency  # type: ignore
+        func.__nerve_rate_limit__ = rate_limit  # type: ignore
+        return func
+
+    return decorator
+
+
+def get_limiter(func: t.Callable[..., t.Any]) -> Limiter | None:
+    """Get the shared limiter for a tool function, or None if the tool is unlimited."""
+
+    max_concurrency = getattr(func, "__nerve_max_concurrency__", None)
+    rate_limit = getattr(func, "__nerve_rate_limit__", None)
+    if max_concurrency is None and rate_limit is None:
+        return None
+
+    # different namespaces can have tools with the same name
+    key = (func.__module__, func.__name__)
+    if key not in _limiters:
+        logger.debug(f"tool {func.__name__} limited to max_concurrency={max_concurrency} rate_limit={rate_limit}")
+        _limiters[key] = Limiter(func.__name__, max_concurrency, rate_limit)
+
+    return _limiters[key]
@@ -0,0 +1,87 @@
+import asyncio
+import time
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools import limits
+from nerve.tools.compiler import wrap_tool_function
+
+
+class TestLimits(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        limits._limiters.clear()
+
+    def test_unlimited_by_default(self) -> None:
+        def tool() -> None:
+            pass
+
+        self.assertIsNone(limits.get_limiter(tool))
+
+    def test_limiter_is_shared_by_name(self) -> None:
+        @limits.limited(max_concurrency=1)
+        def tool() -> None:
+            pass
+
+        self.assertIs(limits.get_limiter(tool), limits.get_limiter(tool))
+
+    def test_same_named_tools_of_different_namespaces_are_not_shared(self) -> None:
+        @limits.limited(max_concurrency=1)
+        def tool() -> None:
+            pass
+
+        @limits.limited(max_concurrency=5)
+        def other() -> None:
+            pass
+
+        other.__name__ = "tool"
+        other.__module__ = "nerve.tools.namespaces.other"
+
+        self.assertEqual(limits.get_limiter(tool).max_concurrency, 1)  # type: ignore
+        self.assertEqual(limits.get_limiter(other).max_concurrency, 5)  # type: ignore
+
+    async def test_acquired_semaphore_is_released(self) -> None:
+        limiter = limits.Limiter("tool", max_concurrency=1)
+
+        async with limiter.acquire():
+            acquired = limiter._semaphore
+            # replaced while the call is in progress
+            limiter._semaphore = None
+
+        self.assertIsNotNone(acquired)
+        self.assertFalse(acquired.locked())  # type: ignore
+
+    @patch("nerve.tools.compiler.state")
+    async def test_max_concurrency_queues_calls(self, _: MagicMock) -> None:
+        running = 0
+        max_running = 0
+
+        @limits.limited(max_concurrency=2)
+        async def tool() -> str:
+            nonlocal running, max_running
+            running += 1
+            max_running = max(max_running, running)
+            await asyncio.sleep(0.05)
+            running -= 1
+            return "done"
+
+        wrapped = wrap_tool_function(tool)
+        results = await asyncio.gather(*[wrapped() for _ in range(6)])
+
+        self.assertEqual(results, ["done"] * 6)
+        self.assertEqual(max_running, 2)
+
+    @patch("nerve.tools.compiler.state")
+    async def test_rate_limit_queues_calls(self, _: MagicMock) -> None:
+        @limits.limited(rate_limit=20)
+        def tool() -> str:
+            return "done"
+
+        wrapped = wrap_tool_function(tool)
+
+        started_at = time.monotonic()
+        results = await asyncio.gather(*[wrapped() for _ in range(30)])
+        elapsed = time.monotonic() - started_at
+
+        # the first 20 calls are a burst, the other 10 are spaced by 50ms
+        self.assertEqual(results, ["done"] * 30)
+        self.assertGreaterEqual(elapsed, 0.45)
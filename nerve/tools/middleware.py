@@ -0,0 +1,24 @@
+import typing as t
+
+
+class ToolVetoed(Exception):
+    """Raised by a middleware to deny the execution of a tool, the message is reported to the model."""
+
+    pass
+
+
+class Middleware:
+    """
+    Runs cross-cutting logic around every tool call, like policy checks, logging or redaction, without
+    modifying the tools. Middlewares are registered on the agent and run in registration order.
+    """
+
+    def before_tool(self, name: str, args: dict[str, t.Any]) -> dict[str, t.Any]:
+        """Called before executing a tool, return the arguments to use or raise ToolVetoed to deny the execution."""
+
+        return args
+
+    def after_tool(self, name: str, args: dict[str, t.Any], result: t.Any, error: Exception | None) -> t.Any:
+        """Called after executing a tool, even if it failed, return the result to send to the model."""
+
+        return result
@@ -0,0 +1,107 @@
+import re
+import typing as t
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.generation import WindowStrategy
+from nerve.generation.litellm import LiteLLMEngine
+from nerve.tools.middleware import Middleware, ToolVetoed
+
+
+class RedactSecrets(Middleware):
+    """Example middleware removing secrets from the tool outputs."""
+
+    def after_tool(self, name: str, args: dict[str, t.Any], result: t.Any, error: Exception | None) -> t.Any:
+        if isinstance(result, str):
+            return re.sub(r"sk-[A-Za-z0-9]+", "[REDACTED]", result)
+        return result
+
+
+class Recorder(Middleware):
+    def __init__(self, label: str, calls: list[str]):
+        self.label = label
+        self.calls = calls
+
+    def before_tool(self, name: str, args: dict[str, t.Any]) -> dict[str, t.Any]:
+        self.calls.append(f"before:{self.label}")
+        return {**args, "text": args["text"] + self.label}
+
+    def after_tool(self, name: str, args: dict[str, t.Any], result: t.Any, error: Exception | None) -> t.Any:
+        self.calls.append(f"after:{self.label}")
+        return result
+
+
+class Veto(Middleware):
+    def before_tool(self, name: str, args: dict[str, t.Any]) -> dict[str, t.Any]:
+        raise ToolVetoed("not allowed by policy")
+
+
+class TestMiddleware(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        self.executed: list[str] = []
+
+        def echo(text: t.Annotated[str, "The text to echo"]) -> str:
+            """Echo the text."""
+            self.executed.append(text)
+            return text
+
+        def fail(text: t.Annotated[str, "The text"]) -> str:
+            """Always fail."""
+            raise RuntimeError("boom")
+
+        self.engine = LiteLLMEngine("openai/gpt-4o", MagicMock(spec=WindowStrategy), [echo, fail], None)
+
+    def _setup_state(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+
+    @patch("nerve.generation.state")
+    async def test_redaction(self, mock_state: MagicMock) -> None:
+        self._setup_state(mock_state)
+        self.engine.middlewares.append(RedactSecrets())
+
+        responses = await self.engine._process_tool_call("call_1", "echo", {"text": "key is sk-abc123"}, {})
+
+        self.assertEqual(responses[0]["content"], "key is [REDACTED]")
+
+    @patch("nerve.generation.state")
+    async def test_registration_order(self, mock_state: MagicMock) -> None:
+        self._setup_state(mock_state)
+        calls: list[str] = []
+        self.engine.middlewares.extend([Recorder("a", calls), Recorder("b", calls)])
+
+        responses = await self.engine._process_tool_call("call_1", "echo", {"text": "x"}, {})
+
+        self.assertEqual(calls, ["before:a", "before:b", "after:a", "after:b"])
+        self.assertEqual(self.executed, ["xab"])
+        self.assertEqual(responses[0]["content"], "xab")
+
+    @patch("nerve.generation.state")
+    async def test_veto(self, mock_state: MagicMock) -> None:
+        self._setup_state(mock_state)
+        self.engine.middlewares.append(Veto())
+
+        responses = await self.engine._process_tool_call("call_1", "echo", {"text": "x"}, {})
+
+        self.assertEqual(self.executed, [])
+        self.assertIn("denied: not allowed by policy", responses[0]["content"])
+        event, data = mock_state.on_event.call_args[0]
+        self.assertEqual(event, "invalid_tool_call")
+        self.assertEqual(data["error"], "vetoed: not allowed by policy")
+
+    @patch("nerve.generation.state")
+    async def test_after_tool_receives_error(self, mock_state: MagicMock) -> None:
+        self._setup_state(mock_state)
+        errors: list[Exception | None] = []
+
+        class Capture(Middleware):
+            def after_tool(self, name: str, args: dict[str, t.Any], result: t.Any, error: Exception | None) -> t.Any:
+                errors.append(error)
+                return "recovered"
+
+        self.engine.middlewares.append(Capture())
+
+        responses = await self.engine._process_tool_call("call_1", "fail", {"text": "x"}, {})
+
+        self.assertIsInstance(errors[0], RuntimeError)
+        self.assertEqual(responses[0]["content"], "recovered")
@@ -0,0 +1,316 @@
+"""
+Let the agent read and create calendar events, stored in a local ICS file.
+
+> [!TIP]
+> The calendar file defaults to `~/.nerve/calendar.ics`, set `NERVE_CALENDAR_FILE` to use a different one (for instance a file synchronized with your CalDAV client). Creating events always requires user confirmation.
+"""
+
+import json
+import pathlib
+import re
+import uuid
+from datetime import date, datetime, timedelta, timezone
+from typing import Annotated
+from zoneinfo import ZoneInfo, ZoneInfoNotFoundError
+
+from loguru import logger
+from pydantic import BaseModel, Field
+
+from nerve.defaults import DEFAULT_CALENDAR_PATH
+from nerve.tools.utils import requires_user_confirmation
+
+# for docs
+EMOJI = "📅"
+
+# the ICS file used to store the events
+calendar_path: pathlib.Path = DEFAULT_CALENDAR_PATH
+
+_DATE_HELP = "expected an ISO 8601 date or datetime like 2025-01-31 or 2025-01-31T14:30:00+01:00"
+# escape sequences of the ICS text values
+_ESCAPED = re.compile(r"\\([\\,;nN])")
+_UNESCAPED = {"\\": "\\", ",": ",", ";": ";", "n": "\n", "N": "\n"}
+
+
+class Event(BaseModel):
+    uid: str
+    title: str
+    start: datetime
+    end: datetime
+    location: str | None = None
+    description: str | None = None
+
+    def to_dict(self) -> dict[str, str]:
+        data = {
+            "uid": self.uid,
+            "title": self.title,
+            "start": _format(self.start),
+            "end": _format(self.end),
+        }
+        if self.location:
+            data["location"] = self.location
+        if self.description:
+            data["description"] = self.description
+        return data
+
+
+def _format(value: datetime) -> str:
+    # always use the local timezone with an explicit offset
+    return value.astimezone().isoformat(timespec="seconds")
+
+
+def _parse_datetime(value: str) -> datetime:
+    value = value.strip()
+    try:
+        # python < 3.11 does not support the Z suffix
+        parsed = datetime.fromisoformat(value[:-1] + "+00:00" if value.endswith("Z") else value)
+    except ValueError:
+        raise ValueError(f"invalid date '{value}', {_DATE_HELP}") from None
+
+    if parsed.tzinfo is None:
+        # naive datetimes are in local time
+        parsed = parsed.astimezone()
+
+    return parsed
+
+
+def _unescape(value: str) -> str:
+    # single pass, so that an escaped backslash followed by n is not a newline
+    return _ESCAPED.sub(lambda match: _UNESCAPED[match.group(1)], value)
+
+
+def _escape(value: str) -> str:
+    return value.replace("\\", "\\\\").replace(";", "\\;").replace(",", "\\,").replace("\n", "\\n")
+
+
+def _parse_ics_datetime(params: dict[str, str], value: str) -> datetime:
+    if params.get("VALUE") == "DATE" or len(value) == 8:
+        # all day events start at local midnight
+        day = date(int(value[0:4]), int(value[4:6]), int(value[6:8]))
+        return datetime.combine(day, datetime.min.time()).astimezone()
+
+    parsed = datetime.strptime(value.rstrip("Z"), "%Y%m%dT%H%M%S")
+    if value.endswith("Z"):
+        return parsed.replace(tzinfo=timezone.utc)
+
+    if "TZID" in params:
+        try:
+            return parsed.replace(tzinfo=ZoneInfo(params["TZID"]))
+        except (ZoneInfoNotFoundError, ValueError):
+            logger.warning(f"unknown timezone {params['TZID']}, using local time")
+
+    # floating time
+    return parsed.astimezone()
+
+
+def _parse_ics(data: str) -> list[Event]:
+    # unfold long lines
+    lines: list[str] = []
+    for line in data.splitlines():
+        if line[:1] in (" ", "\t") and lines:
+            lines[-1] += line[1:]
+        elif line:
+            lines.append(line)
+
+    events: list[Event] = []
+    current: dict[str, tuple[dict[str, str], str]] | None = None
+
+    for line in lines:
+        if line == "BEGIN:VEVENT":
+            current = {}
+        elif line == "END:VEVENT" and current is not None:
+            try:
+                start = _parse_ics_datetime(*current["DTSTART"])
+                if "DTEND" in current:
+                    end = _parse_ics_datetime(*current["DTEND"])
+                elif "VALUE" in current["DTSTART"][0]:
+                    end = start + timedelta(days=1)
+                else:
+                    end = start
+
+                events.append(
+                    Event(
+                        uid=current.get("UID", ({}, str(uuid.uuid4())))[1],
+                        title=_unescape(current.get("SUMMARY", ({}, ""))[1]),
+                        start=start,
+                        end=end,
+                        location=_unescape(current["LOCATION"][1]) if "LOCATION" in current else None,
+                        description=_unescape(current["DESCRIPTION"][1]) if "DESCRIPTION" in current else None,
+                    )
+                )
+            except (KeyError, ValueError) as e:
+                logger.warning(f"skipping invalid calendar event: {e}")
+
+            current = None
+        elif current is not None and ":" in line:
+            key, value = line.split(":", 1)
+            name, *raw_params = key.split(";")
+            params = dict(p.split("=", 1) for p in raw_params if "=" in p)
+            current[name.upper()] = (params, value)
+
+    return events
+
+
+def _load_events() -> list[Event]:
+    if not calendar_path.exists():
+        return []
+
+    return _parse_ics(calendar_path.read_text())
+
+
+def _to_ics(event: Event) -> str:
+    def utc(value: datetime) -> str:
+        return value.astimezone(timezone.utc).strftime("%Y%m%dT%H%M%SZ")
+
+    lines = [
+        "BEGIN:VEVENT",
+        f"UID:{event.uid}",
+        f"DTSTAMP:{utc(datetime.now(timezone.utc))}",
+        f"DTSTART:{utc(event.start)}",
+        f"DTEND:{utc(event.end)}",
+        f"SUMMARY:{_escape(event.title)}",
+    ]
+    if event.location:
+        lines.append(f"LOCATION:{_escape(event.location)}")
+    if event.description:
+        lines.append(f"DESCRIPTION:{_escape(event.description)}")
+    lines.append("END:VEVENT")
+
+    return "\r\n".join(lines) + "\r\n"
+
+
+def _save_event(event: Event) -> None:
+    if calendar_path.exists():
+        data = calendar_path.read_text()
+    else:
+        calendar_path.parent.mkdir(parents=True, exist_ok=True)
+        data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//nerve//calendar//EN\r\nEND:VCALENDAR\r\n"
+
+    idx = data.rfind("END:VCALENDAR")
+    if idx == -1:
+        raise ValueError(f"{calendar_path} is not a valid ICS file")
+
+    calendar_path.write_text(data[:idx] + _to_ics(event) + data[idx:])
+
+
+def _get_range(start: str, end: str) -> tuple[datetime, datetime]:
+    range_start = _parse_datetime(start) if start else datetime.now().astimezone()
+    range_end = _parse_datetime(end) if end else range_start + timedelta(days=7)
+    if range_end <= range_start:
+        raise ValueError(f"the end of the range ({_format(range_end)}) must be after the start ({_format(range_start)})")
+
+    return range_start, range_end
+
+
+def _events_in_range(range_start: datetime, range_end: datetime) -> list[Event]:
+    events = [e for e in _load_events() if e.start < range_end and e.end > range_start]
+    return sorted(events, key=lambda e: e.start)
+
+
+def list_events(
+    start: Annotated[
+        str,
+        Field(description="Start of the range as ISO 8601 date or datetime, defaults to now.", examples=["2025-01-31"]),
+    ] = "",
+    end: Annotated[
+        str,
+        Field(
+            description="End of the range as ISO 8601 date or datetime, defaults to 7 days after the start.",
+            examples=["2025-02-07T18:00:00+01:00"],
+        ),
+    ] = "",
+) -> str:
+    """List the calendar events in a given time range. All times are returned in ISO 8601 format with timezone offset."""
+
+    range_start, range_end = _get_range(start, end)
+    events = _events_in_range(range_start, range_end)
+    if not events:
+        return f"no events between {_format(range_start)} and {_format(range_end)}"
+
+    return json.dumps([e.to_dict() for e in events], indent=2)
+
+
+@requires_user_confirmation()
+def create_event(
+    event: Annotated[
+        str,
+        Field(
+            description="JSON object with the title, start and end of the event (ISO 8601 datetimes) "
+            "and optional location and description.",
+            examples=[
+                '{"title": "Meeting with Bob", "start": "2025-01-31T14:30:00+01:00", "end": "2025-01-31T15:30:00+01:00"}'
+            ],
+        ),
+    ],
+) -> str:
+    """Create a new calendar event."""
+
+    try:
+        payload = json.loads(event)
+    except json.JSONDecodeError as e:
+        raise ValueError(f"the event must be a valid JSON object: {e}") from None
+
+    if not isinstance(payload, dict):
+        raise ValueError("the event must be a JSON object")
+
+    for field in ("title", "start", "end"):
+        if not payload.get(field):
+            raise ValueError(f"the event is missing the required '{field}' field")
+
+    new_event = Event(
+        uid=f"{uuid.uuid4()}@nerve",
+        title=str(payload["title"]),
+        start=_parse_datetime(str(payload["start"])),
+        end=_parse_datetime(str(payload["end"])),
+        location=payload.get("location"),
+        description=payload.get("description"),
+    )
+    if new_event.end <= new_event.start:
+        raise ValueError("the end of the event must be after its start")
+
+    _save_event(new_event)
+
+    return f"event created: {json.dumps(new_event.to_dict())}"
+
+
+def free_busy(
+    start: Annotated[
+        str,
+        Field(description="Start of the range as ISO 8601 date or datetime, defaults to now.", examples=["2025-01-31"]),
+    ] = "",
+    end: Annotated[
+        str,
+        Field(
+            description="End of the range as ISO 8601 date or datetime, defaults to 7 days after the start.",
+            examples=["2025-02-07T18:00:00+01:00"],
+        ),
+    ] = "",
+) -> str:
+    """Get the busy and free time slots in a given time range. All times are returned in ISO 8601 format with timezone offset."""
+
+    range_start, range_end = _get_range(start, end)
+
+    # merge overlapping events
+    busy: list[list[datetime]] = []
+    for e in _events_in_range(range_start, range_end):
+        slot_start, slot_end = max(e.start, range_start), min(e.end, range_end)
+        if busy and slot_start <= busy[-1][1]:
+            busy[-1][1] = max(busy[-1][1], slot_end)
+        else:
+            busy.append([slot_start, slot_end])
+
+    free: list[list[datetime]] = []
+    cursor = range_start
+    for slot_start, slot_end in busy:
+        if slot_start > cursor:
+            free.append([cursor, slot_start])
+        cursor = slot_end
+    if cursor < range_end:
+        free.append([cursor, range_end])
+
+    return json.dumps(
+        {
+            "busy": [{"start": _format(s), "end": _format(e)} for s, e in busy],
+            "free": [{"start": _format(s), "end": _format(e)} for s, e in free],
+        },
+        indent=2,
+    )
@@ -0,0 +1,124 @@
+import json
+import pathlib
+import tempfile
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import calendar
+
+ICS = """BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:utc@test
+DTSTART:20250131T100000Z
+DTEND:20250131T110000Z
+SUMMARY:Standup\\, daily
+END:VEVENT
+BEGIN:VEVENT
+UID:tzid@test
+DTSTART;TZID=Europe/Rome:20250131T113000
+DTEND;TZID=Europe/Rome:20250131T133000
+SUMMARY:Lunch with a very long
+  title
+LOCATION:Rome
+END:VEVENT
+BEGIN:VEVENT
+UID:later@test
+DTSTART:20250210T100000Z
+DTEND:20250210T110000Z
+SUMMARY:Later
+END:VEVENT
+END:VCALENDAR
+"""
+
+
+class TestCalendar(unittest.TestCase):
+    def setUp(self) -> None:
+        self.temp_dir = tempfile.TemporaryDirectory()
+        self.calendar_path = calendar.calendar_path
+        calendar.calendar_path = pathlib.Path(self.temp_dir.name) / "calendar.ics"
+        calendar.calendar_path.write_text(ICS)
+
+    def tearDown(self) -> None:
+        calendar.calendar_path = self.calendar_path
+        self.temp_dir.cleanup()
+
+    def test_parse_ics(self) -> None:
+        events = {e.uid: e for e in calendar._parse_ics(ICS)}
+
+        self.assertEqual(events["utc@test"].title, "Standup, daily")
+        self.assertEqual(events["utc@test"].start, datetime(2025, 1, 31, 10, tzinfo=timezone.utc))
+        self.assertEqual(events["tzid@test"].title, "Lunch with a very long title")
+        self.assertEqual(events["tzid@test"].start, datetime(2025, 1, 31, 10, 30, tzinfo=timezone.utc))
+        self.assertEqual(events["tzid@test"].location, "Rome")
+
+    def test_unescape(self) -> None:
+        self.assertEqual(calendar._unescape("one\\ntwo\\Nthree"), "one\ntwo\nthree")
+        # an escaped backslash followed by n is not a newline
+        self.assertEqual(calendar._unescape("C:\\\\new"), "C:\\new")
+        for value in ("C:\\new", "a, b; c\nd"):
+            self.assertEqual(calendar._unescape(calendar._escape(value)), value)
+
+    def test_list_events_in_range(self) -> None:
+        events = json.loads(calendar.list_events("2025-01-31T00:00:00Z", "2025-02-01T00:00:00Z"))
+
+        self.assertEqual([e["uid"] for e in events], ["utc@test", "tzid@test"])
+        self.assertEqual(datetime.fromisoformat(events[0]["start"]), datetime(2025, 1, 31, 10, tzinfo=timezone.utc))
+
+    def test_list_events_empty_range(self) -> None:
+        self.assertIn("no events", calendar.list_events("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"))
+
+    def test_invalid_dates(self) -> None:
+        with self.assertRaisesRegex(ValueError, "invalid date 'tomorrow'"):
+            calendar.list_events("tomorrow")
+
+        with self.assertRaisesRegex(ValueError, "must be after"):
+            calendar.list_events("2025-02-01T00:00:00Z", "2025-01-01T00:00:00Z")
+
+    def test_free_busy(self) -> None:
+        result = json.loads(calendar.free_busy("2025-01-31T09:00:00Z", "2025-01-31T13:00:00Z"))
+
+        busy = [(datetime.fromisoformat(s["start"]), datetime.fromisoformat(s["end"])) for s in result["busy"]]
+        free = [(datetime.fromisoformat(s["start"]), datetime.fromisoformat(s["end"])) for s in result["free"]]
+
+        # overlapping events are merged
+        self.assertEqual(len(busy), 1)
+        self.assertEqual(busy[0][0], datetime(2025, 1, 31, 10, tzinfo=timezone.utc))
+        self.assertEqual(busy[0][1], datetime(2025, 1, 31, 12, 30, tzinfo=timezone.utc))
+        self.assertEqual(len(free), 2)
+        self.assertEqual(free[0][1], datetime(2025, 1, 31, 10, tzinfo=timezone.utc))
+        self.assertEqual(free[1][0], datetime(2025, 1, 31, 12, 30, tzinfo=timezone.utc))
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed")
+    def test_create_event(self, mock_confirm: MagicMock) -> None:
+        mock_confirm.return_value = True
+
+        result = calendar.create_event(
+            json.dumps({"title": "Review", "start": "2025-02-03T15:00:00+01:00", "end": "2025-02-03T16:00:00+01:00"})
+        )
+        self.assertIn("event created", result)
+
+        events = json.loads(calendar.list_events("2025-02-03T00:00:00Z", "2025-02-04T00:00:00Z"))
+        self.assertEqual(len(events), 1)
+        self.assertEqual(events[0]["title"], "Review")
+        self.assertEqual(datetime.fromisoformat(events[0]["start"]), datetime(2025, 2, 3, 14, tzinfo=timezone.utc))
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed")
+    def test_create_event_denied(self, mock_confirm: MagicMock) -> None:
+        mock_confirm.return_value = False
+
+        result = calendar.create_event(json.dumps({"title": "Nope", "start": "2025-02-03", "end": "2025-02-04"}))
+
+        self.assertIn("did not allow", result)
+        self.assertIn("no events", calendar.list_events("2025-02-03T00:00:00Z", "2025-02-04T00:00:00Z"))
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed")
+    def test_create_event_invalid_payload(self, mock_confirm: MagicMock) -> None:
+        mock_confirm.return_value = True
+
+        with self.assertRaisesRegex(ValueError, "missing the required 'end'"):
+            calendar.create_event(json.dumps({"title": "Review", "start": "2025-02-03T15:00:00"}))
+
+        with self.assertRaisesRegex(ValueError, "valid JSON"):
+            calendar.create_event("not json")
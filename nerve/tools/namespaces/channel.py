@@ -0,0 +1,74 @@
+"""
+Let agents running in the same process, like the actors of a workflow, exchange messages with each other.
+"""
+
+import threading
+import time
+from typing import Annotated
+
+from pydantic import BaseModel
+
+import nerve.runtime.state as state
+
+# for docs
+EMOJI = "📨"
+
+
+class Message(BaseModel):
+    sender: str
+    timestamp: float
+    content: str
+
+
+class Broker:
+    """
+    Queues the messages of each recipient until it receives them.
+    """
+
+    def __init__(self) -> None:
+        self._lock = threading.Lock()
+        self._queues: dict[str, list[Message]] = {}
+
+    def send(self, recipient: str, message: Message) -> None:
+        with self._lock:
+            self._queues.setdefault(recipient, []).append(message)
+
+    def receive(self, recipient: str) -> list[Message]:
+        with self._lock:
+            return self._queues.pop(recipient, [])
+
+
+# shared by every agent in this process
+broker: Broker = Broker()
+
+
+def _get_agent_name() -> str:
+    actor = state.get_current_actor()
+    runtime = getattr(actor, "runtime", None)
+    return getattr(runtime, "name", None) or "unknown"
+
+
+def send_message(
+    to: Annotated[str, "The name of the agent to send the message to"],
+    message: Annotated[str, "The message to send"],
+) -> str:
+    """Send a message to another agent, it will be delivered the next time the agent receives its messages."""
+
+    sender = _get_agent_name()
+    broker.send(to, Message(sender=sender, timestamp=time.time(), content=message))
+    state.on_event("message_sent", {"from": sender, "to": to, "message": message})
+
+    return f"message sent to {to}"
+
+
+def receive_messages() -> str:
+    """Receive the messages sent to you by other agents since the last time you checked."""
+
+    messages = broker.receive(_get_agent_name())
+    if not messages:
+        return "no new messages"
+
+    return "\n\n".join(
+        f"from {m.sender} at {time.strftime('%Y-%m-%d %H:%M:%S', time.localtime(m.timestamp))}:\n{m.content}"
+        for m in messages
+    )
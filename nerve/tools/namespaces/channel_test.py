@@ -0,0 +1,41 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import channel
+
+
+def _actor(name: str) -> MagicMock:
+    actor = MagicMock()
+    actor.runtime.name = name
+    return actor
+
+
+@patch("nerve.tools.namespaces.channel.state")
+class TestChannel(unittest.TestCase):
+    def setUp(self) -> None:
+        channel.broker = channel.Broker()
+
+    def test_messages_are_delivered_to_the_recipient(self, mock_state: MagicMock) -> None:
+        mock_state.get_current_actor.return_value = _actor("scanner")
+        channel.send_message("reporter", "port 22 is open")
+        channel.send_message("reporter", "port 80 is open")
+        channel.send_message("exploiter", "try ssh")
+
+        mock_state.on_event.assert_any_call(
+            "message_sent", {"from": "scanner", "to": "reporter", "message": "port 22 is open"}
+        )
+
+        mock_state.get_current_actor.return_value = _actor("reporter")
+        received = channel.receive_messages()
+
+        self.assertIn("from scanner at ", received)
+        self.assertLess(received.index("port 22 is open"), received.index("port 80 is open"))
+        self.assertNotIn("try ssh", received)
+
+        # delivered only once
+        self.assertEqual(channel.receive_messages(), "no new messages")
+
+    def test_no_messages(self, mock_state: MagicMock) -> None:
+        mock_state.get_current_actor.return_value = _actor("reporter")
+
+        self.assertEqual(channel.receive_messages(), "no new messages")
@@ -0,0 +1,257 @@
+"""
+Let the agent execute Python, JavaScript and Bash code snippets in a sandboxed, resource limited environment.
+
+> [!TIP]
+> Code runs in a local resource limited subprocess by default, set `NERVE_CODE_SANDBOX=docker` to run it inside a Docker container instead. The subprocess sandbox disables the network with `unshare`, code without network access is refused where it's not available.
+"""
+
+import os
+import shutil
+import signal
+import subprocess
+import tempfile
+import typing as t
+import uuid
+from abc import ABC, abstractmethod
+from typing import Annotated
+
+from loguru import logger
+from pydantic import BaseModel
+
+from nerve.defaults import DEFAULT_CODE_MAX_MEMORY, DEFAULT_CODE_SANDBOX, DEFAULT_CODE_TIMEOUT
+
+# for docs
+EMOJI = "🧪"
+
+
+class Language(BaseModel):
+    # source file extension
+    extension: str
+    # interpreter command line used by the local sandbox
+    interpreter: list[str]
+    # docker image used by the docker sandbox
+    image: str
+
+
+LANGUAGES: dict[str, Language] = {
+    "python": Language(extension=".py", interpreter=["python3", "-I"], image="python:3-alpine"),
+    "javascript": Language(extension=".js", interpreter=["node"], image="node:alpine"),
+    "bash": Language(extension=".sh", interpreter=["bash"], image="bash:latest"),
+}
+
+
+class Result(BaseModel):
+    exit_code: int | None
+    stdout: str
+    stderr: str
+    timed_out: bool = False
+
+    def __str__(self) -> str:
+        if self.timed_out:
+            out = "EXIT CODE: killed (timeout)"
+        else:
+            out = f"EXIT CODE: {self.exit_code}"
+
+        if self.stdout:
+            out += f"\n\nSTDOUT:\n{self.stdout}"
+        if self.stderr:
+            out += f"\n\nSTDERR:\n{self.stderr}"
+
+        return out
+
+
+class Sandbox(ABC):
+    """
+    A sandbox is responsible for running a source file of a given language with resource limits.
+    """
+
+    def __init__(self, timeout: int = DEFAULT_CODE_TIMEOUT, max_memory: int = DEFAULT_CODE_MAX_MEMORY) -> None:
+        # timeout in seconds
+        self.timeout = timeout
+        # max memory in megabytes
+        self.max_memory = max_memory
+
+    @abstractmethod
+    def run(self, language: Language, source_path: str, allow_network: bool) -> Result:
+        pass
+
+    def _communicate(self, command: list[str], on_timeout: t.Callable[[subprocess.Popen[bytes]], None]) -> Result:
+        logger.debug(f"sandbox command: {command}")
+
+        process = subprocess.Popen(
+            command,
+            stdin=subprocess.DEVNULL,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.PIPE,
+            # create a new process group so that we can kill the whole tree on timeout
+            start_new_session=True,
+            preexec_fn=self._preexec_fn(),
+        )
+        try:
+            stdout, stderr = process.communicate(timeout=self.timeout)
+            timed_out = False
+        except subprocess.TimeoutExpired:
+            on_timeout(process)
+            stdout, stderr = process.communicate()
+            timed_out = True
+
+        return Result(
+            exit_code=process.returncode,
+            stdout=stdout.decode("utf-8", errors="replace").strip(),
+            stderr=stderr.decode("utf-8", errors="replace").strip(),
+            timed_out=timed_out,
+        )
+
+    def _preexec_fn(self) -> t.Callable[[], None] | None:
+        return None
+
+
+def _kill_process_group(process: subprocess.Popen[bytes]) -> None:
+    try:
+        os.killpg(process.pid, signal.SIGKILL)
+    except ProcessLookupError:
+        pass
+
+
+class SubprocessSandbox(Sandbox):
+    """
+    Runs the code as a local subprocess with cpu, memory and file size limits. On Linux the
+    network is disabled by running the process in a new network namespace, if unshare or the
+    unprivileged user namespaces are not available the code is not executed.
+    """
+
+    def __init__(self, timeout: int = DEFAULT_CODE_TIMEOUT, max_memory: int = DEFAULT_CODE_MAX_MEMORY) -> None:
+        super().__init__(timeout, max_memory)
+        # checked once, the first time that the network must be disabled
+        self._isolation_error: str | None = None
+        self._isolation_checked = False
+
+    def _check_network_isolation(self) -> str | None:
+        if self._isolation_checked:
+            return self._isolation_error
+
+        self._isolation_checked = True
+        if shutil.which("unshare") is None:
+            self._isolation_error = "the unshare command is not installed"
+        else:
+            try:
+                probe = subprocess.run(["unshare", "--map-root-user", "--net", "true"], capture_output=True, timeout=10)
+                if probe.returncode != 0:
+                    # usually because unprivileged user namespaces are disabled
+                    error = probe.stderr.decode("utf-8", errors="replace").strip()
+                    self._isolation_error = f"unshare failed: {error or f'exit code {probe.returncode}'}"
+            except (OSError, subprocess.TimeoutExpired) as e:
+                self._isolation_error = f"unshare failed: {e}"
+
+        if self._isolation_error:
+            logger.warning(f"network isolation is not available with the subprocess sandbox: {self._isolation_error}")
+
+        return self._isolation_error
+
+    def _preexec_fn(self) -> t.Callable[[], None] | None:
+        try:
+            import resource
+        except ImportError:
+            logger.warning("resource limits are not supported on this platform")
+            return None
+
+        max_memory = self.max_memory * 1024 * 1024
+        max_cpu = self.timeout + 1
+
+        def set_limits() -> None:
+            resource.setrlimit(resource.RLIMIT_CPU, (max_cpu, max_cpu))
+            resource.setrlimit(resource.RLIMIT_DATA, (max_memory, max_memory))
+            # 64MB max file size
+            resource.setrlimit(resource.RLIMIT_FSIZE, (64 * 1024 * 1024, 64 * 1024 * 1024))
+
+        return set_limits
+
+    def run(self, language: Language, source_path: str, allow_network: bool) -> Result:
+        command = [*language.interpreter, source_path]
+
+        if not allow_network:
+            # never run the code with network access if it was not allowed
+            error = self._check_network_isolation()
+            if error:
+                raise ValueError(
+                    f"network isolation is not available with the subprocess sandbox ({error}), "
+                    "set NERVE_CODE_SANDBOX=docker or allow_network if the code can access the network"
+                )
+            command = ["unshare", "--map-root-user", "--net", *command]
+
+        return self._communicate(command, _kill_process_group)
+
+
+class DockerSandbox(Sandbox):
+    """
+    Runs the code inside a disposable Docker container with cpu, memory and network limits.
+    """
+
+    def run(self, language: Language, source_path: str, allow_network: bool) -> Result:
+        container_name = f"nerve-code-{uuid.uuid4().hex[:12]}"
+        source_dir = os.path.dirname(source_path)
+        source_name = os.path.basename(source_path)
+        command = [
+            "docker",
+            "run",
+            "--rm",
+            "--name",
+            container_name,
+            "--network",
+            "bridge" if allow_network else "none",
+            "--memory",
+            f"{self.max_memory}m",
+            "--cpus",
+            "1",
+            "--pids-limit",
+            "64",
+            "-v",
+            f"{source_dir}:/sandbox:ro",
+            "-w",
+            "/sandbox",
+            language.image,
+            language.interpreter[0],
+            f"/sandbox/{source_name}",
+        ]
+
+        def on_timeout(process: subprocess.Popen[bytes]) -> None:
+            # killing the docker client does not stop the container
+            subprocess.run(["docker", "kill", container_name], capture_output=True)
+            _kill_process_group(process)
+
+        return self._communicate(command, on_timeout)
+
+
+def _sandbox_from_name(name: str) -> Sandbox:
+    if name == "subprocess":
+        return SubprocessSandbox()
+    elif name == "docker":
+        return DockerSandbox()
+    else:
+        raise ValueError(f"unknown code sandbox: {name}")
+
+
+# the sandbox backend used to execute the code
+sandbox: Sandbox = _sandbox_from_name(DEFAULT_CODE_SANDBOX)
+
+
+def execute_code(
+    language: Annotated[str, "The programming language of the code, one of: python, javascript, bash"],
+    code: Annotated[str, "The source code to execute"],
+    allow_network: Annotated[bool, "Set to true only if the code needs network access"] = False,
+) -> str:
+    """Execute a code snippet in a sandboxed environment with no network access by default and return its exit code, standard output and standard error."""
+
+    lang = LANGUAGES.get(language.strip().lower())
+    if lang is None:
+        raise ValueError(f"unsupported language '{language}', supported languages are: {', '.join(LANGUAGES)}")
+
+    with tempfile.TemporaryDirectory(prefix="nerve-code-") as temp_dir:
+        # make the folder readable from containers running as a different user
+        os.chmod(temp_dir, 0o755)
+
+        source_path = os.path.join(temp_dir, f"main{lang.extension}")
+        with open(source_path, "w") as f:
+            f.write(code)
+
+        return str(sandbox.run(lang, source_path, allow_network))
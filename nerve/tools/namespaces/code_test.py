@@ -0,0 +1,76 @@
+import subprocess
+import unittest
+from unittest.mock import patch
+
+from nerve.tools.namespaces import code
+
+
+class TestCode(unittest.TestCase):
+    def setUp(self) -> None:
+        self.sandbox = code.sandbox
+        code.sandbox = code.SubprocessSandbox(timeout=5)
+
+    def tearDown(self) -> None:
+        code.sandbox = self.sandbox
+
+    def test_execute_python(self) -> None:
+        result = code.execute_code("python", "print(40 + 2)", allow_network=True)
+        self.assertIn("EXIT CODE: 0", result)
+        self.assertIn("STDOUT:\n42", result)
+
+    def test_execute_bash_with_exit_code_and_stderr(self) -> None:
+        result = code.execute_code("bash", "echo 'something went wrong' >&2; exit 3", allow_network=True)
+        self.assertIn("EXIT CODE: 3", result)
+        self.assertIn("STDERR:\nsomething went wrong", result)
+        self.assertNotIn("STDOUT:", result)
+
+    def test_language_is_case_insensitive(self) -> None:
+        result = code.execute_code(" Python ", "print('ok')", allow_network=True)
+        self.assertIn("STDOUT:\nok", result)
+
+    def test_unsupported_language(self) -> None:
+        with self.assertRaises(ValueError):
+            code.execute_code("cobol", "DISPLAY 'HELLO'.")
+
+    def test_timeout_kills_process(self) -> None:
+        code.sandbox = code.SubprocessSandbox(timeout=1)
+
+        result = code.execute_code(
+            "python", "import time\nprint('started', flush=True)\ntime.sleep(30)", allow_network=True
+        )
+        self.assertIn("EXIT CODE: killed (timeout)", result)
+        self.assertIn("started", result)
+
+    def test_network_is_isolated(self) -> None:
+        error = code.sandbox._check_network_isolation()  # type: ignore
+        if error:
+            self.skipTest(f"network isolation is not available: {error}")
+
+        result = code.execute_code("python", "import socket\nprint(socket.if_nameindex())")
+        self.assertIn("STDOUT:\n[(1, 'lo')]", result)
+
+    def test_missing_network_isolation_is_an_error(self) -> None:
+        with patch("nerve.tools.namespaces.code.shutil.which", return_value=None):
+            with self.assertRaisesRegex(ValueError, "network isolation is not available.*not installed"):
+                code.execute_code("python", "print('ok')")
+
+            # the code can still run if it is allowed to access the network
+            self.assertIn("STDOUT:\nok", code.execute_code("python", "print('ok')", allow_network=True))
+
+    def test_disabled_user_namespaces_are_an_error(self) -> None:
+        error = b"unshare: write failed /proc/self/uid_map: Operation not permitted"
+        probe = subprocess.CompletedProcess([], 1, b"", error)
+        with (
+            patch("nerve.tools.namespaces.code.shutil.which", return_value="/usr/bin/unshare"),
+            patch("nerve.tools.namespaces.code.subprocess.run", return_value=probe) as mock_run,
+        ):
+            for _ in range(2):
+                with self.assertRaisesRegex(ValueError, "Operation not permitted"):
+                    code.execute_code("python", "print('ok')")
+
+        # checked only once
+        mock_run.assert_called_once()
+
+    def test_unknown_sandbox(self) -> None:
+        with self.assertRaises(ValueError):
+            code._sandbox_from_name("vm")
@@ -0,0 +1,174 @@
+"""
+Let the agent manage Docker containers through the Docker Engine API.
+
+> [!TIP]
+> The API is reached via `DOCKER_HOST` (`unix:///var/run/docker.sock` by default, `tcp://host:port` is supported too). Executing commands in a container and restarting it always require user confirmation.
+"""
+
+import http.client
+import json
+import os
+import socket
+import time
+import urllib.parse
+from typing import Annotated
+
+from nerve.tools.utils import requires_user_confirmation
+
+# for docs
+EMOJI = "🐳"
+
+# address of the Docker Engine API
+docker_host: str = os.getenv("DOCKER_HOST", "unix:///var/run/docker.sock")
+# maximum number of characters returned by logs and commands, the oldest output is cut
+max_output_length: int = 16_000
+# timeout in seconds of the API requests
+timeout: float = 30.0
+
+
+class _UnixHTTPConnection(http.client.HTTPConnection):
+    def __init__(self, path: str, timeout: float):
+        super().__init__("localhost", timeout=timeout)
+        self.socket_path = path
+
+    def connect(self) -> None:
+        self.sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self.sock.settimeout(self.timeout)
+        self.sock.connect(self.socket_path)
+
+
+def _connect() -> http.client.HTTPConnection:
+    if docker_host.startswith("unix://"):
+        return _UnixHTTPConnection(docker_host[len("unix://") :], timeout)
+
+    parsed = urllib.parse.urlparse(docker_host.replace("tcp://", "http://", 1))
+    return http.client.HTTPConnection(parsed.hostname or "localhost", parsed.port or 2375, timeout=timeout)
+
+
+def _read(connection: http.client.HTTPConnection, response: http.client.HTTPResponse, deadline: float) -> bytes:
+    # read a streamed response until it ends or the deadline expires
+    data = b""
+    while (remaining := deadline - time.monotonic()) > 0:
+        if connection.sock is not None:
+            connection.sock.settimeout(remaining)
+        try:
+            chunk = response.read1(65536)
+        except (TimeoutError, socket.timeout):
+            break
+        if not chunk:
+            break
+        data += chunk
+
+    return data
+
+
+def _request(method: str, path: str, body: dict[str, object] | None = None, deadline: float | None = None) -> bytes:
+    connection = _connect()
+    try:
+        connection.request(
+            method,
+            path,
+            body=json.dumps(body) if body is not None else None,
+            headers={"Content-Type": "application/json"},
+        )
+        response = connection.getresponse()
+        data = _read(connection, response, deadline) if deadline is not None else response.read()
+    finally:
+        connection.close()
+
+    if response.status >= 400:
+        try:
+            message = json.loads(data)["message"]
+        except Exception:
+            message = data.decode("utf-8", errors="replace")
+        raise RuntimeError(f"docker API error {response.status}: {message}")
+
+    return data
+
+
+def _demux(data: bytes) -> str:
+    # without a TTY stdout and stderr are multiplexed in frames with an 8 bytes header
+    if len(data) < 8 or data[0] not in (0, 1, 2) or data[1:4] != b"\x00\x00\x00":
+        return data.decode("utf-8", errors="replace")
+
+    out = b""
+    while len(data) >= 8:
+        size = int.from_bytes(data[4:8], "big")
+        out += data[8 : 8 + size]
+        data = data[8 + size :]
+
+    return out.decode("utf-8", errors="replace")
+
+
+def _truncate(output: str) -> str:
+    if len(output) <= max_output_length:
+        return output
+
+    return f"[output truncated to the last {max_output_length} characters]\n" + output[-max_output_length:]
+
+
+def _quote(container: str) -> str:
+    return urllib.parse.quote(container, safe="")
+
+
+def list_containers(
+    include_stopped: Annotated[bool, "Set to true to include the stopped containers"] = False,
+) -> str:
+    """List the Docker containers with their id, image, status and names."""
+
+    containers = json.loads(_request("GET", f"/containers/json?all={int(include_stopped)}"))
+    if not containers:
+        return "no containers found"
+
+    return "\n".join(
+        f"{c['Id'][:12]}  {c['Image']}  {c['Status']}  {', '.join(name.lstrip('/') for name in c.get('Names', []))}"
+        for c in containers
+    )
+
+
+def get_container_logs(
+    container: Annotated[str, "The id or name of the container"],
+    tail: Annotated[int, "How many of the most recent lines to return"] = 100,
+    follow_seconds: Annotated[int, "Optional number of seconds to keep collecting new lines for"] = 0,
+) -> str:
+    """Get the most recent logs of a Docker container, optionally waiting for new lines for some seconds."""
+
+    path = f"/containers/{_quote(container)}/logs?stdout=1&stderr=1&tail={tail}"
+    if follow_seconds > 0:
+        # the stream never ends by itself, it is cut when the window expires
+        data = _request("GET", f"{path}&follow=1", deadline=time.monotonic() + follow_seconds)
+    else:
+        data = _request("GET", path)
+
+    return _truncate(_demux(data)) or "no logs"
+
+
+@requires_user_confirmation()
+def exec_in_container(
+    container: Annotated[str, "The id or name of the container"],
+    command: Annotated[str, "The shell command to execute"],
+) -> str:
+    """Execute a shell command in a running Docker container and return its output and exit code."""
+
+    created = json.loads(
+        _request(
+            "POST",
+            f"/containers/{_quote(container)}/exec",
+            {"AttachStdout": True, "AttachStderr": True, "Cmd": ["sh", "-c", command]},
+        )
+    )
+    output = _demux(_request("POST", f"/exec/{created['Id']}/start", {"Detach": False, "Tty": False}))
+    exit_code = json.loads(_request("GET", f"/exec/{created['Id']}/json")).get("ExitCode")
+
+    return f"exit code: {exit_code}\n\n{_truncate(output)}"
+
+
+@requires_user_confirmation()
+def restart_container(
+    container: Annotated[str, "The id or name of the container"],
+) -> str:
+    """Restart a Docker container."""
+
+    _request("POST", f"/containers/{_quote(container)}/restart?t=10")
+
+    return f"container {container} restarted"
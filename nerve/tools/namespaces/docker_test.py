@@ -0,0 +1,112 @@
+import http.server
+import json
+import threading
+import time
+import typing as t
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import docker
+
+
+def _frame(stream: int, data: bytes) -> bytes:
+    return bytes([stream, 0, 0, 0]) + len(data).to_bytes(4, "big") + data
+
+
+class _Handler(http.server.BaseHTTPRequestHandler):
+    protocol_version = "HTTP/1.1"
+
+    def _send(self, status: int, body: bytes) -> None:
+        self.send_response(status)
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def do_GET(self) -> None:
+        if self.path.startswith("/containers/json"):
+            containers = [{"Id": "a" * 64, "Image": "nginx", "Status": "Up 2 hours", "Names": ["/web"]}]
+            self._send(200, json.dumps(containers if "all=1" in self.path else []).encode())
+        elif self.path.startswith("/containers/missing/"):
+            self._send(404, b'{"message": "No such container: missing"}')
+        elif "follow=1" in self.path:
+            # a followed stream never ends
+            self.send_response(200)
+            self.send_header("Transfer-Encoding", "chunked")
+            self.end_headers()
+            chunk = _frame(1, b"tick\n")
+            try:
+                for _ in range(20):
+                    self.wfile.write(f"{len(chunk):x}\r\n".encode() + chunk + b"\r\n")
+                    self.wfile.flush()
+                    time.sleep(0.1)
+            except (BrokenPipeError, ConnectionResetError):
+                # the client stopped following
+                pass
+        elif self.path.startswith("/containers/web/logs"):
+            self._send(200, _frame(1, b"started\n") + _frame(2, b"warning\n"))
+        elif self.path == "/exec/exec1/json":
+            self._send(200, b'{"ExitCode": 0}')
+
+    def do_POST(self) -> None:
+        self.rfile.read(int(self.headers.get("Content-Length", 0)))
+        if self.path == "/containers/web/exec":
+            self._send(201, b'{"Id": "exec1"}')
+        elif self.path == "/exec/exec1/start":
+            self._send(200, _frame(1, b"uid=0(root)\n"))
+        elif self.path.startswith("/containers/web/restart"):
+            self._send(204, b"")
+
+    def log_message(self, format: str, *args: t.Any) -> None:
+        pass
+
+
+class TestDocker(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), _Handler)
+        cls.server.daemon_threads = True
+        threading.Thread(target=cls.server.serve_forever, daemon=True).start()
+
+    @classmethod
+    def tearDownClass(cls) -> None:
+        cls.server.shutdown()
+        cls.server.server_close()
+
+    def setUp(self) -> None:
+        docker.docker_host = f"tcp://127.0.0.1:{self.server.server_address[1]}"
+        docker.max_output_length = 16_000
+
+    def test_list_containers(self) -> None:
+        self.assertEqual(docker.list_containers(), "no containers found")
+        self.assertEqual(docker.list_containers(include_stopped=True), "aaaaaaaaaaaa  nginx  Up 2 hours  web")
+
+    def test_logs_are_demultiplexed(self) -> None:
+        self.assertEqual(docker.get_container_logs("web"), "started\nwarning\n")
+
+    def test_logs_are_truncated(self) -> None:
+        docker.max_output_length = 8
+
+        self.assertEqual(docker.get_container_logs("web"), "[output truncated to the last 8 characters]\nwarning\n")
+
+    def test_follow_stops_after_the_window(self) -> None:
+        started_at = time.monotonic()
+
+        logs = docker.get_container_logs("web", follow_seconds=1)
+
+        self.assertLess(time.monotonic() - started_at, 1.5)
+        self.assertTrue(logs.startswith("tick\n"))
+
+    def test_api_errors(self) -> None:
+        with self.assertRaisesRegex(RuntimeError, "docker API error 404: No such container: missing"):
+            docker.get_container_logs("missing")
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed", return_value=True)
+    def test_exec_and_restart(self, _: MagicMock) -> None:
+        self.assertEqual(docker.exec_in_container("web", "id"), "exit code: 0\n\nuid=0(root)\n")
+        self.assertEqual(docker.restart_container("web"), "container web restarted")
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed", return_value=False)
+    def test_exec_requires_confirmation(self, _: MagicMock) -> None:
+        self.assertEqual(
+            docker.exec_in_container("web", "rm -rf /"), "the user did not allow the execution of exec_in_container"
+        )
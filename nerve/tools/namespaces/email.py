@@ -0,0 +1,204 @@
+"""
+Let the agent read emails over IMAP and send emails over SMTP.
+
+> [!IMPORTANT]
+> This namespace is not configured by default, set the `NERVE_SMTP_HOST`, `NERVE_SMTP_USER`, `NERVE_SMTP_PASSWORD`, `NERVE_IMAP_HOST`, `NERVE_IMAP_USER` and `NERVE_IMAP_PASSWORD` environment variables to enable it. Sending emails always requires user confirmation.
+"""
+
+import base64
+import email
+import email.policy
+import imaplib
+import os
+import smtplib
+import typing as t
+from email.message import EmailMessage
+from typing import Annotated
+
+from pydantic import Field
+
+from nerve.tools.utils import html_to_text, requires_user_confirmation
+
+# for docs
+EMOJI = "📧"
+
+# maximum number of characters of a message body returned to the model
+max_body_length: int = 10_000
+
+
+def _get_env(name: str, fallback: str | None = None, default: str | None = None) -> str:
+    value = os.getenv(name) or (os.getenv(fallback) if fallback else None) or default
+    if not value:
+        raise ValueError(f"the {name} environment variable is required to use the email namespace")
+
+    return value
+
+
+def _imap() -> imaplib.IMAP4:
+    host = _get_env("NERVE_IMAP_HOST")
+    port = int(_get_env("NERVE_IMAP_PORT", default="993"))
+    client = imaplib.IMAP4_SSL(host, port) if port == 993 else imaplib.IMAP4(host, port)
+    if port != 993:
+        client.starttls()
+
+    client.login(
+        _get_env("NERVE_IMAP_USER", fallback="NERVE_SMTP_USER"),
+        _get_env("NERVE_IMAP_PASSWORD", fallback="NERVE_SMTP_PASSWORD"),
+    )
+    return client
+
+
+def _fetch_message(uid: str, folder: str) -> EmailMessage:
+    client = _imap()
+    try:
+        client.select(folder, readonly=True)
+        status, data = client.uid("fetch", uid, "(BODY.PEEK[])")
+        if status != "OK" or not data or data[0] is None or not isinstance(data[0], tuple):
+            raise ValueError(f"message {uid} not found in {folder}")
+
+        return t.cast(EmailMessage, email.message_from_bytes(data[0][1], policy=email.policy.default))
+    finally:
+        client.logout()
+
+
+def _get_body(message: EmailMessage) -> str:
+    part = message.get_body(preferencelist=("plain", "html"))
+    if part is None:
+        return ""
+
+    content = str(part.get_content())
+    if part.get_content_type() == "text/html":
+        content = html_to_text(content)
+
+    content = content.strip()
+    if len(content) > max_body_length:
+        content = content[:max_body_length] + f"\n\n[... truncated, {len(content) - max_body_length} more characters]"
+
+    return content
+
+
+def _get_attachments(message: EmailMessage) -> list[EmailMessage]:
+    return [part for part in message.iter_attachments() if part.get_filename()]  # type: ignore
+
+
+def list_inbox(
+    limit: Annotated[int, "Maximum number of messages to return, most recent first"] = 10,
+    unread_only: Annotated[bool, "Only return unread messages"] = False,
+    folder: Annotated[str, "The mailbox folder to list"] = "INBOX",
+) -> str:
+    """List the most recent messages in the mailbox with their unique id, sender, date and subject."""
+
+    client = _imap()
+    try:
+        client.select(folder, readonly=True)
+        status, data = client.uid("search", None, "UNSEEN" if unread_only else "ALL")  # type: ignore
+        if status != "OK":
+            raise ValueError(f"could not search folder {folder}")
+
+        uids = data[0].split()[-limit:][::-1] if data and data[0] else []
+        if not uids:
+            return f"no messages in {folder}"
+
+        out = []
+        for uid in uids:
+            status, data = client.uid("fetch", uid, "(BODY.PEEK[HEADER.FIELDS (FROM SUBJECT DATE)])")
+            if status != "OK" or not data or not isinstance(data[0], tuple):
+                continue
+
+            headers = email.message_from_bytes(data[0][1], policy=email.policy.default)
+            out.append(
+                f"[{uid.decode()}] {headers.get('Date', '')} | from: {headers.get('From', '')} | "
+                f"subject: {headers.get('Subject', '')}"
+            )
+
+        return "\n".join(out)
+    finally:
+        client.logout()
+
+
+def read_message(
+    uid: Annotated[str, "The unique id of the message, as returned by list_inbox"],
+    folder: Annotated[str, "The mailbox folder containing the message"] = "INBOX",
+) -> str:
+    """Read a message, returning its headers, its text content and the list of its attachments."""
+
+    message = _fetch_message(uid, folder)
+
+    out = ""
+    for header in ("From", "To", "Cc", "Date", "Subject"):
+        if message.get(header):
+            out += f"{header}: {message[header]}\n"
+
+    out += f"\n{_get_body(message)}\n"
+
+    attachments = _get_attachments(message)
+    if attachments:
+        out += "\nATTACHMENTS:\n"
+        for part in attachments:
+            payload = part.get_payload(decode=True) or b""
+            out += f"  - {part.get_filename()} ({part.get_content_type()}, {len(payload)} bytes)\n"  # type: ignore
+
+    return out.strip()
+
+
+def get_attachment(
+    uid: Annotated[str, "The unique id of the message, as returned by list_inbox"],
+    filename: Annotated[str, "The name of the attachment, as returned by read_message"],
+    folder: Annotated[str, "The mailbox folder containing the message"] = "INBOX",
+) -> str | dict[str, t.Any]:
+    """Get the contents of a message attachment. Images are returned as images, text files as text."""
+
+    message = _fetch_message(uid, folder)
+    for part in _get_attachments(message):
+        if part.get_filename() != filename:
+            continue
+
+        mime = part.get_content_type()
+        payload: bytes = part.get_payload(decode=True) or b""  # type: ignore
+        if mime.startswith("image/"):
+            return {
+                "type": "image_url",
+                "image_url": {"url": f"data:{mime};base64,{base64.b64encode(payload).decode()}"},
+            }
+
+        try:
+            text = payload.decode(part.get_content_charset() or "utf-8")
+        except (UnicodeDecodeError, LookupError):
+            raise ValueError(f"attachment {filename} is a binary file of type {mime} and can not be read") from None
+
+        if mime == "text/html":
+            text = html_to_text(text)
+
+        return text[:max_body_length]
+
+    raise ValueError(f"attachment {filename} not found in message {uid}")
+
+
+@requires_user_confirmation()
+def send_email(
+    to: Annotated[
+        str,
+        Field(description="Comma separated list of recipients.", examples=["bob@example.com"]),
+    ],
+    subject: Annotated[str, "The subject of the email"],
+    body: Annotated[str, "The plain text body of the email"],
+) -> str:
+    """Send an email."""
+
+    user = _get_env("NERVE_SMTP_USER")
+
+    message = EmailMessage()
+    message["From"] = _get_env("NERVE_EMAIL_FROM", fallback="NERVE_SMTP_USER")
+    message["To"] = to
+    message["Subject"] = subject
+    message.set_content(body)
+
+    host = _get_env("NERVE_SMTP_HOST")
+    port = int(_get_env("NERVE_SMTP_PORT", default="587"))
+    with smtplib.SMTP_SSL(host, port) if port == 465 else smtplib.SMTP(host, port) as client:
+        if port != 465:
+            client.starttls()
+        client.login(user, _get_env("NERVE_SMTP_PASSWORD"))
+        client.send_message(message)
+
+    return f"email sent to {to}"
@@ -0,0 +1,63 @@
+import unittest
+from email.message import EmailMessage
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import email
+
+
+def _message() -> EmailMessage:
+    message = EmailMessage()
+    message["From"] = "alice@example.com"
+    message["To"] = "bob@example.com"
+    message["Subject"] = "Report"
+    message.set_content(
+        "<html><head><style>p {}</style></head><body><p>Hello <b>Bob</b>,</p><p>see attached.</p></body></html>",
+        subtype="html",
+    )
+    message.add_attachment(b"a,b\n1,2\n", maintype="text", subtype="csv", filename="data.csv")
+    message.add_attachment(b"\x89PNG\r\n", maintype="image", subtype="png", filename="chart.png")
+    return message
+
+
+class TestEmail(unittest.TestCase):
+    def test_html_body_is_cleaned(self) -> None:
+        self.assertEqual(email._get_body(_message()), "Hello Bob,\n\nsee attached.")
+
+    def test_body_is_truncated(self) -> None:
+        message = EmailMessage()
+        message.set_content("x" * (email.max_body_length + 100))
+
+        body = email._get_body(message)
+        self.assertTrue(body.startswith("x" * email.max_body_length))
+        self.assertIn("truncated, 100 more characters", body)
+
+    @patch("nerve.tools.namespaces.email._fetch_message")
+    def test_read_message_lists_attachments(self, mock_fetch: MagicMock) -> None:
+        mock_fetch.return_value = _message()
+
+        result = email.read_message("42")
+        self.assertIn("Subject: Report", result)
+        self.assertIn("data.csv (text/csv, 8 bytes)", result)
+        self.assertIn("chart.png (image/png, 6 bytes)", result)
+
+    @patch("nerve.tools.namespaces.email._fetch_message")
+    def test_get_attachment(self, mock_fetch: MagicMock) -> None:
+        mock_fetch.return_value = _message()
+
+        self.assertEqual(email.get_attachment("42", "data.csv"), "a,b\n1,2\n")
+
+        image = email.get_attachment("42", "chart.png")
+        assert isinstance(image, dict)
+        self.assertEqual(image["type"], "image_url")
+        self.assertTrue(image["image_url"]["url"].startswith("data:image/png;base64,"))
+
+        with self.assertRaises(ValueError):
+            email.get_attachment("42", "missing.txt")
+
+    @patch("nerve.runtime.state.on_user_confirmation_needed")
+    @patch("nerve.tools.namespaces.email.smtplib.SMTP")
+    def test_send_email_requires_confirmation(self, mock_smtp: MagicMock, mock_confirm: MagicMock) -> None:
+        mock_confirm.return_value = False
+
+        self.assertIn("did not allow", email.send_email("bob@example.com", "hi", "hello"))
+        mock_smtp.assert_not_called()
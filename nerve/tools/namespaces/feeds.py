@@ -0,0 +1,162 @@
+"""
+Let the agent read RSS and Atom feeds, useful to monitor news and release announcements.
+
+> [!TIP]
+> Entries are normalized to title, link, published date and summary regardless of the feed format. Feeds are fetched conditionally with the `ETag` and `Last-Modified` headers returned by the server, so polling an unchanged feed does not download it again.
+"""
+
+import email.utils
+import json
+import typing as t
+import xml.etree.ElementTree as ET
+from datetime import datetime
+from typing import Annotated
+
+from nerve.defaults import DEFAULT_HTTP_TIMEOUT
+from nerve.runtime import network
+from nerve.tools.utils import html_to_text
+
+# for docs
+EMOJI = "📰"
+
+# maximum number of entries returned for each feed
+max_entries: int = 20
+# maximum number of characters of the summary of each entry
+max_summary_length: int = 500
+
+# etag, last modified date and parsed feed of every fetched url
+_cache: dict[str, tuple[str | None, str | None, dict[str, t.Any]]] = {}
+
+
+def _name(element: ET.Element) -> str:
+    # drop the namespace, RSS 1.0, RSS 2.0 and Atom use different ones
+    return element.tag.rsplit("}", 1)[-1].lower()
+
+
+def _child(element: ET.Element, *names: str) -> ET.Element | None:
+    for name in names:
+        for child in element:
+            if _name(child) == name:
+                return child
+    return None
+
+
+def _text(element: ET.Element, *names: str) -> str:
+    child = _child(element, *names)
+    return (child.text or "").strip() if child is not None else ""
+
+
+def _normalize_date(value: str) -> str | None:
+    if not value:
+        return None
+
+    try:
+        # RSS uses RFC 2822 dates
+        return email.utils.parsedate_to_datetime(value).isoformat()
+    except (TypeError, ValueError):
+        pass
+
+    try:
+        # Atom and RSS 1.0 use ISO 8601 dates
+        return datetime.fromisoformat(value.replace("Z", "+00:00")).isoformat()
+    except ValueError:
+        return value
+
+
+def _summarize(value: str) -> str:
+    summary = " ".join(html_to_text(value).split())
+    if len(summary) > max_summary_length:
+        summary = summary[:max_summary_length] + "..."
+    return summary
+
+
+def _atom_link(entry: ET.Element) -> str:
+    links = [child for child in entry if _name(child) == "link"]
+    for link in links:
+        if link.get("rel", "alternate") == "alternate":
+            return link.get("href", "")
+    return links[0].get("href", "") if links else ""
+
+
+def _parse_entry(item: ET.Element, atom: bool) -> dict[str, t.Any]:
+    if atom:
+        link = _atom_link(item)
+        published = _text(item, "published", "updated")
+        summary = _text(item, "summary", "content")
+    else:
+        link = _text(item, "link")
+        published = _text(item, "pubdate", "date")
+        summary = _text(item, "description", "encoded")
+
+    return {
+        "title": _text(item, "title"),
+        "link": link,
+        "published": _normalize_date(published),
+        "summary": _summarize(summary),
+    }
+
+
+def _parse_feed(data: bytes) -> dict[str, t.Any]:
+    try:
+        root = ET.fromstring(data)
+    except ET.ParseError as e:
+        raise ValueError(f"the feed is not valid XML: {e}") from None
+
+    kind = _name(root)
+    if kind == "feed":
+        # Atom
+        channel, items, atom = root, [child for child in root if _name(child) == "entry"], True
+    elif kind == "rss":
+        # RSS 2.0
+        rss_channel = _child(root, "channel")
+        if rss_channel is None:
+            raise ValueError("the RSS feed has no channel")
+        channel, items, atom = rss_channel, [child for child in rss_channel if _name(child) == "item"], False
+    elif kind == "rdf":
+        # RSS 1.0, items are siblings of the channel
+        rdf_channel = _child(root, "channel")
+        channel = rdf_channel if rdf_channel is not None else root
+        items, atom = [child for child in root if _name(child) == "item"], False
+    else:
+        raise ValueError(f"expected an RSS or Atom feed, got a <{kind}> document")
+
+    return {
+        "title": _text(channel, "title"),
+        "entries": [_parse_entry(item, atom) for item in items],
+    }
+
+
+def fetch_feed(
+    url: Annotated[str, "The URL of the RSS or Atom feed"],
+    limit: Annotated[int, "Optional maximum number of entries to return, most feeds list the newest first"] = 0,
+) -> str:
+    """Fetch an RSS or Atom feed and return its entries as JSON, with title, link, published date and summary."""
+
+    headers: dict[str, str] = {}
+    cached = _cache.get(url)
+    if cached is not None:
+        etag, last_modified, _ = cached
+        if etag:
+            headers["If-None-Match"] = etag
+        if last_modified:
+            headers["If-Modified-Since"] = last_modified
+
+    response = network.request("GET", url, headers=headers, timeout=DEFAULT_HTTP_TIMEOUT)
+    if response.status_code == 304 and cached is not None:
+        feed, unchanged = cached[2], True
+    else:
+        response.raise_for_status()
+        feed, unchanged = _parse_feed(response.content), False
+        _cache[url] = (response.headers.get("ETag"), response.headers.get("Last-Modified"), feed)
+
+    count = min(limit, max_entries) if limit > 0 else max_entries
+    return json.dumps(
+        {
+            "title": feed["title"],
+            "unchanged_since_last_fetch": unchanged,
+            "total_entries": len(feed["entries"]),
+            "entries": feed["entries"][:count],
+        },
+        indent=2,
+        ensure_ascii=False,
+    )
@@ -0,0 +1,111 @@
+import json
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import feeds
+
+_RSS = b"""<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>Nerve releases</title>
+    <item>
+      <title>v1.2.0</title>
+      <link>https://example.com/v1.2.0</link>
+      <pubDate>Wed, 01 May 2024 10:00:00 +0000</pubDate>
+      <description>&lt;p&gt;New &lt;b&gt;namespaces&lt;/b&gt;&lt;/p&gt;</description>
+    </item>
+    <item>
+      <title>v1.1.0</title>
+      <link>https://example.com/v1.1.0</link>
+    </item>
+  </channel>
+</rss>"""
+
+_ATOM = b"""<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Security advisories</title>
+  <entry>
+    <title>CVE-2024-0001</title>
+    <link rel="self" href="https://example.com/feed/1"/>
+    <link rel="alternate" href="https://example.com/advisories/1"/>
+    <updated>2024-05-01T10:00:00Z</updated>
+    <summary>Remote code execution</summary>
+  </entry>
+</feed>"""
+
+
+def _response(status_code: int = 200, content: bytes = b"", headers: dict[str, str] | None = None) -> MagicMock:
+    return MagicMock(status_code=status_code, content=content, headers=headers or {})
+
+
+@patch("nerve.tools.namespaces.feeds.network.request")
+class TestFetchFeed(unittest.TestCase):
+    def setUp(self) -> None:
+        feeds._cache.clear()
+
+    def test_rss(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(content=_RSS)
+
+        result = json.loads(feeds.fetch_feed("https://example.com/rss"))
+
+        self.assertEqual(result["title"], "Nerve releases")
+        self.assertEqual(
+            result["entries"][0],
+            {
+                "title": "v1.2.0",
+                "link": "https://example.com/v1.2.0",
+                "published": "2024-05-01T10:00:00+00:00",
+                "summary": "New namespaces",
+            },
+        )
+        self.assertIsNone(result["entries"][1]["published"])
+
+    def test_atom(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(content=_ATOM)
+
+        result = json.loads(feeds.fetch_feed("https://example.com/atom"))
+
+        self.assertEqual(result["title"], "Security advisories")
+        self.assertEqual(
+            result["entries"],
+            [
+                {
+                    "title": "CVE-2024-0001",
+                    "link": "https://example.com/advisories/1",
+                    "published": "2024-05-01T10:00:00+00:00",
+                    "summary": "Remote code execution",
+                }
+            ],
+        )
+
+    def test_entries_are_capped(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(content=_RSS)
+
+        result = json.loads(feeds.fetch_feed("https://example.com/rss", limit=1))
+        self.assertEqual(len(result["entries"]), 1)
+        self.assertEqual(result["total_entries"], 2)
+
+        with patch.object(feeds, "max_entries", 1):
+            self.assertEqual(len(json.loads(feeds.fetch_feed("https://example.com/rss", limit=5))["entries"]), 1)
+
+    def test_conditional_fetch(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(content=_RSS, headers={"ETag": '"v1"', "Last-Modified": "yesterday"})
+        feeds.fetch_feed("https://example.com/rss")
+
+        mock_request.return_value = _response(status_code=304)
+        result = json.loads(feeds.fetch_feed("https://example.com/rss"))
+
+        self.assertEqual(
+            mock_request.call_args.kwargs["headers"], {"If-None-Match": '"v1"', "If-Modified-Since": "yesterday"}
+        )
+        self.assertTrue(result["unchanged_since_last_fetch"])
+        self.assertEqual(len(result["entries"]), 2)
+
+    def test_invalid_feeds(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(content=b"<rss><channel>")
+        with self.assertRaisesRegex(ValueError, "not valid XML"):
+            feeds.fetch_feed("https://example.com/broken")
+
+        mock_request.return_value = _response(content=b"<html><body>hello</body></html>")
+        with self.assertRaisesRegex(ValueError, "expected an RSS or Atom feed, got a <html> document"):
+            feeds.fetch_feed("https://example.com/page")
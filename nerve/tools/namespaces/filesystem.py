@@ -3,15 +3,18 @@ Read-only access primitives to the local filesystem.
 """
 
 import os
+import typing as t
 from typing import Annotated
 
-from nerve.tools.utils import maybe_text, path_acl
+from nerve.tools.utils import file_part, maybe_text, path_acl
 
 # for docs
 EMOJI = "📂"
 
 # if set, the agent will only have access to these paths
 jail: list[str] = []
+# maximum total size in bytes of the files returned by read_many
+max_read_size: int = 512 * 1024
 
 # TODO: fix this missed interpolation:
 # "\u001b[31m\u001b[1m[04-17-25 14:04:03] ERROR\u001b[0m list_folder_contents: access to path /Users/evilsocket/.nerve/agents/eval-regex/cases is not allowed, only allowed paths are: ['{{ CASE_PATH }}']",
@@ -30,10 +33,56 @@ def list_folder_contents(
     return os.popen(f"ls -la {path}").read()
 
 
-def read_file(path: Annotated[str, "The path to the file to read"]) -> str | bytes:
+def read_file(path: Annotated[str, "The path to the file to read"]) -> str | bytes | dict[str, t.Any]:
     """Read the contents of a file from disk."""
 
     path_acl(path, jail)
 
     with open(path, "rb") as f:
-        return maybe_text(f.read())
+        data = f.read()
+
+    # documents are attached as they are, the models that don't accept them get their text
+    if path.lower().endswith(".pdf"):
+        return file_part(data, "application/pdf", os.path.basename(path))
+
+    return maybe_text(data)
+
+
+def read_many(
+    paths: Annotated[list[str], "The paths of the files to read"],
+) -> str:
+    """Read the contents of multiple files from disk at once, each one preceded by a header with its path."""
+
+    out = []
+    remaining = max_read_size
+    for i, path in enumerate(paths):
+        if remaining <= 0:
+            out.append(f"==> skipped because the size limit has been reached: {', '.join(paths[i:])} <==")
+            break
+
+        try:
+            path_acl(path, jail)
+            with open(path, "rb") as f:
+                data = f.read(remaining + 1)
+        except Exception as e:
+            # one unreadable file does not prevent reading the others
+            out.append(f"==> {path} <==\nERROR: {e}")
+            continue
+
+        truncated = len(data) > remaining
+        data = data[:remaining]
+        remaining -= len(data)
+
+        content = maybe_text(data)
+        # a truncated file might end in the middle of a multi-byte character
+        for cut in range(1, 4):
+            if not truncated or isinstance(content, str):
+                break
+            content = maybe_text(data[:-cut])
+
+        if isinstance(content, bytes):
+            out.append(f"==> {path} <==\nERROR: binary file, use read_file to read it")
+        else:
+            out.append(f"==> {path} <==\n{content}" + ("\n[truncated]" if truncated else ""))
+
+    return "\n\n".join(out)
@@ -39,6 +39,20 @@ class TestFilesystem(unittest.TestCase):
         result = filesystem.read_file(str(self.test_file))
         self.assertEqual(result, "test content")
 
+    def test_read_pdf_file(self) -> None:
+        pdf_file = self.test_dir / "report.pdf"
+        pdf_file.write_bytes(b"%PDF-1.7")
+
+        result = filesystem.read_file(str(pdf_file))
+
+        self.assertEqual(
+            result,
+            {
+                "type": "file",
+                "file": {"filename": "report.pdf", "file_data": "data:application/pdf;base64,JVBERi0xLjc="},
+            },
+        )
+
     def test_list_folder_contents_with_jail_allowed(self) -> None:
         # Set jail to allow only the test directory
         filesystem.jail = [str(self.test_dir)]
@@ -137,3 +151,32 @@ class TestFilesystem(unittest.TestCase):
         result = filesystem.read_file(str(binary_file))
         self.assertIsInstance(result, bytes)
         self.assertEqual(result, binary_content)
+
+    def test_read_many(self) -> None:
+        missing = self.test_dir / "missing.txt"
+
+        result = filesystem.read_many([str(self.test_file), str(missing), str(self.test_subfile)])
+
+        self.assertIn(f"==> {self.test_file} <==\ntest content", result)
+        self.assertIn(f"==> {missing} <==\nERROR:", result)
+        self.assertIn(f"==> {self.test_subfile} <==\nsubfile content", result)
+
+    def test_read_many_with_jail(self) -> None:
+        filesystem.jail = [str(self.test_subdir)]
+
+        result = filesystem.read_many([str(self.test_file), str(self.test_subfile)])
+
+        self.assertIn(f"==> {self.test_file} <==\nERROR: access to path", result)
+        self.assertIn("subfile content", result)
+
+    def test_read_many_size_limit(self) -> None:
+        original = filesystem.max_read_size
+        filesystem.max_read_size = 20
+        try:
+            result = filesystem.read_many([str(self.test_file), str(self.test_subfile), str(self.test_file)])
+        finally:
+            filesystem.max_read_size = original
+
+        self.assertIn("test content", result)
+        self.assertIn(f"==> {self.test_subfile} <==\nsubfile\n[truncated]", result)
+        self.assertIn(f"==> skipped because the size limit has been reached: {self.test_file} <==", result)
@@ -3,6 +3,7 @@ Write primitives to the local filesystem.
 """
 
 import os
+import re
 from typing import Annotated
 
 from nerve.tools.utils import path_acl
@@ -13,6 +14,8 @@ EMOJI = "📂"
 # if set, the agent will only have access to these paths
 jail: list[str] = []
 
+_HUNK_HEADER = re.compile(r"^@@ -(\d+)(?:,(\d+))? \+\d+(?:,(\d+))? @@")
+
 
 def create_file(
     path: Annotated[str, "The path to the file to create"],
@@ -52,3 +55,159 @@ def delete_file(path: Annotated[str, "The path to the file to delete"]) -> str:
 
     os.remove(path)
     return f"File {path} deleted."
+
+
+def _get_patch_path(header: str) -> str | None:
+    # "--- a/path/to/file\t2024-01-01 00:00:00" -> "path/to/file"
+    path = header[4:].split("\t")[0].strip()
+    if path == "/dev/null":
+        return None
+    if path.startswith(("a/", "b/")):
+        path = path[2:]
+    return path
+
+
+def _parse_patch(patch: str) -> list[tuple[str | None, str | None, list[tuple[int, list[str]]]]]:
+    # list of (old path, new path, hunks) where each hunk is its starting line and its lines with their prefix
+    files: list[tuple[str | None, str | None, list[tuple[int, list[str]]]]] = []
+    lines = patch.splitlines()
+    i = 0
+    while i < len(lines):
+        if lines[i].startswith("--- ") and i + 1 < len(lines) and lines[i + 1].startswith("+++ "):
+            files.append((_get_patch_path(lines[i]), _get_patch_path(lines[i + 1]), []))
+            i += 2
+            continue
+
+        match = _HUNK_HEADER.match(lines[i])
+        if match:
+            if not files:
+                raise ValueError("the patch has a hunk without file headers")
+
+            # the line counts tell where the hunk ends, since removed lines can start with "--- " too
+            old_count = int(match.group(2) or 1)
+            new_count = int(match.group(3) or 1)
+            hunk: list[str] = []
+            i += 1
+            while i < len(lines) and (old_count > 0 or new_count > 0) and not lines[i].startswith("@@"):
+                line = lines[i]
+                if line.startswith("\\"):
+                    # "\ No newline at end of file"
+                    i += 1
+                    continue
+                if line == "":
+                    # some editors strip the trailing space of empty context lines
+                    line = " "
+                elif line[0] not in (" ", "-", "+"):
+                    break
+
+                hunk.append(line)
+                old_count -= line[0] != "+"
+                new_count -= line[0] != "-"
+                i += 1
+
+            # the marker of the last line of the hunk
+            while i < len(lines) and lines[i].startswith("\\"):
+                i += 1
+
+            files[-1][2].append((int(match.group(1)), hunk))
+            continue
+
+        i += 1
+
+    if not files:
+        raise ValueError("no file headers found, the patch must be in unified diff format")
+
+    return files
+
+
+def _find_hunk(lines: list[str], old: list[str], expected: int) -> tuple[int, bool] | None:
+    # position of the old lines in the file closest to the expected one, and whether whitespace was ignored
+    for fuzzy in (False, True):
+        normalized = [line.strip() for line in lines] if fuzzy else lines
+        target = [line.strip() for line in old] if fuzzy else old
+        candidates = [
+            pos for pos in range(len(lines) - len(old) + 1) if normalized[pos : pos + len(old)] == target
+        ]
+        if candidates:
+            return min(candidates, key=lambda pos: abs(pos - expected)), fuzzy
+
+    return None
+
+
+def apply_patch(
+    patch: Annotated[str, "The patch to apply, in unified diff format"],
+    root: Annotated[str, "The folder the paths of the patch are relative to"] = ".",
+) -> str:
+    """
+    Apply a unified diff to one or more files, use this to edit files instead of rewriting them entirely.
+    The patch is applied only if all of its hunks apply, otherwise no file is changed.
+    """
+
+    # compute every change in memory first, so that nothing is written if any hunk fails
+    changes: list[tuple[str, str | None]] = []
+    report: list[str] = []
+    failed = False
+
+    for old_path, new_path, hunks in _parse_patch(patch):
+        path = os.path.join(root, new_path or old_path or "")
+        path_acl(path, jail)
+
+        lines: list[str] = []
+        if old_path is not None:
+            source = os.path.join(root, old_path)
+            path_acl(source, jail)
+            if not os.path.exists(source):
+                report.append(f"{source}: file not found")
+                failed = True
+                continue
+            with open(source) as f:
+                lines = f.read().splitlines()
+
+            if new_path is not None and new_path != old_path:
+                # renamed, the old file is removed once the new one is written
+                changes.append((source, None))
+
+        # hunks are applied in order, each one shifting the position of the following ones
+        offset = 0
+        for num, (start, hunk) in enumerate(hunks, 1):
+            old = [line[1:] for line in hunk if line[0] in (" ", "-")]
+            new = [line[1:] for line in hunk if line[0] in (" ", "+")]
+            # the starting line of a hunk adding to an empty file is 0
+            expected = max(start - 1, 0) + offset
+
+            found = _find_hunk(lines, old, expected)
+            if found is None:
+                report.append(f"{path}: hunk {num} failed, the context does not match")
+                failed = True
+                continue
+
+            pos, fuzzy = found
+            lines[pos : pos + len(old)] = new
+            offset += len(new) - len(old)
+
+            note = f" at offset {pos - expected:+d}" if pos != expected else ""
+            if fuzzy:
+                note += " ignoring whitespace"
+            report.append(f"{path}: hunk {num} applied{note}")
+
+        if new_path is None:
+            changes.append((path, None))
+        else:
+            changes.append((path, "\n".join(lines) + "\n" if lines else ""))
+
+    if failed:
+        return "patch rejected, no file has been changed:\n\n" + "\n".join(report)
+
+    # new files are written before the renamed ones are removed
+    for path, content in sorted(changes, key=lambda change: change[1] is None):
+        if content is None:
+            os.remove(path)
+            report.append(f"{path}: deleted")
+        else:
+            parent_dir = os.path.dirname(path)
+            if parent_dir:
+                os.makedirs(parent_dir, exist_ok=True)
+            with open(path, "w") as f:
+                f.write(content)
+
+    return "patch applied:\n\n" + "\n".join(report)
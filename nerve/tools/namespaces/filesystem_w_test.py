@@ -0,0 +1,148 @@
+import tempfile
+import unittest
+from pathlib import Path
+
+from nerve.tools.namespaces import filesystem_w
+
+ORIGINAL = "def greet(name):\n    print('hello', name)\n\n\ndef main():\n    greet('world')\n"
+
+
+class TestApplyPatch(unittest.TestCase):
+    def setUp(self) -> None:
+        self.temp_dir = tempfile.TemporaryDirectory()
+        self.root = Path(self.temp_dir.name)
+        self.file = self.root / "hello.py"
+        self.file.write_text(ORIGINAL)
+
+        filesystem_w.jail = []
+
+    def tearDown(self) -> None:
+        self.temp_dir.cleanup()
+
+    def test_clean_apply(self) -> None:
+        patch = (
+            "--- a/hello.py\n"
+            "+++ b/hello.py\n"
+            "@@ -1,2 +1,2 @@\n"
+            " def greet(name):\n"
+            "-    print('hello', name)\n"
+            "+    print('hi', name)\n"
+            "@@ -5,2 +5,3 @@\n"
+            " def main():\n"
+            "     greet('world')\n"
+            "+    greet('nerve')\n"
+        )
+
+        result = filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertIn("hunk 1 applied", result)
+        self.assertIn("hunk 2 applied", result)
+        self.assertEqual(
+            self.file.read_text(),
+            "def greet(name):\n    print('hi', name)\n\n\ndef main():\n    greet('world')\n    greet('nerve')\n",
+        )
+
+    def test_fuzzy_context(self) -> None:
+        # wrong line numbers and different indentation of the context
+        patch = (
+            "--- a/hello.py\n"
+            "+++ b/hello.py\n"
+            "@@ -10,2 +10,2 @@\n"
+            " def main():\n"
+            "-  greet('world')\n"
+            "+    greet('everyone')\n"
+        )
+
+        result = filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertIn("hunk 1 applied at offset -5 ignoring whitespace", result)
+        self.assertTrue(self.file.read_text().endswith("def main():\n    greet('everyone')\n"))
+
+    def test_rejection_changes_nothing(self) -> None:
+        other = self.root / "other.txt"
+        other.write_text("one\ntwo\n")
+        patch = (
+            "--- a/other.txt\n"
+            "+++ b/other.txt\n"
+            "@@ -1,2 +1,2 @@\n"
+            " one\n"
+            "-two\n"
+            "+three\n"
+            "--- a/hello.py\n"
+            "+++ b/hello.py\n"
+            "@@ -1,2 +1,2 @@\n"
+            " def greet(name):\n"
+            "-    print('goodbye', name)\n"
+            "+    print('hi', name)\n"
+        )
+
+        result = filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertTrue(result.startswith("patch rejected"))
+        self.assertIn("other.txt: hunk 1 applied", result)
+        self.assertIn("hello.py: hunk 1 failed", result)
+        self.assertEqual(other.read_text(), "one\ntwo\n")
+        self.assertEqual(self.file.read_text(), ORIGINAL)
+
+    def test_create_and_delete_files(self) -> None:
+        patch = (
+            "--- /dev/null\n"
+            "+++ b/new/file.txt\n"
+            "@@ -0,0 +1,2 @@\n"
+            "+first\n"
+            "+second\n"
+            "--- a/hello.py\n"
+            "+++ /dev/null\n"
+            "@@ -1,6 +0,0 @@\n" + "".join(f"-{line}\n" for line in ORIGINAL.splitlines())
+        )
+
+        filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertEqual((self.root / "new" / "file.txt").read_text(), "first\nsecond\n")
+        self.assertFalse(self.file.exists())
+
+    def test_removed_lines_looking_like_file_headers(self) -> None:
+        sql = self.root / "schema.sql"
+        sql.write_text("-- users\n-- accounts\nCREATE TABLE users (id INT);\n")
+        patch = (
+            "--- a/schema.sql\n"
+            "+++ b/schema.sql\n"
+            "@@ -1,3 +1,2 @@\n"
+            "--- users\n"
+            "--- accounts\n"
+            "+-- all the users\n"
+            " CREATE TABLE users (id INT);\n"
+        )
+
+        result = filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertIn("hunk 1 applied", result)
+        self.assertEqual(sql.read_text(), "-- all the users\nCREATE TABLE users (id INT);\n")
+
+    def test_rename(self) -> None:
+        patch = (
+            "--- a/hello.py\n"
+            "+++ b/greetings.py\n"
+            "@@ -1,1 +1,1 @@\n"
+            "-def greet(name):\n"
+            "+def greet(who):\n"
+        )
+
+        result = filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertIn("hello.py: deleted", result)
+        self.assertFalse(self.file.exists())
+        self.assertTrue((self.root / "greetings.py").read_text().startswith("def greet(who):\n"))
+
+    def test_jail(self) -> None:
+        filesystem_w.jail = [str(self.root / "allowed")]
+        patch = "--- a/hello.py\n+++ b/hello.py\n@@ -1,1 +1,1 @@\n-def greet(name):\n+def greet(who):\n"
+
+        with self.assertRaises(ValueError):
+            filesystem_w.apply_patch(patch, str(self.root))
+
+        self.assertEqual(self.file.read_text(), ORIGINAL)
+
+    def test_invalid_patch(self) -> None:
+        with self.assertRaises(ValueError):
+            filesystem_w.apply_patch("this is not a patch", str(self.root))
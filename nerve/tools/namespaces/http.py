@@ -0,0 +1,184 @@
+"""
+Let the agent perform HTTP requests, transient errors are retried with exponential backoff.
+
+> [!TIP]
+> Response bodies are rendered according to their content type: JSON is pretty printed, HTML is converted to readable text, images are returned as images and PDF documents are attached as files, unless the raw body is requested. Compressed responses are decompressed (brotli requires the `brotli` extra), and bodies larger than `NERVE_HTTP_MAX_RESPONSE_SIZE` bytes (1MB by default) once decompressed are truncated, and at most `NERVE_HTTP_MAX_REDIRECTS` redirects are followed.
+"""
+
+import base64
+import gzip
+import json
+import time
+import typing as t
+import zlib
+from typing import Annotated
+
+import requests
+from requests.structures import CaseInsensitiveDict
+
+from nerve.defaults import (
+    DEFAULT_HTTP_MAX_REDIRECTS,
+    DEFAULT_HTTP_MAX_RESPONSE_SIZE,
+    DEFAULT_HTTP_MAX_RETRIES,
+    DEFAULT_HTTP_RETRY_DELAY,
+    DEFAULT_HTTP_TIMEOUT,
+)
+from nerve.runtime import network
+from nerve.tools.utils import file_part, html_to_text
+
+try:
+    import brotli  # type: ignore
+
+    # only brotli >= 1.1 can limit the size of the decompressed output
+    _ENCODINGS: tuple[str, ...] = (
+        ("gzip", "deflate", "br") if hasattr(brotli.Decompressor, "can_accept_more_data") else ("gzip", "deflate")
+    )
+except ImportError:
+    brotli = None
+    _ENCODINGS = ("gzip", "deflate")
+
+# for docs
+EMOJI = "🌐"
+
+# maximum size in bytes of the response body, the rest is truncated
+max_response_size: int = DEFAULT_HTTP_MAX_RESPONSE_SIZE
+# maximum number of redirects to follow
+max_redirects: int = DEFAULT_HTTP_MAX_REDIRECTS
+
+
+class _Decoder:
+    """Decompress a response body incrementally, without ever expanding it beyond the requested size."""
+
+    def __init__(self, encoding: str):
+        self.encoding = encoding
+        self.started = False
+        self.brotli = brotli.Decompressor() if encoding == "br" else None
+        self.zlib = zlib.decompressobj(16 + zlib.MAX_WBITS if encoding == "gzip" else zlib.MAX_WBITS)
+
+    def decode(self, data: bytes, limit: int) -> bytes:
+        try:
+            if self.brotli is not None:
+                # the rest of the output is kept by the decompressor, but the body is cut off once the limit is reached
+                return bytes(self.brotli.process(data, output_buffer_limit=limit))
+
+            try:
+                out = self.zlib.decompress(self.zlib.unconsumed_tail + data, limit)
+            except zlib.error:
+                if self.encoding != "deflate" or self.started:
+                    raise
+                # some servers send raw deflate data without the zlib header
+                self.zlib = zlib.decompressobj(-zlib.MAX_WBITS)
+                out = self.zlib.decompress(data, limit)
+
+            self.started = True
+            return out
+
+        except Exception as e:
+            raise ValueError(f"can not decompress the {self.encoding} response body: {e}") from e
+
+
+def _read_body(response: requests.Response, deadline: float) -> tuple[bytes, str | None]:
+    # read up to max_response_size decompressed bytes, a server streaming more data or too slowly is cut off
+    encoding = response.headers.get("Content-Encoding", "").strip().lower()
+    decoder = _Decoder(encoding) if encoding in _ENCODINGS else None
+    chunks: t.Iterable[bytes] = (
+        response.raw.stream(8192, decode_content=False) if decoder else response.iter_content(chunk_size=8192)
+    )
+
+    body = b""
+    try:
+        for chunk in chunks:
+            body += decoder.decode(chunk, max_response_size + 1 - len(body)) if decoder else chunk
+            if len(body) > max_response_size:
+                return body[:max_response_size], f"response truncated to {max_response_size} bytes"
+            if time.monotonic() > deadline:
+                return body, f"response truncated after {len(body)} bytes because the timeout expired"
+    finally:
+        response.close()
+
+    return body, None
+
+
+def _render_body(content_type: str, text: str) -> str:
+    if content_type == "application/json" or content_type.endswith("+json"):
+        try:
+            return json.dumps(json.loads(text), indent=2, ensure_ascii=False)
+        except json.JSONDecodeError:
+            return text
+
+    elif content_type in ("text/html", "application/xhtml+xml"):
+        return html_to_text(text)
+
+    return text
+
+
+def http_request(
+    method: Annotated[str, "The HTTP method to use, like GET, POST, PUT or DELETE"],
+    url: Annotated[str, "The URL to request"],
+    headers: Annotated[dict[str, str] | None, "Optional HTTP headers to send"] = None,
+    body: Annotated[str, "Optional request body"] = "",
+    max_retries: Annotated[int, "How many times to retry on transient errors"] = DEFAULT_HTTP_MAX_RETRIES,
+    base_delay_ms: Annotated[int, "Base delay in milliseconds between retries"] = DEFAULT_HTTP_RETRY_DELAY,
+    retry_non_idempotent: Annotated[
+        bool, "Set to true only if it is safe to retry a non idempotent request like POST"
+    ] = False,
+    timeout: Annotated[int, "Timeout in seconds for the whole response"] = DEFAULT_HTTP_TIMEOUT,
+    raw: Annotated[
+        bool, "Set to true to get the unprocessed body instead of formatted JSON, text extracted from HTML and images"
+    ] = False,
+    compress: Annotated[
+        bool, "Set to true to send the request body gzip compressed, only if the server supports it"
+    ] = False,
+) -> str | dict[str, t.Any]:
+    """Perform an HTTP request and return the response status, headers and body."""
+
+    policy = network.RetryPolicy(
+        max_retries=max_retries,
+        base_delay_ms=base_delay_ms,
+        retry_non_idempotent=retry_non_idempotent,
+    )
+
+    request_headers: CaseInsensitiveDict[str] = CaseInsensitiveDict({"Accept-Encoding": ", ".join(_ENCODINGS)})
+    request_headers.update(headers or {})
+
+    data = body.encode("utf-8") if body else None
+    if data and compress:
+        data = gzip.compress(data)
+        request_headers["Content-Encoding"] = "gzip"
+
+    deadline = time.monotonic() + timeout
+    response = network.request(
+        method.upper(),
+        url,
+        policy=policy,
+        max_redirects=max_redirects,
+        headers=request_headers,
+        data=data,
+        timeout=timeout,
+        stream=True,
+    )
+
+    out = f"HTTP {response.status_code} {response.reason}\n"
+    for name, value in response.headers.items():
+        out += f"{name}: {value}\n"
+
+    content, note = _read_body(response, deadline)
+    content_type = response.headers.get("Content-Type", "").split(";")[0].strip().lower()
+
+    # a truncated image can't be decoded, and svg images are not supported by the models
+    if not raw and content_type.startswith("image/") and content_type != "image/svg+xml" and note is None:
+        return {
+            "type": "image_url",
+            "image_url": {"url": f"data:{content_type};base64,{base64.b64encode(content).decode()}"},
+        }
+
+    # documents are attached as they are, the models that don't accept them get their text
+    if not raw and content_type == "application/pdf" and note is None:
+        return file_part(content, content_type, url.split("?")[0].rstrip("/").rsplit("/", 1)[-1] or "document.pdf")
+
+    text = content.decode(response.encoding or "utf-8", errors="replace")
+    out += "\n" + (text if raw else _render_body(content_type, text))
+    if note:
+        out += f"\n\n[{note}]"
+
+    return out
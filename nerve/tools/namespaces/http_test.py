@@ -0,0 +1,187 @@
+import gzip
+import http.server
+import threading
+import time
+import typing as t
+import unittest
+import zlib
+from unittest.mock import MagicMock, patch
+
+import requests
+
+from nerve.tools.namespaces import http as http_ns
+
+
+_BODIES = {
+    "/big": ("text/plain", b"x" * 1000),
+    "/json": ("application/json; charset=utf-8", b'{"name":"nerve","tags":["agents"]}'),
+    "/html": (
+        "text/html; charset=utf-8",
+        b"<html><head><style>p { color: red; }</style></head><body><p>Hello</p><script>alert(1)</script></body></html>",
+    ),
+    "/image": ("image/png", b"\x89PNG\r\n\x1a\n"),
+    "/report.pdf": ("application/pdf", b"%PDF-1.7"),
+}
+
+_COMPRESSED = {
+    "/gzip": ("gzip", gzip.compress(b"hello compressed")),
+    # raw deflate, without the zlib header
+    "/deflate": ("deflate", zlib.compress(b"hello deflated")[2:-4]),
+    "/bomb": ("gzip", gzip.compress(b"\x00" * 5 * 1024 * 1024)),
+}
+
+
+class _Handler(http.server.BaseHTTPRequestHandler):
+    def do_GET(self) -> None:
+        if self.path == "/stall":
+            time.sleep(2)
+        elif self.path.startswith("/redirect/"):
+            hops = int(self.path.split("/")[-1])
+            self.send_response(302)
+            self.send_header("Location", f"/redirect/{hops - 1}" if hops > 1 else "/")
+            self.end_headers()
+            return
+
+        elif self.path in _COMPRESSED:
+            encoding, body = _COMPRESSED[self.path]
+            self.send_response(200)
+            self.send_header("Content-Type", "text/plain")
+            self.send_header("Content-Encoding", encoding)
+            self.send_header("Content-Length", str(len(body)))
+            self.end_headers()
+            try:
+                self.wfile.write(body)
+            except (BrokenPipeError, ConnectionResetError):
+                # the client stops reading once the size limit is reached
+                pass
+            return
+
+        content_type, body = _BODIES.get(self.path, ("text/plain", b"hello"))
+        self.send_response(200)
+        self.send_header("Content-Type", content_type)
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def do_POST(self) -> None:
+        # echo the request encodings and the decompressed body
+        data = self.rfile.read(int(self.headers["Content-Length"]))
+        if self.headers.get("Content-Encoding") == "gzip":
+            data = gzip.decompress(data)
+
+        body = f"{self.headers.get('Content-Encoding')} {self.headers.get('Accept-Encoding')} ".encode() + data
+        self.send_response(200)
+        self.send_header("Content-Type", "text/plain")
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def log_message(self, format: str, *args: t.Any) -> None:
+        pass
+
+
+class TestHttpRequest(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), _Handler)
+        cls.base_url = f"http://127.0.0.1:{cls.server.server_address[1]}"
+        threading.Thread(target=cls.server.serve_forever, daemon=True).start()
+
+    @classmethod
+    def tearDownClass(cls) -> None:
+        cls.server.shutdown()
+        cls.server.server_close()
+
+    def test_response(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/")
+
+        self.assertTrue(result.startswith("HTTP 200 OK\n"))
+        self.assertTrue(result.endswith("\nhello"))
+
+    def test_stalled_response_times_out(self) -> None:
+        started_at = time.monotonic()
+
+        with self.assertRaises(requests.Timeout):
+            http_ns.http_request("GET", f"{self.base_url}/stall", max_retries=0, timeout=1)
+
+        self.assertLess(time.monotonic() - started_at, 2)
+
+    def test_oversized_body_is_truncated(self) -> None:
+        with patch.object(http_ns, "max_response_size", 100):
+            result = http_ns.http_request("GET", f"{self.base_url}/big")
+
+        self.assertIn("\n" + "x" * 100 + "\n\n[response truncated to 100 bytes]", result)
+        self.assertNotIn("x" * 101, result)
+
+    def test_json_is_pretty_printed(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/json")
+
+        self.assertTrue(result.endswith('\n{\n  "name": "nerve",\n  "tags": [\n    "agents"\n  ]\n}'))
+
+    def test_text_is_extracted_from_html(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/html")
+
+        self.assertTrue(result.endswith("\nHello"))
+        self.assertNotIn("alert", result)
+
+    def test_images_are_returned_as_images(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/image")
+
+        self.assertEqual(result, {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}})
+
+    def test_documents_are_returned_as_files(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/report.pdf")
+
+        self.assertEqual(
+            result,
+            {
+                "type": "file",
+                "file": {"filename": "report.pdf", "file_data": "data:application/pdf;base64,JVBERi0xLjc="},
+            },
+        )
+
+    def test_raw_body(self) -> None:
+        result = http_ns.http_request("GET", f"{self.base_url}/html", raw=True)
+
+        self.assertIn("<script>alert(1)</script>", result)
+
+    def test_redirects_are_capped(self) -> None:
+        with patch.object(http_ns, "max_redirects", 3):
+            self.assertIn("hello", http_ns.http_request("GET", f"{self.base_url}/redirect/3"))
+
+            with self.assertRaises(requests.TooManyRedirects):
+                http_ns.http_request("GET", f"{self.base_url}/redirect/4")
+
+    def test_gzip_response_is_decompressed(self) -> None:
+        self.assertTrue(http_ns.http_request("GET", f"{self.base_url}/gzip").endswith("\nhello compressed"))
+
+    def test_raw_deflate_response_is_decompressed(self) -> None:
+        self.assertTrue(http_ns.http_request("GET", f"{self.base_url}/deflate").endswith("\nhello deflated"))
+
+    def test_size_limit_applies_after_decompression(self) -> None:
+        with patch.object(http_ns, "max_response_size", 1000):
+            result = http_ns.http_request("GET", f"{self.base_url}/bomb")
+
+        self.assertTrue(result.endswith("\n\n[response truncated to 1000 bytes]"))
+
+    def test_compressed_request_body(self) -> None:
+        result = http_ns.http_request("POST", f"{self.base_url}/", body="hello server", compress=True)
+
+        self.assertTrue(result.endswith(f"\ngzip {', '.join(http_ns._ENCODINGS)} hello server"))
+
+    def test_plain_request_body(self) -> None:
+        result = http_ns.http_request("POST", f"{self.base_url}/", body="hello server")
+
+        self.assertTrue(result.endswith(" hello server"))
+        self.assertIn("\nNone gzip, deflate", result)
+
+
+class TestDecoder(unittest.TestCase):
+    def test_brotli_output_is_limited(self) -> None:
+        decompressor = MagicMock()
+        decompressor.process.return_value = b"x" * 10
+
+        with patch.object(http_ns, "brotli", MagicMock(Decompressor=MagicMock(return_value=decompressor))):
+            self.assertEqual(http_ns._Decoder("br").decode(b"data", 10), b"x" * 10)
+
+        decompressor.process.assert_called_once_with(b"data", output_buffer_limit=10)
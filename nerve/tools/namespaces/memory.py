@@ -0,0 +1,125 @@
+"""
+Let the agent store facts in its memory, shown in the system prompt, and periodically consolidate them by merging duplicates.
+"""
+
+import difflib
+import re
+from typing import Annotated
+
+import nerve.runtime.state as state
+
+# for docs
+EMOJI = "💾"
+
+# words inverting the meaning of a fact
+_NEGATIONS = {"not", "no", "never", "none", "cannot", "isnt", "arent", "wasnt", "dont", "doesnt", "didnt", "wont"}
+
+_memories: list[str] = []
+
+
+def _update_storage() -> None:
+    if _memories:
+        state.write_knowledge("memories", "\n".join(f"{i}. {m}" for i, m in enumerate(_memories, 1)))
+    else:
+        state.clear_knowledge("memories")
+
+
+def _get_words(fact: str) -> list[str]:
+    return re.sub(r"[^\w\s]", "", fact.lower()).split()
+
+
+def _compare(a: str, b: str) -> tuple[float, bool]:
+    # similarity of the two facts regardless of negations, and whether only one of them is negated
+    words_a, words_b = _get_words(a), _get_words(b)
+    negated_a, negated_b = _NEGATIONS.intersection(words_a), _NEGATIONS.intersection(words_b)
+    plain_a = " ".join(w for w in words_a if w not in _NEGATIONS)
+    plain_b = " ".join(w for w in words_b if w not in _NEGATIONS)
+
+    return difflib.SequenceMatcher(None, plain_a, plain_b).ratio(), bool(negated_a) != bool(negated_b)
+
+
+def save_memory(
+    fact: Annotated[str, "The fact to remember"],
+) -> str:
+    """Store a fact in your memory in order to remember it for the rest of the task."""
+
+    fact = fact.strip()
+    if not fact:
+        raise ValueError("the fact can not be empty")
+
+    _memories.append(fact)
+    _update_storage()
+
+    return f"fact saved as memory {len(_memories)}"
+
+
+def forget_memory(
+    number: Annotated[int, "The number of the memory to forget as shown in the memories list"],
+) -> str:
+    """Remove a fact that is no longer true or relevant from your memory."""
+
+    if number < 1 or number > len(_memories):
+        raise ValueError(f"memory {number} does not exist, there are {len(_memories)} memories")
+
+    fact = _memories.pop(number - 1)
+    _update_storage()
+
+    return f"forgot: {fact}"
+
+
+def consolidate_memories(
+    threshold: Annotated[float, "How similar two facts must be, from 0.0 to 1.0, to be merged (default 0.85)"] = 0.85,
+) -> str:
+    """
+    Merge duplicated and near-duplicated facts of your memory into a single entry in order to keep it compact.
+    Contradicting facts are reported and kept, decide which one is true and forget the other.
+    """
+
+    global _memories
+
+    if not 0.0 < threshold <= 1.0:
+        raise ValueError("the threshold must be greater than 0.0 and at most 1.0")
+
+    # each cluster is a list of similar facts, the first one being their representative
+    clusters: list[list[str]] = []
+    contradictions: list[tuple[str, str]] = []
+    for fact in _memories:
+        for cluster in clusters:
+            similarity, contradicting = _compare(cluster[0], fact)
+            if similarity >= threshold:
+                if contradicting:
+                    contradictions.append((cluster[0], fact))
+                else:
+                    cluster.append(fact)
+                    break
+        else:
+            clusters.append([fact])
+
+    merged: list[str] = []
+    removed: list[str] = []
+    for cluster in clusters:
+        # keep the most detailed fact
+        canonical = max(range(len(cluster)), key=lambda i: len(cluster[i]))
+        merged.append(cluster[canonical])
+        removed.extend(fact for i, fact in enumerate(cluster) if i != canonical)
+
+    if removed:
+        _memories = merged
+        _update_storage()
+
+    state.on_event(
+        "memories_consolidated",
+        {
+            "removed": removed,
+            "memories": len(_memories),
+            "contradictions": [list(pair) for pair in contradictions],
+        },
+    )
+
+    result = f"{len(removed)} duplicated memories merged, {len(_memories)} memories left"
+    if contradictions:
+        result += "\n\nthe following memories contradict each other:\n\n" + "\n".join(
+            f"- {a} <-> {b}" for a, b in contradictions
+        )
+
+    return result
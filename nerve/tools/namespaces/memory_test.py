@@ -0,0 +1,81 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import memory
+
+
+@patch("nerve.tools.namespaces.memory.state")
+class TestMemory(unittest.TestCase):
+    def setUp(self) -> None:
+        memory._memories.clear()
+
+    def test_save_and_forget(self, mock_state: MagicMock) -> None:
+        memory.save_memory("the server runs nginx")
+        memory.save_memory("the admin password is in /etc/secret")
+
+        mock_state.write_knowledge.assert_called_with(
+            "memories", "1. the server runs nginx\n2. the admin password is in /etc/secret"
+        )
+
+        memory.forget_memory(1)
+        mock_state.write_knowledge.assert_called_with("memories", "1. the admin password is in /etc/secret")
+
+        memory.forget_memory(1)
+        mock_state.clear_knowledge.assert_called_with("memories")
+
+        with self.assertRaisesRegex(ValueError, "memory 1 does not exist"):
+            memory.forget_memory(1)
+
+    def test_consolidate_merges_duplicates(self, mock_state: MagicMock) -> None:
+        for fact in (
+            "The server runs nginx.",
+            "the server runs nginx",
+            "port 22 is open",
+            "The server runs nginx 1.18",
+        ):
+            memory.save_memory(fact)
+
+        result = memory.consolidate_memories()
+
+        self.assertEqual(memory._memories, ["The server runs nginx 1.18", "port 22 is open"])
+        self.assertIn("2 duplicated memories merged", result)
+        name, data = mock_state.on_event.call_args[0]
+        self.assertEqual(name, "memories_consolidated")
+        self.assertEqual(data["removed"], ["The server runs nginx.", "the server runs nginx"])
+
+    def test_consolidate_is_idempotent(self, mock_state: MagicMock) -> None:
+        for fact in ("port 22 is open", "Port 22 is open!", "the user is root"):
+            memory.save_memory(fact)
+
+        memory.consolidate_memories()
+        consolidated = list(memory._memories)
+        mock_state.write_knowledge.reset_mock()
+
+        result = memory.consolidate_memories()
+
+        self.assertEqual(memory._memories, consolidated)
+        self.assertIn("0 duplicated memories merged", result)
+        mock_state.write_knowledge.assert_not_called()
+
+    def test_consolidate_flags_contradictions(self, mock_state: MagicMock) -> None:
+        memory.save_memory("port 22 is open")
+        memory.save_memory("port 22 is not open")
+
+        result = memory.consolidate_memories()
+
+        # both are kept for the agent to decide
+        self.assertEqual(memory._memories, ["port 22 is open", "port 22 is not open"])
+        self.assertIn("port 22 is open <-> port 22 is not open", result)
+
+    def test_threshold(self, mock_state: MagicMock) -> None:
+        memory.save_memory("the server runs nginx")
+        memory.save_memory("the server runs apache")
+
+        memory.consolidate_memories(threshold=1.0)
+        self.assertEqual(len(memory._memories), 2)
+
+        memory.consolidate_memories(threshold=0.5)
+        self.assertEqual(memory._memories, ["the server runs apache"])
+
+        with self.assertRaisesRegex(ValueError, "threshold"):
+            memory.consolidate_memories(threshold=0)
@@ -0,0 +1,166 @@
+"""
+Let the agent create a step by step plan, keep track of its progress and revise it when reality diverges from it.
+"""
+
+import typing as t
+from typing import Annotated
+
+from pydantic import BaseModel, Field
+
+import nerve.runtime.state as state
+
+# for docs
+EMOJI = "🗺️"
+
+# how many revisions to show in the system prompt
+max_visible_revisions: int = 5
+
+
+class Step(BaseModel):
+    description: str
+    done: bool = False
+
+
+class StepEdit(t.TypedDict):
+    step: Annotated[int, Field(description="The number of the step as shown in the current plan.")]
+    action: Annotated[
+        str,
+        Field(
+            description="One of: replace (replace the step), insert (add a new step before it, use the number after the last step to append), remove (delete the step).",
+            examples=["replace", "insert", "remove"],
+        ),
+    ]
+    description: Annotated[str, Field(description="The new step description, ignored when removing a step.")]
+
+
+_plan: list[Step] = []
+_revisions: list[str] = []
+
+
+def _render_plan() -> str:
+    return "\n".join(f"{i}. [{'x' if s.done else ' '}] {s.description}" for i, s in enumerate(_plan, 1))
+
+
+def _update_storage() -> None:
+    state.write_knowledge("plan", _render_plan())
+    if _revisions:
+        state.write_knowledge("plan_revisions", "\n".join(_revisions[-max_visible_revisions:]))
+    else:
+        state.clear_knowledge("plan_revisions")
+
+
+def _get_step(step: int) -> Step:
+    if not _plan:
+        raise ValueError("there is no plan yet, create one first")
+    if step < 1 or step > len(_plan):
+        raise ValueError(f"step {step} does not exist, the plan has steps from 1 to {len(_plan)}")
+
+    return _plan[step - 1]
+
+
+def create_plan(
+    steps: Annotated[list[str], "The ordered list of steps to reach the objective"],
+) -> str:
+    """Create a new plan to reach the objective, replacing the current one if any."""
+
+    global _plan
+
+    if not steps:
+        raise ValueError("the plan must have at least one step")
+
+    _plan = [Step(description=s) for s in steps]
+    _revisions.clear()
+    _update_storage()
+
+    return f"plan created with {len(_plan)} steps"
+
+
+def complete_step(
+    step: Annotated[int, "The number of the completed step as shown in the current plan"],
+) -> str:
+    """Mark a step of the plan as completed."""
+
+    _get_step(step).done = True
+    state.on_event("plan_revised", {"revision": len(_revisions), "changes": [{"step": step, "action": "complete"}]})
+    _update_storage()
+
+    return f"step {step} completed"
+
+
+def revise_plan(
+    edits: Annotated[list[StepEdit], "The edits to apply, all step numbers refer to the plan before the revision"],
+    reason: Annotated[str, "Why the plan needs to be revised"],
+) -> str:
+    """Revise specific steps of the current plan when it no longer matches reality, instead of rebuilding it from scratch."""
+
+    global _plan
+
+    if not _plan:
+        raise ValueError("there is no plan yet, create one first")
+    if not edits:
+        raise ValueError("no edits provided")
+
+    # validate all the edits before applying any of them
+    inserts: dict[int, list[str]] = {}
+    changes: dict[int, dict[str, t.Any]] = {}
+    for edit in edits:
+        step = int(edit.get("step", 0))
+        action = str(edit.get("action", "")).strip().lower()
+        description = str(edit.get("description", "")).strip()
+
+        if action == "insert":
+            if step < 1 or step > len(_plan) + 1:
+                raise ValueError(f"can not insert before step {step}, valid positions are from 1 to {len(_plan) + 1}")
+        elif action in ("replace", "remove"):
+            _get_step(step)
+            if step in changes:
+                raise ValueError(f"step {step} is edited more than once")
+        else:
+            raise ValueError(f"unknown action '{action}' for step {step}, use one of: replace, insert, remove")
+
+        if action != "remove" and not description:
+            raise ValueError(f"a description is required to {action} step {step}")
+
+        if action == "insert":
+            inserts.setdefault(step, []).append(description)
+        else:
+            changes[step] = {"action": action, "description": description}
+
+    revised: list[Step] = []
+    summary: list[str] = []
+    for i in range(1, len(_plan) + 2):
+        for description in inserts.get(i, []):
+            revised.append(Step(description=description))
+            summary.append(f"inserted before step {i}: {description}")
+
+        if i > len(_plan):
+            break
+
+        current = _plan[i - 1]
+        change = changes.get(i)
+        if change is None:
+            revised.append(current)
+        elif change["action"] == "replace":
+            revised.append(Step(description=change["description"]))
+            summary.append(f"step {i} replaced: {current.description} -> {change['description']}")
+        else:
+            summary.append(f"step {i} removed: {current.description}")
+
+    if not revised:
+        raise ValueError("the revised plan must have at least one step")
+
+    _plan = revised
+    _revisions.append(f"revision {len(_revisions) + 1} ({reason}): " + "; ".join(summary))
+
+    state.on_event(
+        "plan_revised",
+        {
+            "revision": len(_revisions),
+            "reason": reason,
+            "changes": [{"step": s, **c} for s, c in changes.items()]
+            + [{"step": s, "action": "insert", "description": d} for s, ds in inserts.items() for d in ds],
+        },
+    )
+    _update_storage()
+
+    return f"plan revised:\n\n{_render_plan()}"
@@ -0,0 +1,87 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import planning
+
+
+@patch("nerve.tools.namespaces.planning.state")
+class TestPlanning(unittest.TestCase):
+    def setUp(self) -> None:
+        planning._plan.clear()
+        planning._revisions.clear()
+
+    def test_create_plan(self, mock_state: MagicMock) -> None:
+        planning.create_plan(["clone the repo", "run the tests"])
+
+        mock_state.write_knowledge.assert_called_with("plan", "1. [ ] clone the repo\n2. [ ] run the tests")
+
+    def test_complete_step(self, mock_state: MagicMock) -> None:
+        planning.create_plan(["clone the repo", "run the tests"])
+        planning.complete_step(1)
+
+        mock_state.write_knowledge.assert_called_with("plan", "1. [x] clone the repo\n2. [ ] run the tests")
+
+    def test_revise_plan(self, mock_state: MagicMock) -> None:
+        planning.create_plan(["clone the repo", "run make", "run the tests"])
+        planning.complete_step(1)
+
+        result = planning.revise_plan(
+            [
+                {"step": 2, "action": "replace", "description": "run cargo build"},
+                {"step": 3, "action": "insert", "description": "install the toolchain"},
+                {"step": 4, "action": "insert", "description": "open a PR"},
+            ],
+            "the project uses cargo",
+        )
+
+        self.assertEqual(
+            planning._render_plan(),
+            "1. [x] clone the repo\n2. [ ] run cargo build\n3. [ ] install the toolchain\n4. [ ] run the tests\n"
+            "5. [ ] open a PR",
+        )
+        self.assertIn("run cargo build", result)
+
+        # only the changed steps are reported
+        name, data = mock_state.on_event.call_args[0]
+        self.assertEqual(name, "plan_revised")
+        self.assertEqual([c["step"] for c in data["changes"]], [2, 3, 4])
+
+        # the revision history is kept in the knowledge
+        mock_state.write_knowledge.assert_any_call(
+            "plan_revisions",
+            "revision 1 (the project uses cargo): step 2 replaced: run make -> run cargo build; "
+            "inserted before step 3: install the toolchain; inserted before step 4: open a PR",
+        )
+
+    def test_remove_step(self, mock_state: MagicMock) -> None:
+        planning.create_plan(["a", "b", "c"])
+        planning.revise_plan([{"step": 2, "action": "remove", "description": ""}], "not needed")
+
+        self.assertEqual(planning._render_plan(), "1. [ ] a\n2. [ ] c")
+
+    def test_revise_rejects_invalid_edits(self, mock_state: MagicMock) -> None:
+        with self.assertRaisesRegex(ValueError, "no plan"):
+            planning.revise_plan([{"step": 1, "action": "remove", "description": ""}], "why")
+
+        planning.create_plan(["a", "b"])
+
+        with self.assertRaisesRegex(ValueError, "step 3 does not exist"):
+            planning.revise_plan([{"step": 3, "action": "replace", "description": "c"}], "why")
+
+        with self.assertRaisesRegex(ValueError, "can not insert before step 4"):
+            planning.revise_plan([{"step": 4, "action": "insert", "description": "c"}], "why")
+
+        with self.assertRaisesRegex(ValueError, "unknown action"):
+            planning.revise_plan([{"step": 1, "action": "rewrite", "description": "c"}], "why")
+
+        with self.assertRaisesRegex(ValueError, "more than once"):
+            planning.revise_plan(
+                [
+                    {"step": 1, "action": "replace", "description": "c"},
+                    {"step": 1, "action": "remove", "description": ""},
+                ],
+                "why",
+            )
+
+        # nothing was applied
+        self.assertEqual(planning._render_plan(), "1. [ ] a\n2. [ ] b")
@@ -0,0 +1,150 @@
+"""
+Let the agent spawn and manage long running background processes, such as servers or watchers.
+
+> [!WARNING]
+> Using this tool will bypass the filesystem jail mechanism. The processes that are still running are killed when the run completes.
+"""
+
+import atexit
+import collections
+import json
+import os
+import signal
+import subprocess
+import threading
+import uuid
+from typing import Annotated
+
+from loguru import logger
+
+from nerve.runtime import state
+from nerve.runtime.events import Event
+
+# for docs
+EMOJI = "⚙️"
+
+# how many of the most recent output lines are kept for each process
+max_output_lines: int = 1000
+
+
+class _Process:
+    def __init__(self, command: str):
+        self.command = command
+        self.lines: collections.deque[str] = collections.deque(maxlen=max_output_lines)
+        # stderr is merged into stdout to keep the lines in order
+        self.popen = subprocess.Popen(
+            command,
+            shell=True,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.STDOUT,
+            stdin=subprocess.DEVNULL,
+            # own process group, so children of the shell are killed too
+            start_new_session=True,
+        )
+        self.reader = threading.Thread(target=self._read, daemon=True)
+        self.reader.start()
+
+    def _read(self) -> None:
+        if self.popen.stdout is None:
+            return
+
+        for line in iter(self.popen.stdout.readline, b""):
+            self.lines.append(line.decode("utf-8", errors="replace").rstrip("\n"))
+        self.popen.stdout.close()
+
+    def kill(self) -> None:
+        if self.popen.poll() is not None:
+            return
+
+        try:
+            os.killpg(self.popen.pid, signal.SIGKILL)
+        except ProcessLookupError:
+            pass
+        self.popen.wait()
+
+
+_processes: dict[str, _Process] = {}
+
+
+def _get_process(process_id: str) -> _Process:
+    if process_id not in _processes:
+        raise ValueError(f"process {process_id} does not exist, running processes: {list(_processes.keys())}")
+    return _processes[process_id]
+
+
+def _cleanup() -> None:
+    for process_id, process in _processes.items():
+        if process.popen.poll() is None:
+            logger.debug(f"killing background process {process_id}: {process.command}")
+        process.kill()
+    _processes.clear()
+
+
+def _on_event(event: Event) -> None:
+    if event.name == "flow_complete":
+        _cleanup()
+
+
+state.add_event_listener(_on_event)
+atexit.register(_cleanup)
+
+
+def spawn_background(
+    command: Annotated[str, "The shell command to execute in the background"],
+) -> str:
+    """Start a long running shell command in the background and return its process id immediately, use it to read its output or kill it."""
+
+    process_id = uuid.uuid4().hex[:8]
+    _processes[process_id] = _Process(command)
+    return f"process {process_id} started (pid {_processes[process_id].popen.pid})"
+
+
+def read_process_output(
+    process_id: Annotated[str, "The id of the process as returned by spawn_background"],
+    tail: Annotated[int, "How many of the most recent output lines to return"] = 50,
+) -> str:
+    """Return the status and the most recent output lines of a background process."""
+
+    process = _get_process(process_id)
+    returncode = process.popen.poll()
+    if returncode is None:
+        status = "running"
+    else:
+        # make sure the output is complete
+        process.reader.join()
+        status = f"exited with code {returncode}"
+
+    lines = list(process.lines)[-tail:] if tail > 0 else []
+    output = "\n".join(lines) if lines else "<no output>"
+    return f"process {process_id} ({process.command}) is {status}\n\n{output}"
+
+
+def list_processes() -> str:
+    """List the background processes started so far and their status."""
+
+    return json.dumps(
+        [
+            {
+                "id": process_id,
+                "command": process.command,
+                "pid": process.popen.pid,
+                "running": process.popen.poll() is None,
+                "exit_code": process.popen.returncode,
+            }
+            for process_id, process in _processes.items()
+        ],
+        indent=2,
+    )
+
+
+def kill_process(
+    process_id: Annotated[str, "The id of the process as returned by spawn_background"],
+) -> str:
+    """Kill a background process and its children."""
+
+    process = _get_process(process_id)
+    if process.popen.poll() is not None:
+        return f"process {process_id} already exited with code {process.popen.returncode}"
+
+    process.kill()
+    return f"process {process_id} killed"
@@ -0,0 +1,66 @@
+import json
+import time
+import unittest
+
+from nerve.runtime.events import Event
+from nerve.tools.namespaces import processes
+
+
+def _spawn(command: str) -> str:
+    return processes.spawn_background(command).split()[1]
+
+
+def _wait_exit(process_id: str) -> None:
+    for _ in range(100):
+        if processes._processes[process_id].popen.poll() is not None:
+            return
+        time.sleep(0.05)
+
+
+class TestProcesses(unittest.TestCase):
+    def tearDown(self) -> None:
+        processes._cleanup()
+        processes.max_output_lines = 1000
+
+    def test_spawn_and_read_output(self) -> None:
+        process_id = _spawn("echo hello; echo world >&2")
+        _wait_exit(process_id)
+
+        output = processes.read_process_output(process_id)
+
+        self.assertIn("is exited with code 0", output)
+        self.assertTrue(output.endswith("hello\nworld"))
+
+    def test_output_is_a_ring_buffer(self) -> None:
+        processes.max_output_lines = 3
+        process_id = _spawn("for i in 1 2 3 4 5; do echo $i; done")
+        _wait_exit(process_id)
+
+        self.assertTrue(processes.read_process_output(process_id).endswith("3\n4\n5"))
+        self.assertTrue(processes.read_process_output(process_id, tail=1).endswith("\n\n5"))
+
+    def test_kill_process(self) -> None:
+        process_id = _spawn("sleep 30")
+
+        self.assertIn("is running", processes.read_process_output(process_id))
+        self.assertEqual(processes.kill_process(process_id), f"process {process_id} killed")
+        self.assertIn("already exited", processes.kill_process(process_id))
+
+        listed = json.loads(processes.list_processes())
+        self.assertEqual(listed[0]["id"], process_id)
+        self.assertFalse(listed[0]["running"])
+
+    def test_unknown_process(self) -> None:
+        with self.assertRaisesRegex(ValueError, "process nope does not exist"):
+            processes.read_process_output("nope")
+
+    def test_processes_are_killed_when_the_flow_completes(self) -> None:
+        process_id = _spawn("sleep 30")
+        popen = processes._processes[process_id].popen
+
+        processes._on_event(Event(name="step_complete", data={}))
+        self.assertIsNone(popen.poll())
+
+        processes._on_event(Event(name="flow_complete", data={}))
+        self.assertIsNotNone(popen.poll())
+        self.assertEqual(processes._processes, {})
@@ -0,0 +1,88 @@
+"""
+Let the agent report and reflect on its progress towards the objective.
+
+> [!TIP]
+> A `progress_stalled` event is emitted when the progress does not advance for `NERVE_PROGRESS_STALL_STEPS` steps (5 by default).
+"""
+
+import threading
+from typing import Annotated
+
+import nerve.runtime.state as state
+from nerve.defaults import DEFAULT_PROGRESS_STALL_STEPS
+from nerve.runtime.events import Event
+
+# for docs
+EMOJI = "📈"
+
+# how many steps without progress before emitting a progress_stalled event
+stall_steps: int = DEFAULT_PROGRESS_STALL_STEPS
+
+_current: int | None = None
+_previous: int | None = None
+_advanced: bool = False
+_steps_without_progress: int = 0
+# set once the step listener is registered
+_listening: bool = False
+# the listener runs in the events thread pool
+_lock = threading.Lock()
+
+
+def _on_event(event: Event) -> None:
+    global _advanced, _steps_without_progress
+
+    if event.name != "step_complete":
+        return
+
+    with _lock:
+        # start tracking only once the agent reported some progress
+        if _current is None:
+            return
+
+        if _advanced:
+            _advanced = False
+            _steps_without_progress = 0
+            return
+
+        _steps_without_progress += 1
+        if stall_steps <= 0 or _steps_without_progress % stall_steps != 0:
+            return
+
+        data = {"current": _current, "steps": _steps_without_progress}
+
+    state.on_event("progress_stalled", data)
+
+
+def set_progress(
+    value: Annotated[int, "The progress towards the objective, from 0 to 100"],
+    note: Annotated[str, "Optional short note about what was achieved or what is blocking"] = "",
+) -> str:
+    """Report the current progress towards the objective. Be honest, reporting the same value means you are not advancing."""
+
+    global _current, _previous, _advanced, _steps_without_progress, _listening
+
+    if value < 0 or value > 100:
+        raise ValueError(f"progress must be between 0 and 100, got {value}")
+
+    with _lock:
+        # the steps are only tracked once the namespace is used
+        if not _listening:
+            state.add_event_listener(_on_event)
+            _listening = True
+
+        _previous, _current = _current, value
+        previous, current = _previous, _current
+        if previous is None or current > previous:
+            _advanced = True
+            _steps_without_progress = 0
+
+    knowledge = f"current: {current}%"
+    if note:
+        knowledge += f" ({note})"
+    if previous is not None:
+        knowledge += f"\nprevious: {previous}%"
+    state.write_knowledge("progress", knowledge)
+
+    state.on_event("progress_update", {"current": current, "previous": previous, "note": note})
+
+    return f"progress set to {current}%"
@@ -0,0 +1,73 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.runtime.events import Event
+from nerve.tools.namespaces import progress
+
+
+def _step_complete() -> None:
+    progress._on_event(Event(name="step_complete", data={}))
+
+
+@patch("nerve.tools.namespaces.progress.state")
+class TestProgress(unittest.TestCase):
+    def setUp(self) -> None:
+        progress._current = None
+        progress._previous = None
+        progress._advanced = False
+        progress._steps_without_progress = 0
+        progress._listening = False
+        progress.stall_steps = 3
+
+    def test_listener_is_registered_on_first_use(self, mock_state: MagicMock) -> None:
+        mock_state.add_event_listener.assert_not_called()
+
+        progress.set_progress(10)
+        progress.set_progress(20)
+
+        mock_state.add_event_listener.assert_called_once_with(progress._on_event)
+
+    def test_set_progress_keeps_previous_value(self, mock_state: MagicMock) -> None:
+        progress.set_progress(10)
+        progress.set_progress(40, "tests are passing")
+
+        mock_state.write_knowledge.assert_called_with("progress", "current: 40% (tests are passing)\nprevious: 10%")
+        mock_state.on_event.assert_called_with(
+            "progress_update", {"current": 40, "previous": 10, "note": "tests are passing"}
+        )
+
+    def test_invalid_values_are_rejected(self, mock_state: MagicMock) -> None:
+        for value in (-1, 101):
+            with self.assertRaises(ValueError):
+                progress.set_progress(value)
+
+        mock_state.write_knowledge.assert_not_called()
+
+    def test_stall_event(self, mock_state: MagicMock) -> None:
+        # no tracking before any progress is reported
+        for _ in range(5):
+            _step_complete()
+        mock_state.on_event.assert_not_called()
+
+        progress.set_progress(20)
+        _step_complete()
+        _step_complete()
+        _step_complete()
+        mock_state.on_event.assert_called_with("progress_update", {"current": 20, "previous": None, "note": ""})
+
+        # reporting the same value is not progress
+        progress.set_progress(20)
+        _step_complete()
+        mock_state.on_event.assert_called_with("progress_stalled", {"current": 20, "steps": 3})
+
+    def test_advancing_resets_stall(self, mock_state: MagicMock) -> None:
+        progress.set_progress(20)
+        _step_complete()
+        _step_complete()
+        _step_complete()
+        progress.set_progress(30)
+        _step_complete()
+        _step_complete()
+        _step_complete()
+
+        self.assertNotIn("progress_stalled", [c[0][0] for c in mock_state.on_event.call_args_list])
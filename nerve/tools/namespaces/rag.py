@@ -0,0 +1,258 @@
+"""
+Let the agent index local documents and retrieve the most relevant passages for a query.
+
+> [!TIP]
+> Documents are embedded with `openai/text-embedding-3-small` by default, use the `embedder` directive in the agent configuration or the `NERVE_EMBEDDER` environment variable to use a different embedding model (for instance `ollama/nomic-embed-text`). The index is stored in `~/.nerve/rag/index.json` unless `NERVE_RAG_INDEX` is set. Chunks are embedded with 4 concurrent requests unless `NERVE_RAG_CONCURRENCY` is set, the chunks that can't be embedded after some retries are skipped and their documents are indexed again at the next update.
+"""
+
+import hashlib
+import json
+import math
+import pathlib
+import typing as t
+from typing import Annotated
+
+from loguru import logger
+
+import nerve.runtime.state as state
+from nerve.defaults import DEFAULT_EMBEDDER, DEFAULT_RAG_CONCURRENCY, DEFAULT_RAG_INDEX_PATH
+from nerve.generation.embeddings import Embedder
+
+# for docs
+EMOJI = "📚"
+
+# embedding model, if None the default one is used
+embedder: str | None = None
+# optional identity of the end user, sent hashed to the embedding providers supporting it
+end_user: str | None = None
+# where the index is persisted
+index_path: pathlib.Path = DEFAULT_RAG_INDEX_PATH
+# size of each chunk in characters
+chunk_size: int = 1000
+# characters shared by consecutive chunks
+chunk_overlap: int = 200
+# number of embedding requests sent concurrently
+concurrency: int = DEFAULT_RAG_CONCURRENCY
+# how many times a failed embedding request is retried before skipping its chunks
+max_retries: int = 3
+
+_TEXT_EXTENSIONS = {".txt", ".md", ".rst", ".csv", ".json", ".yml", ".yaml", ".html", ".xml", ".log"}
+
+_embedder: tuple[tuple[str | None, str | None], Embedder] | None = None
+_index: list[dict[str, t.Any]] | None = None
+
+
+def _get_embedder() -> Embedder:
+    global _embedder
+
+    # re-create it if the embedding model changed
+    if _embedder is None or _embedder[0] != (embedder, end_user):
+        if not (embedder or DEFAULT_EMBEDDER):
+            raise ValueError(
+                "no embedding model is configured, set the embedder directive of the agent or NERVE_EMBEDDER"
+            )
+        _embedder = ((embedder, end_user), Embedder(embedder, end_user=end_user))
+
+    return _embedder[1]
+
+
+def _load_index() -> list[dict[str, t.Any]]:
+    global _index
+
+    if _index is None:
+        _index = json.loads(index_path.read_text()) if index_path.exists() else []
+
+    return _index
+
+
+def _save_index() -> None:
+    index_path.parent.mkdir(parents=True, exist_ok=True)
+    index_path.write_text(json.dumps(_load_index()))
+
+
+def _get_chunks(text: str) -> list[str]:
+    step = max(1, chunk_size - chunk_overlap)
+    return [text[i : i + chunk_size] for i in range(0, max(1, len(text) - chunk_overlap), step) if text[i:].strip()]
+
+
+def _get_documents(path: pathlib.Path) -> list[pathlib.Path]:
+    if path.is_file():
+        return [path]
+
+    return sorted(p for p in path.rglob("*") if p.is_file() and p.suffix.lower() in _TEXT_EXTENSIONS)
+
+
+def _similarity(a: list[float], b: list[float]) -> float:
+    norm = math.sqrt(sum(x * x for x in a)) * math.sqrt(sum(y * y for y in b))
+    return sum(x * y for x, y in zip(a, b, strict=False)) / norm if norm else 0.0
+
+
+def _matches(chunk: dict[str, t.Any], filters: dict[str, t.Any]) -> bool:
+    metadata = chunk.get("metadata") or {}
+    for key, expected in filters.items():
+        value = metadata.get(key)
+        # a list of values means any of them
+        if isinstance(expected, list):
+            if value not in [str(e) for e in expected]:
+                return False
+        elif value != str(expected):
+            return False
+
+    return True
+
+
+async def _update_index(
+    root: pathlib.Path, remove_deleted: bool, metadata: dict[str, str] | None = None
+) -> dict[str, int]:
+    index = _load_index()
+    # hash of the content of every indexed document
+    hashes = {chunk["source"]: chunk.get("hash") for chunk in index}
+    # documents keep their metadata when updated, unless new metadata is given
+    metadatas = {chunk["source"]: chunk.get("metadata") or {} for chunk in index}
+    if metadata is not None:
+        metadata = {key: str(value) for key, value in metadata.items()}
+    counts = {"documents": 0, "chunks": 0, "added": 0, "updated": 0, "unchanged": 0, "removed": 0, "failed": 0}
+    chunks: list[dict[str, t.Any]] = []
+    seen: set[str] = set()
+
+    for document in _get_documents(root) if root.exists() else []:
+        source = str(document.absolute())
+        try:
+            text = document.read_text(errors="ignore")
+        except Exception as e:
+            logger.warning(f"can't read {source}: {e}")
+            continue
+
+        counts["documents"] += 1
+        seen.add(source)
+
+        # only new and changed documents are embedded
+        content_hash = hashlib.sha256(text.encode()).hexdigest()
+        if source not in hashes:
+            counts["added"] += 1
+        elif hashes[source] != content_hash:
+            counts["updated"] += 1
+        else:
+            counts["unchanged"] += 1
+            if metadata is not None:
+                for chunk in index:
+                    if chunk["source"] == source:
+                        chunk["metadata"] = metadata
+            continue
+
+        chunks.extend(
+            {
+                "source": source,
+                "hash": content_hash,
+                "metadata": metadata if metadata is not None else metadatas.get(source, {}),
+                "text": chunk,
+            }
+            for chunk in _get_chunks(text)
+        )
+
+    # embed all the chunks with as few requests as possible
+    if chunks:
+
+        def on_progress(processed: int, total: int, failed: int) -> None:
+            state.on_event("rag_indexing_progress", {"processed": processed, "total": total, "failed": failed})
+
+        embeddings = await _get_embedder().embed_concurrently(
+            [chunk["text"] for chunk in chunks], concurrency, max_retries, on_progress
+        )
+        for chunk, embedding in zip(chunks, embeddings, strict=True):
+            chunk["embedding"] = embedding
+
+    # replace any previous version of the documents
+    replaced = {chunk["source"] for chunk in chunks}
+
+    # the chunks that could not be embedded are skipped, and their documents indexed again at the next update
+    incomplete = {chunk["source"] for chunk in chunks if chunk["embedding"] is None}
+    counts["failed"] = sum(1 for chunk in chunks if chunk["embedding"] is None)
+    chunks = [chunk for chunk in chunks if chunk["embedding"] is not None]
+    for chunk in chunks:
+        if chunk["source"] in incomplete:
+            chunk["hash"] = None
+
+    if remove_deleted:
+        # documents indexed from this path that no longer exist
+        deleted = {
+            source for source in hashes if source not in seen and pathlib.Path(source).is_relative_to(root.absolute())
+        }
+        counts["removed"] = len(deleted)
+        replaced |= deleted
+
+    index[:] = [chunk for chunk in index if chunk["source"] not in replaced] + chunks
+    counts["chunks"] = len(chunks)
+    _save_index()
+
+    return counts
+
+
+async def index_documents(
+    path: Annotated[str, "The file or folder to index, folders are indexed recursively."],
+    metadata: Annotated[
+        dict[str, str] | None,
+        "Optional key value pairs to assign to the documents, they can be used to filter searches.",
+    ] = None,
+) -> str:
+    """Index text documents so that they can be searched with search_documents, unchanged documents are skipped."""
+
+    root = pathlib.Path(path).expanduser()
+    if not root.exists():
+        return f"ERROR: {path} does not exist"
+
+    counts = await _update_index(root, remove_deleted=False, metadata=metadata)
+
+    result = f"indexed {counts['chunks']} chunks from {counts['documents']} documents"
+    if counts["unchanged"]:
+        result += f", {counts['unchanged']} unchanged"
+    if counts["failed"]:
+        result += f", {counts['failed']} chunks could not be embedded and have been skipped"
+
+    return result
+
+
+async def reindex_documents(
+    path: Annotated[str, "The file or folder to re-index, folders are re-indexed recursively."],
+) -> str:
+    """
+    Update the index of a previously indexed file or folder: new and changed documents are indexed again,
+    documents that no longer exist are removed and unchanged ones are skipped.
+    """
+
+    # if the path does not exist anymore, everything indexed from it is removed
+    counts = await _update_index(pathlib.Path(path).expanduser(), remove_deleted=True)
+
+    result = (
+        f"{counts['added']} documents added, {counts['updated']} updated, "
+        f"{counts['unchanged']} unchanged, {counts['removed']} removed"
+    )
+    if counts["failed"]:
+        result += f", {counts['failed']} chunks could not be embedded and have been skipped"
+
+    return result
+
+
+async def search_documents(
+    query: Annotated[str, "What to search for."],
+    top_k: Annotated[int, "Maximum number of passages to return."] = 5,
+    filters: Annotated[
+        dict[str, str | list[str]] | None,
+        "Optional metadata the documents must have, a list of values matches any of them.",
+    ] = None,
+) -> str:
+    """Search the indexed documents and return the passages most relevant to the query."""
+
+    index = _load_index()
+    if not index:
+        return "no documents indexed"
+
+    if filters:
+        index = [chunk for chunk in index if _matches(chunk, filters)]
+        if not index:
+            return "no documents match the filters"
+
+    query_embedding = await _get_embedder().embed(query)
+    ranked = sorted(index, key=lambda chunk: _similarity(query_embedding, chunk["embedding"]), reverse=True)
+
+    return "\n\n".join(f"[{chunk['source']}]\n{chunk['text']}" for chunk in ranked[:top_k])
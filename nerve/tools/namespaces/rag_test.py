@@ -0,0 +1,173 @@
+import pathlib
+import tempfile
+import unittest
+from unittest.mock import patch
+
+from nerve.generation.embeddings import Embedder
+from nerve.tools.namespaces import rag
+
+
+class FakeEmbedder(Embedder):
+    def __init__(self) -> None:
+        super().__init__()
+        self.batches: list[list[str]] = []
+
+    # two dimensional embeddings: how much the text is about cats and about dogs
+    async def embed(self, text: str) -> list[float]:
+        return [float(text.count("cat")), float(text.count("dog"))]
+
+    async def embed_batch(self, texts: list[str]) -> list[list[float]]:
+        self.batches.append(texts)
+        if any("poison" in text for text in texts):
+            raise ValueError("can't embed this")
+        return [await self.embed(text) for text in texts]
+
+
+class TestRag(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        self.temp_dir = tempfile.TemporaryDirectory()
+        self.root = pathlib.Path(self.temp_dir.name)
+        (self.root / "docs").mkdir()
+        (self.root / "docs" / "cats.md").write_text("the cat sat on the mat, cats are great")
+        (self.root / "docs" / "dogs.txt").write_text("a dog barked at another dog")
+        (self.root / "docs" / "image.png").write_bytes(b"\x89PNG")
+
+        rag.index_path = self.root / "index.json"
+        rag._index = None
+        rag.embedder = None
+        self.embedder = FakeEmbedder()
+        rag._embedder = ((None, None), self.embedder)  # type: ignore
+
+    def tearDown(self) -> None:
+        self.temp_dir.cleanup()
+
+    async def test_index_and_search(self) -> None:
+        result = await rag.index_documents(str(self.root / "docs"))
+        self.assertEqual(result, "indexed 2 chunks from 2 documents")
+        self.assertTrue(rag.index_path.exists())
+        # all chunks are embedded with a single batch
+        self.assertEqual(len(self.embedder.batches), 1)
+
+        result = await rag.search_documents("dog", top_k=1)
+        self.assertIn("dogs.txt", result)
+        self.assertNotIn("cats.md", result)
+
+    async def test_index_is_persisted(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+        rag._index = None
+
+        result = await rag.search_documents("cat", top_k=1)
+        self.assertIn("cats.md", result)
+
+    async def test_reindexing_replaces_the_document(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+        await rag.index_documents(str(self.root / "docs" / "cats.md"))
+
+        self.assertEqual(len(rag._load_index()), 2)
+
+    async def test_search_without_index(self) -> None:
+        self.assertEqual(await rag.search_documents("cat"), "no documents indexed")
+
+    async def test_index_missing_path(self) -> None:
+        self.assertIn("does not exist", await rag.index_documents(str(self.root / "nope")))
+
+    def test_chunks_overlap(self) -> None:
+        rag.chunk_size, rag.chunk_overlap = 10, 2
+        try:
+            self.assertEqual(rag._get_chunks("abcdefghijklmnop"), ["abcdefghij", "ijklmnop"])
+        finally:
+            rag.chunk_size, rag.chunk_overlap = 1000, 200
+
+    def test_embedder_follows_the_configuration(self) -> None:
+        rag.embedder = "ollama/nomic-embed-text"
+
+        self.assertEqual(rag._get_embedder().generator_id, "ollama/nomic-embed-text")
+
+    async def test_missing_embedder(self) -> None:
+        rag.embedder = None
+        rag._embedder = None
+
+        with patch.object(rag, "DEFAULT_EMBEDDER", ""):
+            with self.assertRaisesRegex(ValueError, "no embedding model is configured"):
+                await rag.index_documents(str(self.root / "docs"))
+
+    async def test_unchanged_documents_are_not_embedded_again(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+
+        result = await rag.index_documents(str(self.root / "docs"))
+
+        self.assertEqual(result, "indexed 0 chunks from 2 documents, 2 unchanged")
+        self.assertEqual(len(self.embedder.batches), 1)
+
+    async def test_reindex(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+        (self.root / "docs" / "cats.md").write_text("cats and more cats")
+        (self.root / "docs" / "dogs.txt").unlink()
+        (self.root / "docs" / "birds.txt").write_text("a bird")
+
+        result = await rag.reindex_documents(str(self.root / "docs"))
+
+        self.assertEqual(result, "1 documents added, 1 updated, 0 unchanged, 1 removed")
+        # only the new and changed documents are embedded
+        self.assertEqual(sorted(self.embedder.batches[-1]), ["a bird", "cats and more cats"])
+        sources = sorted(pathlib.Path(chunk["source"]).name for chunk in rag._load_index())
+        self.assertEqual(sources, ["birds.txt", "cats.md"])
+
+    async def test_reindex_deleted_folder(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+        for document in (self.root / "docs").iterdir():
+            document.unlink()
+        (self.root / "docs").rmdir()
+
+        result = await rag.reindex_documents(str(self.root / "docs"))
+
+        self.assertEqual(result, "0 documents added, 0 updated, 0 unchanged, 2 removed")
+        self.assertEqual(rag._load_index(), [])
+
+    async def test_search_with_filters(self) -> None:
+        (self.root / "handbook").mkdir()
+        (self.root / "handbook" / "pets.md").write_text("employees can bring a dog to the office")
+        await rag.index_documents(str(self.root / "docs"), metadata={"source": "wiki"})
+        await rag.index_documents(str(self.root / "handbook"), metadata={"source": "handbook"})
+
+        result = await rag.search_documents("dog", filters={"source": "handbook"})
+        self.assertIn("pets.md", result)
+        self.assertNotIn("dogs.txt", result)
+
+        result = await rag.search_documents("dog", filters={"source": ["handbook", "wiki"]})
+        self.assertIn("pets.md", result)
+        self.assertIn("dogs.txt", result)
+
+        result = await rag.search_documents("dog", filters={"source": "blog"})
+        self.assertEqual(result, "no documents match the filters")
+
+    async def test_metadata_is_kept_when_reindexing(self) -> None:
+        await rag.index_documents(str(self.root / "docs"), metadata={"source": "wiki"})
+        (self.root / "docs" / "cats.md").write_text("cats and more cats")
+
+        await rag.reindex_documents(str(self.root / "docs"))
+
+        self.assertTrue(all(chunk["metadata"] == {"source": "wiki"} for chunk in rag._load_index()))
+
+    async def test_metadata_of_unchanged_documents_is_updated(self) -> None:
+        await rag.index_documents(str(self.root / "docs"))
+        await rag.index_documents(str(self.root / "docs"), metadata={"source": "wiki"})
+
+        # without embedding the documents again
+        self.assertEqual(len(self.embedder.batches), 1)
+        self.assertIn("cats.md", await rag.search_documents("cat", filters={"source": "wiki"}))
+
+    @patch.object(rag, "max_retries", 0)
+    async def test_chunks_failing_are_skipped(self) -> None:
+        (self.root / "docs" / "bad.txt").write_text("poison")
+
+        result = await rag.index_documents(str(self.root / "docs"))
+
+        self.assertIn("indexed 2 chunks from 3 documents, 1 chunks could not be embedded", result)
+        self.assertNotIn(str((self.root / "docs" / "bad.txt").absolute()), [c["source"] for c in rag._load_index()])
+
+        # the failed document is indexed again once it can be embedded
+        (self.root / "docs" / "bad.txt").write_text("a cat")
+        result = await rag.reindex_documents(str(self.root / "docs"))
+
+        self.assertTrue(result.startswith("1 documents added, 0 updated, 2 unchanged"))
@@ -0,0 +1,94 @@
+"""
+Let the agent see the screen of the host, independently of any browser.
+
+> [!TIP]
+> Multiple monitors can be listed and captured by index if the `mss` package is installed, otherwise the whole screen is captured with `pillow`. Captured images are downscaled like any other image before being sent to the model.
+"""
+
+import base64
+import io
+import json
+import typing as t
+from typing import Annotated
+
+try:
+    import mss  # type: ignore
+    import mss.tools  # type: ignore
+except ImportError:
+    mss = None
+
+# this is an extra feature, so we need to indicate it
+OPTIONAL_FEATURE = "computer_use"
+# for docs
+EMOJI = "🖥️"
+
+
+def _get_monitors() -> list[dict[str, int]]:
+    # geometry of every monitor, or of the whole screen if they can't be enumerated
+    if mss is not None:
+        with mss.mss() as sct:
+            # the first one is the virtual screen spanning all monitors
+            return [
+                {"left": m["left"], "top": m["top"], "width": m["width"], "height": m["height"]}
+                for m in sct.monitors[1:]
+            ]
+
+    from PIL import ImageGrab
+
+    width, height = ImageGrab.grab(all_screens=True).size
+    return [{"left": 0, "top": 0, "width": width, "height": height}]
+
+
+def _grab(region: dict[str, int]) -> bytes:
+    # capture the region, in absolute coordinates, as a PNG
+    if mss is not None:
+        with mss.mss() as sct:
+            shot = sct.grab(region)
+            return bytes(mss.tools.to_png(shot.rgb, shot.size))
+
+    from PIL import ImageGrab
+
+    left, top = region["left"], region["top"]
+    image = ImageGrab.grab(
+        bbox=(left, top, left + region["width"], top + region["height"]),
+        all_screens=True,
+    )
+    buffer = io.BytesIO()
+    image.save(buffer, format="PNG", optimize=True)
+    return buffer.getvalue()
+
+
+def list_monitors() -> str:
+    """List the monitors of the host with their index, position and size."""
+
+    return json.dumps([{"index": i, **monitor} for i, monitor in enumerate(_get_monitors())], indent=2)
+
+
+def capture(
+    x: Annotated[int, "Optional horizontal offset in pixels of the region to capture, relative to the monitor"] = 0,
+    y: Annotated[int, "Optional vertical offset in pixels of the region to capture, relative to the monitor"] = 0,
+    width: Annotated[int, "Optional width in pixels of the region to capture, 0 for the rest of the monitor"] = 0,
+    height: Annotated[int, "Optional height in pixels of the region to capture, 0 for the rest of the monitor"] = 0,
+    monitor: Annotated[int, "Optional index of the monitor to capture, as returned by list_monitors"] = 0,
+) -> dict[str, t.Any]:
+    """Capture the screen of the host, or a region of it, and return it as an image."""
+
+    monitors = _get_monitors()
+    if not 0 <= monitor < len(monitors):
+        raise ValueError(f"monitor {monitor} does not exist, there are {len(monitors)} monitors")
+
+    bounds = monitors[monitor]
+    if not (0 <= x < bounds["width"] and 0 <= y < bounds["height"]) or width < 0 or height < 0:
+        raise ValueError(f"the region must be within the {bounds['width']}x{bounds['height']} monitor {monitor}")
+
+    region = {
+        "left": bounds["left"] + x,
+        "top": bounds["top"] + y,
+        "width": min(width or bounds["width"], bounds["width"] - x),
+        "height": min(height or bounds["height"], bounds["height"] - y),
+    }
+
+    return {
+        "type": "image_url",
+        "image_url": {"url": f"data:image/png;base64,{base64.b64encode(_grab(region)).decode()}"},
+    }
@@ -0,0 +1,44 @@
+import base64
+import json
+import typing as t
+import unittest
+from unittest.mock import patch
+
+from nerve.tools.namespaces import screen
+
+_MONITORS = [
+    {"left": 0, "top": 0, "width": 1920, "height": 1080},
+    {"left": 1920, "top": 0, "width": 1280, "height": 1024},
+]
+
+
+@patch("nerve.tools.namespaces.screen._get_monitors", return_value=_MONITORS)
+class TestCapture(unittest.TestCase):
+    def test_list_monitors(self, _: t.Any) -> None:
+        monitors = json.loads(screen.list_monitors())
+
+        self.assertEqual([m["index"] for m in monitors], [0, 1])
+        self.assertEqual(monitors[1]["width"], 1280)
+
+    def test_capture_whole_monitor(self, _: t.Any) -> None:
+        with patch("nerve.tools.namespaces.screen._grab", return_value=b"png") as grab:
+            result = screen.capture()
+
+        grab.assert_called_once_with({"left": 0, "top": 0, "width": 1920, "height": 1080})
+        self.assertEqual(result["type"], "image_url")
+        self.assertEqual(result["image_url"]["url"], f"data:image/png;base64,{base64.b64encode(b'png').decode()}")
+
+    def test_capture_region_of_monitor(self, _: t.Any) -> None:
+        with patch("nerve.tools.namespaces.screen._grab", return_value=b"png") as grab:
+            screen.capture(x=100, y=50, width=2000, height=200, monitor=1)
+
+        # offset by the monitor position and clamped to its size
+        grab.assert_called_once_with({"left": 2020, "top": 50, "width": 1180, "height": 200})
+
+    def test_invalid_monitor(self, _: t.Any) -> None:
+        with self.assertRaisesRegex(ValueError, "monitor 2 does not exist"):
+            screen.capture(monitor=2)
+
+    def test_region_outside_of_monitor(self, _: t.Any) -> None:
+        with self.assertRaisesRegex(ValueError, "within the 1280x1024 monitor 1"):
+            screen.capture(x=1500, monitor=1)
@@ -5,32 +5,78 @@ Let the agent execute shell commands.
 > Using this tool will bypass the filesystem jail mechanism
 """
 
+import contextvars
+import os
+import signal
 import subprocess
+import threading
+import typing as t
 from typing import Annotated
 
 from nerve.defaults import DEFAULT_SHELL_TIMEOUT
+from nerve.runtime import state
 from nerve.tools.utils import maybe_text
 
 # for docs
 EMOJI = "💻"
 
+# how long to wait for the output to be read once the command is killed
+_READERS_TIMEOUT = 1.0
+
+
+def _read_stream(stream: t.IO[bytes] | None, lines: list[bytes], name: str) -> None:
+    if stream is None:
+        return
+
+    for line in iter(stream.readline, b""):
+        lines.append(line)
+        state.on_tool_output(line.decode("utf-8", errors="replace"), name)
+    stream.close()
+
 
 def shell(
     command: Annotated[str, "The shell command to execute"],
 ) -> str | bytes:
     """Execute a shell command on the local computer and return the output. Non interactive shell with a timeout of 30 seconds."""
 
-    result = subprocess.run(command, shell=True, capture_output=True, timeout=DEFAULT_SHELL_TIMEOUT)
+    # a new process group, so that the commands started by the shell are killed with it on timeout
+    process = subprocess.Popen(
+        command, shell=True, stdout=subprocess.PIPE, stderr=subprocess.PIPE, start_new_session=True
+    )
+    stdout: list[bytes] = []
+    stderr: list[bytes] = []
+    # the output is reported line by line while the command runs, the readers need the context for the call id
+    readers = [
+        threading.Thread(target=contextvars.copy_context().run, args=(_read_stream, pipe, lines, name), daemon=True)
+        for pipe, lines, name in ((process.stdout, stdout, "stdout"), (process.stderr, stderr, "stderr"))
+    ]
+    for reader in readers:
+        reader.start()
+
+    try:
+        returncode = process.wait(timeout=DEFAULT_SHELL_TIMEOUT)
+    except subprocess.TimeoutExpired:
+        try:
+            os.killpg(process.pid, signal.SIGKILL)
+        except ProcessLookupError:
+            pass
+        process.wait()
+        raise
+    finally:
+        # processes that left the group can still hold the pipes open
+        for reader in readers:
+            reader.join(timeout=_READERS_TIMEOUT)
 
-    raw_output = result.stdout or b""
+    raw_output = b"".join(stdout)
+    raw_error = b"".join(stderr)
 
-    if result.returncode != 0:
-        raw_output += b"\nEXIT CODE: " + str(result.returncode).encode("utf-8")
+    if returncode != 0:
+        raw_output += b"\nEXIT CODE: " + str(returncode).encode("utf-8")
 
-    if result.stderr:
-        if result.returncode != 0:
-            raw_output += b"\nERROR: " + result.stderr
+    if raw_error:
+        if returncode != 0:
+            raw_output += b"\nERROR: " + raw_error
         else:
-            raw_output += b"\n" + result.stderr
+            raw_output += b"\n" + raw_error
 
     return maybe_text(raw_output)
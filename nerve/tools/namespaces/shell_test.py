@@ -1,6 +1,9 @@
+import subprocess
 import tempfile
+import time
 import unittest
 from pathlib import Path
+from unittest.mock import patch
 
 from nerve.tools.namespaces import shell
 
@@ -43,3 +46,25 @@ class TestShell(unittest.TestCase):
         # verify the result is bytes, not str
         self.assertIsInstance(result, bytes)
         self.assertTrue(result[:4] == b"\x7fELF" or result[:4] == b"\xca\xfe\xba\xbe")
+
+    def test_shell_streams_the_output(self) -> None:
+        with patch("nerve.tools.namespaces.shell.state.on_tool_output") as on_tool_output:
+            result = shell.shell("echo one; echo two; echo oops >&2")
+
+        self.assertEqual(result.strip(), "one\ntwo\n\noops")
+        streamed = [call.args for call in on_tool_output.call_args_list]
+        self.assertEqual([args for args in streamed if args[1] == "stdout"], [("one\n", "stdout"), ("two\n", "stdout")])
+        self.assertIn(("oops\n", "stderr"), streamed)
+
+    @patch("nerve.tools.namespaces.shell.DEFAULT_SHELL_TIMEOUT", 0.5)
+    def test_shell_timeout(self) -> None:
+        with self.assertRaises(subprocess.TimeoutExpired):
+            shell.shell("exec sleep 5")
+
+    @patch("nerve.tools.namespaces.shell.DEFAULT_SHELL_TIMEOUT", 0.5)
+    def test_shell_timeout_kills_child_processes(self) -> None:
+        started_at = time.monotonic()
+        with self.assertRaises(subprocess.TimeoutExpired):
+            shell.shell("sleep 5; echo x")
+
+        self.assertLess(time.monotonic() - started_at, 3)
@@ -0,0 +1,106 @@
+"""
+Let the agent inspect the system it is running on: environment variables, working directory, user and resources.
+
+> [!TIP]
+> Environment variables whose name looks like a secret (API keys, tokens, passwords and so on) are never disclosed, set `NERVE_SECRET_ENV_PATTERN` to customize the regular expression used to detect them.
+"""
+
+import getpass
+import os
+import platform
+import re
+import shutil
+import socket
+from typing import Annotated
+
+# for docs
+EMOJI = "🖥️"
+
+# environment variables matching this pattern are never disclosed
+secret_pattern: re.Pattern[str] = re.compile(
+    os.getenv(
+        "NERVE_SECRET_ENV_PATTERN",
+        r"(?i)(KEY|TOKEN|SECRET|PASSW(OR)?D|PASS|CREDENTIAL|AUTH|COOKIE|SESSION|PRIVATE)",
+    )
+)
+
+
+def _is_secret(name: str) -> bool:
+    return secret_pattern.search(name) is not None
+
+
+def _get_memory() -> tuple[int, int] | None:
+    # total and available memory in bytes
+    try:
+        with open("/proc/meminfo") as f:
+            info = {line.split(":")[0]: int(line.split()[1]) * 1024 for line in f if ":" in line}
+        return info["MemTotal"], info.get("MemAvailable", info.get("MemFree", 0))
+    except Exception:
+        pass
+
+    try:
+        page_size = os.sysconf("SC_PAGE_SIZE")
+        return os.sysconf("SC_PHYS_PAGES") * page_size, os.sysconf("SC_AVPHYS_PAGES") * page_size
+    except (ValueError, OSError, AttributeError):
+        return None
+
+
+def _gb(num_bytes: int) -> str:
+    return f"{num_bytes / (1024**3):.1f} GB"
+
+
+def get_env(
+    name: Annotated[str, "The name of the environment variable."],
+) -> str:
+    """Get the value of an environment variable."""
+
+    if _is_secret(name):
+        return f"ERROR: {name} might contain a secret and can't be disclosed"
+
+    value = os.environ.get(name)
+    if value is None:
+        return f"{name} is not set"
+
+    return value
+
+
+def list_env() -> str:
+    """List the environment variables, the values of the ones that might contain secrets are redacted."""
+
+    return "\n".join(
+        f"{name}={'<redacted>' if _is_secret(name) else value}" for name, value in sorted(os.environ.items())
+    )
+
+
+def get_working_directory() -> str:
+    """Get the current working directory."""
+
+    return os.getcwd()
+
+
+def whoami() -> str:
+    """Get the name of the current user and the host name."""
+
+    return f"{getpass.getuser()}@{socket.gethostname()}"
+
+
+def get_system_info() -> str:
+    """Get information about the operating system, CPU, memory and disk space."""
+
+    info = [
+        f"os: {platform.system()} {platform.release()} ({platform.version()})",
+        f"architecture: {platform.machine()}",
+        f"cpus: {os.cpu_count() or 'unknown'}",
+    ]
+
+    memory = _get_memory()
+    if memory is not None:
+        info.append(f"memory: {_gb(memory[1])} available of {_gb(memory[0])}")
+
+    disk = shutil.disk_usage(os.getcwd())
+    info.append(f"disk: {_gb(disk.free)} free of {_gb(disk.total)}")
+
+    if hasattr(os, "getloadavg"):
+        info.append("load average: " + ", ".join(f"{load:.2f}" for load in os.getloadavg()))
+
+    return "\n".join(info)
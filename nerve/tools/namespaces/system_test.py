@@ -0,0 +1,48 @@
+import os
+import re
+import unittest
+from unittest.mock import patch
+
+from nerve.tools.namespaces import system
+
+
+class TestGetEnv(unittest.TestCase):
+    @patch.dict(os.environ, {"NERVE_TEST_VAR": "hello"})
+    def test_returns_value(self) -> None:
+        self.assertEqual(system.get_env("NERVE_TEST_VAR"), "hello")
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_not_set(self) -> None:
+        self.assertEqual(system.get_env("NERVE_TEST_VAR"), "NERVE_TEST_VAR is not set")
+
+    @patch.dict(os.environ, {"OPENAI_API_KEY": "sk-123", "DB_PASSWORD": "hunter2", "github_token": "ghp"})
+    def test_refuses_secrets(self) -> None:
+        for name in ("OPENAI_API_KEY", "DB_PASSWORD", "github_token"):
+            result = system.get_env(name)
+            self.assertTrue(result.startswith("ERROR:"))
+            self.assertNotIn(os.environ[name], result)
+
+    @patch.dict(os.environ, {"MY_VAR": "value"})
+    def test_custom_pattern(self) -> None:
+        with patch.object(system, "secret_pattern", re.compile("^MY_")):
+            self.assertTrue(system.get_env("MY_VAR").startswith("ERROR:"))
+
+
+class TestListEnv(unittest.TestCase):
+    @patch.dict(os.environ, {"HOME": "/home/user", "AWS_SECRET_ACCESS_KEY": "abc"}, clear=True)
+    def test_redacts_secrets(self) -> None:
+        self.assertEqual(system.list_env(), "AWS_SECRET_ACCESS_KEY=<redacted>\nHOME=/home/user")
+
+
+class TestSystemInfo(unittest.TestCase):
+    def test_get_working_directory(self) -> None:
+        self.assertEqual(system.get_working_directory(), os.getcwd())
+
+    def test_whoami(self) -> None:
+        self.assertIn("@", system.whoami())
+
+    def test_get_system_info(self) -> None:
+        info = system.get_system_info()
+        self.assertIn("os: ", info)
+        self.assertIn("cpus: ", info)
+        self.assertIn("disk: ", info)
@@ -1,27 +1,220 @@
 """
-Let the agent autonomously set the task as complete or failed.
+Let the agent autonomously set the task as complete or failed, or delegate a subtask to a child agent.
 """
+import json
+import os
+import pathlib
+import tempfile
 import typing as t
 
+from pydantic_yaml import to_yaml_str
+
 import nerve.runtime.state as state
+from nerve.defaults import DEFAULT_MAX_STEPS, DEFAULT_MAX_SUBTASK_DEPTH
+from nerve.models import Configuration, Usage
+from nerve.tools.utils import completes_task
 
 # for docs
 EMOJI = "✅"
 
+# set by the parent process to the depth of the current subtask
+_DEPTH_ENV = "NERVE_SUBTASK_DEPTH"
+
+
+_SELF_CHECK_PROMPT = (
+    "You verify the work of an agent that claims to have completed its task. Reply with COMPLETE if the report "
+    "and the collected information show that the task has been fully solved, otherwise reply with a short "
+    "explanation of what is still missing or wrong."
+)
+
+
+def _get_unmet_conditions(check: Configuration.CompletionCheck) -> list[str]:
+    knowledge = state.get_knowledge()
+    unmet = []
+
+    if check.plan:
+        plan = knowledge.get("plan")
+        if not plan:
+            unmet.append("no plan has been created")
+        else:
+            pending = [line for line in str(plan).splitlines() if "[ ]" in line]
+            if pending:
+                unmet.append("the following steps of the plan are not completed:\n" + "\n".join(pending))
+
+    for key in check.knowledge:
+        if knowledge.get(key) in (None, "", [], {}):
+            unmet.append(f"the '{key}' knowledge entry has not been set")
+
+    return unmet
+
+
+async def _self_check(actor: t.Any, reason: str | None, result: dict[str, t.Any] | None) -> str | None:
+    engine = getattr(actor, "generation_engine", None)
+    if engine is None:
+        return None
 
-def task_complete_success(
+    task = state.interpolate(actor.configuration.task or "", working_dir=actor.runtime.working_dir)
+    prompt = f"<task>\n{task}\n</task>\n\n<report>\n{reason or ''}\n</report>"
+    if result is not None:
+        prompt += f"\n\n<result>\n{json.dumps(result, default=str)}\n</result>"
+    for key, value in state.get_knowledge().items():
+        prompt += f"\n\n<{key}>\n{value}\n</{key}>"
+
+    usage, verdict = await engine.complete(_SELF_CHECK_PROMPT, prompt)
+    # the request is not part of the step of the agent
+    state.update_usage(usage)
+
+    verdict = (verdict or "").strip()
+    return None if verdict.upper().startswith("COMPLETE") else verdict
+
+
+@completes_task
+async def task_complete_success(
     reason: t.Annotated[
         str | None, "Optional reason why the task is complete or report of conclusive information."
     ] = None,
-) -> None:
+    result: t.Annotated[
+        dict[str, t.Any] | None, "Optional structured result of the task as a JSON object, if requested."
+    ] = None,
+) -> str | None:
     """When your objective has been reached use this tool to set the task as complete."""
 
-    state.set_task_complete(reason)
+    actor = state.get_current_actor()
+    check = getattr(getattr(actor, "configuration", None), "completion_check", None)
+    if check is not None:
+        unmet = _get_unmet_conditions(check)
+        if not unmet and check.self_check:
+            feedback = await _self_check(actor, reason, result)
+            if feedback:
+                unmet.append(feedback)
+
+        if unmet:
+            state.on_event("task_completion_blocked", {"reason": reason, "unmet": unmet})
+            return "the task can not be set as complete yet:\n\n" + "\n\n".join(f"- {u}" for u in unmet)
 
+    state.set_task_complete(reason, result)
+    return None
 
+
+@completes_task
 def task_failed(
     reason: t.Annotated[str, "The reason why the task is impossible"],
 ) -> None:
     """Use this tool if you determine that the given goal or task is impossible given the information you have."""
 
     state.set_task_failed(reason)
+
+
+def _get_depth() -> int:
+    return int(os.getenv(_DEPTH_ENV, "0"))
+
+
+async def spawn_subtask(
+    task: t.Annotated[str, "A detailed description of the subtask, including all the context the child agent needs"],
+    using: t.Annotated[
+        list[str], "The tool namespaces the child agent can use, must be a subset of the ones available to you"
+    ],
+    agent: t.Annotated[str, "Optional system prompt for the child agent, defaults to your own"] = "",
+) -> str:
+    """Delegate a self contained subtask to a child agent and wait for its result."""
+
+    # imported here to avoid a circular import
+    from nerve.runtime import flow
+    from nerve.runtime.runner import Arguments, Runner
+
+    depth = _get_depth()
+    if depth >= DEFAULT_MAX_SUBTASK_DEPTH:
+        raise ValueError(f"maximum subtask depth of {DEFAULT_MAX_SUBTASK_DEPTH} reached, complete this task directly")
+
+    parent = state.get_current_actor()
+    parent_config: Configuration | None = getattr(parent, "configuration", None)
+    if parent_config is None:
+        raise ValueError("subtasks can only be spawned by an agent")
+
+    not_allowed = [ns for ns in using if ns not in parent_config.using]
+    if not_allowed:
+        raise ValueError(f"namespaces {not_allowed} are not available, allowed namespaces: {parent_config.using}")
+
+    # the child shares the budget of the parent, the current step is still in progress
+    active_flow = flow.ACTIVE_FLOW
+    max_steps = active_flow.max_steps if active_flow else DEFAULT_MAX_STEPS
+    if active_flow and active_flow.max_steps > 0:
+        max_steps = active_flow.get_steps_left() - 1
+        if max_steps <= 0:
+            raise ValueError("no steps left for a subtask")
+
+    max_cost = 0.0
+    if active_flow and active_flow.max_cost > 0:
+        max_cost = active_flow.max_cost - (state.get_usage().cost or 0.0)
+        if max_cost <= 0:
+            raise ValueError("no budget left for a subtask")
+
+    child_config = Configuration(
+        generator=parent_config.generator,
+        agent=agent or parent_config.agent,
+        task=task,
+        # the child always needs to be able to set its task as complete
+        using=list(dict.fromkeys([*using, "task"])),
+        jail={ns: paths for ns, paths in parent_config.jail.items() if ns in using},
+        settings={ns: settings for ns, settings in parent_config.settings.items() if ns in using},
+    )
+
+    with tempfile.TemporaryDirectory(prefix="nerve-subtask-") as temp_dir:
+        agent_path = pathlib.Path(temp_dir) / "agent.yml"
+        agent_path.write_text(to_yaml_str(child_config, exclude_defaults=True))
+
+        runner = Runner(
+            Arguments(
+                input_path=agent_path,
+                task=None,
+                generator=parent_config.generator or "",
+                conversation_strategy_string="full",
+                conversation_strategy=None,
+                interactive=False,
+                debug=False,
+                litellm_debug=False,
+                litellm_tracing=None,
+                quiet=True,
+                max_steps=max_steps,
+                max_cost=max_cost,
+                timeout=None,
+                log_path=None,
+                trace=None,
+                start_state={},
+            ),
+            # the task is passed via the agent file
+            input_state={k: str(v) for k, v in state.get_variables().items() if k != "task"},
+            base_path=pathlib.Path(temp_dir),
+            clean_at_exit=False,
+            env={_DEPTH_ENV: str(depth + 1)},
+        )
+
+        state.on_event("subtask_started", {"id": runner.id, "depth": depth + 1, "task": task, "using": using})
+        output = await runner.run()
+
+    if output.usage:
+        state.update_usage(Usage(**output.usage))
+
+    # the steps of the child count towards the ones of the parent
+    if active_flow:
+        active_flow.consumed_steps += output.steps
+
+    state.on_event(
+        "subtask_complete",
+        {
+            "id": runner.id,
+            "depth": depth + 1,
+            "success": output.task_success,
+            "output": output.output,
+            "steps": output.steps,
+            "usage": output.usage,
+        },
+    )
+
+    result = output.output.get("result") or output.output.get("reason")
+    if result is None:
+        result = output.output.get("output") or output.output.get("response")
+    if result is None:
+        result = output.output
+
+    return f"subtask {'completed' if output.task_success else 'did not complete'} in {output.steps} steps: {result}"
@@ -0,0 +1,171 @@
+import os
+import typing as t
+import unittest
+from unittest.mock import ANY, AsyncMock, MagicMock, patch
+
+from nerve.models import Configuration, Usage
+from nerve.runtime.flow import Flow
+from nerve.runtime.runner import Output
+from nerve.tools.namespaces import task
+
+
+def _output(success: bool = True) -> Output:
+    return Output(
+        generated_at=0,
+        command_line=[],
+        exit_code=0,
+        stdout=[],
+        stderr=[],
+        events=[],
+        output={"reason": "the answer is 42"},
+        task_success=success,
+        steps=3,
+        time=1.0,
+        usage={"cost": 0.5, "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
+    )
+
+
+@patch("nerve.tools.namespaces.task.state")
+class TestSpawnSubtask(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        # not affected by the flows of other tests
+        patcher = patch("nerve.runtime.flow.ACTIVE_FLOW", None)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _parent(self, mock_state: MagicMock) -> None:
+        mock_state.get_current_actor.return_value = MagicMock(
+            configuration=Configuration(
+                generator="openai/gpt-4o", agent="You are a helpful assistant.", using=["shell", "task"]
+            )
+        )
+        mock_state.get_variables.return_value = {"task": "parent task", "target": "example.com"}
+        mock_state.get_usage.return_value = Usage()
+
+    @patch("nerve.runtime.runner.Runner")
+    async def test_spawn_subtask(self, mock_runner: MagicMock, mock_state: MagicMock) -> None:
+        self._parent(mock_state)
+        mock_runner.return_value.id = "child"
+        mock_runner.return_value.run = AsyncMock(return_value=_output())
+
+        result = await task.spawn_subtask("find the answer", ["shell"])
+
+        self.assertEqual(result, "subtask completed in 3 steps: the answer is 42")
+
+        kwargs = mock_runner.call_args.kwargs
+        self.assertEqual(kwargs["env"], {"NERVE_SUBTASK_DEPTH": "1"})
+        self.assertEqual(kwargs["input_state"], {"target": "example.com"})
+
+        # the child usage is added to the parent one
+        mock_state.update_usage.assert_called_once()
+        self.assertEqual(mock_state.update_usage.call_args[0][0].cost, 0.5)
+
+        events = [c[0][0] for c in mock_state.on_event.call_args_list]
+        self.assertEqual(events, ["subtask_started", "subtask_complete"])
+
+    @patch("nerve.runtime.runner.Runner")
+    async def test_child_shares_the_steps_of_the_parent(self, mock_runner: MagicMock, mock_state: MagicMock) -> None:
+        self._parent(mock_state)
+        mock_runner.return_value.run = AsyncMock(return_value=_output())
+        active_flow = MagicMock(max_steps=10, curr_step=4, consumed_steps=0, max_cost=0.0)
+        active_flow.get_steps_left.side_effect = lambda: Flow.get_steps_left(active_flow)
+
+        with patch("nerve.runtime.flow.ACTIVE_FLOW", active_flow):
+            await task.spawn_subtask("find the answer", ["shell"])
+
+            # only the steps left to the parent
+            self.assertEqual(mock_runner.call_args.args[0].max_steps, 6)
+            # the 3 steps of the child count towards the limit of the parent
+            self.assertEqual(active_flow.consumed_steps, 3)
+            self.assertEqual(active_flow.max_steps, 10)
+
+            active_flow.curr_step = 7
+            with self.assertRaisesRegex(ValueError, "no steps left"):
+                await task.spawn_subtask("find the answer", ["shell"])
+
+    async def test_namespaces_must_be_a_subset(self, mock_state: MagicMock) -> None:
+        self._parent(mock_state)
+
+        with self.assertRaisesRegex(ValueError, "not available"):
+            await task.spawn_subtask("read my files", ["filesystem"])
+
+    async def test_max_depth(self, mock_state: MagicMock) -> None:
+        self._parent(mock_state)
+
+        with patch.dict(os.environ, {"NERVE_SUBTASK_DEPTH": str(task.DEFAULT_MAX_SUBTASK_DEPTH)}):
+            with self.assertRaisesRegex(ValueError, "maximum subtask depth"):
+                await task.spawn_subtask("recurse", ["shell"])
+
+
+@patch("nerve.tools.namespaces.task.state")
+class TestCompletionCheck(unittest.IsolatedAsyncioTestCase):
+    def _actor(self, mock_state: MagicMock, verdict: str = "COMPLETE", **check: t.Any) -> MagicMock:
+        actor = MagicMock(
+            configuration=Configuration(
+                agent="You are a helpful assistant.",
+                task="find the answer",
+                completion_check=Configuration.CompletionCheck(**check),
+            )
+        )
+        actor.generation_engine.complete = AsyncMock(return_value=(Usage(cost=0.1), verdict))
+        mock_state.get_current_actor.return_value = actor
+        mock_state.interpolate.side_effect = lambda raw, **_: raw
+        return actor
+
+    async def test_without_check(self, mock_state: MagicMock) -> None:
+        mock_state.get_current_actor.return_value = MagicMock(configuration=Configuration(agent="agent"))
+
+        self.assertIsNone(await task.task_complete_success("done"))
+        mock_state.set_task_complete.assert_called_once_with("done", None)
+
+    async def test_plan_not_completed(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state, plan=True)
+        mock_state.get_knowledge.return_value = {"plan": "1. [x] scan the ports\n2. [ ] exploit the service"}
+
+        result = await task.task_complete_success("done")
+
+        self.assertIn("2. [ ] exploit the service", result)
+        mock_state.set_task_complete.assert_not_called()
+        mock_state.on_event.assert_called_once_with("task_completion_blocked", ANY)
+
+    async def test_plan_completed(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state, plan=True)
+        mock_state.get_knowledge.return_value = {"plan": "1. [x] scan the ports\n2. [x] exploit the service"}
+
+        self.assertIsNone(await task.task_complete_success("done"))
+        mock_state.set_task_complete.assert_called_once()
+
+    async def test_missing_knowledge(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state, knowledge=["findings"])
+        mock_state.get_knowledge.return_value = {"findings": ""}
+
+        result = await task.task_complete_success("done")
+
+        self.assertIn("'findings' knowledge entry has not been set", result)
+        mock_state.set_task_complete.assert_not_called()
+
+    async def test_self_check_rejects(self, mock_state: MagicMock) -> None:
+        actor = self._actor(mock_state, verdict="the answer has not been verified", self_check=True)
+        mock_state.get_knowledge.return_value = {}
+
+        result = await task.task_complete_success("the answer is 42")
+
+        self.assertIn("the answer has not been verified", result)
+        mock_state.set_task_complete.assert_not_called()
+        # the verification is counted in the usage
+        mock_state.update_usage.assert_called_once_with(Usage(cost=0.1))
+        self.assertIn("the answer is 42", actor.generation_engine.complete.call_args[0][1])
+
+    async def test_self_check_accepts(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state, self_check=True)
+        mock_state.get_knowledge.return_value = {}
+
+        self.assertIsNone(await task.task_complete_success("the answer is 42", {"answer": 42}))
+        mock_state.set_task_complete.assert_called_once_with("the answer is 42", {"answer": 42})
+
+    async def test_self_check_skipped_when_predicates_fail(self, mock_state: MagicMock) -> None:
+        actor = self._actor(mock_state, plan=True, self_check=True)
+        mock_state.get_knowledge.return_value = {}
+
+        self.assertIn("no plan has been created", await task.task_complete_success("done"))
+        actor.generation_engine.complete.assert_not_called()
@@ -0,0 +1,179 @@
+"""
+Let the agent summarize or extract information from long texts, files or web pages in a single tool call, without reading them in the conversation first, parse them with regular expressions and compare them.
+
+> [!TIP]
+> Texts are summarized by the same model of the agent with a separate request, whose cost is added to the usage of the run. Inputs longer than 100000 characters are truncated.
+"""
+
+import difflib
+import json
+import re
+from typing import Annotated
+
+import nerve.runtime.state as state
+from nerve.tools.namespaces import web
+from nerve.tools.utils import html_to_text, path_acl
+
+# for docs
+EMOJI = "📝"
+
+# if set, the agent will only have access to these paths
+jail: list[str] = []
+# maximum number of characters sent to the model, the rest is truncated
+max_input_length: int = 100_000
+# default number of unchanged lines shown around the changes of a diff
+diff_context_lines: int = 3
+# maximum number of matches returned by extract
+max_matches: int = 1000
+
+# regular expressions that can be used by name with extract
+patterns: dict[str, str] = {
+    "email": r"(?P<email>[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,})",
+    # trailing punctuation is not part of the url
+    "url": r"(?P<url>https?://[^\s<>\"']*[^\s<>\"'.,;:!?)\]])",
+    "ipv4": r"(?P<ipv4>\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b)",
+    "uuid": r"(?P<uuid>\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b)",
+    "key_value": r"(?P<key>[A-Za-z_][\w.-]*)=(?P<value>\"[^\"]*\"|'[^']*'|\S*)",
+}
+
+_SYSTEM_PROMPT = (
+    "You process texts on behalf of an agent. Follow the instruction using only the information in the text, "
+    "be concise and do not add any introduction or comment. If the text does not contain the requested "
+    "information, say so."
+)
+
+
+def _get_input(text: str, path: str, url: str) -> str:
+    if sum(1 for source in (text, path, url) if source) != 1:
+        raise ValueError("exactly one of text, path or url must be provided")
+
+    if path:
+        path_acl(path, jail)
+        with open(path, errors="replace") as f:
+            return f.read()
+
+    elif url:
+        # downloaded like the web namespace does, up to its max_page_size
+        body, _, content_type = web._fetch(url)
+        return html_to_text(body) if "html" in content_type else body
+
+    return text
+
+
+async def summarize(
+    instruction: Annotated[
+        str, "What to do with the text, for instance 'summarize it' or 'extract all email addresses'"
+    ] = "Summarize the text, keeping every relevant fact.",
+    text: Annotated[str, "The text to process, if it is not in a file or a web page"] = "",
+    path: Annotated[str, "The path of a file to process"] = "",
+    url: Annotated[str, "The URL of a web page to process"] = "",
+) -> str:
+    """
+    Summarize a long text, file or web page, or extract information from it according to an instruction,
+    and return only the result.
+    """
+
+    engine = getattr(state.get_current_actor(), "generation_engine", None)
+    if engine is None:
+        raise ValueError("texts can only be summarized by an agent")
+
+    content = _get_input(text, path, url)
+    if not content.strip():
+        raise ValueError("the text is empty")
+
+    note = ""
+    if len(content) > max_input_length:
+        content = content[:max_input_length]
+        note = f"\n\n[only the first {max_input_length} characters of the text have been processed]"
+
+    usage, result = await engine.complete(
+        _SYSTEM_PROMPT, f"<instruction>{instruction}</instruction>\n\n<text>\n{content}\n</text>"
+    )
+    # the request is not part of the step of the agent
+    state.update_usage(usage)
+
+    state.on_event("text_summarized", {"instruction": instruction, "length": len(content), "usage": usage})
+
+    return (result or "the model returned an empty result") + note
+
+
+def _read_side(text: str, path: str) -> str | bytes:
+    # the text of one side of a diff, or the raw data of binary files
+    if not path:
+        return text
+
+    path_acl(path, jail)
+    with open(path, "rb") as f:
+        data = f.read()
+
+    if b"\0" in data:
+        return data
+    try:
+        return data.decode("utf-8")
+    except UnicodeDecodeError:
+        return data
+
+
+def diff(
+    old_text: Annotated[str, "The original text, if it is not in a file"] = "",
+    new_text: Annotated[str, "The modified text, if it is not in a file"] = "",
+    old_path: Annotated[str, "The path of the original file"] = "",
+    new_path: Annotated[str, "The path of the modified file"] = "",
+    context_lines: Annotated[int, "Optional number of unchanged lines to show around each change"] = -1,
+) -> str:
+    """Compare two texts or files and return their differences as a unified diff."""
+
+    if (old_text and old_path) or (new_text and new_path):
+        raise ValueError("each side can be either a text or a path, not both")
+
+    old = _read_side(old_text, old_path)
+    new = _read_side(new_text, new_path)
+    old_label = old_path or "old"
+    new_label = new_path or "new"
+
+    # a line by line diff of binary data would only be noise
+    if isinstance(old, bytes) or isinstance(new, bytes):
+        return "no differences" if old == new else f"binary files {old_label} and {new_label} differ"
+
+    lines = difflib.unified_diff(
+        old.splitlines(keepends=True),
+        new.splitlines(keepends=True),
+        fromfile=old_label,
+        tofile=new_label,
+        n=context_lines if context_lines >= 0 else diff_context_lines,
+    )
+    # the last line of a text without a trailing newline would be joined with the next one
+    result = "".join(line if line.endswith("\n") else f"{line}\n\\ No newline at end of file\n" for line in lines)
+    return result.rstrip("\n") or "no differences"
+
+
+def extract(
+    pattern: Annotated[
+        str,
+        "The name of a builtin pattern (email, url, ipv4, uuid or key_value) or a Python regular expression, "
+        "whose named or numbered capture groups become the fields of each match",
+    ],
+    text: Annotated[str, "The text to parse, if it is not in a file or a web page"] = "",
+    path: Annotated[str, "The path of a file to parse"] = "",
+    url: Annotated[str, "The URL of a web page to parse"] = "",
+) -> str:
+    """Extract structured data from a text, file or web page with a regular expression and return the matches as JSON."""
+
+    try:
+        regex = re.compile(patterns.get(pattern, pattern))
+    except re.error as e:
+        raise ValueError(f"invalid regular expression {pattern!r}: {e}") from e
+
+    matches = []
+    for match in regex.finditer(_get_input(text, path, url)):
+        if len(matches) == max_matches:
+            break
+
+        if regex.groupindex:
+            matches.append(match.groupdict())
+        elif regex.groups:
+            matches.append({f"group_{i}": value for i, value in enumerate(match.groups(), 1)})
+        else:
+            matches.append({"match": match.group(0)})
+
+    return json.dumps(matches, indent=2)
@@ -0,0 +1,173 @@
+import json
+import tempfile
+import typing as t
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.generation.conversation import FullHistoryStrategy
+from nerve.generation.litellm import LiteLLMEngine
+from nerve.generation.testing import ScriptedClient, text
+from nerve.models import Usage
+from nerve.tools.namespaces import text as text_ns
+from nerve.tools.namespaces import web
+
+
+@patch("nerve.tools.namespaces.text.state")
+class TestSummarize(unittest.IsolatedAsyncioTestCase):
+    def _actor(self, mock_state: MagicMock, result: str) -> ScriptedClient:
+        client = ScriptedClient([text(result)], Usage(prompt_tokens=100, completion_tokens=10))
+        engine = LiteLLMEngine("openai/gpt-4o", FullHistoryStrategy(), client=client)
+        mock_state.get_current_actor.return_value = MagicMock(generation_engine=engine)
+        return client
+
+    async def test_extract_from_text(self, mock_state: MagicMock) -> None:
+        client = self._actor(mock_state, "alice@example.com")
+
+        result = await text_ns.summarize("extract all email addresses", text="write to alice@example.com")
+
+        self.assertEqual(result, "alice@example.com")
+        prompt = client.conversations[0][-1]["content"]
+        self.assertIn("<instruction>extract all email addresses</instruction>", prompt)
+        self.assertIn("write to alice@example.com", prompt)
+
+        usage = mock_state.update_usage.call_args.args[0]
+        self.assertEqual((usage.prompt_tokens, usage.completion_tokens), (100, 10))
+        self.assertEqual(mock_state.on_event.call_args.args[0], "text_summarized")
+
+    async def test_summarize_file(self, mock_state: MagicMock) -> None:
+        client = self._actor(mock_state, "a short summary")
+
+        with tempfile.NamedTemporaryFile("w", suffix=".txt") as f:
+            f.write("a very long document")
+            f.flush()
+
+            self.assertEqual(await text_ns.summarize(path=f.name), "a short summary")
+
+        self.assertIn("a very long document", client.conversations[0][-1]["content"])
+
+    async def test_input_is_capped(self, mock_state: MagicMock) -> None:
+        client = self._actor(mock_state, "summary")
+
+        with patch.object(text_ns, "max_input_length", 10):
+            result = await text_ns.summarize(text="x" * 20)
+
+        self.assertIn("only the first 10 characters", result)
+        self.assertIn("<text>\n" + "x" * 10 + "\n</text>", client.conversations[0][-1]["content"])
+
+    @patch("nerve.tools.namespaces.web.network.request")
+    async def test_download_is_capped(self, mock_request: MagicMock, mock_state: MagicMock) -> None:
+        client = self._actor(mock_state, "summary")
+        response = MagicMock(headers={"Content-Type": "text/html"}, encoding=None, url="https://example.com/")
+        response.iter_content.return_value = [b"<p>" + b"a" * 100 + b"</p>"]
+        mock_request.return_value = response
+
+        with patch.object(web, "max_page_size", 20):
+            await text_ns.summarize(url="https://example.com/")
+
+        self.assertTrue(mock_request.call_args.kwargs["stream"])
+        self.assertIn("<text>\n" + "a" * 17 + "\n</text>", client.conversations[0][-1]["content"])
+        response.close.assert_called_once()
+
+    async def test_exactly_one_source(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state, "summary")
+
+        with self.assertRaisesRegex(ValueError, "exactly one of"):
+            await text_ns.summarize(text="a", url="http://example.com")
+
+        with self.assertRaisesRegex(ValueError, "exactly one of"):
+            await text_ns.summarize()
+
+
+class TestDiff(unittest.TestCase):
+    def test_diff_texts(self) -> None:
+        result = text_ns.diff(old_text="a\nb\nc\n", new_text="a\nB\nc\n")
+
+        self.assertEqual(result, "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c")
+
+    def test_context_lines(self) -> None:
+        old = "".join(f"{i}\n" for i in range(10))
+        new = old.replace("5\n", "five\n")
+
+        result = text_ns.diff(old_text=old, new_text=new, context_lines=1)
+
+        self.assertEqual(result.splitlines()[2:], ["@@ -5,3 +5,3 @@", " 4", "-5", "+five", " 6"])
+
+    def test_missing_trailing_newline(self) -> None:
+        result = text_ns.diff(old_text="a", new_text="b")
+
+        self.assertTrue(result.endswith("-a\n\\ No newline at end of file\n+b\n\\ No newline at end of file"))
+
+    def test_no_differences(self) -> None:
+        self.assertEqual(text_ns.diff(old_text="same", new_text="same"), "no differences")
+
+    def test_diff_files(self) -> None:
+        with tempfile.TemporaryDirectory() as folder:
+            with open(f"{folder}/a.txt", "w") as f:
+                f.write("hello\n")
+
+            result = text_ns.diff(old_path=f"{folder}/a.txt", new_text="hello world\n")
+
+        self.assertIn(f"--- {folder}/a.txt\n+++ new\n", result)
+        self.assertIn("-hello\n+hello world", result)
+
+    def test_binary_files(self) -> None:
+        with tempfile.TemporaryDirectory() as folder:
+            for name, data in (("a.bin", b"\x00\x01"), ("b.bin", b"\x00\x02")):
+                with open(f"{folder}/{name}", "wb") as f:
+                    f.write(data)
+
+            result = text_ns.diff(old_path=f"{folder}/a.bin", new_path=f"{folder}/b.bin")
+            same = text_ns.diff(old_path=f"{folder}/a.bin", new_path=f"{folder}/a.bin")
+
+        self.assertEqual(result, f"binary files {folder}/a.bin and {folder}/b.bin differ")
+        self.assertEqual(same, "no differences")
+
+    def test_jail(self) -> None:
+        with patch.object(text_ns, "jail", ["/nonexistent"]), self.assertRaisesRegex(ValueError, "is not allowed"):
+            text_ns.diff(old_path="/etc/hostname", new_text="")
+
+    def test_text_or_path(self) -> None:
+        with self.assertRaisesRegex(ValueError, "either a text or a path"):
+            text_ns.diff(old_text="a", old_path="a.txt")
+
+
+class TestExtract(unittest.TestCase):
+    def _extract(self, pattern: str, text: str) -> t.Any:
+        return json.loads(text_ns.extract(pattern, text=text))
+
+    def test_builtin_patterns(self) -> None:
+        log = (
+            "2024-05-01 login from 10.0.0.1 by bob@example.com (https://example.com/login?id=1). "
+            "session 123e4567-e89b-12d3-a456-426614174000 from 999.1.1.1"
+        )
+
+        self.assertEqual(self._extract("ipv4", log), [{"ipv4": "10.0.0.1"}])
+        self.assertEqual(self._extract("email", log), [{"email": "bob@example.com"}])
+        self.assertEqual(self._extract("url", log), [{"url": "https://example.com/login?id=1"}])
+        self.assertEqual(self._extract("uuid", log), [{"uuid": "123e4567-e89b-12d3-a456-426614174000"}])
+        self.assertEqual(
+            self._extract("key_value", 'user=bob status="ok failed" retries=3'),
+            [
+                {"key": "user", "value": "bob"},
+                {"key": "status", "value": '"ok failed"'},
+                {"key": "retries", "value": "3"},
+            ],
+        )
+
+    def test_custom_patterns(self) -> None:
+        text = "GET /a 200\nPOST /b 500"
+
+        self.assertEqual(
+            self._extract(r"(?P<method>[A-Z]+) (?P<path>\S+) (?P<status>\d+)", text),
+            [{"method": "GET", "path": "/a", "status": "200"}, {"method": "POST", "path": "/b", "status": "500"}],
+        )
+        self.assertEqual(self._extract(r"(\w+) /(\w)", text)[1], {"group_1": "POST", "group_2": "b"})
+        self.assertEqual(self._extract(r"\d{3}", text), [{"match": "200"}, {"match": "500"}])
+
+    def test_max_matches(self) -> None:
+        with patch.object(text_ns, "max_matches", 2):
+            self.assertEqual(len(self._extract(r"\d", "1 2 3 4")), 2)
+
+    def test_invalid_pattern(self) -> None:
+        with self.assertRaisesRegex(ValueError, "invalid regular expression '\\(unclosed'"):
+            text_ns.extract("(unclosed", text="text")
@@ -1,18 +1,191 @@
 """
-Provides tools for getting the current date and time and waiting for a given number of seconds.
+Provides tools for getting the current date and time in any timezone, doing date arithmetic, converting dates between formats and waiting for a given number of seconds or until a given time.
+
+> [!TIP]
+> Dates are returned in ISO 8601 followed by a human readable rendering. Timezones are IANA names like `Europe/Rome` or `UTC`, dates without a timezone are assumed to be in the local one.
 """
 
+import asyncio
+import email.utils
+import re
 import time
+import zoneinfo
+from datetime import datetime, timedelta, tzinfo
 from typing import Annotated
 
+from pydantic import Field
+
+import nerve.runtime.state as state
+
 # for docs
 EMOJI = "🕒"
 
+_DURATION_PARSER = re.compile(r"^(?:(\d+(?:\.\d+)?)h)?(?:(\d+(?:\.\d+)?)m)?(?:(\d+(?:\.\d+)?)s)?$")
+
+
+def _parse_wait_seconds(until: str) -> float:
+    until = until.strip()
+
+    # plain number of seconds
+    try:
+        return float(until)
+    except ValueError:
+        pass
+
+    # absolute timestamp, possibly with a space between the date and the time
+    try:
+        target = datetime.fromisoformat(until.upper().replace("Z", "+00:00"))
+    except ValueError:
+        target = None
+
+    if target is not None:
+        if target.tzinfo is None:
+            target = target.astimezone()
+
+        return target.timestamp() - time.time()
+
+    # duration like 1h30m, 45s or 1h 30m
+    duration = until.lower().replace(" ", "")
+    match = _DURATION_PARSER.match(duration)
+    if duration and match:
+        hours, minutes, seconds = (float(g) if g else 0.0 for g in match.groups())
+        return hours * 3600 + minutes * 60 + seconds
+
+    raise ValueError(
+        f"invalid value '{until}', expected a number of seconds, a duration like 1h30m or an ISO 8601 timestamp"
+    )
+
+
+def _get_timezone(name: str) -> tzinfo | None:
+    if not name:
+        return None
+
+    try:
+        return zoneinfo.ZoneInfo(name.strip())
+    except (zoneinfo.ZoneInfoNotFoundError, ValueError):
+        raise ValueError(f"unknown timezone '{name}', expected an IANA name like Europe/Rome or UTC") from None
+
+
+def _parse_date(value: str, input_format: str = "", timezone: tzinfo | None = None) -> datetime:
+    value = value.strip()
+    parsed: datetime | None = None
+
+    if input_format:
+        try:
+            parsed = datetime.strptime(value, input_format)
+        except ValueError as e:
+            raise ValueError(f"'{value}' does not match the format '{input_format}': {e}") from None
+
+    elif value.lower() == "now":
+        parsed = datetime.now(timezone).astimezone(timezone)
+
+    else:
+        # unix timestamp
+        try:
+            parsed = datetime.fromtimestamp(float(value)).astimezone(timezone)
+        except (ValueError, OverflowError, OSError):
+            pass
+
+        # ISO 8601
+        if parsed is None:
+            try:
+                parsed = datetime.fromisoformat(value.replace("Z", "+00:00").replace("z", "+00:00"))
+            except ValueError:
+                pass
+
+        # RFC 2822, as used by emails and HTTP headers
+        if parsed is None:
+            try:
+                parsed = email.utils.parsedate_to_datetime(value)
+            except (TypeError, ValueError):
+                raise ValueError(
+                    f"invalid date '{value}', expected 'now', a unix timestamp, an ISO 8601 or RFC 2822 date, "
+                    "or an explicit input format"
+                ) from None
+
+    if parsed.tzinfo is None:
+        parsed = parsed.replace(tzinfo=timezone) if timezone else parsed.astimezone()
+
+    return parsed
 
-def current_time_and_date() -> str:
-    """Get the current date and time."""
 
-    return time.strftime("%H:%M%p %Z on %b %d, %Y")
+def _render(date: datetime) -> str:
+    return f"{date.isoformat()} ({date.strftime('%A, %B %d, %Y at %H:%M:%S %Z')})"
+
+
+def current_time_and_date(
+    timezone: Annotated[str, "Optional IANA timezone like Europe/Rome or America/New_York, local if empty"] = "",
+) -> str:
+    """Get the current date and time, optionally in a given timezone."""
+
+    return _render(_parse_date("now", timezone=_get_timezone(timezone)))
+
+
+def add_to_date(
+    date: Annotated[str, "The date to start from, 'now', a unix timestamp, an ISO 8601 or RFC 2822 date"],
+    weeks: Annotated[float, "Weeks to add, negative to subtract"] = 0,
+    days: Annotated[float, "Days to add, negative to subtract"] = 0,
+    hours: Annotated[float, "Hours to add, negative to subtract"] = 0,
+    minutes: Annotated[float, "Minutes to add, negative to subtract"] = 0,
+    seconds: Annotated[float, "Seconds to add, negative to subtract"] = 0,
+    timezone: Annotated[str, "Optional IANA timezone of the result, the one of the date if empty"] = "",
+) -> str:
+    """Add or subtract an amount of time to a date and return the resulting date."""
+
+    tz = _get_timezone(timezone)
+    result = _parse_date(date, timezone=tz) + timedelta(
+        weeks=weeks, days=days, hours=hours, minutes=minutes, seconds=seconds
+    )
+
+    return _render(result.astimezone(tz) if tz else result)
+
+
+def date_difference(
+    start: Annotated[str, "The first date, 'now', a unix timestamp, an ISO 8601 or RFC 2822 date"],
+    end: Annotated[str, "The second date, 'now', a unix timestamp, an ISO 8601 or RFC 2822 date"],
+) -> str:
+    """Get the amount of time between two dates, negative if the second date precedes the first one."""
+
+    delta = _parse_date(end) - _parse_date(start)
+    total = delta.total_seconds()
+    sign = "-" if total < 0 else ""
+    days, rest = divmod(abs(total), 86400)
+    hours, rest = divmod(rest, 3600)
+    minutes, secs = divmod(rest, 60)
+
+    return (
+        f"{sign}{int(days)} days, {int(hours)} hours, {int(minutes)} minutes and {secs:g} seconds "
+        f"({int(total) if total.is_integer() else total} seconds)"
+    )
+
+
+def convert_date(
+    date: Annotated[str, "The date to convert, 'now', a unix timestamp, an ISO 8601 or RFC 2822 date"],
+    output_format: Annotated[
+        str, "iso, unix, rfc2822 or a strftime format like '%d/%m/%Y %H:%M', iso by default"
+    ] = "iso",
+    timezone: Annotated[str, "Optional IANA timezone to convert the date to"] = "",
+    input_format: Annotated[str, "Optional strptime format of the date, if it is in a custom format"] = "",
+) -> str:
+    """Parse a date and convert it to another format and/or timezone."""
+
+    tz = _get_timezone(timezone)
+    parsed = _parse_date(date, input_format, tz)
+    if tz:
+        parsed = parsed.astimezone(tz)
+
+    fmt = output_format.strip()
+    if fmt.lower() == "iso":
+        return _render(parsed)
+    elif fmt.lower() == "unix":
+        timestamp = parsed.timestamp()
+        return str(int(timestamp)) if timestamp.is_integer() else str(timestamp)
+    elif fmt.lower() == "rfc2822":
+        return email.utils.format_datetime(parsed)
+    elif "%" not in fmt:
+        raise ValueError(f"invalid output format '{output_format}', expected iso, unix, rfc2822 or a strftime format")
+
+    return parsed.strftime(fmt)
 
 
 def wait(
@@ -21,3 +194,33 @@ def wait(
     """Wait for a given number of seconds."""
 
     time.sleep(seconds)
+
+
+async def wait_until(
+    until: Annotated[
+        str,
+        Field(
+            description="Either a duration (number of seconds or a value like 30s, 5m, 1h30m) or an absolute ISO 8601 timestamp.",
+            examples=["90", "5m", "1h30m", "2025-01-31T14:30:00+01:00"],
+        ),
+    ],
+) -> str:
+    """Suspend the execution until a given time or for a given duration, useful to poll external systems on a schedule."""
+
+    seconds = _parse_wait_seconds(until)
+    if seconds <= 0:
+        return f"{until} is in the past, no need to wait"
+
+    state.on_event("sleeping", {"seconds": seconds, "until": until})
+
+    started_at = time.monotonic()
+    deadline = started_at + seconds
+    # sleep in small chunks so that the wait is interrupted as soon as the task is done,
+    # cancellation of the run interrupts the sleep immediately
+    while not state.is_active_task_done():
+        remaining = deadline - time.monotonic()
+        if remaining <= 0:
+            break
+        await asyncio.sleep(min(remaining, 1.0))
+
+    return f"waited for {time.monotonic() - started_at:.1f} seconds"
@@ -0,0 +1,118 @@
+import asyncio
+import time
+import unittest
+from datetime import datetime, timedelta, timezone
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import time as time_ns
+
+
+class TestParseWaitSeconds(unittest.TestCase):
+    def test_durations(self) -> None:
+        self.assertEqual(time_ns._parse_wait_seconds("90"), 90)
+        self.assertEqual(time_ns._parse_wait_seconds("1.5"), 1.5)
+        self.assertEqual(time_ns._parse_wait_seconds("45s"), 45)
+        self.assertEqual(time_ns._parse_wait_seconds("5m"), 300)
+        self.assertEqual(time_ns._parse_wait_seconds("1h 30m"), 5400)
+
+    def test_timestamp(self) -> None:
+        target = datetime.now(timezone.utc) + timedelta(minutes=10)
+
+        seconds = time_ns._parse_wait_seconds(target.isoformat())
+        self.assertAlmostEqual(seconds, 600, delta=2)
+
+    def test_timestamp_with_space(self) -> None:
+        target = datetime.now().replace(microsecond=0) + timedelta(minutes=10)
+
+        seconds = time_ns._parse_wait_seconds(target.strftime("%Y-%m-%d %H:%M:%S"))
+        self.assertAlmostEqual(seconds, 600, delta=2)
+
+    def test_invalid(self) -> None:
+        for value in ("", "soon", "5x"):
+            with self.assertRaises(ValueError):
+                time_ns._parse_wait_seconds(value)
+
+
+@patch("nerve.tools.namespaces.time.state")
+class TestWaitUntil(unittest.IsolatedAsyncioTestCase):
+    async def test_wait_returns_elapsed_time(self, mock_state: MagicMock) -> None:
+        mock_state.is_active_task_done.return_value = False
+
+        started_at = time.monotonic()
+        result = await time_ns.wait_until("0.2")
+
+        self.assertGreaterEqual(time.monotonic() - started_at, 0.2)
+        self.assertTrue(result.startswith("waited for 0.2 seconds"))
+        mock_state.on_event.assert_called_once_with("sleeping", {"seconds": 0.2, "until": "0.2"})
+
+    async def test_past_timestamp(self, mock_state: MagicMock) -> None:
+        self.assertIn("in the past", await time_ns.wait_until("2000-01-01T00:00:00Z"))
+        mock_state.on_event.assert_not_called()
+
+    async def test_wait_is_cancellable(self, mock_state: MagicMock) -> None:
+        mock_state.is_active_task_done.return_value = False
+
+        task = asyncio.create_task(time_ns.wait_until("1h"))
+        await asyncio.sleep(0.1)
+        task.cancel()
+
+        with self.assertRaises(asyncio.CancelledError):
+            await task
+
+
+class TestDates(unittest.TestCase):
+    def test_current_time_in_timezone(self) -> None:
+        result = time_ns.current_time_and_date("Asia/Tokyo")
+
+        self.assertRegex(result, r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+\+09:00 \(\w+, .+ JST\)$")
+
+    def test_unknown_timezone(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unknown timezone 'Mars/Olympus'"):
+            time_ns.current_time_and_date("Mars/Olympus")
+
+    def test_add_to_date(self) -> None:
+        self.assertEqual(
+            time_ns.add_to_date("2024-02-28T12:00:00+00:00", days=1, hours=-2),
+            "2024-02-29T10:00:00+00:00 (Thursday, February 29, 2024 at 10:00:00 UTC)",
+        )
+
+    def test_add_to_date_across_dst(self) -> None:
+        # the result is converted to the timezone, one hour of summer time is lost
+        result = time_ns.add_to_date("2024-03-30T12:00:00+01:00", days=1, timezone="Europe/Rome")
+
+        self.assertTrue(result.startswith("2024-03-31T13:00:00+02:00"))
+
+    def test_date_difference(self) -> None:
+        self.assertEqual(
+            time_ns.date_difference("2024-01-01T00:00:00Z", "2024-01-02T01:30:15Z"),
+            "1 days, 1 hours, 30 minutes and 15 seconds (91815 seconds)",
+        )
+        self.assertTrue(time_ns.date_difference("2024-01-02T00:00:00Z", "2024-01-01T00:00:00Z").startswith("-1 days"))
+
+    def test_convert_date(self) -> None:
+        self.assertEqual(
+            time_ns.convert_date("0", "iso", "UTC"),
+            "1970-01-01T00:00:00+00:00 (Thursday, January 01, 1970 at 00:00:00 UTC)",
+        )
+        self.assertEqual(time_ns.convert_date("2024-05-01T10:00:00Z", "unix"), "1714557600")
+        self.assertEqual(time_ns.convert_date("2024-05-01T10:00:00Z", "rfc2822"), "Wed, 01 May 2024 10:00:00 +0000")
+        self.assertEqual(
+            time_ns.convert_date("Wed, 01 May 2024 10:00:00 +0000", "%d/%m/%Y %H:%M", "America/New_York"),
+            "01/05/2024 06:00",
+        )
+
+    def test_convert_custom_input_format(self) -> None:
+        self.assertEqual(
+            time_ns.convert_date("31/12/2024 23:59", "iso", "UTC", input_format="%d/%m/%Y %H:%M"),
+            "2024-12-31T23:59:00+00:00 (Tuesday, December 31, 2024 at 23:59:00 UTC)",
+        )
+
+        with self.assertRaisesRegex(ValueError, "does not match the format"):
+            time_ns.convert_date("2024-12-31", input_format="%d/%m/%Y")
+
+    def test_invalid_input(self) -> None:
+        with self.assertRaisesRegex(ValueError, "invalid date 'yesterday'"):
+            time_ns.convert_date("yesterday")
+
+        with self.assertRaisesRegex(ValueError, "invalid output format"):
+            time_ns.convert_date("now", "european")
@@ -0,0 +1,83 @@
+"""
+Let the agent activate additional tool namespaces at runtime, from the ones pre-approved in the agent configuration.
+
+> [!TIP]
+> This keeps the initial set of tools small while allowing the agent to get more capabilities when it needs them, only the namespaces listed in `optional_using` can be activated:
+>
+> ```yaml
+> using: [task, toolbox]
+> optional_using: [filesystem, http]
+> ```
+"""
+
+import importlib
+from typing import Annotated
+
+import nerve.runtime.state as state
+from nerve.models import Configuration
+from nerve.tools import compiler
+
+# for docs
+EMOJI = "🧰"
+
+
+def _get_configuration() -> Configuration:
+    configuration: Configuration | None = getattr(state.get_current_actor(), "configuration", None)
+    if configuration is None:
+        raise ValueError("namespaces can only be activated by an agent")
+
+    return configuration
+
+
+def _get_description(namespace: str) -> str:
+    try:
+        module = importlib.import_module(f"nerve.tools.namespaces.{namespace}")
+    except ImportError:
+        return ""
+
+    # first line of the module docstring
+    return (module.__doc__ or "").strip().split("\n")[0]
+
+
+def list_optional_namespaces() -> str:
+    """List the tool namespaces that you can activate if you need more capabilities."""
+
+    configuration = _get_configuration()
+    available = [ns for ns in configuration.optional_using if ns not in configuration.using]
+    if not available:
+        return "no namespaces can be activated"
+
+    return "\n".join(f"- {ns}: {_get_description(ns)}" for ns in available)
+
+
+def activate_namespace(
+    namespace: Annotated[str, "The name of the namespace to activate"],
+) -> str:
+    """Activate a tool namespace in order to use its tools from the next step."""
+
+    configuration = _get_configuration()
+    if namespace in configuration.using:
+        return f"namespace {namespace} is already active"
+
+    if namespace not in configuration.optional_using:
+        return (
+            f"ERROR: activation of namespace {namespace} denied, "
+            f"the namespaces that can be activated are: {configuration.optional_using}"
+        )
+
+    tools = compiler.get_tools_from_namespace(
+        namespace,
+        configuration.jail.get(namespace, []),
+        configuration.allow.get(namespace),
+        configuration.deny.get(namespace),
+        configuration.settings.get(namespace),
+    )
+    for tool in tools:
+        state.set_extra_tool(tool)
+
+    configuration.using.append(namespace)
+
+    names = [tool.__name__ for tool in tools]
+    state.on_event("namespace_activated", {"namespace": namespace, "tools": names})
+
+    return f"namespace {namespace} activated, new tools: {', '.join(names)}"
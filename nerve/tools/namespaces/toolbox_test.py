@@ -0,0 +1,54 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.models import Configuration
+from nerve.tools.namespaces import toolbox
+
+
+@patch("nerve.tools.namespaces.toolbox.state")
+class TestToolbox(unittest.TestCase):
+    def setUp(self) -> None:
+        self.configuration = Configuration(using=["task", "toolbox"], optional_using=["time", "filesystem"])
+
+    def _actor(self, mock_state: MagicMock) -> None:
+        mock_state.get_current_actor.return_value = MagicMock(configuration=self.configuration)
+
+    def test_list_optional_namespaces(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state)
+        self.configuration.using.append("filesystem")
+
+        result = toolbox.list_optional_namespaces()
+
+        self.assertIn("- time: ", result)
+        self.assertNotIn("filesystem", result)
+
+    def test_activate_namespace(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state)
+
+        result = toolbox.activate_namespace("time")
+
+        names = [call.args[0].__name__ for call in mock_state.set_extra_tool.call_args_list]
+        self.assertIn("current_time_and_date", names)
+        self.assertIn("current_time_and_date", result)
+        self.assertIn("time", self.configuration.using)
+        mock_state.on_event.assert_called_once_with("namespace_activated", {"namespace": "time", "tools": names})
+
+        self.assertEqual(toolbox.activate_namespace("time"), "namespace time is already active")
+
+    def test_activation_is_denied_for_namespaces_not_allowed(self, mock_state: MagicMock) -> None:
+        self._actor(mock_state)
+
+        result = toolbox.activate_namespace("shell")
+
+        self.assertTrue(result.startswith("ERROR: activation of namespace shell denied"))
+        mock_state.set_extra_tool.assert_not_called()
+        self.assertNotIn("shell", self.configuration.using)
+
+    def test_allow_and_deny_are_applied(self, mock_state: MagicMock) -> None:
+        self.configuration.allow = {"filesystem": ["read_file"]}
+        self._actor(mock_state)
+
+        toolbox.activate_namespace("filesystem")
+
+        names = [call.args[0].__name__ for call in mock_state.set_extra_tool.call_args_list]
+        self.assertEqual(names, ["read_file"])
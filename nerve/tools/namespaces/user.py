@@ -0,0 +1,28 @@
+"""
+Let the agent ask the user clarifying questions during the run and wait for the answer.
+
+> [!TIP]
+> Questions are asked on the terminal in interactive mode. Applications embedding Nerve can answer them instead: after calling `state.enable_host_questions(timeout=300)`, each question emits a `question_asked` event with its `id`, to be answered with `state.respond_to_question(id, answer)`. When nobody answers, the agent is told so and continues on its own.
+"""
+
+import asyncio
+from typing import Annotated
+
+import nerve.runtime.state as state
+
+# for docs
+EMOJI = "🙋"
+
+_NO_RESPONSE = "the user did not respond, continue with your best judgement or with a different approach"
+
+
+async def ask(
+    question: Annotated[str, "The question to ask the user"],
+    choices: Annotated[list[str] | None, "Optional answers to suggest, the user can answer something else"] = None,
+) -> str:
+    """Ask the user a question when you need information or a decision that only the user can provide."""
+
+    # waiting for the answer must not block the other tasks of the run
+    answer = await asyncio.to_thread(state.on_user_question, question, choices or None)
+
+    return answer if answer else _NO_RESPONSE
@@ -0,0 +1,19 @@
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import user
+
+
+@patch("nerve.tools.namespaces.user.state")
+class TestAsk(unittest.IsolatedAsyncioTestCase):
+    async def test_answer(self, mock_state: MagicMock) -> None:
+        mock_state.on_user_question.return_value = "production"
+
+        self.assertEqual(await user.ask("which environment?", ["staging", "production"]), "production")
+        mock_state.on_user_question.assert_called_once_with("which environment?", ["staging", "production"])
+
+    async def test_no_response(self, mock_state: MagicMock) -> None:
+        mock_state.on_user_question.return_value = None
+
+        self.assertIn("the user did not respond", await user.ask("which environment?"))
+        mock_state.on_user_question.assert_called_once_with("which environment?", None)
@@ -0,0 +1,121 @@
+"""
+Let the agent render charts from data and see the result as an image.
+
+> [!TIP]
+> Charts are rendered with `matplotlib`. Rendered images are downscaled like any other image before being sent to the model.
+"""
+
+import base64
+import io
+import json
+import typing as t
+from typing import Annotated
+
+# this is an extra feature, so we need to indicate it
+OPTIONAL_FEATURE = "viz"
+# for docs
+EMOJI = "📊"
+
+# maximum number of data points of a chart, across all of its series
+max_points: int = 10_000
+# size of the rendered charts, in pixels
+width: int = 1024
+height: int = 768
+
+_KINDS = ("line", "bar", "scatter")
+
+
+def _parse_series(data: str) -> list[dict[str, t.Any]]:
+    try:
+        parsed = json.loads(data)
+    except json.JSONDecodeError as e:
+        raise ValueError(f"series must be valid JSON: {e}") from e
+
+    # a single series can be passed as it is
+    if isinstance(parsed, dict):
+        parsed = [parsed]
+    if not isinstance(parsed, list) or not parsed:
+        raise ValueError('series must be a non empty list of {"name": ..., "x": [...], "y": [...]} objects')
+
+    series = []
+    total = 0
+    for i, item in enumerate(parsed):
+        if not isinstance(item, dict) or not isinstance(item.get("y"), list) or not item["y"]:
+            raise ValueError(f"series {i} must be an object with a non empty y list")
+
+        y = item["y"]
+        if not all(isinstance(v, int | float) and not isinstance(v, bool) for v in y):
+            raise ValueError(f"the y values of series {i} must be numbers")
+
+        x = item.get("x", list(range(len(y))))
+        if not isinstance(x, list) or len(x) != len(y):
+            raise ValueError(f"series {i} has {len(y)} y values but {len(x) if isinstance(x, list) else 0} x values")
+
+        total += len(y)
+        if total > max_points:
+            raise ValueError(f"charts can have at most {max_points} data points, reduce or aggregate the data")
+
+        series.append({"name": str(item.get("name", f"series {i}")), "x": x, "y": y})
+
+    return series
+
+
+def _render(kind: str, series: list[dict[str, t.Any]], title: str, x_label: str, y_label: str) -> bytes:
+    try:
+        import matplotlib
+
+        matplotlib.use("Agg")
+        import matplotlib.pyplot as plt
+    except ImportError as e:
+        raise RuntimeError("matplotlib is not installed, charts can't be rendered") from e
+
+    dpi = 100
+    figure, axes = plt.subplots(figsize=(width / dpi, height / dpi), dpi=dpi)
+    try:
+        for i, s in enumerate(series):
+            if kind == "line":
+                axes.plot(s["x"], s["y"], label=s["name"])
+            elif kind == "scatter":
+                axes.scatter(s["x"], s["y"], label=s["name"])
+            else:
+                # bars of multiple series are placed side by side
+                bar_width = 0.8 / len(series)
+                positions = [j + i * bar_width for j in range(len(s["x"]))]
+                axes.bar(positions, s["y"], width=bar_width, label=s["name"])
+                axes.set_xticks([j + 0.4 - bar_width / 2 for j in range(len(s["x"]))], [str(v) for v in s["x"]])
+
+        axes.set_title(title)
+        axes.set_xlabel(x_label)
+        axes.set_ylabel(y_label)
+        if len(series) > 1:
+            axes.legend()
+        figure.tight_layout()
+
+        buffer = io.BytesIO()
+        figure.savefig(buffer, format="png")
+        return buffer.getvalue()
+    finally:
+        plt.close(figure)
+
+
+def plot(
+    kind: Annotated[str, "The type of chart: line, bar or scatter"],
+    series: Annotated[
+        str,
+        'The data as a JSON list of series like [{"name": "revenue", "x": [2023, 2024], "y": [10, 12.5]}], '
+        "x is optional and can contain labels for bar charts",
+    ],
+    title: Annotated[str, "Optional title of the chart"] = "",
+    x_label: Annotated[str, "Optional label of the x axis"] = "",
+    y_label: Annotated[str, "Optional label of the y axis"] = "",
+) -> dict[str, t.Any]:
+    """Render a line, bar or scatter chart of the given data and return it as an image."""
+
+    if kind not in _KINDS:
+        raise ValueError(f"unsupported chart type {kind}, use one of {list(_KINDS)}")
+
+    png = _render(kind, _parse_series(series), title, x_label, y_label)
+    return {
+        "type": "image_url",
+        "image_url": {"url": f"data:image/png;base64,{base64.b64encode(png).decode()}"},
+    }
@@ -0,0 +1,53 @@
+import base64
+import json
+import unittest
+from unittest.mock import patch
+
+from nerve.tools.namespaces import viz
+
+
+class TestPlot(unittest.TestCase):
+    def tearDown(self) -> None:
+        viz.max_points = 10_000
+
+    def test_plot_returns_an_image(self) -> None:
+        series = [{"name": "a", "x": [1, 2], "y": [3, 4.5]}, {"y": [1, 2]}]
+        with patch("nerve.tools.namespaces.viz._render", return_value=b"png") as render:
+            result = viz.plot("line", json.dumps(series), title="t")
+
+        render.assert_called_once_with(
+            "line",
+            [{"name": "a", "x": [1, 2], "y": [3, 4.5]}, {"name": "series 1", "x": [0, 1], "y": [1, 2]}],
+            "t",
+            "",
+            "",
+        )
+        self.assertEqual(result["type"], "image_url")
+        self.assertEqual(result["image_url"]["url"], f"data:image/png;base64,{base64.b64encode(b'png').decode()}")
+
+    def test_single_series(self) -> None:
+        with patch("nerve.tools.namespaces.viz._render", return_value=b"png") as render:
+            viz.plot("bar", '{"x": ["a", "b"], "y": [1, 2]}')
+
+        self.assertEqual(render.call_args.args[1], [{"name": "series 0", "x": ["a", "b"], "y": [1, 2]}])
+
+    def test_invalid_data_is_rejected(self) -> None:
+        cases = {
+            "not json": "must be valid JSON",
+            "[]": "non empty list",
+            '[{"x": [1]}]': "series 0 must be an object with a non empty y list",
+            '[{"y": [1, "two"]}]': "y values of series 0 must be numbers",
+            '[{"x": [1], "y": [1, 2]}]': "series 0 has 2 y values but 1 x values",
+        }
+        for data, error in cases.items():
+            with self.subTest(data=data), self.assertRaisesRegex(ValueError, error):
+                viz.plot("line", data)
+
+    def test_unsupported_kind(self) -> None:
+        with self.assertRaisesRegex(ValueError, "unsupported chart type pie"):
+            viz.plot("pie", '{"y": [1]}')
+
+    def test_oversized_data_is_rejected(self) -> None:
+        viz.max_points = 3
+        with self.assertRaisesRegex(ValueError, "at most 3 data points"):
+            viz.plot("scatter", '[{"y": [1, 2]}, {"y": [3, 4]}]')
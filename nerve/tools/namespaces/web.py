@@ -0,0 +1,415 @@
+"""
+Let the agent read web pages as clean Markdown, keeping only their main content.
+
+> [!TIP]
+> Navigation, ads, scripts and the other boilerplate are removed readability-style, while headings, links, lists, tables and code blocks are preserved. Pages that need JavaScript to show their content can be read with `render_js`, that requires the `web` extra and the chromium browser of playwright (`playwright install chromium`).
+"""
+
+import asyncio
+import re
+import typing as t
+from html.parser import HTMLParser
+from typing import Annotated
+from urllib.parse import urljoin
+
+from nerve.defaults import DEFAULT_HTTP_MAX_REDIRECTS, DEFAULT_HTTP_MAX_RESPONSE_SIZE, DEFAULT_HTTP_TIMEOUT
+from nerve.runtime import network
+
+# for docs
+EMOJI = "📖"
+
+# maximum size in bytes of the downloaded page, the rest is ignored
+max_page_size: int = DEFAULT_HTTP_MAX_RESPONSE_SIZE
+# maximum number of redirects to follow
+max_redirects: int = DEFAULT_HTTP_MAX_REDIRECTS
+# maximum number of characters of the returned Markdown
+max_length: int = 20_000
+
+# never part of the content
+_SKIP_TAGS = {
+    "head",
+    "script",
+    "style",
+    "noscript",
+    "template",
+    "svg",
+    "canvas",
+    "iframe",
+    "form",
+    "button",
+    "input",
+    "select",
+    "textarea",
+    "nav",
+    "aside",
+    "footer",
+}
+_VOID_TAGS = {"area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"}
+_BLOCK_TAGS = {
+    "address",
+    "article",
+    "blockquote",
+    "div",
+    "dl",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+}
+_HEADINGS = {"h1", "h2", "h3", "h4", "h5", "h6"}
+# classes and ids of the elements that are usually not part of the content
+_BOILERPLATE = re.compile(
+    r"(?:^|[\s_-])(?:ads?|advert\w*|banner|breadcrumbs?|comments?|cookies?|footer|menu|nav|navbar|navigation|"
+    r"newsletter|popup|promo|related|share|sharing|sidebar|social|sponsored|subscribe)(?:$|[\s_-])",
+    re.IGNORECASE,
+)
+_BOILERPLATE_ROLES = {"navigation", "banner", "complementary", "contentinfo", "search", "dialog"}
+# paragraphs shorter than this are not considered when looking for the main content
+_MIN_PARAGRAPH_LENGTH = 25
+
+
+class _Node:
+    def __init__(self, tag: str, attrs: dict[str, str], parent: "_Node | None"):
+        self.tag = tag
+        self.attrs = attrs
+        self.parent = parent
+        self.children: list[_Node | str] = []
+
+    def iter(self) -> t.Iterator["_Node"]:
+        yield self
+        for child in self.children:
+            if isinstance(child, _Node):
+                yield from child.iter()
+
+    def text(self) -> str:
+        return "".join(child if isinstance(child, str) else child.text() for child in self.children)
+
+
+class _TreeBuilder(HTMLParser):
+    def __init__(self) -> None:
+        super().__init__(convert_charrefs=True)
+        self.root = _Node("root", {}, None)
+        self.current = self.root
+        self.title = ""
+        self.in_title = False
+
+    def handle_starttag(self, tag: str, attrs: list[tuple[str, str | None]]) -> None:
+        if tag == "title":
+            self.in_title = True
+        # paragraphs and list items don't need to be closed
+        if (tag in _BLOCK_TAGS and self.current.tag == "p") or (tag == "li" and self.current.tag == "li"):
+            self.handle_endtag(self.current.tag)
+
+        node = _Node(tag, {name: value or "" for name, value in attrs}, self.current)
+        self.current.children.append(node)
+        if tag not in _VOID_TAGS:
+            self.current = node
+
+    def handle_startendtag(self, tag: str, attrs: list[tuple[str, str | None]]) -> None:
+        self.handle_starttag(tag, attrs)
+        if tag not in _VOID_TAGS:
+            self.handle_endtag(tag)
+
+    def handle_endtag(self, tag: str) -> None:
+        if tag == "title":
+            self.in_title = False
+
+        # close the innermost open element with this tag, stray end tags are ignored
+        node: _Node | None = self.current
+        while node is not None and node.tag != tag:
+            node = node.parent
+        if node is not None and node.parent is not None:
+            self.current = node.parent
+
+    def handle_data(self, data: str) -> None:
+        if self.in_title:
+            self.title += data
+        self.current.children.append(data)
+
+
+def _is_boilerplate(node: _Node) -> bool:
+    if node.tag in _SKIP_TAGS:
+        return True
+    if node.tag in ("html", "body", "main", "article"):
+        return False
+    if "hidden" in node.attrs or node.attrs.get("aria-hidden") == "true":
+        return True
+    if node.attrs.get("role") in _BOILERPLATE_ROLES:
+        return True
+
+    return bool(_BOILERPLATE.search(f"{node.attrs.get('class', '')} {node.attrs.get('id', '')}"))
+
+
+def _prune(node: _Node) -> None:
+    node.children = [child for child in node.children if isinstance(child, str) or not _is_boilerplate(child)]
+    for child in node.children:
+        if isinstance(child, _Node):
+            _prune(child)
+
+
+def _link_density(node: _Node) -> float:
+    length = len(node.text().strip())
+    if not length:
+        return 1.0
+
+    links = sum(len(link.text().strip()) for link in node.iter() if link.tag == "a")
+    return min(links / length, 1.0)
+
+
+def _find_main(root: _Node) -> _Node:
+    # pages marking their content explicitly
+    for tag in ("article", "main"):
+        candidates = [node for node in root.iter() if node.tag == tag]
+        if candidates:
+            return max(candidates, key=lambda node: len(node.text().strip()))
+
+    # otherwise score the containers of the paragraphs by their amount of text, like readability does
+    scores: dict[int, tuple[_Node, float]] = {}
+    for node in root.iter():
+        if node.tag not in ("p", "pre", "blockquote", "td"):
+            continue
+
+        text = node.text().strip()
+        if len(text) < _MIN_PARAGRAPH_LENGTH:
+            continue
+
+        score = 1 + text.count(",") + min(len(text) // 100, 3)
+        parent = node.parent
+        for weight in (1.0, 0.5):
+            if parent is None or parent.tag == "root":
+                break
+            _, current = scores.get(id(parent), (parent, 0.0))
+            scores[id(parent)] = (parent, current + score * weight)
+            parent = parent.parent
+
+    if not scores:
+        return next((node for node in root.iter() if node.tag == "body"), root)
+
+    return max(scores.values(), key=lambda item: item[1] * (1 - _link_density(item[0])))[0]
+
+
+class _MarkdownRenderer:
+    def __init__(self, base_url: str):
+        self.base_url = base_url
+        # code blocks are rendered last, so that their whitespace is preserved
+        self.code_blocks: list[str] = []
+
+    def render(self, node: _Node) -> str:
+        markdown = self._children(node)
+        lines = [line.rstrip() for line in markdown.split("\n")]
+        markdown = re.sub(r"\n{3,}", "\n\n", "\n".join(lines)).strip()
+        for i, block in enumerate(self.code_blocks):
+            markdown = markdown.replace(f"\x00{i}\x00", block)
+        return markdown
+
+    def _children(self, node: _Node) -> str:
+        parts: list[str] = []
+        for child in node.children:
+            if isinstance(child, str):
+                text = re.sub(r"\s+", " ", child)
+                # text following a block doesn't start with a space
+                if not parts or parts[-1].endswith("\n"):
+                    text = text.lstrip()
+                parts.append(text)
+            else:
+                parts.append(self._element(child))
+        return "".join(parts)
+
+    def _url(self, url: str) -> str:
+        return urljoin(self.base_url, url.strip())
+
+    def _element(self, node: _Node) -> str:
+        tag = node.tag
+
+        if tag in _HEADINGS:
+            text = self._children(node).strip()
+            return f"\n\n{'#' * int(tag[1])} {text}\n\n" if text else ""
+
+        if tag == "pre":
+            code = node.text().strip("\n")
+            classes = " ".join(child.attrs.get("class", "") for child in node.iter())
+            language = re.search(r"(?:lang|language)-(\w+)", classes)
+            self.code_blocks.append(f"```{language.group(1) if language else ''}\n{code}\n```")
+            return f"\n\n\x00{len(self.code_blocks) - 1}\x00\n\n"
+
+        if tag in ("ul", "ol"):
+            items = []
+            for child in node.children:
+                if not isinstance(child, _Node) or child.tag != "li":
+                    continue
+                marker = f"{len(items) + 1}." if tag == "ol" else "-"
+                content = re.sub(r"\n{2,}", "\n", self._children(child).strip())
+                # nested lists and multiline items are indented under their marker
+                items.append(f"{marker} " + content.replace("\n", "\n" + " " * (len(marker) + 1)))
+            return "\n\n" + "\n".join(items) + "\n\n" if items else ""
+
+        if tag == "blockquote":
+            content = re.sub(r"\n{3,}", "\n\n", self._children(node).strip())
+            return "\n\n" + "\n".join(f"> {line}" if line else ">" for line in content.split("\n")) + "\n\n"
+
+        if tag == "table":
+            return self._table(node)
+
+        if tag == "a":
+            text = self._children(node).strip()
+            href = node.attrs.get("href", "").strip()
+            if not text or not href or href.startswith(("#", "javascript:")):
+                return text
+            return f"[{text}]({self._url(href)})"
+
+        if tag == "img":
+            alt = node.attrs.get("alt", "").strip()
+            src = node.attrs.get("src", "").strip()
+            return f"![{alt}]({self._url(src)})" if alt and src else ""
+
+        if tag in ("strong", "b", "em", "i", "code"):
+            text = self._children(node).strip()
+            if not text:
+                return ""
+            wrap = "`" if tag == "code" else "**" if tag in ("strong", "b") else "*"
+            return f"{wrap}{text}{wrap}"
+
+        if tag == "br":
+            return "\n"
+
+        if tag == "hr":
+            return "\n\n---\n\n"
+
+        if tag == "li":
+            return f"\n- {self._children(node).strip()}\n"
+
+        if tag in _BLOCK_TAGS or tag in ("body", "dd", "dt", "figcaption", "tr"):
+            return f"\n\n{self._children(node).strip()}\n\n"
+
+        return self._children(node)
+
+    def _table(self, table: _Node) -> str:
+        rows = []
+        for row in table.iter():
+            if row.tag != "tr":
+                continue
+            cells = [
+                self._children(cell).strip().replace("\n", " ").replace("|", "\\|")
+                for cell in row.children
+                if isinstance(cell, _Node) and cell.tag in ("td", "th")
+            ]
+            if cells:
+                rows.append(cells)
+
+        if not rows:
+            return ""
+
+        width = max(len(row) for row in rows)
+        rows = [row + [""] * (width - len(row)) for row in rows]
+        lines = [f"| {' | '.join(row)} |" for row in rows]
+        lines.insert(1, f"|{'---|' * width}")
+        return "\n\n" + "\n".join(lines) + "\n\n"
+
+
+def _to_markdown(html: str, url: str) -> str:
+    builder = _TreeBuilder()
+    builder.feed(html)
+    builder.close()
+
+    _prune(builder.root)
+    markdown = _MarkdownRenderer(url).render(_find_main(builder.root))
+
+    title = " ".join(builder.title.split())
+    if title and not markdown.startswith("# "):
+        markdown = f"# {title}\n\n{markdown}"
+
+    return markdown
+
+
+def _fetch(url: str) -> tuple[str, str, str]:
+    response = network.request(
+        "GET",
+        url,
+        max_redirects=max_redirects,
+        headers={"Accept": "text/html,application/xhtml+xml;q=0.9,*/*;q=0.5"},
+        timeout=DEFAULT_HTTP_TIMEOUT,
+        stream=True,
+    )
+
+    body = b""
+    try:
+        response.raise_for_status()
+        for chunk in response.iter_content(chunk_size=8192):
+            body += chunk
+            if len(body) >= max_page_size:
+                body = body[:max_page_size]
+                break
+    finally:
+        response.close()
+
+    content_type = response.headers.get("Content-Type", "")
+    # requests assumes latin-1 for the html pages without a charset, most of them are utf-8
+    encoding = response.encoding if "charset=" in content_type.lower() else "utf-8"
+    return (
+        body.decode(encoding or "utf-8", errors="replace"),
+        response.url or url,
+        content_type.split(";")[0].strip().lower(),
+    )
+
+
+async def _render(url: str) -> tuple[str, str]:
+    try:
+        from playwright.async_api import async_playwright  # type: ignore
+    except ImportError as e:
+        raise RuntimeError(
+            "playwright is not installed, the page can't be rendered: install it with its chromium browser "
+            "or read the page without render_js"
+        ) from e
+
+    async with async_playwright() as playwright:
+        browser = await playwright.chromium.launch()
+        try:
+            page = await browser.new_page()
+            await page.goto(url, timeout=DEFAULT_HTTP_TIMEOUT * 1000, wait_until="networkidle")
+            return (await page.content())[:max_page_size], page.url
+        finally:
+            await browser.close()
+
+
+def _truncate(markdown: str) -> str:
+    if len(markdown) <= max_length:
+        return markdown
+    return markdown[:max_length] + f"\n\n[page truncated, {len(markdown) - max_length} characters omitted]"
+
+
+async def read_page(
+    url: Annotated[str, "The URL of the web page to read"],
+    render_js: Annotated[
+        bool,
+        "Set to true to render the page in a headless browser first, only if it needs JavaScript to show its content",
+    ] = False,
+) -> str:
+    """Read a web page and return its main content as Markdown, without navigation, ads and the other boilerplate."""
+
+    if render_js:
+        html, final_url = await _render(url)
+    else:
+        html, final_url, content_type = await asyncio.to_thread(_fetch, url)
+        if content_type.startswith("text/") and content_type != "text/html":
+            # plain text, markdown and so on are returned as they are
+            return _truncate(html.strip())
+        if content_type and content_type not in ("text/html", "application/xhtml+xml"):
+            raise ValueError(f"{url} is not a web page but {content_type}, use the http namespace to download it")
+
+    markdown = _to_markdown(html, final_url)
+    if not markdown:
+        return "the page has no readable content" + ("" if render_js else ", try again with render_js")
+
+    return _truncate(markdown)
@@ -0,0 +1,123 @@
+import sys
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.tools.namespaces import web
+
+_ARTICLE = """<html>
+<head><title>Using nerve</title><style>body { color: red; }</style></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+  <div id="sidebar"><p>Subscribe to our newsletter to get a new article like this one every week.</p></div>
+  <div class="post">
+    <h2>Getting started</h2>
+    <p>Nerve is an agent development kit, it lets you build agents with <a href="/docs">simple YAML files</a>
+      and <b>tools</b> written in Python.
+    <p>Install it with pip, then create a new agent with the wizard, answering a few questions.</p>
+    <pre><code class="language-bash">pip install nerve-adk
+  nerve create agent</code></pre>
+    <ul><li>fast<li>simple<ul><li>nested</li></ul></li></ul>
+    <div class="share-buttons"><a href="https://twitter.com/share">Tweet this</a></div>
+  </div>
+  <script>track();</script>
+  <footer>Copyright 2024</footer>
+</body>
+</html>"""
+
+
+def _response(content: bytes, content_type: str = "text/html; charset=utf-8") -> MagicMock:
+    response = MagicMock(headers={"Content-Type": content_type}, encoding="utf-8", url="https://example.com/blog/post")
+    response.iter_content.return_value = [content]
+    return response
+
+
+class TestToMarkdown(unittest.TestCase):
+    def test_main_content(self) -> None:
+        markdown = web._to_markdown(_ARTICLE, "https://example.com/blog/post")
+
+        self.assertEqual(
+            markdown,
+            "# Using nerve\n\n"
+            "## Getting started\n\n"
+            "Nerve is an agent development kit, it lets you build agents with "
+            "[simple YAML files](https://example.com/docs) and **tools** written in Python.\n\n"
+            "Install it with pip, then create a new agent with the wizard, answering a few questions.\n\n"
+            "```bash\npip install nerve-adk\n  nerve create agent\n```\n\n"
+            "- fast\n- simple\n  - nested",
+        )
+
+    def test_article_is_preferred(self) -> None:
+        html = "<body><div><p>Short teaser.</p></div><article><h1>Title</h1><p>The content.</p></article></body>"
+
+        self.assertEqual(web._to_markdown(html, "https://example.com/"), "# Title\n\nThe content.")
+
+    def test_tables_and_quotes(self) -> None:
+        html = (
+            "<main><table><tr><th>name</th><th>value</th></tr><tr><td>a|b</td><td>1</td></tr></table>"
+            "<blockquote><p>first</p><p>second</p></blockquote><ol><li>one</li><li>two</li></ol></main>"
+        )
+
+        self.assertEqual(
+            web._to_markdown(html, "https://example.com/"),
+            "| name | value |\n|---|---|\n| a\\|b | 1 |\n\n> first\n>\n> second\n\n1. one\n2. two",
+        )
+
+    def test_hidden_and_boilerplate_elements(self) -> None:
+        html = (
+            '<main><p>visible</p><p hidden>hidden</p><div role="dialog">cookies</div>'
+            '<div class="related-posts">more</div><a href="javascript:void(0)">click</a></main>'
+        )
+
+        self.assertEqual(web._to_markdown(html, "https://example.com/"), "visible\n\nclick")
+
+
+@patch("nerve.tools.namespaces.web.network.request")
+class TestReadPage(unittest.IsolatedAsyncioTestCase):
+    async def test_read_page(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(_ARTICLE.encode())
+
+        markdown = await web.read_page("https://example.com/post")
+
+        self.assertTrue(markdown.startswith("# Using nerve\n\n## Getting started"))
+        self.assertNotIn("newsletter", markdown)
+        self.assertNotIn("Tweet", markdown)
+        self.assertEqual(mock_request.call_args.kwargs["max_redirects"], web.max_redirects)
+
+    async def test_output_is_truncated(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(f"<main><p>{'a' * 100}</p></main>".encode())
+
+        with patch.object(web, "max_length", 10):
+            markdown = await web.read_page("https://example.com/post")
+
+        self.assertEqual(markdown, "aaaaaaaaaa\n\n[page truncated, 90 characters omitted]")
+
+    async def test_download_is_capped(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(b"<main><p>" + b"a" * 100 + b"</p></main>")
+
+        with patch.object(web, "max_page_size", 20):
+            markdown = await web.read_page("https://example.com/post")
+
+        self.assertEqual(markdown, "a" * 11)
+
+    async def test_plain_text_is_returned_as_is(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(b"# README\n\n<b>not html</b>\n", "text/markdown")
+
+        self.assertEqual(await web.read_page("https://example.com/README.md"), "# README\n\n<b>not html</b>")
+
+    async def test_other_content_types_are_rejected(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(b"%PDF-1.4", "application/pdf")
+
+        with self.assertRaisesRegex(ValueError, "not a web page but application/pdf"):
+            await web.read_page("https://example.com/paper.pdf")
+
+    async def test_empty_page(self, mock_request: MagicMock) -> None:
+        mock_request.return_value = _response(b"<html><body><div id='app'></div><script>render()</script></body></html>")
+
+        self.assertEqual(
+            await web.read_page("https://example.com/app"), "the page has no readable content, try again with render_js"
+        )
+
+    async def test_render_js_requires_playwright(self, _: MagicMock) -> None:
+        with patch.dict(sys.modules, {"playwright.async_api": None}):
+            with self.assertRaisesRegex(RuntimeError, "playwright is not installed"):
+                await web.read_page("https://example.com/app", render_js=True)
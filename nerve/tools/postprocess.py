@@ -0,0 +1,93 @@
+import re
+import typing as t
+from abc import ABC, abstractmethod
+
+if t.TYPE_CHECKING:
+    from nerve.models import Configuration
+
+# color codes, cursor movements and the other escape sequences of terminals
+_ANSI_ESCAPE = re.compile(r"\x1b(?:\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[@-Z\\-_])")
+
+
+class PostProcessor(ABC):
+    """
+    Transforms the text output of the tools that completed successfully, before it is sent to the model.
+    Post processors are registered on the agent and run in registration order.
+    """
+
+    def __init__(self, tools: list[str] | None = None):
+        # optional names of the tools to transform the output of, all of them if empty
+        self.tools = tools or []
+
+    def applies_to(self, name: str) -> bool:
+        return not self.tools or name in self.tools
+
+    @abstractmethod
+    def process(self, name: str, output: str) -> str:
+        """Return the transformed output of the given tool."""
+        pass
+
+
+class StripANSI(PostProcessor):
+    """Removes the colors and the other terminal escape sequences, common in the output of commands."""
+
+    def process(self, name: str, output: str) -> str:
+        return _ANSI_ESCAPE.sub("", output)
+
+
+class MaskSecrets(PostProcessor):
+    """Replaces every match of the given regular expressions."""
+
+    def __init__(self, patterns: list[str], replacement: str = "[REDACTED]", tools: list[str] | None = None):
+        super().__init__(tools)
+        self.patterns = [re.compile(pattern) for pattern in patterns]
+        self.replacement = replacement
+
+    def process(self, name: str, output: str) -> str:
+        for pattern in self.patterns:
+            output = pattern.sub(self.replacement, output)
+        return output
+
+
+class Truncate(PostProcessor):
+    """Cuts the outputs longer than the given number of characters."""
+
+    def __init__(self, max_length: int, tools: list[str] | None = None):
+        super().__init__(tools)
+        self.max_length = max_length
+
+    def process(self, name: str, output: str) -> str:
+        if len(output) <= self.max_length:
+            return output
+
+        return output[: self.max_length] + f"\n\n[output truncated, {len(output) - self.max_length} characters omitted]"
+
+
+def from_config(config: "Configuration.PostProcessor") -> PostProcessor:
+    if config.type == "strip_ansi":
+        return StripANSI(config.tools)
+
+    if config.type == "mask":
+        if not config.patterns:
+            raise ValueError("the mask post processor requires at least one pattern")
+        return MaskSecrets(config.patterns, tools=config.tools)
+
+    if config.max_length <= 0:
+        raise ValueError("the truncate post processor requires a max_length greater than 0")
+    return Truncate(config.max_length, config.tools)
+
+
+def post_process(processors: list[PostProcessor], name: str, output: t.Any) -> t.Any:
+    """Run the processors applying to the tool on its text output, other outputs like images are left as they are."""
+
+    if isinstance(output, list):
+        return [post_process(processors, name, item) for item in output]
+
+    if not isinstance(output, str):
+        return output
+
+    for processor in processors:
+        if processor.applies_to(name):
+            output = processor.process(name, output)
+
+    return output
@@ -0,0 +1,75 @@
+import typing as t
+import unittest
+from unittest.mock import MagicMock, patch
+
+from nerve.generation import WindowStrategy
+from nerve.generation.litellm import LiteLLMEngine
+from nerve.models import Configuration
+from nerve.tools.postprocess import MaskSecrets, StripANSI, Truncate, from_config, post_process
+
+
+class TestPostProcessors(unittest.TestCase):
+    def test_strip_ansi(self) -> None:
+        output = "\x1b[1;31merror\x1b[0m: \x1b]0;title\x07done\x1b[2K"
+
+        self.assertEqual(StripANSI().process("shell", output), "error: done")
+
+    def test_mask_secrets(self) -> None:
+        processor = MaskSecrets([r"sk-[A-Za-z0-9]+", r"token=\w+"])
+
+        self.assertEqual(processor.process("http", "key sk-abc123, token=xyz"), "key [REDACTED], [REDACTED]")
+
+    def test_truncate(self) -> None:
+        processor = Truncate(5)
+
+        self.assertEqual(processor.process("shell", "short"), "short")
+        self.assertEqual(processor.process("shell", "longer text"), "longe\n\n[output truncated, 6 characters omitted]")
+
+    def test_processors_run_in_order_on_the_targeted_tools(self) -> None:
+        processors = [StripANSI(), Truncate(3, tools=["shell"])]
+
+        self.assertEqual(
+            post_process(processors, "shell", "\x1b[31mabcdef"), "abc\n\n[output truncated, 3 characters omitted]"
+        )
+        self.assertEqual(
+            post_process(processors, "http", ["\x1b[31mabcdef", {"type": "image_url"}]),
+            ["abcdef", {"type": "image_url"}],
+        )
+
+    def test_from_config(self) -> None:
+        processor = from_config(Configuration.PostProcessor(type="truncate", max_length=10, tools=["shell"]))
+
+        self.assertIsInstance(processor, Truncate)
+        self.assertEqual(processor.tools, ["shell"])
+
+        for config, error in (
+            (Configuration.PostProcessor(type="mask"), "at least one pattern"),
+            (Configuration.PostProcessor(type="truncate"), "max_length greater than 0"),
+        ):
+            with self.subTest(type=config.type), self.assertRaisesRegex(ValueError, error):
+                from_config(config)
+
+
+class TestEnginePostProcessing(unittest.IsolatedAsyncioTestCase):
+    def setUp(self) -> None:
+        def echo(text: t.Annotated[str, "The text to echo"]) -> str:
+            """Echo the text."""
+            return text
+
+        def fail(text: t.Annotated[str, "The text"]) -> str:
+            """Always fail."""
+            raise RuntimeError(text)
+
+        self.engine = LiteLLMEngine("openai/gpt-4o", MagicMock(spec=WindowStrategy), [echo, fail], None)
+        self.engine.post_processors.append(MaskSecrets([r"sk-\w+"]))
+
+    @patch("nerve.generation.state")
+    async def test_successful_outputs_are_processed(self, mock_state: MagicMock) -> None:
+        mock_state.get_replay.return_value = None
+        mock_state.is_cancelled.return_value = False
+
+        responses = await self.engine._process_tool_call("call_1", "echo", {"text": "key is sk-abc123"}, {})
+        self.assertEqual(responses[0]["content"], "key is [REDACTED]")
+
+        responses = await self.engine._process_tool_call("call_2", "fail", {"text": "sk-abc123"}, {})
+        self.assertEqual(responses[0]["content"], "ERROR while executing tool fail: sk-abc123")
@@ -2,8 +2,16 @@ import inspect
 import typing as t
 from typing import Annotated
 
+import jsonschema
 from loguru import logger
 
+from nerve.models import ToolDescriptor
+
+# argument types that can be validated before calling a tool
+_SCALAR_TYPES = ("string", "integer", "number", "boolean")
+# placeholders of the required arguments without examples in the tool descriptors
+_EXAMPLE_VALUES: dict[str, t.Any] = {"string": "...", "integer": 0, "number": 0.0, "boolean": False, "array": []}
+
 
 def get_tool_schema(generator: str, func: t.Callable[..., t.Any]) -> dict[str, t.Any]:
     signature = inspect.signature(func)
@@ -73,6 +81,70 @@ def get_tool_schema(generator: str, func: t.Callable[..., t.Any]) -> dict[str, t
     return tool
 
 
+def get_tool_descriptor(func: t.Callable[..., t.Any]) -> ToolDescriptor:
+    """Describe a tool, with example arguments built from the examples and the defaults of its parameters."""
+
+    function = get_tool_schema("", func)["function"]
+    parameters = function["parameters"]
+    defaults = {name: param.default for name, param in inspect.signature(func).parameters.items()}
+
+    example: dict[str, t.Any] = {}
+    for name, schema in parameters["properties"].items():
+        if schema.get("examples"):
+            example[name] = schema["examples"][0]
+        elif defaults.get(name, inspect.Parameter.empty) not in (inspect.Parameter.empty, None):
+            example[name] = defaults[name]
+        elif name in parameters["required"]:
+            example[name] = _EXAMPLE_VALUES.get(schema["type"], {})
+
+    module = getattr(func, "__module__", None) or ""
+    return ToolDescriptor(
+        name=function["name"],
+        description=function["description"],
+        namespace=module.rsplit(".", 1)[-1] if module.startswith("nerve.tools.namespaces.") else None,
+        parameters=parameters,
+        example=example,
+        requires_confirmation=getattr(func, "__nerve_requires_confirmation__", False),
+        completes_task=getattr(func, "__nerve_completes_task__", False),
+    )
+
+
+def validate_tool_args(func: t.Callable[..., t.Any], args: dict[str, t.Any]) -> str | None:
+    """
+    Validate the arguments of a tool call, returning a description of the problem or None if they are valid.
+    """
+
+    try:
+        inspect.signature(func).bind(**args)
+    except TypeError as e:
+        return str(e)
+
+    try:
+        type_hints = t.get_type_hints(func, include_extras=True)
+    except Exception:
+        # can't resolve the annotations, let the tool deal with the arguments
+        return None
+
+    for name, value in args.items():
+        hint = type_hints.get(name)
+        if hint is None:
+            continue
+
+        if t.get_origin(hint) is Annotated:
+            hint = t.get_args(hint)[0]
+
+        schema = process_type(hint)
+        if schema["type"] not in _SCALAR_TYPES:
+            continue
+
+        try:
+            jsonschema.validate(value, schema)
+        except jsonschema.ValidationError:
+            return f"argument '{name}' must be of type {schema['type']}, got {type(value).__name__}: {value!r}"
+
+    return None
+
+
 def get_tool_response(response: t.Any) -> t.Any:
     response = response or ""
     if isinstance(response, str):
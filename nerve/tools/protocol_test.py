@@ -1,7 +1,11 @@
 import unittest
 from typing import Annotated
 
-from nerve.tools.protocol import get_tool_schema
+from pydantic import Field
+
+from nerve.tools.namespaces import task
+from nerve.tools.protocol import get_tool_descriptor, get_tool_schema, validate_tool_args
+from nerve.tools.utils import requires_user_confirmation
 
 
 class TestProtocol(unittest.IsolatedAsyncioTestCase):
@@ -32,3 +36,74 @@ class TestProtocol(unittest.IsolatedAsyncioTestCase):
                 },
             },
         )
+
+
+def _tool(
+    path: Annotated[str, "The path."],
+    count: Annotated[int, "How many."] = 1,
+    recursive: bool = False,
+    extra: str | None = None,
+) -> str:
+    """Description."""
+    return path
+
+
+class TestValidateToolArgs(unittest.TestCase):
+    def test_valid_arguments(self) -> None:
+        self.assertIsNone(validate_tool_args(_tool, {"path": "/tmp", "count": 2, "recursive": True, "extra": None}))
+
+    def test_missing_argument(self) -> None:
+        self.assertIn("missing a required argument: 'path'", validate_tool_args(_tool, {"count": 2}) or "")
+
+    def test_unknown_argument(self) -> None:
+        self.assertIn("unexpected keyword argument 'size'", validate_tool_args(_tool, {"path": "/", "size": 1}) or "")
+
+    def test_wrong_type(self) -> None:
+        self.assertEqual(
+            validate_tool_args(_tool, {"path": "/", "count": "2"}),
+            "argument 'count' must be of type integer, got str: '2'",
+        )
+        self.assertIsNotNone(validate_tool_args(_tool, {"path": "/", "count": True}))
+
+    def test_untyped_arguments_are_not_checked(self) -> None:
+        def untyped(value):  # type: ignore
+            return value
+
+        self.assertIsNone(validate_tool_args(untyped, {"value": [1, 2]}))
+
+
+class TestToolDescriptor(unittest.TestCase):
+    def test_example_arguments(self) -> None:
+        def _search(
+            query: Annotated[str, Field(description="What to search", examples=["nerve agents"])],
+            limit: Annotated[int, "Maximum number of results"] = 10,
+            site: Annotated[str, "Optional site to search"] = "",
+            language: Annotated[str | None, "Optional language of the results"] = None,
+            strict: Annotated[bool, "Match the exact query"] = False,
+        ) -> str:
+            """Search the web."""
+            return query
+
+        descriptor = get_tool_descriptor(_search)
+
+        self.assertEqual(descriptor.name, "_search")
+        self.assertEqual(descriptor.description, "Search the web.")
+        self.assertIsNone(descriptor.namespace)
+        self.assertEqual(descriptor.parameters["required"], ["query"])
+        # falsy defaults are also valid examples, None is not
+        self.assertEqual(descriptor.example, {"query": "nerve agents", "limit": 10, "site": "", "strict": False})
+        self.assertFalse(descriptor.requires_confirmation)
+        self.assertFalse(descriptor.completes_task)
+
+    def test_confirmation_and_completion(self) -> None:
+        @requires_user_confirmation()
+        def _delete(path: Annotated[str, "The path to delete"]) -> None:
+            """Delete a file."""
+
+        self.assertTrue(get_tool_descriptor(_delete).requires_confirmation)
+        self.assertEqual(get_tool_descriptor(_delete).example, {"path": "..."})
+
+        descriptor = get_tool_descriptor(task.task_complete_success)
+        self.assertEqual(descriptor.namespace, "task")
+        self.assertTrue(descriptor.completes_task)
+        self.assertFalse(get_tool_descriptor(task.spawn_subtask).completes_task)
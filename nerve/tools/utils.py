@@ -1,3 +1,8 @@
+import base64
+import functools
+import re
+import typing as t
+from html.parser import HTMLParser
 from pathlib import Path
 
 from loguru import logger
@@ -31,3 +36,85 @@ def maybe_text(output: bytes) -> str | bytes:
         return output.decode("utf-8").strip()
     except UnicodeDecodeError:
         return output
+
+
+def file_part(data: bytes, mime_type: str, name: str) -> dict[str, t.Any]:
+    """
+    Return a tool response attaching a document, like a PDF, to the model input. Models that don't accept files
+    get its text instead.
+    """
+
+    return {
+        "type": "file",
+        "file": {
+            "filename": name,
+            "file_data": f"data:{mime_type};base64,{base64.b64encode(data).decode()}",
+        },
+    }
+
+
+def requires_user_confirmation(
+    prompt: str | None = None,
+) -> t.Callable[[t.Callable[..., t.Any]], t.Callable[..., t.Any]]:
+    """Decorator that asks the user for confirmation before executing a tool."""
+
+    def decorator(func: t.Callable[..., t.Any]) -> t.Callable[..., t.Any]:
+        @functools.wraps(func)
+        def wrapper(*args: t.Any, **kwargs: t.Any) -> t.Any:
+            arguments = ", ".join([repr(arg) for arg in args] + [f"{k}={v!r}" for k, v in kwargs.items()])
+            question = prompt or f"Allow the agent to execute {func.__name__}({arguments})?"
+            if not state.on_user_confirmation_needed(question, func.__name__, kwargs):
+                return f"the user did not allow the execution of {func.__name__}"
+
+            return func(*args, **kwargs)
+
+        # reported by the tool descriptors
+        wrapper.__nerve_requires_confirmation__ = True  # type: ignore
+        return wrapper
+
+    return decorator
+
+
+def completes_task(func: t.Callable[..., t.Any]) -> t.Callable[..., t.Any]:
+    """Decorator marking a tool that completes the task when called, reported by the tool descriptors."""
+
+    func.__nerve_completes_task__ = True  # type: ignore
+    return func
+
+
+class _HTMLToText(HTMLParser):
+    BLOCK_TAGS = {"p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "table", "ul", "ol", "pre"}
+    SKIP_TAGS = {"script", "style", "head", "title", "noscript"}
+
+    def __init__(self) -> None:
+        super().__init__()
+        self.parts: list[str] = []
+        self.skip = 0
+
+    def handle_starttag(self, tag: str, attrs: list[tuple[str, str | None]]) -> None:
+        if tag in self.SKIP_TAGS:
+            self.skip += 1
+        elif tag in self.BLOCK_TAGS:
+            self.parts.append("\n")
+
+    def handle_endtag(self, tag: str) -> None:
+        if tag in self.SKIP_TAGS:
+            self.skip = max(0, self.skip - 1)
+        elif tag in self.BLOCK_TAGS:
+            self.parts.append("\n")
+
+    def handle_data(self, data: str) -> None:
+        if not self.skip:
+            self.parts.append(data)
+
+
+def html_to_text(html: str) -> str:
+    """Convert an HTML document to plain text, dropping scripts and styles."""
+
+    parser = _HTMLToText()
+    parser.feed(html)
+    parser.close()
+
+    lines = [" ".join(line.split()) for line in "".join(parser.parts).splitlines()]
+    # collapse multiple empty lines
+    return re.sub(r"\n{3,}", "\n\n", "\n".join(lines)).strip()